@@ -3,7 +3,7 @@ extern crate sdl2_sys;
 #[macro_use] extern crate corange_rs;
 #[macro_use] extern crate lazy_static;
 
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Once};
 use libc::c_void;
 use corange_rs::corange::*;
 
@@ -14,15 +14,19 @@ lazy_static! {
 fn main() {
     unsafe {
         // Initialize engine and load default assets/shaders
-        corange_rs::engine::initialize("./src/corange/assets_core/");
+        corange_rs::engine::initialize_with_embedded_assets();
+
+        // Load the models in the background behind a loading screen
+        for folder in &["podium", "cello", "piano", "dino", "imrod"] {
+            corange_rs::engine::load_folder_in_background(&format!("./examples/assets/{}/", folder)).unwrap();
+        }
+        corange_rs::engine::show_loading_screen();
 
         // Initialize podium
-        folder_load(path("./examples/assets/podium/"));
         let s_podium = entity_new_type_id(str("podium"), *STATIC_TYPE) as *mut static_object;
         (*s_podium).renderable = asset_hndl_new(path("./examples/assets/podium/podium.bmf"));
 
         // Initialize cello
-        folder_load(path("./examples/assets/cello/"));
         let s_cello = entity_new_type_id(str("cello"), *STATIC_TYPE) as *mut static_object;
         (*s_cello).renderable = asset_hndl_new(path("./examples/assets/cello/cello.bmf"));
         (*s_cello).position = vec3_new(0.0, 3.0, 0.0);
@@ -30,21 +34,17 @@ fn main() {
         (*s_cello).scale = vec3_new(0.75, 0.75, 0.75);
 
         // Initialize piano
-        folder_load(path("./examples/assets/piano/"));
         let s_piano = entity_new_type_id(str("piano"), *STATIC_TYPE) as *mut static_object;
         (*s_piano).renderable = asset_hndl_new(path("./examples/assets/piano/piano.bmf"));
         (*s_piano).position = vec3_new(1.0, 5.0, 0.0);
 
         // Initialize dino
-        folder_load(path("./examples/assets/dino/"));
         let s_dino = entity_new_type_id(str("dino"), *STATIC_TYPE) as *mut static_object;
         (*s_dino).renderable = asset_hndl_new(path("./examples/assets/dino/dino.bmf"));
         (*s_dino).scale = vec3_new(4.0, 4.0, 4.0);
 
         // Initialize imrod
-        folder_load(path("./examples/assets/imrod/"));
         let a_imrod = entity_new_type_id(str("imrod"), *ANIMATED_TYPE) as *mut animated_object;
-        animated_object_load_skeleton(a_imrod, asset_hndl_new(path("./examples/assets/imrod/imrod.skl")));
         (*a_imrod).renderable = asset_hndl_new(path("./examples/assets/imrod/imrod.bmf"));
         (*a_imrod).animation = asset_hndl_new(path("./examples/assets/imrod/imrod.ani"));
         (*a_imrod).rotation = quat_rotation_y(1.57);
@@ -93,10 +93,23 @@ fn main() {
         ui_button_set_label(b_imrod, str("Imrod"));
         ui_button_set_onclick(b_imrod, Some(select_imrod));
 
+        let loaded = Once::new();
         let update_fn = | _:f64, renderer:*mut renderer | {
             // Update UI
             ui_button_set_label(framerate, frame_rate_string());
 
+            // Wait for the models, the skeleton is needed to pose imrod
+            let progress = corange_rs::engine::loading_progress();
+            if !progress.done() {
+                return;
+            }
+            loaded.call_once(|| {
+                for err in &progress.errors {
+                    eprintln!("{}", err);
+                }
+                animated_object_load_skeleton(a_imrod, asset_hndl_new(path("./examples/assets/imrod/imrod.skl")));
+            });
+
             // Update animated objects
             animated_object_update(a_imrod, frame_time() as f32 * 0.25);
 
@@ -0,0 +1,461 @@
+//! Zip archives of assets, mounted over asset folders
+//!
+//! `mount` makes the files of an archive appear below a folder of the asset system, such as
+//! `./assets/` or `$CORANGE`, so `asset_hndl_new`, `folder_load` and the loaders find them there.
+//! Each archive is read into memory and handed to the engine with `asset_mount`, and the loaders
+//! open files through `asset_open`. A path is looked up in the archives on its folder newest
+//! first, so a patch archive mounted after the base one overrides its files, and then on disk, so
+//! loose files the archives don't have are still found. Assets loaded before the mount keep their
+//! files. `read_file` does the same lookup for the readers written in Rust.
+//!
+//! Stored and deflated entries can be read, so any zip tool can build archives. `write` and the
+//! `corange-pack` tool store files without compression.
+
+use std::borrow::Cow;
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::io;
+use std::path::{Component, Path};
+use std::sync::Mutex;
+use libc::{c_int, c_void};
+use corange::*;
+use inflate;
+use png::crc32;
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+
+/// Size of the end of central directory record without its comment
+const END_SIZE: usize = 22;
+
+/// Entries are stored, or compressed with DEFLATE
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// Version 2.0, enough for stored and deflated entries
+const VERSION: u16 = 20;
+
+/// General purpose flags for names encoded as UTF-8
+const UTF8_NAMES: u16 = 0x0800;
+
+/// 1980-01-01 00:00, so packing the same files gives the same archive
+const DOS_TIME: u16 = 0;
+const DOS_DATE: u16 = 0x0021;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    /// Path in the archive, with forward slashes
+    pub name: String,
+    pub size: usize,
+    pub compressed_size: usize,
+    method: u16,
+    crc: u32,
+    offset: usize
+}
+
+/// An archive read into memory
+#[derive(Clone, Debug)]
+pub struct Archive {
+    pub entries: Vec<Entry>,
+    bytes: Vec<u8>
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> Option<u16> {
+    bytes.get(offset..offset + 2).map(|b| b[0] as u16 | (b[1] as u16) << 8)
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Option<u32> {
+    bytes.get(offset..offset + 4).map(|b| b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16 | (b[3] as u32) << 24)
+}
+
+impl Archive {
+    pub fn parse(bytes: Vec<u8>) -> Result<Archive, String> {
+        // The end record is last, followed by a comment of up to 64KB
+        let last = bytes.len().checked_sub(END_SIZE).ok_or("too short for a zip archive")?;
+        let first = last.saturating_sub(0xFFFF);
+        let end = (first..last + 1).rev()
+            .find(|&offset| u32_at(&bytes, offset) == Some(END_OF_DIRECTORY))
+            .ok_or("not a zip archive, missing the end of central directory")?;
+
+        let count = u16_at(&bytes, end + 10).unwrap() as usize;
+        let directory = u32_at(&bytes, end + 16).unwrap() as usize;
+        if count == 0xFFFF || directory == 0xFFFF_FFFF {
+            return Err("zip64 archives aren't supported".to_string());
+        }
+
+        let truncated = |offset: usize| format!("byte {}: central directory is truncated", offset);
+        let mut entries = Vec::with_capacity(count);
+        let mut offset = directory;
+        for _ in 0..count {
+            if u32_at(&bytes, offset) != Some(CENTRAL_HEADER) {
+                return Err(format!("byte {}: expected a central directory header", offset));
+            }
+            let field16 = |at: usize| u16_at(&bytes, offset + at).ok_or_else(|| truncated(offset));
+            let field32 = |at: usize| u32_at(&bytes, offset + at).ok_or_else(|| truncated(offset));
+            let flags = field16(8)?;
+            let method = field16(10)?;
+            let crc = field32(16)?;
+            let compressed_size = field32(20)? as usize;
+            let size = field32(24)? as usize;
+            let lengths = (field16(28)? as usize, field16(30)? as usize, field16(32)? as usize);
+            let local = field32(42)? as usize;
+            let name = bytes.get(offset + 46..offset + 46 + lengths.0).ok_or_else(|| truncated(offset))?;
+            let name = String::from_utf8_lossy(name).replace('\\', "/");
+            if flags & 1 != 0 {
+                return Err(format!("{} is encrypted", name));
+            }
+            if method != STORED && method != DEFLATED {
+                return Err(format!("{} uses compression method {}, only stored and deflated entries are supported", name, method));
+            }
+            entries.push(Entry {
+                name: name,
+                size: size,
+                compressed_size: compressed_size,
+                method: method,
+                crc: crc,
+                offset: local
+            });
+            offset += 46 + lengths.0 + lengths.1 + lengths.2;
+        }
+
+        Ok(Archive { entries: entries, bytes: bytes })
+    }
+
+    pub fn open(filename: &str) -> Result<Archive, String> {
+        let bytes = fs::read(filename).map_err(|err| format!("{}: {}", filename, err))?;
+        Archive::parse(bytes).map_err(|err| format!("{}: {}", filename, err))
+    }
+
+    /// Names of the files, leaving out folder entries
+    pub fn files(&self) -> Vec<&str> {
+        self.entries.iter().filter(|entry| !entry.name.ends_with('/')).map(|entry| entry.name.as_str()).collect()
+    }
+
+    pub fn entry(&self, name: &str) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    /// Contents of the file `name`, checked against its CRC-32
+    pub fn read(&self, name: &str) -> Result<Vec<u8>, String> {
+        let entry = self.entry(name).ok_or_else(|| format!("{} isn't in the archive", name))?;
+        let local = entry.offset;
+        if u32_at(&self.bytes, local) != Some(LOCAL_HEADER) {
+            return Err(format!("{}: byte {}: expected a local file header", name, local));
+        }
+        let lengths = (u16_at(&self.bytes, local + 26), u16_at(&self.bytes, local + 28));
+        let start = match lengths {
+            (Some(name_length), Some(extra_length)) => local + 30 + name_length as usize + extra_length as usize,
+            _ => return Err(format!("{}: byte {}: local file header is truncated", name, local))
+        };
+        let data = self.bytes.get(start..start + entry.compressed_size)
+            .ok_or_else(|| format!("{}: {} bytes don't fit in the rest of the archive", name, entry.compressed_size))?;
+
+        let contents = if entry.method == DEFLATED {
+            inflate::inflate(data).map_err(|err| format!("{}: {}", name, err))?
+        } else {
+            data.to_vec()
+        };
+        if contents.len() != entry.size || crc32(&contents) != entry.crc {
+            return Err(format!("{}: contents don't match the checksum", name));
+        }
+        Ok(contents)
+    }
+
+    /// Write every file below `folder`, keeping the folders of the archive
+    pub fn unpack(&self, folder: &Path) -> Result<(), String> {
+        for name in self.files() {
+            // Names leaving the folder would write anywhere on disk
+            let relative = Path::new(name);
+            if !relative.components().all(|component| matches!(component, Component::Normal(_))) {
+                return Err(format!("{} is outside the archive's folder", name));
+            }
+            let path = folder.join(relative);
+            let contents = self.read(name)?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|err| format!("{}: {}", parent.display(), err))?;
+            }
+            fs::write(&path, contents).map_err(|err| format!("{}: {}", path.display(), err))?;
+        }
+        Ok(())
+    }
+}
+
+fn push16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend_from_slice(&[value as u8, (value >> 8) as u8]);
+}
+
+fn push32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+}
+
+/// A zip archive storing `files`, given as names and contents
+pub fn write(files: &[(String, Vec<u8>)]) -> Result<Vec<u8>, String> {
+    if files.len() >= 0xFFFF {
+        return Err(format!("{} files don't fit in a zip archive without zip64", files.len()));
+    }
+    let mut bytes = Vec::new();
+    let mut directory = Vec::new();
+    for (name, contents) in files {
+        if name.len() > 0xFFFF || contents.len() as u64 >= 0xFFFF_FFFF || bytes.len() as u64 >= 0xFFFF_FFFF {
+            return Err(format!("{} doesn't fit in a zip archive without zip64", name));
+        }
+        let crc = crc32(contents);
+        let offset = bytes.len() as u32;
+
+        push32(&mut bytes, LOCAL_HEADER);
+        for &value in &[VERSION, UTF8_NAMES, STORED, DOS_TIME, DOS_DATE] {
+            push16(&mut bytes, value);
+        }
+        for &value in &[crc, contents.len() as u32, contents.len() as u32] {
+            push32(&mut bytes, value);
+        }
+        push16(&mut bytes, name.len() as u16);
+        push16(&mut bytes, 0);
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.extend_from_slice(contents);
+
+        push32(&mut directory, CENTRAL_HEADER);
+        for &value in &[VERSION, VERSION, UTF8_NAMES, STORED, DOS_TIME, DOS_DATE] {
+            push16(&mut directory, value);
+        }
+        for &value in &[crc, contents.len() as u32, contents.len() as u32] {
+            push32(&mut directory, value);
+        }
+        // Name length, no extra field, comment, disk number or attributes
+        for &value in &[name.len() as u16, 0, 0, 0, 0] {
+            push16(&mut directory, value);
+        }
+        push32(&mut directory, 0);
+        push32(&mut directory, offset);
+        directory.extend_from_slice(name.as_bytes());
+    }
+    if bytes.len() as u64 + directory.len() as u64 >= 0xFFFF_FFFF {
+        return Err("the files don't fit in a zip archive without zip64".to_string());
+    }
+
+    let directory_offset = bytes.len() as u32;
+    bytes.extend_from_slice(&directory);
+    push32(&mut bytes, END_OF_DIRECTORY);
+    for &value in &[0, 0, files.len() as u16, files.len() as u16] {
+        push16(&mut bytes, value);
+    }
+    push32(&mut bytes, directory.len() as u32);
+    push32(&mut bytes, directory_offset);
+    push16(&mut bytes, 0);
+    Ok(bytes)
+}
+
+/// Every file below `folder` named by its path relative to it, sorted by name
+pub fn read_folder(folder: &Path) -> Result<Vec<(String, Vec<u8>)>, String> {
+    fn walk(folder: &Path, prefix: &str, files: &mut Vec<(String, Vec<u8>)>) -> Result<(), String> {
+        let entries = fs::read_dir(folder).map_err(|err| format!("{}: {}", folder.display(), err))?;
+        for entry in entries {
+            let path = entry.map_err(|err| format!("{}: {}", folder.display(), err))?.path();
+            let name = format!("{}{}", prefix, path.file_name().unwrap().to_string_lossy());
+            if path.is_dir() {
+                walk(&path, &format!("{}/", name), files)?;
+            } else {
+                let contents = fs::read(&path).map_err(|err| format!("{}: {}", path.display(), err))?;
+                files.push((name, contents));
+            }
+        }
+        Ok(())
+    }
+    let mut files = Vec::new();
+    walk(folder, "", &mut files)?;
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(files)
+}
+
+/// Files of an archive mounted on a folder, kept alive for the engine until it is unmounted
+struct Mount {
+    id: c_int,
+    folder: String,
+    archive: String,
+    files: Vec<(String, Cow<'static, [u8]>)>
+}
+
+impl Drop for Mount {
+    fn drop(&mut self) {
+        unsafe { asset_unmount(self.id); }
+    }
+}
+
+lazy_static! {
+    static ref MOUNTS: Mutex<Vec<Mount>> = Mutex::new(Vec::new());
+}
+
+/// `folder` as the engine compares mounted folders, so `$CORANGE` and its mapping are the same
+fn clean_path(folder: &str) -> String {
+    let folder = CString::new(folder).unwrap();
+    unsafe {
+        let clean = asset_map_cleanpath(P(folder.as_ptr()));
+        CStr::from_ptr(clean.ptr.as_ptr()).to_string_lossy().into_owned()
+    }
+}
+
+/// `path` with `.` and `..` components resolved, like the clean paths of the engine
+fn normalize(path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "." => {}
+            ".." if parts.len() > 1 => { parts.pop(); }
+            "" if !parts.is_empty() => {}
+            part => parts.push(part)
+        }
+    }
+    parts.join("/")
+}
+
+/// The part of `path` below `folder`, empty for the folder itself
+fn relative<'a>(path: &'a str, folder: &str) -> Option<&'a str> {
+    let folder = folder.trim_end_matches('/');
+    if !path.starts_with(folder) {
+        return None;
+    }
+    let rest = &path[folder.len()..];
+    if rest.is_empty() {
+        Some(rest)
+    } else if rest.starts_with('/') {
+        Some(&rest[1..])
+    } else {
+        None
+    }
+}
+
+/// Mount `files`, named relative to `folder`, as `source` over what is mounted there already
+fn mount_files(files: Vec<(String, Cow<'static, [u8]>)>, source: &str, folder: &str) -> Result<(), String> {
+    let folder = clean_path(folder);
+    let mut mounts = MOUNTS.lock().unwrap();
+    if mounts.iter().any(|mount| mount.folder == folder && mount.archive == source) {
+        return Err(format!("{} is already mounted on {}", source, folder));
+    }
+    let folder_path = CString::new(folder.as_str()).unwrap();
+    let id = unsafe { asset_mount(P(folder_path.as_ptr())) };
+    for &(ref name, ref contents) in &files {
+        let name = CString::new(name.as_str()).unwrap();
+        unsafe { asset_mount_file(id, P(name.as_ptr()), contents.as_ptr() as *const c_void, contents.len() as c_int); }
+    }
+    mounts.push(Mount { id: id, folder: folder, archive: source.to_string(), files: files });
+    Ok(())
+}
+
+/// Mount `archive` on `folder`, over the archives already mounted there. Mount before loading
+/// the assets in the folder.
+pub fn mount(archive: &str, folder: &str) -> Result<(), String> {
+    let opened = Archive::open(archive)?;
+    let mut files = Vec::new();
+    for name in opened.files() {
+        // Names leaving the folder would cover files anywhere on disk
+        if !Path::new(name).components().all(|component| matches!(component, Component::Normal(_))) {
+            return Err(format!("{}: {} is outside the archive's folder", archive, name));
+        }
+        let contents = opened.read(name).map_err(|err| format!("{}: {}", archive, err))?;
+        files.push((name.to_string(), Cow::Owned(contents)));
+    }
+    mount_files(files, archive, folder)
+}
+
+/// Mount `files`, named relative to `folder`, as `source` without copying them, like the core
+/// assets compiled into the library
+pub fn mount_static(files: &[(&str, &'static [u8])], source: &str, folder: &str) -> Result<(), String> {
+    let files = files.iter().map(|&(name, contents)| (name.to_string(), Cow::Borrowed(contents))).collect();
+    mount_files(files, source, folder)
+}
+
+/// Unmount `archive` from `folder`, which shows the archives left or else the files on disk
+pub fn unmount(archive: &str, folder: &str) -> Result<(), String> {
+    let folder = clean_path(folder);
+    let mut mounts = MOUNTS.lock().unwrap();
+    let index = mounts.iter().position(|mount| mount.folder == folder && mount.archive == archive)
+        .ok_or_else(|| format!("{} isn't mounted on {}", archive, folder))?;
+    mounts.remove(index);
+    Ok(())
+}
+
+/// Mounted folders as full paths with their archives, in mount order
+pub fn mounted() -> Vec<(String, Vec<String>)> {
+    let mut folders: Vec<(String, Vec<String>)> = Vec::new();
+    for mount in MOUNTS.lock().unwrap().iter() {
+        match folders.iter().position(|&(ref folder, _)| *folder == mount.folder) {
+            Some(i) => folders[i].1.push(mount.archive.clone()),
+            None => folders.push((mount.folder.clone(), vec![mount.archive.clone()]))
+        }
+    }
+    folders
+}
+
+/// Contents of the clean `path` in the newest mount holding it
+fn mounted_file<'a>(mounts: &'a [Mount], path: &str) -> Option<&'a [u8]> {
+    mounts.iter().rev()
+        .filter_map(|mount| relative(path, &mount.folder).and_then(|name| mount.files.iter().find(|&&(ref file, _)| file == name)))
+        .map(|&(_, ref contents)| &contents[..])
+        .next()
+}
+
+/// Contents of the file at the mapped `path`, from the newest archive holding it or else from
+/// disk. Doesn't call into the engine, so workers can read files with it.
+pub fn read_file(path: &str) -> io::Result<Vec<u8>> {
+    let path = normalize(path);
+    if let Some(contents) = mounted_file(&MOUNTS.lock().unwrap(), &path) {
+        return Ok(contents.to_vec());
+    }
+    fs::read(path)
+}
+
+/// Whether the mapped `path` is a mounted file or a file on disk
+pub fn is_file(path: &str) -> bool {
+    let path = normalize(path);
+    mounted_file(&MOUNTS.lock().unwrap(), &path).is_some() || Path::new(&path).is_file()
+}
+
+fn disk_files(folder: &Path, recursive: bool, out: &mut Vec<String>) -> io::Result<()> {
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+        if path.is_file() {
+            out.push(path.to_string_lossy().into_owned());
+        } else if recursive && path.is_dir() {
+            disk_files(&path, recursive, out)?;
+        }
+    }
+    Ok(())
+}
+
+fn list_files(folder: &str, recursive: bool) -> io::Result<Vec<String>> {
+    let clean = normalize(folder);
+    let mut found = false;
+    let mut files = Vec::new();
+    for mount in MOUNTS.lock().unwrap().iter() {
+        if let Some(inside) = relative(&clean, &mount.folder) {
+            for &(ref name, _) in &mount.files {
+                let rest = if inside.is_empty() { Some(name.as_str()) } else { relative(name, inside) };
+                if let Some(rest) = rest {
+                    found = true;
+                    if recursive || !rest.contains('/') {
+                        files.push(format!("{}/{}", clean, rest));
+                    }
+                }
+            }
+            found = found || inside.is_empty();
+        }
+    }
+    if let Err(err) = disk_files(Path::new(&clean), recursive, &mut files) {
+        if !found { return Err(err); }
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Paths of the files directly in the mapped `folder`, from the archives mounted over it and
+/// from disk, each once and sorted
+pub fn folder_files(folder: &str) -> io::Result<Vec<String>> {
+    list_files(folder, false)
+}
+
+/// Like `folder_files`, but also lists the files in every folder below `folder`
+pub fn folder_tree(folder: &str) -> io::Result<Vec<String>> {
+    list_files(folder, true)
+}
@@ -0,0 +1,138 @@
+extern crate corange_rs;
+
+use std::env;
+use std::ffi::CString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use corange_rs::core_assets;
+use corange_rs::corange::*;
+use corange_rs::collision_mesh;
+use corange_rs::collision_mesh::CollisionOptions;
+
+const USAGE: &'static str = "\
+Usage: corange-col [options] <file or folder>...
+
+Writes a .col collision mesh next to every renderable given, and next to
+every .bmf found in the given folders.
+
+Options:
+  --simplify <size>   merge vertices on a grid with cells of this size
+  --hull              write the convex hull of the mesh instead
+  --output <file>     output path, only with a single input file
+  --force             overwrite existing .col files
+  --core <path>       read the core assets from this folder instead of the
+                      copy compiled into the tool";
+
+struct Arguments {
+    options: CollisionOptions,
+    output: Option<String>,
+    force: bool,
+    core: Option<String>,
+    inputs: Vec<String>
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("corange-col: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+fn parse_arguments() -> Arguments {
+    let mut arguments = Arguments {
+        options: CollisionOptions::default(),
+        output: None,
+        force: false,
+        core: None,
+        inputs: Vec::new()
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--simplify" => {
+                let size = args.next().and_then(|size| size.parse::<f32>().ok()).filter(|&size| size > 0.0);
+                arguments.options.simplify = Some(size.unwrap_or_else(|| fail("--simplify needs a positive cell size")));
+            }
+            "--hull" => arguments.options.convex_hull = true,
+            "--output" => arguments.output = Some(args.next().unwrap_or_else(|| fail("--output needs a path"))),
+            "--force" => arguments.force = true,
+            "--core" => arguments.core = Some(args.next().unwrap_or_else(|| fail("--core needs a path"))),
+            "--help" | "-h" => { println!("{}", USAGE); process::exit(0); }
+            _ if arg.starts_with("--") => fail(&format!("unknown option {}", arg)),
+            _ => arguments.inputs.push(arg)
+        }
+    }
+
+    if arguments.inputs.is_empty() {
+        fail("no input given");
+    }
+    if arguments.output.is_some() && (arguments.inputs.len() != 1 || !Path::new(&arguments.inputs[0]).is_file()) {
+        fail("--output can only be used with a single input file");
+    }
+    arguments
+}
+
+/// Every .bmf below `folder`
+fn find_bmf(folder: &Path, out: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(err) => { eprintln!("{}: {}", folder.display(), err); return; }
+    };
+    let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            find_bmf(&path, out);
+        } else if path.extension().map_or(false, |extension| extension == "bmf") {
+            out.push(path);
+        }
+    }
+}
+
+fn main() {
+    let arguments = parse_arguments();
+
+    let mut jobs: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for input in &arguments.inputs {
+        let path = Path::new(input);
+        if path.is_dir() {
+            let mut found = Vec::new();
+            find_bmf(path, &mut found);
+            jobs.extend(found.into_iter().map(|path| { let output = path.with_extension("col"); (path, output) }));
+        } else {
+            let output = arguments.output.as_ref().map_or(path.with_extension("col"), PathBuf::from);
+            jobs.push((path.to_path_buf(), output));
+        }
+    }
+
+    // Meshes are read without vertex buffers, so there is no window or GL context
+    let core = match arguments.core {
+        Some(ref core) => format!("{}/", core.trim_end_matches('/')),
+        None => match core_assets::mount() {
+            Ok(folder) => folder.to_string(),
+            Err(err) => { eprintln!("Failed to mount the core assets: {}", err); process::exit(1); }
+        }
+    };
+    unsafe {
+        let (variable, core) = (CString::new("$CORANGE").unwrap(), CString::new(core).unwrap());
+        asset_init();
+        asset_add_path_variable(P(variable.as_ptr()), P(core.as_ptr()));
+    }
+
+    let mut failed = 0;
+    for (input, output) in jobs {
+        if output.exists() && !arguments.force {
+            println!("{}: skipped, {} exists", input.display(), output.display());
+            continue;
+        }
+        match collision_mesh::convert(&input.to_string_lossy(), &output.to_string_lossy(), &arguments.options) {
+            Ok(report) => println!("{}: {} triangles -> {} triangles in {}",
+                input.display(), report.source_triangles, report.triangles, output.display()),
+            Err(err) => { eprintln!("{}", err); failed += 1; }
+        }
+    }
+
+    if failed > 0 {
+        process::exit(1);
+    }
+}
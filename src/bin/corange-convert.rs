@@ -0,0 +1,169 @@
+extern crate corange_rs;
+
+use std::env;
+use std::ffi::CString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use corange_rs::core_assets;
+use corange_rs::corange::*;
+use corange_rs::mesh_conversion;
+use corange_rs::mesh_conversion::ConvertOptions;
+
+const USAGE: &'static str = "\
+Usage: corange-convert [options] <file or folder>...
+
+Writes a .bmf next to every mesh given, and next to every mesh found in the
+given folders (.obj, .smd, .ply, .gltf and .glb).
+
+Options:
+  --scale <factor>    scale positions by this factor
+  --flip <axes>       negate the given axes, any of x, y and z (e.g. --flip z)
+  --z-up              the source is z up, rotate it to the engine's y up
+  --normals           regenerate normals with mesh_generate_normals
+  --tangents          regenerate tangents with mesh_generate_tangents
+  --output <file>     output path, only with a single input file
+  --force             overwrite existing .bmf files
+  --core <path>       read the core assets from this folder instead of the
+                      copy compiled into the tool
+
+Rigged meshes can't be scaled, flipped or rotated since their skeleton and
+animations would no longer match.";
+
+struct Arguments {
+    options: ConvertOptions,
+    output: Option<String>,
+    force: bool,
+    core: Option<String>,
+    inputs: Vec<String>
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("corange-convert: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+fn parse_arguments() -> Arguments {
+    let mut arguments = Arguments {
+        options: ConvertOptions::default(),
+        output: None,
+        force: false,
+        core: None,
+        inputs: Vec::new()
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--scale" => {
+                let scale = args.next().and_then(|scale| scale.parse::<f32>().ok()).filter(|&scale| scale > 0.0);
+                arguments.options.scale = scale.unwrap_or_else(|| fail("--scale needs a positive factor"));
+            }
+            "--flip" => {
+                let axes = args.next().unwrap_or_else(|| fail("--flip needs axes"));
+                if axes.is_empty() {
+                    fail("--flip needs axes");
+                }
+                for axis in axes.to_lowercase().chars() {
+                    match axis {
+                        'x' => arguments.options.flip[0] = true,
+                        'y' => arguments.options.flip[1] = true,
+                        'z' => arguments.options.flip[2] = true,
+                        _ => fail(&format!("unknown axis '{}' for --flip", axis))
+                    }
+                }
+            }
+            "--z-up" => arguments.options.z_up = true,
+            "--normals" => arguments.options.normals = true,
+            "--tangents" => arguments.options.tangents = true,
+            "--output" => arguments.output = Some(args.next().unwrap_or_else(|| fail("--output needs a path"))),
+            "--force" => arguments.force = true,
+            "--core" => arguments.core = Some(args.next().unwrap_or_else(|| fail("--core needs a path"))),
+            "--help" | "-h" => { println!("{}", USAGE); process::exit(0); }
+            _ if arg.starts_with("--") => fail(&format!("unknown option {}", arg)),
+            _ => arguments.inputs.push(arg)
+        }
+    }
+
+    if arguments.inputs.is_empty() {
+        fail("no input given");
+    }
+    if arguments.output.is_some() && (arguments.inputs.len() != 1 || !Path::new(&arguments.inputs[0]).is_file()) {
+        fail("--output can only be used with a single input file");
+    }
+    arguments
+}
+
+/// Every convertible mesh below `folder`
+fn find_meshes(folder: &Path, out: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(err) => { eprintln!("{}: {}", folder.display(), err); return; }
+    };
+    let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            find_meshes(&path, out);
+        } else if mesh_conversion::is_source(&path) {
+            out.push(path);
+        }
+    }
+}
+
+fn main() {
+    let arguments = parse_arguments();
+
+    let mut jobs: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for input in &arguments.inputs {
+        let path = Path::new(input);
+        if path.is_dir() {
+            let mut found = Vec::new();
+            find_meshes(path, &mut found);
+            jobs.extend(found.into_iter().map(|path| { let output = path.with_extension("bmf"); (path, output) }));
+        } else {
+            let output = arguments.output.as_ref().map_or(path.with_extension("bmf"), PathBuf::from);
+            jobs.push((path.to_path_buf(), output));
+        }
+    }
+
+    // Meshes are read and written without vertex buffers, so there is no window or GL context
+    let core = match arguments.core {
+        Some(ref core) => format!("{}/", core.trim_end_matches('/')),
+        None => match core_assets::mount() {
+            Ok(folder) => folder.to_string(),
+            Err(err) => { eprintln!("Failed to mount the core assets: {}", err); process::exit(1); }
+        }
+    };
+    unsafe {
+        let (variable, core) = (CString::new("$CORANGE").unwrap(), CString::new(core).unwrap());
+        asset_init();
+        asset_add_path_variable(P(variable.as_ptr()), P(core.as_ptr()));
+    }
+
+    let mut failed = 0;
+    for (input, output) in jobs {
+        if output.exists() && !arguments.force {
+            println!("{}: skipped, {} exists", input.display(), output.display());
+            continue;
+        }
+        match mesh_conversion::convert(&input.to_string_lossy(), &output.to_string_lossy(), &arguments.options) {
+            Ok(report) => {
+                println!("{}: {} surfaces, {} vertices, {} triangles{} -> {}",
+                    input.display(), report.surfaces.len(), report.vertices(), report.triangles(),
+                    if report.rigged { ", rigged" } else { "" }, output.display());
+                for (i, surface) in report.surfaces.iter().enumerate() {
+                    println!("  surface {}: {} vertices, {} triangles", i, surface.vertices, surface.triangles);
+                }
+                if !Path::new(&report.material).is_file() {
+                    println!("  warning: material {} isn't a file, the .bmf won't find it", report.material);
+                }
+            }
+            Err(err) => { eprintln!("{}", err); failed += 1; }
+        }
+    }
+
+    if failed > 0 {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,135 @@
+extern crate corange_rs;
+
+use std::env;
+use std::ffi::CString;
+use std::fs;
+use std::process;
+use corange_rs::core_assets;
+use corange_rs::corange::*;
+use corange_rs::dependencies::{short_path, Graph};
+
+const USAGE: &'static str = "\
+Usage: corange-deps [options] <folder>...
+
+Reads every asset below the given folders and the files they reference, and
+lists references to missing files, files nothing uses and circular
+references. Paths resolve like they do in the game: $CORANGE is the core
+assets and relative paths start from the folder the game runs in.
+
+Options:
+  --from <folder>     the folder the game runs in, the current one by default
+  --root <file>       a texture, shader or material the game loads by name, so
+                      it isn't unused, can be given several times
+  --dot <file>        write the references as a Graphviz dot graph
+  --strict            fail on unused files too
+  --core <path>       read the core assets from this folder instead of the
+                      copy compiled into the tool
+
+Exits with 1 on missing files, circular references and files that can't be
+read.";
+
+struct Arguments {
+    from: Option<String>,
+    roots: Vec<String>,
+    dot: Option<String>,
+    strict: bool,
+    core: Option<String>,
+    folders: Vec<String>
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("corange-deps: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+fn parse_arguments() -> Arguments {
+    let mut arguments = Arguments {
+        from: None,
+        roots: Vec::new(),
+        dot: None,
+        strict: false,
+        core: None,
+        folders: Vec::new()
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => arguments.from = Some(args.next().unwrap_or_else(|| fail("--from needs a folder"))),
+            "--root" => arguments.roots.push(args.next().unwrap_or_else(|| fail("--root needs a file"))),
+            "--dot" => arguments.dot = Some(args.next().unwrap_or_else(|| fail("--dot needs a path"))),
+            "--strict" => arguments.strict = true,
+            "--core" => arguments.core = Some(args.next().unwrap_or_else(|| fail("--core needs a path"))),
+            "--help" | "-h" => { println!("{}", USAGE); process::exit(0); }
+            _ if arg.starts_with("--") => fail(&format!("unknown option {}", arg)),
+            _ => arguments.folders.push(arg)
+        }
+    }
+
+    if arguments.folders.is_empty() {
+        fail("no folder given");
+    }
+    arguments
+}
+
+fn main() {
+    let arguments = parse_arguments();
+
+    // Only the asset paths are needed, so there is no window or GL context
+    let core = match arguments.core {
+        Some(ref core) => format!("{}/", core.trim_end_matches('/')),
+        None => match core_assets::mount() {
+            Ok(folder) => folder.to_string(),
+            Err(err) => { eprintln!("Failed to mount the core assets: {}", err); process::exit(1); }
+        }
+    };
+    if let Some(ref from) = arguments.from {
+        if let Err(err) = env::set_current_dir(from) {
+            eprintln!("{}: {}", from, err);
+            process::exit(1);
+        }
+    }
+    unsafe {
+        let (variable, core) = (CString::new("$CORANGE").unwrap(), CString::new(core).unwrap());
+        asset_init();
+        asset_add_path_variable(P(variable.as_ptr()), P(core.as_ptr()));
+    }
+
+    let folders: Vec<&str> = arguments.folders.iter().map(|folder| folder.as_str()).collect();
+    let graph = match Graph::scan(&folders) {
+        Ok(graph) => graph,
+        Err(err) => { eprintln!("{}", err); process::exit(1); }
+    };
+
+    for err in &graph.errors {
+        println!("{}", err);
+    }
+    let missing = graph.missing();
+    for &(path, reference) in &missing {
+        println!("{}: {} doesn't exist", short_path(path), reference.written);
+    }
+    let cycles = graph.cycles();
+    for cycle in &cycles {
+        let mut names: Vec<String> = cycle.iter().map(|path| short_path(path)).collect();
+        names.push(names[0].clone());
+        println!("circular: {}", names.join(" -> "));
+    }
+    let roots: Vec<&str> = arguments.roots.iter().map(|root| root.as_str()).collect();
+    let unused = graph.unused(&roots);
+    for path in &unused {
+        println!("{}: unused", short_path(path));
+    }
+    println!("{} files, {} unreadable, {} missing, {} circular, {} unused",
+        graph.assets.len(), graph.errors.len(), missing.len(), cycles.len(), unused.len());
+
+    if let Some(ref dot) = arguments.dot {
+        if let Err(err) = fs::write(dot, graph.to_dot()) {
+            eprintln!("{}: {}", dot, err);
+            process::exit(1);
+        }
+    }
+
+    if !graph.errors.is_empty() || !missing.is_empty() || !cycles.is_empty() || (arguments.strict && !unused.is_empty()) {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,102 @@
+extern crate corange_rs;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+use corange_rs::archive;
+use corange_rs::archive::Archive;
+
+const USAGE: &'static str = "\
+Usage: corange-pack [options] <folder> <archive>
+       corange-pack --list <archive>
+
+Packs every file below <folder> into a zip archive, named by their path
+relative to the folder. Mount the archive over the folder with
+archive::mount to load the assets from it.
+
+Options:
+  --exclude <suffix>  leave out files ending with suffix (e.g. --exclude .blend),
+                      can be given several times
+  --force             overwrite an existing archive
+  --list              list the files in an archive";
+
+struct Arguments {
+    exclude: Vec<String>,
+    force: bool,
+    list: bool,
+    inputs: Vec<String>
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("corange-pack: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+fn parse_arguments() -> Arguments {
+    let mut arguments = Arguments {
+        exclude: Vec::new(),
+        force: false,
+        list: false,
+        inputs: Vec::new()
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--exclude" => arguments.exclude.push(args.next().unwrap_or_else(|| fail("--exclude needs a suffix"))),
+            "--force" => arguments.force = true,
+            "--list" => arguments.list = true,
+            "--help" | "-h" => { println!("{}", USAGE); process::exit(0); }
+            _ if arg.starts_with("--") => fail(&format!("unknown option {}", arg)),
+            _ => arguments.inputs.push(arg)
+        }
+    }
+
+    match (arguments.list, arguments.inputs.len()) {
+        (true, 1) | (false, 2) => arguments,
+        (true, _) => fail("--list needs a single archive"),
+        (false, _) => fail("expected a folder and an archive")
+    }
+}
+
+fn list(filename: &str) -> Result<(), String> {
+    let archive = Archive::open(filename)?;
+    for entry in archive.entries.iter().filter(|entry| !entry.name.ends_with('/')) {
+        println!("{:>10}  {}", entry.size, entry.name);
+    }
+    println!("{} files", archive.files().len());
+    Ok(())
+}
+
+fn pack(arguments: &Arguments) -> Result<(), String> {
+    let (folder, output) = (Path::new(&arguments.inputs[0]), Path::new(&arguments.inputs[1]));
+    if !folder.is_dir() {
+        return Err(format!("{} isn't a folder", folder.display()));
+    }
+    if output.exists() && !arguments.force {
+        return Err(format!("{} exists, use --force to overwrite it", output.display()));
+    }
+
+    // An archive written inside the folder would pack the previous version of itself
+    let existing = fs::canonicalize(output).ok();
+    let mut files = archive::read_folder(folder)?;
+    files.retain(|(name, _)| {
+        !arguments.exclude.iter().any(|suffix| name.ends_with(suffix.as_str())) &&
+        existing.as_ref().map_or(true, |existing| fs::canonicalize(folder.join(name)).ok().as_ref() != Some(existing))
+    });
+
+    let bytes = archive::write(&files)?;
+    fs::write(output, &bytes).map_err(|err| format!("{}: {}", output.display(), err))?;
+    println!("{}: {} files, {} bytes -> {}", folder.display(), files.len(), bytes.len(), output.display());
+    Ok(())
+}
+
+fn main() {
+    let arguments = parse_arguments();
+    let result = if arguments.list { list(&arguments.inputs[0]) } else { pack(&arguments) };
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
@@ -1,7 +1,40 @@
+use std::env;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Every file below `folder`, sorted
+fn find_files(folder: &Path, out: &mut Vec<PathBuf>) {
+    let mut paths: Vec<PathBuf> = fs::read_dir(folder)
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", folder.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            find_files(&path, out);
+        } else {
+            out.push(path);
+        }
+    }
+}
+
+/// Write `assets_core.rs`, listing the core assets with their contents for `core_assets`
+fn embed_core_assets(out_dir: &str) {
+    let core = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("src/corange/assets_core");
+    let mut files = Vec::new();
+    find_files(&core, &mut files);
+
+    let mut source = String::from("const FILES: &'static [(&'static str, &'static [u8])] = &[\n");
+    for path in files {
+        let name = path.strip_prefix(&core).unwrap().to_string_lossy().replace('\\', "/");
+        source.push_str(&format!("    ({:?}, include_bytes!({:?})),\n", name, path.to_string_lossy()));
+    }
+    source.push_str("];\n");
+    fs::write(Path::new(out_dir).join("assets_core.rs"), source)
+        .unwrap_or_else(|e| panic!("failed to write assets_core.rs: {}", e));
+}
+
 fn main() {
     // Make Corange
     let make_output =
@@ -26,23 +59,8 @@ fn main() {
         .ok()
         .expect("Failed to move file");
 
-    // // Copy required assets to the cargo target directory
-    // let copy_assets_output =
-    //     Command::new("cp")
-    //         .arg("-r")
-    //         .arg("./corange/assets_core")
-    //         .arg(Path::new(out_dir).join("assets_core"))
-    //         .output()
-    //         .unwrap_or_else(|e| {
-    //             panic!("failed to execute make: {}", e)
-    //         });
-    //
-    // if !copy_assets_output.status.success() {
-    //     panic!("asset copy failed with error {}:\n{}\n{}",
-    //         copy_assets_output.status.code().unwrap(),
-    //         String::from_utf8_lossy(&copy_assets_output.stdout),
-    //         String::from_utf8_lossy(&copy_assets_output.stderr));
-    // }
+    // Compile the core assets into the library
+    embed_core_assets(out_dir);
 
     // Export rustc flags
     println!("cargo:rustc-flags=-l GL");
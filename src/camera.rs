@@ -1,10 +1,149 @@
+use std::slice;
+use std::sync::{Arc, Mutex};
+use libc::c_int;
+use sdl2_sys::event::SDL_Event;
+use sdl2_sys::keyboard::SDL_GetKeyboardState;
+use sdl2_sys::mouse::{SDL_GetMouseState, SDL_GetRelativeMouseState, SDL_BUTTON_LMASK};
+use sdl2_sys::scancode::*;
+use corange;
 use corange::*;
 
+/// Keyboard and mouse state sampled once at the start of every frame
+#[derive(Clone)]
+pub struct Input {
+    pub keyboard: Vec<u8>,
+    pub mouse_x: i32,
+    pub mouse_y: i32,
+    pub mouse_dx: i32,
+    pub mouse_dy: i32,
+    pub mouse_buttons: u32
+}
+
+impl Input {
+    pub fn capture() -> Input {
+        unsafe {
+            let mut keys: c_int = 0;
+            let state = SDL_GetKeyboardState(&mut keys);
+            let (mut x, mut y, mut dx, mut dy) = (0, 0, 0, 0);
+            let buttons = SDL_GetMouseState(&mut x, &mut y);
+            SDL_GetRelativeMouseState(&mut dx, &mut dy);
+            Input {
+                keyboard: slice::from_raw_parts(state, keys as usize).to_vec(),
+                mouse_x: x,
+                mouse_y: y,
+                mouse_dx: dx,
+                mouse_dy: dy,
+                mouse_buttons: buttons
+            }
+        }
+    }
+
+    pub fn key(&self, scancode: SDL_Scancode) -> bool {
+        self.keyboard.get(scancode as usize).map_or(false, |&state| state != 0)
+    }
+
+    pub fn button(&self, mask: u32) -> bool {
+        self.mouse_buttons & mask != 0
+    }
+}
+
+/// Moves a camera from user input. `event` sees every SDL event, `update` runs once per frame.
+pub trait CameraController: Send {
+    fn event(&mut self, _camera: *mut corange::camera, _event: SDL_Event) {}
+    fn update(&mut self, camera: *mut corange::camera, input: &Input, timestep: f32);
+}
+
+/// Rotate around the target while the left mouse button is held, zoom with the wheel
+pub struct OrbitController;
+
+impl CameraController for OrbitController {
+    fn event(&mut self, camera: *mut corange::camera, event: SDL_Event) {
+        unsafe { camera_control_orbit(camera, event) }
+    }
+
+    fn update(&mut self, _camera: *mut corange::camera, _input: &Input, _timestep: f32) {}
+}
+
+/// Fly with WASD and look around while the left mouse button is held
+pub struct FreeController {
+    pub speed: f32,
+    pub sensitivity: f32
+}
+
+impl Default for FreeController {
+    fn default() -> FreeController {
+        FreeController {
+            speed: 100.0,
+            sensitivity: 0.005
+        }
+    }
+}
+
+impl CameraController for FreeController {
+    fn update(&mut self, camera: *mut corange::camera, input: &Input, timestep: f32) {
+        unsafe {
+            let up = vec3_new(0.0, 1.0, 0.0);
+            let mut direction = vec3_normalize(vec3_sub((*camera).target, (*camera).position));
+            let side = vec3_normalize(vec3_cross(direction, up));
+
+            let mut movement = vec3_zero();
+            if input.key(SDL_SCANCODE_W) { movement = vec3_add(movement, direction); }
+            if input.key(SDL_SCANCODE_S) { movement = vec3_sub(movement, direction); }
+            if input.key(SDL_SCANCODE_D) { movement = vec3_add(movement, side); }
+            if input.key(SDL_SCANCODE_A) { movement = vec3_sub(movement, side); }
+
+            movement = vec3_mul(movement, self.speed * timestep);
+            (*camera).position = vec3_add((*camera).position, movement);
+            (*camera).target = vec3_add((*camera).target, movement);
+
+            if input.button(SDL_BUTTON_LMASK) {
+                let yaw = -(input.mouse_dx as f32) * self.sensitivity;
+                let pitch = (input.mouse_dy as f32) * self.sensitivity;
+                direction.y -= pitch;
+                let side = vec3_normalize(vec3_cross(direction, up));
+                direction = vec3_normalize(vec3_add(direction, vec3_mul(side, -yaw)));
+                (*camera).target = vec3_add((*camera).position, direction);
+            }
+        }
+    }
+}
+
+/// Rotate around the target with the first joystick's left stick
+pub struct JoystickOrbitController;
+
+impl CameraController for JoystickOrbitController {
+    fn update(&mut self, camera: *mut corange::camera, _input: &Input, timestep: f32) {
+        unsafe { camera_control_joyorbit(camera, timestep) }
+    }
+}
+
 #[derive(Clone)]
 pub enum CameraType {
     Manual,
     Orbit,
     Free,
+    JoystickOrbit,
+    Custom(Arc<Mutex<CameraController>>)
+}
+
+impl CameraType {
+    /// Controller driving the camera, `None` for manually positioned cameras
+    pub fn controller(&self) -> Option<Arc<Mutex<CameraController>>> {
+        match *self {
+            CameraType::Manual => None,
+            CameraType::Orbit => Some(Arc::new(Mutex::new(OrbitController))),
+            CameraType::Free => Some(Arc::new(Mutex::new(FreeController::default()))),
+            CameraType::JoystickOrbit => Some(Arc::new(Mutex::new(JoystickOrbitController))),
+            CameraType::Custom(ref controller) => Some(controller.clone())
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    Perspective,
+    /// Parallel projection showing this many world units either side of the center horizontally
+    Orthographic(f32)
 }
 
 #[derive(Clone)]
@@ -14,6 +153,8 @@ pub struct Camera {
     pub fov: f32,
     pub near_clip: f32,
     pub far_clip: f32,
+    pub roll: f32,
+    pub projection: Projection,
     pub movement: CameraType,
     pub frame: u64
 }
@@ -28,6 +169,8 @@ impl Default for Camera {
                 far_clip: 512.0,
                 near_clip: 0.10,
                 fov:  0.78,
+                roll: 0.0,
+                projection: Projection::Perspective,
                 frame: 0
             }
         }
@@ -35,13 +178,47 @@ impl Default for Camera {
 }
 
 impl Camera {
+    /// Orthographic camera looking down the z axis at the xy plane, for 2D scenes
+    pub fn orthographic_2d(x: f32, y: f32, width: f32) -> Camera {
+        unsafe {
+            Camera {
+                movement: CameraType::Manual,
+                position: vec3_new(x, y, 100.0),
+                target: vec3_new(x, y, 0.0),
+                far_clip: 200.0,
+                near_clip: 0.10,
+                projection: Projection::Orthographic(width),
+                ..Camera::default()
+            }
+        }
+    }
+
+    /// Orthographic camera looking straight down at `center`, for top-down views
+    pub fn orthographic_top_down(center: vec3, width: f32, height: f32) -> Camera {
+        unsafe {
+            Camera {
+                movement: CameraType::Manual,
+                position: vec3_add(center, vec3_new(0.0, height, 0.0)),
+                target: center,
+                far_clip: height * 2.0,
+                near_clip: 0.10,
+                projection: Projection::Orthographic(width),
+                ..Camera::default()
+            }
+        }
+    }
+
     pub fn apply(self, camera:*mut camera) {
         unsafe {
             (*camera).position = self.position;
             (*camera).target = self.target;
             (*camera).far_clip = self.far_clip;
             (*camera).near_clip = self.near_clip;
-            (*camera).fov = self.fov;
+            (*camera).roll = self.roll;
+            match self.projection {
+                Projection::Perspective => camera_set_perspective(camera, self.fov),
+                Projection::Orthographic(width) => camera_set_orthographic(camera, width)
+            }
         }
     }
 }
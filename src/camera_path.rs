@@ -0,0 +1,277 @@
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+use corange;
+use corange::*;
+use camera::{CameraController, Input};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interpolation {
+    /// Smooth curve passing through every key
+    CatmullRom,
+    /// Cubic segments of four keys sharing their end keys, inner keys act as control points
+    Bezier
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    In,
+    Out,
+    InOut
+}
+
+impl Easing {
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::In => t * t,
+            Easing::Out => t * (2.0 - t),
+            Easing::InOut => t * t * (3.0 - 2.0 * t)
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Easing::Linear => "linear",
+            Easing::In => "in",
+            Easing::Out => "out",
+            Easing::InOut => "in_out"
+        }
+    }
+
+    fn parse(name: &str) -> Option<Easing> {
+        match name {
+            "linear" => Some(Easing::Linear),
+            "in" => Some(Easing::In),
+            "out" => Some(Easing::Out),
+            "in_out" => Some(Easing::InOut),
+            _ => None
+        }
+    }
+}
+
+/// Camera state at a point in time. Easing applies to the segment leaving this key.
+#[derive(Clone, Copy)]
+pub struct CameraKey {
+    pub time: f32,
+    pub position: vec3,
+    pub target: vec3,
+    pub roll: f32,
+    pub easing: Easing
+}
+
+/// Camera rail replayed through the camera controller interface
+#[derive(Clone)]
+pub struct CameraPath {
+    pub interpolation: Interpolation,
+    pub keys: Vec<CameraKey>,
+    pub looping: bool,
+    pub playing: bool,
+    pub time: f32,
+    /// Advance by this amount every frame instead of the frame time, for reproducible runs
+    pub fixed_timestep: Option<f32>
+}
+
+fn vec3_of(x: f32, y: f32, z: f32) -> vec3 {
+    vec3 { x: x, y: y, z: z }
+}
+
+fn catmull_rom(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * ((2.0 * p1) + (-p0 + p2) * t + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2 + (-p0 + 3.0 * p1 - 3.0 * p2 + p3) * t3)
+}
+
+fn bezier(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    let u = 1.0 - t;
+    u * u * u * p0 + 3.0 * u * u * t * p1 + 3.0 * u * t * t * p2 + t * t * t * p3
+}
+
+fn curve(f: fn(f32, f32, f32, f32, f32) -> f32, p: [vec3; 4], t: f32) -> vec3 {
+    vec3_of(
+        f(p[0].x, p[1].x, p[2].x, p[3].x, t),
+        f(p[0].y, p[1].y, p[2].y, p[3].y, t),
+        f(p[0].z, p[1].z, p[2].z, p[3].z, t))
+}
+
+fn invalid(line: usize, message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("camera path line {}: {}", line, message))
+}
+
+impl Default for CameraPath {
+    fn default() -> CameraPath {
+        CameraPath {
+            interpolation: Interpolation::CatmullRom,
+            keys: Vec::new(),
+            looping: false,
+            playing: true,
+            time: 0.0,
+            fixed_timestep: None
+        }
+    }
+}
+
+impl CameraPath {
+    pub fn new(interpolation: Interpolation) -> CameraPath {
+        CameraPath { interpolation: interpolation, ..CameraPath::default() }
+    }
+
+    /// Insert a key, keeping keys ordered by time
+    pub fn add_key(&mut self, key: CameraKey) {
+        let index = self.keys.iter().position(|k| k.time > key.time).unwrap_or(self.keys.len());
+        self.keys.insert(index, key);
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keys.last().map_or(0.0, |key| key.time)
+    }
+
+    pub fn finished(&self) -> bool {
+        !self.looping && self.time >= self.duration()
+    }
+
+    /// Position, target and roll of the camera at `time`
+    pub fn evaluate(&self, time: f32) -> Option<(vec3, vec3, f32)> {
+        let stride = match self.interpolation {
+            Interpolation::CatmullRom => 1,
+            Interpolation::Bezier => 3
+        };
+
+        let anchors: Vec<usize> = (0..self.keys.len()).filter(|i| i % stride == 0).collect();
+        let first = match anchors.first() { Some(&first) => first, None => return None };
+        if anchors.len() == 1 || time <= self.keys[first].time {
+            let key = self.keys[first];
+            return Some((key.position, key.target, key.roll));
+        }
+
+        let segment = anchors.windows(2)
+            .position(|w| time < self.keys[w[1]].time)
+            .unwrap_or(anchors.len() - 2);
+        let (i0, i1) = (anchors[segment], anchors[segment + 1]);
+        let (k0, k1) = (self.keys[i0], self.keys[i1]);
+
+        let span = k1.time - k0.time;
+        let t = if span > 0.0 { ((time - k0.time) / span).max(0.0).min(1.0) } else { 1.0 };
+        let t = k0.easing.apply(t);
+
+        let (position, target) = match self.interpolation {
+            Interpolation::CatmullRom => {
+                let before = self.keys[if i0 > 0 { i0 - 1 } else { i0 }];
+                let after = self.keys[if i1 + 1 < self.keys.len() { i1 + 1 } else { i1 }];
+                (curve(catmull_rom, [before.position, k0.position, k1.position, after.position], t),
+                 curve(catmull_rom, [before.target, k0.target, k1.target, after.target], t))
+            }
+            Interpolation::Bezier => {
+                let (c0, c1) = (self.keys[i0 + 1], self.keys[i0 + 2]);
+                (curve(bezier, [k0.position, c0.position, c1.position, k1.position], t),
+                 curve(bezier, [k0.target, c0.target, c1.target, k1.target], t))
+            }
+        };
+
+        Some((position, target, k0.roll + (k1.roll - k0.roll) * t))
+    }
+
+    /// Text format: an `interpolation` line, a `looping` line and one
+    /// `key time px py pz tx ty tz roll easing` line per key in time order. `#` starts a comment.
+    /// Bezier paths need 3n+1 keys.
+    pub fn parse(text: &str) -> io::Result<CameraPath> {
+        let mut path = CameraPath::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.first() {
+                None => continue,
+                Some(&"interpolation") => {
+                    path.interpolation = match words.get(1) {
+                        Some(&"catmull_rom") => Interpolation::CatmullRom,
+                        Some(&"bezier") => Interpolation::Bezier,
+                        _ => return Err(invalid(number + 1, "expected catmull_rom or bezier"))
+                    };
+                }
+                Some(&"looping") => {
+                    path.looping = match words.get(1) {
+                        Some(&"true") => true,
+                        Some(&"false") => false,
+                        _ => return Err(invalid(number + 1, "expected true or false"))
+                    };
+                }
+                Some(&"key") => {
+                    if words.len() != 10 {
+                        return Err(invalid(number + 1, "expected time, position, target, roll and easing"));
+                    }
+                    let mut values = [0.0f32; 8];
+                    for (value, word) in values.iter_mut().zip(&words[1..9]) {
+                        *value = word.parse().map_err(|_| invalid(number + 1, "expected a number"))?;
+                    }
+                    let easing = Easing::parse(words[9]).ok_or_else(|| invalid(number + 1, "unknown easing"))?;
+                    if path.keys.last().map_or(false, |key| values[0] < key.time) {
+                        return Err(invalid(number + 1, "key is earlier than the one before"));
+                    }
+                    path.keys.push(CameraKey {
+                        time: values[0],
+                        position: vec3_of(values[1], values[2], values[3]),
+                        target: vec3_of(values[4], values[5], values[6]),
+                        roll: values[7],
+                        easing: easing
+                    });
+                }
+                Some(word) => return Err(invalid(number + 1, &format!("unknown entry '{}'", word)))
+            }
+        }
+        if path.interpolation == Interpolation::Bezier && !path.keys.is_empty() && path.keys.len() % 3 != 1 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                format!("camera path: bezier paths need 3n+1 keys, found {}", path.keys.len())));
+        }
+        Ok(path)
+    }
+
+    pub fn load(filename: &str) -> io::Result<CameraPath> {
+        let mut text = String::new();
+        File::open(filename)?.read_to_string(&mut text)?;
+        CameraPath::parse(&text)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        text.push_str(match self.interpolation {
+            Interpolation::CatmullRom => "interpolation catmull_rom\n",
+            Interpolation::Bezier => "interpolation bezier\n"
+        });
+        text.push_str(&format!("looping {}\n", self.looping));
+        for key in &self.keys {
+            text.push_str(&format!("key {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {}\n",
+                key.time,
+                key.position.x, key.position.y, key.position.z,
+                key.target.x, key.target.y, key.target.z,
+                key.roll, key.easing.name()));
+        }
+        text
+    }
+
+    pub fn save(&self, filename: &str) -> io::Result<()> {
+        File::create(filename)?.write_all(self.to_text().as_bytes())
+    }
+}
+
+impl CameraController for CameraPath {
+    fn update(&mut self, camera: *mut corange::camera, _input: &Input, timestep: f32) {
+        if self.playing {
+            self.time += self.fixed_timestep.unwrap_or(timestep);
+            let duration = self.duration();
+            if self.looping && duration > 0.0 {
+                self.time %= duration;
+            } else {
+                self.time = self.time.min(duration);
+            }
+        }
+
+        if let Some((position, target, roll)) = self.evaluate(self.time) {
+            unsafe {
+                (*camera).position = position;
+                (*camera).target = target;
+                (*camera).roll = roll;
+            }
+        }
+    }
+}
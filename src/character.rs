@@ -0,0 +1,255 @@
+use libc::c_void;
+use corange::*;
+
+/// Largest gap between the ellipsoid and the ground at which it still counts as standing
+const GROUND_DISTANCE: f32 = 0.01;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Pass {
+    Up,
+    Forward,
+    Down
+}
+
+/// State shared with the collision callback while sliding
+struct Sweep {
+    controller: *const CharacterController,
+    pass: Pass,
+    ground: Option<vec3>,
+    steep: Option<vec3>,
+    ceiling: bool
+}
+
+fn max_component(v: vec3) -> f32 {
+    v.x.abs().max(v.y.abs()).max(v.z.abs())
+}
+
+unsafe extern "C" fn sweep_collide(x: *mut c_void, position: *mut vec3, movement: *mut vec3) -> collision {
+    let sweep = &mut *(x as *mut Sweep);
+    let controller = &*sweep.controller;
+    let e = ellipsoid_new(*position, controller.radiuses);
+    let reach = vec3_length(*movement) + max_component(controller.radiuses);
+
+    let mut hit = collision_none();
+    for &object in &controller.colliders {
+        if asset_hndl_isnull(&mut (*object).collision_body) != 0 { continue; }
+        let mesh = asset_hndl_ptr(&mut (*object).collision_body) as *mut cmesh;
+        let world = static_object_world(object);
+        let bound = cmesh_bound(mesh);
+        let center = mat4_mul_vec3(world, bound.center);
+        if vec3_dist(center, *position) > bound.radius * max_component((*object).scale) + reach { continue; }
+        hit = collision_merge(hit, ellipsoid_collide_mesh(e, *movement, mesh, world, static_object_world_normal(object)));
+    }
+    for &s in &controller.spheres {
+        hit = collision_merge(hit, ellipsoid_collide_sphere(e, *movement, s));
+    }
+    if hit.collided == 0 { return hit; }
+
+    let walkable = hit.norm.y >= controller.slope_limit.cos();
+    match sweep.pass {
+        Pass::Up => {
+            if hit.norm.y < 0.0 { sweep.ceiling = true; }
+        }
+        Pass::Forward => {
+            // Steep slopes act as walls instead of ramps
+            if !walkable && hit.norm.y > 0.0 {
+                hit.norm = vec3_normalize(vec3_new(hit.norm.x, 0.0, hit.norm.z));
+            }
+        }
+        Pass::Down => {
+            if walkable {
+                // Stand still on walkable ground rather than sliding down it
+                if sweep.ground.map_or(true, |ground| hit.norm.y > ground.y) { sweep.ground = Some(hit.norm); }
+                hit.norm = vec3_new(0.0, 1.0, 0.0);
+            } else if hit.norm.y > 0.0 {
+                sweep.steep = Some(hit.norm);
+            } else {
+                sweep.ceiling = true;
+            }
+        }
+    }
+    hit
+}
+
+/// Walking character shaped as an ellipsoid. Sweeps against static collision meshes,
+/// spheres and a landscape, sliding along whatever it hits. Nothing is rendered, copy
+/// `position` to an entity to show the character.
+pub struct CharacterController {
+    /// Center of the ellipsoid
+    pub position: vec3,
+    pub radiuses: vec3,
+    /// Velocity from gravity and jumping, walking is added on top
+    pub velocity: vec3,
+    pub gravity: vec3,
+    pub walk_speed: f32,
+    pub jump_speed: f32,
+    /// Tallest ledge climbed without jumping
+    pub step_height: f32,
+    /// Steepest walkable slope in radians
+    pub slope_limit: f32,
+    /// Static objects whose collision bodies block the character
+    pub colliders: Vec<*mut static_object>,
+    pub spheres: Vec<sphere>,
+    pub landscape: Option<*mut landscape>,
+    grounded: bool,
+    ground_normal: vec3
+}
+
+impl CharacterController {
+    pub fn new(position: vec3, radiuses: vec3) -> CharacterController {
+        unsafe {
+            CharacterController {
+                position: position,
+                radiuses: radiuses,
+                velocity: vec3_zero(),
+                gravity: vec3_gravity(),
+                walk_speed: 5.0,
+                jump_speed: 5.0,
+                step_height: 0.3,
+                slope_limit: 0.8,
+                colliders: Vec::new(),
+                spheres: Vec::new(),
+                landscape: None,
+                grounded: false,
+                ground_normal: vec3_new(0.0, 1.0, 0.0)
+            }
+        }
+    }
+
+    pub fn add_collider(&mut self, object: *mut static_object) {
+        self.colliders.push(object);
+    }
+
+    pub fn grounded(&self) -> bool {
+        self.grounded
+    }
+
+    /// Normal of the ground stood on, straight up while airborne
+    pub fn ground_normal(&self) -> vec3 {
+        self.ground_normal
+    }
+
+    /// Leave the ground at `jump_speed`, returns false while airborne
+    pub fn jump(&mut self) -> bool {
+        if !self.grounded { return false; }
+        self.velocity.y = self.jump_speed;
+        self.grounded = false;
+        true
+    }
+
+    /// Move `movement`, a horizontal direction with a length up to one, scaled by the walking speed
+    pub fn update(&mut self, movement: vec3, timestep: f32) {
+        unsafe {
+            let mut walk = vec3_new(movement.x, 0.0, movement.z);
+            if vec3_length(walk) > 1.0 { walk = vec3_normalize(walk); }
+            walk = vec3_mul(walk, self.walk_speed);
+
+            if !self.grounded {
+                self.velocity = vec3_add(self.velocity, vec3_mul(self.gravity, timestep));
+            }
+            let displacement = vec3_mul(vec3_add(walk, self.velocity), timestep);
+            let stepping = self.grounded && displacement.y <= 0.0;
+
+            // Lift by the step height so low ledges are walked over
+            let mut risen = 0.0;
+            if stepping && self.step_height > 0.0 {
+                let before = self.position.y;
+                self.slide(Pass::Up, vec3_new(0.0, self.step_height, 0.0));
+                risen = self.position.y - before;
+            }
+
+            // Walk, refusing to climb landscape steeper than the slope limit
+            let before = self.position;
+            self.slide(Pass::Forward, vec3_new(displacement.x, 0.0, displacement.z));
+            if let Some(normal) = self.landscape_contact() {
+                if normal.y < self.slope_limit.cos() && self.position.y > before.y {
+                    self.position = before;
+                }
+            }
+
+            // Settle back down, following the ground when walking downhill
+            let probe = if displacement.y <= 0.0 { GROUND_DISTANCE } else { 0.0 };
+            let (mut ground, mut steep, ceiling) = self.vertical(displacement.y - risen - probe);
+            if stepping && ground.is_none() && self.step_height > 0.0 {
+                let before = self.position;
+                let (snapped, _, _) = self.vertical(-self.step_height);
+                if snapped.is_some() {
+                    ground = snapped;
+                    steep = None;
+                } else {
+                    self.position = before;
+                }
+            }
+
+            self.grounded = ground.is_some() && self.velocity.y <= 0.0;
+            self.ground_normal = ground.unwrap_or(vec3_new(0.0, 1.0, 0.0));
+            if self.grounded {
+                self.velocity = vec3_zero();
+            } else {
+                if ceiling && self.velocity.y > 0.0 {
+                    self.velocity.y = 0.0;
+                }
+                // Slide down slopes too steep to stand on
+                if let Some(normal) = steep {
+                    if vec3_dot(self.velocity, normal) < 0.0 {
+                        self.velocity = vec3_project(self.velocity, normal);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Move along the y axis, returning walkable ground, too steep ground and whether a ceiling was hit
+    fn vertical(&mut self, distance: f32) -> (Option<vec3>, Option<vec3>, bool) {
+        unsafe {
+            let pass = if distance > 0.0 { Pass::Up } else { Pass::Down };
+            let mut sweep = self.slide(pass, vec3_new(0.0, distance, 0.0));
+            if pass == Pass::Down {
+                if let Some(normal) = self.landscape_contact() {
+                    if normal.y >= self.slope_limit.cos() {
+                        if sweep.ground.map_or(true, |ground| normal.y > ground.y) { sweep.ground = Some(normal); }
+                    } else if sweep.ground.is_none() {
+                        sweep.steep = Some(normal);
+                    }
+                }
+            } else {
+                self.landscape_contact();
+            }
+            (sweep.ground, sweep.steep, sweep.ceiling)
+        }
+    }
+
+    fn slide(&mut self, pass: Pass, movement: vec3) -> Sweep {
+        unsafe {
+            let mut sweep = Sweep {
+                controller: self as *const CharacterController,
+                pass: pass,
+                ground: None,
+                steep: None,
+                ceiling: false
+            };
+            if vec3_length(movement) > 1e-6 {
+                let mut position = self.position;
+                let mut movement = movement;
+                collision_response_slide(&mut sweep as *mut Sweep as *mut c_void, &mut position, &mut movement, Some(sweep_collide));
+                self.position = position;
+            }
+            sweep
+        }
+    }
+
+    /// Lift the ellipsoid out of the landscape, returns the landscape normal when standing on it
+    fn landscape_contact(&mut self) -> Option<vec3> {
+        unsafe {
+            let landscape = match self.landscape { Some(landscape) => landscape, None => return None };
+            let at = vec2_new(self.position.x, self.position.z);
+            let height = landscape_height(landscape, at);
+            let bottom = self.position.y - self.radiuses.y;
+            if bottom > height + GROUND_DISTANCE { return None; }
+            if bottom < height {
+                self.position.y = height + self.radiuses.y;
+            }
+            Some(vec3_normalize(landscape_normal(landscape, at)))
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::ffi::CString;
+use std::path::Path;
+use libc::c_char;
+use corange::*;
+use mesh_conversion;
+
+/// Extensions of the registered renderable loaders
+pub const RENDERABLE_EXTENSIONS: &'static [&'static str] = &["bmf", "obj", "smd", "ply", "gltf", "glb"];
+
+/// How `from_renderable` reads triangles back from a renderable
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Source {
+    /// Positions straight from the vertex buffers, works for rigged renderables too
+    Surfaces,
+    /// Through `renderable_to_model`, which rejects rigged renderables
+    Model
+}
+
+#[derive(Clone, Debug)]
+pub struct CollisionOptions {
+    pub source: Source,
+    /// Merge vertices on a grid with this cell size, dropping collapsed triangles
+    pub simplify: Option<f32>,
+    /// Replace the triangles by their convex hull
+    pub convex_hull: bool
+}
+
+impl Default for CollisionOptions {
+    fn default() -> CollisionOptions {
+        CollisionOptions {
+            source: Source::Surfaces,
+            simplify: None,
+            convex_hull: false
+        }
+    }
+}
+
+/// Triangle counts of a renderable and of the collision mesh generated from it
+#[derive(Clone, Copy, Debug)]
+pub struct Report {
+    pub source_triangles: usize,
+    pub triangles: usize
+}
+
+/// Collision mesh for `r`, owned by the caller and freed with `cmesh_delete`.
+/// Needs a GL context since the triangles are read back from the vertex buffers.
+pub fn from_renderable(r: *mut renderable, options: &CollisionOptions) -> *mut cmesh {
+    unsafe {
+        let mesh = match options.source {
+            Source::Surfaces => renderable_to_cmesh(r),
+            Source::Model => {
+                let model = renderable_to_model(r);
+                let mesh = cmesh_from_model(model);
+                model_delete(model);
+                mesh
+            }
+        };
+        finish(mesh, options)
+    }
+}
+
+/// Collision mesh for the meshes of `m`, owned by the caller and freed with `cmesh_delete`
+pub fn from_model(m: *mut model, options: &CollisionOptions) -> *mut cmesh {
+    unsafe { finish(cmesh_from_model(m), options) }
+}
+
+/// Simplify `mesh` or replace it by its hull as asked
+unsafe fn finish(mut mesh: *mut cmesh, options: &CollisionOptions) -> *mut cmesh {
+    if let Some(cell_size) = options.simplify {
+        cmesh_simplify(mesh, cell_size);
+    }
+
+    if options.convex_hull {
+        let hull = cmesh_convex_hull(mesh);
+        cmesh_delete(mesh);
+        mesh = hull;
+    }
+
+    mesh
+}
+
+/// Write `mesh` in the `.col` format read by `col_load_file`
+pub fn save(mesh: *mut cmesh, path: &str) {
+    unsafe {
+        let path = CString::new(path).unwrap();
+        col_save_file(mesh, path.as_ptr() as *mut c_char);
+    }
+}
+
+/// Whether `path` has the extension of a registered renderable loader
+pub fn is_renderable(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map_or(false, |extension| RENDERABLE_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+/// Read the meshes of the renderable at `input`, build their collision mesh and write it to
+/// `output`. Only the mesh half of the loaders runs, so this needs no GL context, doesn't touch
+/// the loaded assets and ignores `options.source`.
+pub fn convert(input: &str, output: &str, options: &CollisionOptions) -> Result<Report, String> {
+    if !Path::new(input).is_file() {
+        return Err(format!("{}: no such file", input));
+    }
+    if !is_renderable(Path::new(input)) {
+        return Err(format!("{}: not a renderable, expected one of {}", input, RENDERABLE_EXTENSIONS.join(", ")));
+    }
+
+    let mut source = mesh_conversion::load_meshes(input)?;
+
+    unsafe {
+        let source_triangles = source.meshes.iter().map(|&m| (*m).num_triangles as usize).sum();
+
+        let mut model = source.model();
+        let mesh = from_model(&mut model, options);
+        let report = Report {
+            source_triangles: source_triangles,
+            triangles: (*mesh).triangles_num as usize
+        };
+        save(mesh, output);
+        cmesh_delete(mesh);
+        Ok(report)
+    }
+}
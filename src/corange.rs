@@ -7,6 +7,7 @@ use gl::types::{GLint, GLuint, GLenum};
 use sdl2_sys::event::SDL_Event;
 use sdl2_sys::video::SDL_GLContext;
 use sdl2_sys::joystick::SDL_Joystick;
+use sdl2_sys::rwops::SDL_RWops;
 
 #[macro_export]
 macro_rules! typeId {
@@ -37,6 +38,8 @@ lazy_static! {
     pub static ref TEXT_TYPE:i32 = unsafe { typeId!(ui_text) };
     pub static ref TEXTBOX_TYPE:i32 = unsafe { typeId!(ui_textbox) };
     pub static ref TOAST_TYPE:i32 = unsafe { typeId!(ui_toast) };
+    pub static ref RENDERABLE_TYPE:i32 = unsafe { typeId!(renderable) };
+    pub static ref TEXTURE_TYPE:i32 = unsafe { typeId!(texture) };
 }
 
 pub const LIGHT_TYPE_POINT: c_uint = 0;
@@ -62,7 +65,15 @@ pub const TEXT_ALIGN_CENTER: c_uint = 2;
 pub const TEXT_ALIGN_TOP: c_uint = 0;
 pub const TEXT_ALIGN_BOTTOM: c_uint = 1;
 pub const RENDERER_MAX_LIGHTS: c_uint = 16;
+pub const RENDERER_MAX_CASCADES: c_uint = 4;
 pub const RENDERER_MAX_DYN_LIGHTS: c_uint = 13;
+pub const RENDERER_MAX_SPOT_SHADOWS: c_uint = 2;
+pub const RENDERER_MAX_POINT_SHADOWS: c_uint = 2;
+pub const RENDERER_MAX_SHADOWS: c_uint = 18;
+pub const SHADOW_SPLIT_MANUAL: c_uint = 0;
+pub const SHADOW_SPLIT_LINEAR: c_uint = 1;
+pub const SHADOW_SPLIT_LOGARITHMIC: c_uint = 2;
+pub const SHADOW_SPLIT_BLENDED: c_uint = 3;
 pub const RO_TYPE_AXIS: c_uint = 0;
 pub const RO_TYPE_STATIC: c_uint = 1;
 pub const RO_TYPE_INSTANCE: c_uint = 2;
@@ -397,35 +408,27 @@ impl Default for ctri {
 
 #[repr(C)]
 #[derive(Copy)]
-pub struct cmesh {
-    pub is_leaf: u8,
-    pub data: [u64; 5usize],
+pub struct cmesh_node {
+    pub min: vec3,
+    pub max: vec3,
+    pub first: c_int,
+    pub count: c_int,
 }
-impl cmesh {
-    pub unsafe fn division(&mut self) -> *mut plane {
-        let raw: *mut u8 = mem::transmute(&self.data);
-        mem::transmute(raw.offset(0))
-    }
-    pub unsafe fn front(&mut self) -> *mut *mut cmesh {
-        let raw: *mut u8 = mem::transmute(&self.data);
-        mem::transmute(raw.offset(24))
-    }
-    pub unsafe fn back(&mut self) -> *mut *mut cmesh {
-        let raw: *mut u8 = mem::transmute(&self.data);
-        mem::transmute(raw.offset(32))
-    }
-    pub unsafe fn triangles(&mut self) -> *mut *mut ctri {
-        let raw: *mut u8 = mem::transmute(&self.data);
-        mem::transmute(raw.offset(0))
-    }
-    pub unsafe fn triangles_num(&mut self) -> *mut c_int {
-        let raw: *mut u8 = mem::transmute(&self.data);
-        mem::transmute(raw.offset(8))
-    }
-    pub unsafe fn bound(&mut self) -> *mut sphere {
-        let raw: *mut u8 = mem::transmute(&self.data);
-        mem::transmute(raw.offset(12))
-    }
+impl Clone for cmesh_node {
+    fn clone(&self) -> Self { *self }
+}
+impl Default for cmesh_node {
+    fn default() -> Self { unsafe { mem::zeroed() } }
+}
+
+#[repr(C)]
+#[derive(Copy)]
+pub struct cmesh {
+    pub triangles: *mut ctri,
+    pub triangles_num: c_int,
+    pub nodes: *mut cmesh_node,
+    pub nodes_num: c_int,
+    pub bound: sphere,
 }
 impl Clone for cmesh {
     fn clone(&self) -> Self { *self }
@@ -460,6 +463,9 @@ pub struct camera {
     pub fov: c_float,
     pub near_clip: c_float,
     pub far_clip: c_float,
+    pub roll: c_float,
+    pub orthographic: u8,
+    pub ortho_width: c_float,
 }
 impl Clone for camera {
     fn clone(&self) -> Self { *self }
@@ -489,6 +495,9 @@ pub struct light {
     pub ortho_height: c_float,
     pub fov: c_float,
     pub aspect_ratio: c_float,
+    pub inner_angle: c_float,
+    pub outer_angle: c_float,
+    pub cookie: asset_hndl,
 }
 impl Clone for light {
     fn clone(&self) -> Self { *self }
@@ -599,6 +608,7 @@ pub struct physics_object {
     pub recieve_shadows: u8,
     pub cast_shadows: u8,
     pub renderable: asset_hndl,
+    pub collision_body: asset_hndl,
 }
 impl Clone for physics_object {
     fn clone(&self) -> Self { *self }
@@ -942,6 +952,14 @@ impl Default for material_item {
     fn default() -> Self { unsafe { mem::zeroed() } }
 }
 
+pub const mat_item_int: c_int = 0;
+pub const mat_item_float: c_int = 1;
+pub const mat_item_vec2: c_int = 2;
+pub const mat_item_vec3: c_int = 3;
+pub const mat_item_vec4: c_int = 4;
+pub const mat_item_shader: c_int = 5;
+pub const mat_item_texture: c_int = 6;
+
 #[repr(C)]
 #[derive(Copy)]
 pub struct material_entry {
@@ -1423,6 +1441,38 @@ impl Default for render_object {
     fn default() -> Self { unsafe { mem::zeroed() } }
 }
 
+#[repr(C)]
+#[derive(Copy)]
+pub struct render_buffers {
+    pub width: c_int,
+    pub height: c_int,
+    pub gfbo: GLuint,
+    pub gdepth_buffer: GLuint,
+    pub gdiffuse_buffer: GLuint,
+    pub gnormals_buffer: GLuint,
+    pub gdiffuse_texture: GLuint,
+    pub gnormals_texture: GLuint,
+    pub gdepth_texture: GLuint,
+    pub ssao_fbo: GLuint,
+    pub ssao_buffer: GLuint,
+    pub ssao_texture: GLuint,
+    pub hdr_fbo: GLuint,
+    pub hdr_buffer: GLuint,
+    pub hdr_texture: GLuint,
+    pub ldr_front_fbo: GLuint,
+    pub ldr_front_buffer: GLuint,
+    pub ldr_front_texture: GLuint,
+    pub ldr_back_fbo: GLuint,
+    pub ldr_back_buffer: GLuint,
+    pub ldr_back_texture: GLuint,
+}
+impl Clone for render_buffers {
+    fn clone(&self) -> Self { *self }
+}
+impl Default for render_buffers {
+    fn default() -> Self { unsafe { mem::zeroed() } }
+}
+
 #[repr(C)]
 #[derive(Copy)]
 pub struct renderer {
@@ -1472,32 +1522,40 @@ pub struct renderer {
     pub tex_white: asset_hndl,
     pub tex_grey: asset_hndl,
     pub tex_skin_lookup: asset_hndl,
-    pub gfbo: GLuint,
-    pub gdepth_buffer: GLuint,
-    pub gdiffuse_buffer: GLuint,
-    pub gnormals_buffer: GLuint,
-    pub gdiffuse_texture: GLuint,
-    pub gnormals_texture: GLuint,
-    pub gdepth_texture: GLuint,
-    pub ssao_fbo: GLuint,
-    pub ssao_buffer: GLuint,
-    pub ssao_texture: GLuint,
-    pub hdr_fbo: GLuint,
-    pub hdr_buffer: GLuint,
-    pub hdr_texture: GLuint,
-    pub ldr_front_fbo: GLuint,
-    pub ldr_front_buffer: GLuint,
-    pub ldr_front_texture: GLuint,
-    pub ldr_back_fbo: GLuint,
-    pub ldr_back_buffer: GLuint,
-    pub ldr_back_texture: GLuint,
-    pub shadows_fbo: [GLuint; 3usize],
-    pub shadows_buffer: [GLuint; 3usize],
-    pub shadows_texture: [GLuint; 3usize],
-    pub shadows_start: [c_float; 3usize],
-    pub shadows_end: [c_float; 3usize],
-    pub shadows_widths: [c_int; 3usize],
-    pub shadows_heights: [c_int; 3usize],
+    pub screen_buffers: render_buffers,
+    pub target_buffers: render_buffers,
+    pub buffers: *mut render_buffers,
+    pub target_fbo: GLuint,
+    pub shadows_fbo: [GLuint; 4usize],
+    pub shadows_buffer: [GLuint; 4usize],
+    pub shadows_texture: [GLuint; 4usize],
+    pub spot_shadows_fbo: [GLuint; 2usize],
+    pub spot_shadows_buffer: [GLuint; 2usize],
+    pub spot_shadows_texture: [GLuint; 2usize],
+    pub point_shadows_fbo: [GLuint; 2usize],
+    pub point_shadows_texture: [GLuint; 2usize],
+    pub shadows_num: c_int,
+    pub shadows_split: c_int,
+    pub shadows_split_blend: c_float,
+    pub shadows_distance: c_float,
+    pub shadows_snap: c_float,
+    pub shadows_bias: c_float,
+    pub shadows_normal_offset: c_float,
+    pub shadows_debug: u8,
+    pub shadows_start: [c_float; 4usize],
+    pub shadows_end: [c_float; 4usize],
+    pub shadows_widths: [c_int; 4usize],
+    pub shadows_heights: [c_int; 4usize],
+    pub spot_shadows_width: c_int,
+    pub spot_shadows_height: c_int,
+    pub point_shadows_size: c_int,
+    pub point_shadows_budget: c_int,
+    pub point_shadows_limit: c_int,
+    pub texture_units: c_int,
+    pub spot_lights_num: c_int,
+    pub spot_light: [*mut light; 2usize],
+    pub point_lights_num: c_int,
+    pub point_light: [*mut light; 2usize],
     pub seed: c_int,
     pub glitch: c_float,
     pub time: c_float,
@@ -1507,20 +1565,25 @@ pub struct renderer {
     pub exposure_target: c_float,
     pub skydome_enabled: u8,
     pub sea_enabled: u8,
+    pub culling_enabled: u8,
+    pub target: *mut texture,
     pub render_objects_num: c_int,
     pub render_objects: *mut render_object,
+    pub culled_num: c_int,
+    pub shadows_culled_num: c_int,
     pub camera_view: mat4,
     pub camera_proj: mat4,
     pub camera_inv_view: mat4,
     pub camera_inv_proj: mat4,
     pub camera_near: c_float,
     pub camera_far: c_float,
+    pub camera_orthographic: u8,
     pub camera_frustum: _box,
-    pub shadow_view: [mat4; 3usize],
-    pub shadow_proj: [mat4; 3usize],
-    pub shadow_near: [c_float; 3usize],
-    pub shadow_far: [c_float; 3usize],
-    pub shadow_frustum: [_box; 3usize],
+    pub shadow_view: [mat4; 18usize],
+    pub shadow_proj: [mat4; 18usize],
+    pub shadow_near: [c_float; 18usize],
+    pub shadow_far: [c_float; 18usize],
+    pub shadow_frustum: [_box; 18usize],
 }
 impl Clone for renderer {
     fn clone(&self) -> Self { *self }
@@ -2068,10 +2131,16 @@ extern "C" {
     pub fn ctri_inside_plane(t: ctri, p: plane) -> u8;
     pub fn ctri_outside_plane(t: ctri, p: plane) -> u8;
     pub fn ctri_intersects_plane(t: ctri, p: plane) -> u8;
-    pub fn col_load_file(filename: *mut c_char) -> *mut cmesh;
+    pub fn cmesh_new() -> *mut cmesh;
+    pub fn cmesh_from_mesh(m: *mut mesh) -> *mut cmesh;
+    pub fn cmesh_from_model(m: *mut model) -> *mut cmesh;
     pub fn cmesh_delete(cm: *mut cmesh) -> ();
+    pub fn col_load_file(filename: *mut c_char) -> *mut cmesh;
+    pub fn col_save_file(cm: *mut cmesh, filename: *mut c_char) -> ();
     pub fn cmesh_bound(cm: *mut cmesh) -> sphere;
-    pub fn cmesh_subdivide(cm: *mut cmesh, iterations: c_int) -> ();
+    pub fn cmesh_simplify(cm: *mut cmesh, cell_size: c_float) -> ();
+    pub fn cmesh_convex_hull(cm: *mut cmesh) -> *mut cmesh;
+    pub fn cmesh_build_bvh(cm: *mut cmesh) -> ();
     pub fn vec3_gravity() -> vec3;
     pub fn quadratic(a: c_float, b: c_float, c: c_float, t0: *mut c_float, t1: *mut c_float) -> u8;
     pub fn collision_none() -> collision;
@@ -2093,6 +2162,9 @@ extern "C" {
     pub fn ellipsoid_collide_mesh(e: ellipsoid, v: vec3, m: *mut cmesh, world: mat4, world_normal: mat3) -> collision;
     pub fn ellipsoid_collide_point(e: ellipsoid, v: vec3, p: vec3) -> collision;
     pub fn ellipsoid_collide_sphere(e: ellipsoid, v: vec3, s: sphere) -> collision;
+    pub fn point_collide_mesh_brute(p: vec3, v: vec3, m: *mut cmesh, world: mat4, world_normal: mat3) -> collision;
+    pub fn sphere_collide_mesh_brute(s: sphere, v: vec3, m: *mut cmesh, world: mat4, world_normal: mat3) -> collision;
+    pub fn ellipsoid_collide_mesh_brute(e: ellipsoid, v: vec3, m: *mut cmesh, world: mat4, world_normal: mat3) -> collision;
     pub fn collision_response_slide(x: *mut c_void, position: *mut vec3, velocity: *mut vec3, colfunc: Option<unsafe extern "C" fn(x: *mut c_void, pos: *mut vec3, vel: *mut vec3) -> collision>) -> ();
     pub fn corange_init(core_assets_path: *const c_char) -> ();
     pub fn corange_finish() -> ();
@@ -2114,7 +2186,10 @@ extern "C" {
     pub fn camera_direction(c: *mut camera) -> vec3;
     pub fn camera_view_matrix(c: *mut camera) -> mat4;
     pub fn camera_proj_matrix(c: *mut camera) -> mat4;
+    pub fn camera_proj_matrix_ratio(c: *mut camera, ratio: c_float) -> mat4;
     pub fn camera_view_proj_matrix(c: *mut camera) -> mat4;
+    pub fn camera_set_perspective(c: *mut camera, fov: c_float) -> ();
+    pub fn camera_set_orthographic(c: *mut camera, width: c_float) -> ();
     pub fn camera_normalize_target(c: *mut camera) -> ();
     pub fn camera_control_orbit(c: *mut camera, e: SDL_Event) -> ();
     pub fn camera_control_freecam(c: *mut camera, timestep: c_float) -> ();
@@ -2124,6 +2199,8 @@ extern "C" {
     pub fn light_new_type(position: vec3, _type: c_int) -> *mut light;
     pub fn light_delete(l: *mut light) -> ();
     pub fn light_set_type(l: *mut light, _type: c_int) -> ();
+    pub fn light_set_cone(l: *mut light, inner_angle: c_float, outer_angle: c_float) -> ();
+    pub fn light_set_cookie(l: *mut light, cookie: asset_hndl) -> ();
     pub fn light_direction(l: *mut light) -> vec3;
     pub fn light_view_matrix(l: *mut light) -> mat4;
     pub fn light_proj_matrix(l: *mut light) -> mat4;
@@ -2141,8 +2218,15 @@ extern "C" {
     pub fn asset_add_path_variable(variable: fpath, mapping: fpath) -> ();
     pub fn asset_map_filename(filename: fpath) -> fpath;
     pub fn asset_unmap_filename(filename: fpath) -> fpath;
+    pub fn asset_map_cleanpath(filename: fpath) -> fpath;
+    pub fn asset_mount(folder: fpath) -> c_int;
+    pub fn asset_mount_file(mount: c_int, name: fpath, data: *const c_void, size: c_int) -> ();
+    pub fn asset_unmount(mount: c_int) -> ();
+    pub fn asset_mounted_data(filename: fpath, size: *mut c_int) -> *const c_void;
+    pub fn asset_open(filename: fpath, mode: *const c_char) -> *mut SDL_RWops;
     pub fn asset_handler_cast(_type: type_id, extension: *const c_char, asset_loader: unsafe extern "C" fn(filename: *const c_char) -> *mut asset, asset_deleter: unsafe extern "C" fn(asset: *mut asset) -> ()) -> ();
     pub fn file_load(filename: fpath) -> ();
+    pub fn file_add(filename: fpath, a: *mut asset) -> ();
     pub fn file_unload(filename: fpath) -> ();
     pub fn file_reload(filename: fpath) -> ();
     pub fn file_isloaded(path: fpath) -> u8;
@@ -2156,6 +2240,7 @@ extern "C" {
     pub fn asset_get_as_type(path: fpath, _type: type_id) -> *mut asset;
     pub fn asset_reload_type_id(_type: type_id) -> ();
     pub fn asset_reload_all() -> ();
+    pub fn asset_map_paths(func: Option<unsafe extern "C" fn(path: *mut c_char) -> ()>) -> ();
     pub fn asset_ptr_path(a: *mut asset) -> *mut c_char;
     pub fn asset_ptr_typename(a: *mut asset) -> *mut c_char;
     pub fn static_object_new() -> *mut static_object;
@@ -2184,6 +2269,8 @@ extern "C" {
     pub fn animated_object_update(ao: *mut animated_object, timestep: c_float) -> ();
     pub fn physics_object_new() -> *mut physics_object;
     pub fn physics_object_delete(po: *mut physics_object) -> ();
+    pub fn physics_object_world(po: *mut physics_object) -> mat4;
+    pub fn physics_object_world_normal(po: *mut physics_object) -> mat3;
     pub fn physics_object_collide_static(po: *mut physics_object, so: *mut static_object, timestep: c_float) -> ();
     pub fn physics_object_update(po: *mut physics_object, timestep: c_float) -> ();
     pub fn instance_object_new() -> *mut instance_object;
@@ -2251,6 +2338,7 @@ extern "C" {
     pub fn image_mask_count(i: *mut image) -> c_long;
     pub fn image_mask_median(i: *mut image, u: *mut c_int, v: *mut c_int) -> ();
     pub fn image_mask_random(i: *mut image, u: *mut c_int, v: *mut c_int) -> ();
+    pub fn image_handler_add(extension: *const c_char, image_loader: unsafe extern "C" fn(filename: *const c_char) -> *mut image) -> ();
     pub fn image_read_from_file(filename: *mut c_char) -> *mut image;
     pub fn image_tga_load_file(filename: *mut c_char) -> *mut image;
     pub fn image_bmp_load_file(filename: *mut c_char) -> *mut image;
@@ -2331,6 +2419,7 @@ extern "C" {
     pub fn gs_load_file(filename: *mut c_char) -> *mut shader;
     pub fn tcs_load_file(filename: *mut c_char) -> *mut shader;
     pub fn tes_load_file(filename: *mut c_char) -> *mut shader;
+    pub fn shader_file_compiles(filename: *mut c_char, log: *mut c_char, log_size: c_int) -> u8;
     pub fn shader_delete(s: *mut shader) -> ();
     pub fn shader_print_log(s: *mut shader) -> ();
     pub fn shader_handle(s: *mut shader) -> GLuint;
@@ -2365,6 +2454,7 @@ extern "C" {
     pub fn shader_program_enable_attribute_instance_matrix(p: *mut shader_program, name: *mut c_char, ptr: *mut c_void) -> ();
     pub fn shader_program_disable_attribute_matrix(p: *mut shader_program, name: *mut c_char) -> ();
     pub fn texture_new() -> *mut texture;
+    pub fn texture_new_empty(width: c_int, height: c_int) -> *mut texture;
     pub fn texture_new_handle(h: GLuint) -> *mut texture;
     pub fn texture_delete(t: *mut texture) -> ();
     pub fn texture_handle(t: *mut texture) -> GLuint;
@@ -2378,6 +2468,7 @@ extern "C" {
     pub fn bmp_load_file(filename: *mut c_char) -> *mut texture;
     pub fn tga_load_file(filename: *mut c_char) -> *mut texture;
     pub fn dds_load_file(filename: *mut c_char) -> *mut texture;
+    pub fn dds_load_levels(data: *const c_void, size: c_int, width: c_int, height: c_int, mipmaps: c_int, pitch: c_int, format: c_int, cubemap: u8) -> *mut texture;
     pub fn lut_load_file(filename: *mut c_char) -> *mut texture;
     pub fn acv_load_file(filename: *mut c_char) -> *mut texture;
     pub fn texture_write_to_file(t: *mut texture, filename: *mut c_char) -> ();
@@ -2389,6 +2480,7 @@ extern "C" {
     pub fn material_new() -> *mut material;
     pub fn material_delete(m: *mut material) -> ();
     pub fn mat_load_file(filename: *mut c_char) -> *mut material;
+    pub fn mat_read_file(filename: *mut c_char) -> *mut material;
     pub fn material_get_entry(m: *mut material, index: c_int) -> *mut material_entry;
     pub fn material_add_entry(m: *mut material) -> *mut material_entry;
     pub fn material_first_program(m: *mut material) -> *mut shader_program;
@@ -2400,11 +2492,18 @@ extern "C" {
     pub fn renderable_add_mesh(r: *mut renderable, m: *mut mesh) -> ();
     pub fn renderable_add_model(r: *mut renderable, m: *mut model) -> ();
     pub fn renderable_set_material(r: *mut renderable, mat: asset_hndl) -> ();
+    pub fn renderable_bound(r: *mut renderable) -> sphere;
     pub fn renderable_to_model(r: *mut renderable) -> *mut model;
+    pub fn renderable_to_cmesh(r: *mut renderable) -> *mut cmesh;
+    pub fn renderable_surface_to_mesh(r: *mut renderable, i: c_int, weights: *mut vertex_weight) -> *mut mesh;
+    pub fn renderable_set_surface(r: *mut renderable, i: c_int, m: *mut mesh, weights: *mut vertex_weight) -> ();
     pub fn bmf_load_file(filename: *mut c_char) -> *mut renderable;
     pub fn obj_load_file(filename: *mut c_char) -> *mut renderable;
     pub fn smd_load_file(filename: *mut c_char) -> *mut renderable;
     pub fn ply_load_file(filename: *mut c_char) -> *mut renderable;
+    pub fn obj_load_model(filename: *mut c_char) -> *mut model;
+    pub fn smd_load_model(filename: *mut c_char, weights: *mut *mut *mut vertex_weight) -> *mut model;
+    pub fn ply_load_model(filename: *mut c_char) -> *mut model;
     pub fn bmf_save_file(r: *mut renderable, filename: *mut c_char) -> ();
     pub fn animation_new() -> *mut animation;
     pub fn animation_delete(a: *mut animation) -> ();
@@ -2590,10 +2689,19 @@ extern "C" {
     pub fn renderer_set_glitch(dr: *mut renderer, glitch: c_float) -> ();
     pub fn renderer_set_skydome_enabled(dr: *mut renderer, enabled: u8) -> ();
     pub fn renderer_set_sea_enabled(dr: *mut renderer, enabled: u8) -> ();
+    pub fn renderer_set_culling_enabled(dr: *mut renderer, enabled: u8) -> ();
+    pub fn renderer_set_point_shadows(dr: *mut renderer, num: c_int) -> ();
+    pub fn renderer_max_shadow_cascades(dr: *mut renderer) -> c_int;
+    pub fn renderer_set_shadow_snap(dr: *mut renderer, snap: c_float) -> ();
+    pub fn renderer_set_shadow_cascades(dr: *mut renderer, num: c_int, split: c_int, blend: c_float, distance: c_float) -> ();
+    pub fn renderer_set_shadow_bias(dr: *mut renderer, bias: c_float, normal_offset: c_float) -> ();
+    pub fn renderer_set_shadow_resolution(dr: *mut renderer, cascade: c_int, width: c_int, height: c_int) -> ();
+    pub fn renderer_set_shadow_debug(dr: *mut renderer, enabled: u8) -> ();
     pub fn renderer_set_tod(dr: *mut renderer, tod: c_float, seed: c_int) -> ();
     pub fn renderer_add(dr: *mut renderer, ro: render_object) -> ();
     pub fn renderer_add_dyn_light(dr: *mut renderer, l: *mut light) -> ();
     pub fn renderer_render(dr: *mut renderer) -> ();
+    pub fn renderer_render_to_texture(dr: *mut renderer, cam: *mut camera, t: *mut texture) -> ();
     pub fn list_new() -> *mut list;
     pub fn list_push_back(l: *mut list, item: *mut c_void) -> ();
     pub fn list_pop_back(l: *mut list) -> *mut c_void;
@@ -21,6 +21,7 @@ uniform sampler2D depth_texture;
 uniform sampler2D shadows_texture0;
 uniform sampler2D shadows_texture1;
 uniform sampler2D shadows_texture2;
+uniform sampler2D shadows_texture3;
 uniform sampler2D ssao_texture;
 uniform samplerCube env_texture;
 uniform sampler2D skin_lookup;
@@ -29,8 +30,10 @@ uniform mat4 inv_view;
 uniform mat4 inv_proj;
 uniform float clip_near;
 uniform float clip_far;
+uniform int clip_orthographic;
 
 uniform vec3 camera_position;
+uniform vec3 camera_direction;
 
 uniform int lights_num;
 uniform float light_power[MAX_LIGHTS];
@@ -40,14 +43,40 @@ uniform vec3 light_target[MAX_LIGHTS];
 uniform vec3 light_diffuse[MAX_LIGHTS];
 uniform vec3 light_ambient[MAX_LIGHTS];
 uniform vec3 light_specular[MAX_LIGHTS];
+uniform float light_cone_inner[MAX_LIGHTS];
+uniform float light_cone_outer[MAX_LIGHTS];
+uniform float light_spot[MAX_LIGHTS];
+uniform float light_point[MAX_LIGHTS];
 
-uniform float light_start[3];
-uniform mat4 light_view[3];
-uniform mat4 light_proj[3];
+uniform int shadows_num;
+uniform float shadows_bias;
+uniform float shadows_normal_offset;
+uniform int shadows_debug;
+
+uniform float light_start[4];
+uniform mat4 light_view[4];
+uniform mat4 light_proj[4];
+
+uniform sampler2D spot_shadows_texture0;
+uniform sampler2D spot_shadows_texture1;
+uniform sampler2D spot_cookie_texture0;
+uniform sampler2D spot_cookie_texture1;
+
+uniform float spot_shadows[2];
+uniform mat4 spot_view[2];
+uniform mat4 spot_proj[2];
+
+uniform samplerCube point_shadows_texture0;
+uniform samplerCube point_shadows_texture1;
+
+uniform float point_shadows[2];
+
+uniform float shadow_clip_near;
+uniform float spot_clip_far[2];
+uniform float point_clip_far[2];
 
 varying vec2 fTexcoord;
 
-#define SHADOW_BIAS 0.001
 #define SHADOW_SAMPLE_SPHERE vec3[32]( \
     vec3(-0.00,  0.02, -0.03), vec3( 0.35, -0.04,  0.31), vec3( 0.66, -0.32,  0.53), \
     vec3(-0.04, -0.04,  0.01), vec3( 0.24, -0.22,  0.89), vec3(-0.09,  0.10, -0.54), \
@@ -62,6 +91,9 @@ varying vec2 fTexcoord;
     vec3( 0.18,  0.04, -0.58), vec3(-0.16,  0.11, -0.26))
 
 
+#define SHADOW_CASCADE_COLORS vec3[4]( \
+    vec3(1.0, 0.2, 0.2), vec3(0.2, 1.0, 0.2), vec3(0.2, 0.2, 1.0), vec3(1.0, 1.0, 0.2))
+
 float shadow_amount(vec3 position, mat4 light_view, mat4 light_proj, sampler2D light_depth, float kernel, vec2 seed) {
  
   vec4 light_pos = light_proj * light_view * vec4(position, 1.0);
@@ -82,15 +114,73 @@ float shadow_amount(vec3 position, mat4 light_view, mat4 light_proj, sampler2D l
   float shadow_depth2 = texture2D( light_depth, pixel_coords + offset2 * kernel ).r;
   float shadow_depth3 = texture2D( light_depth, pixel_coords + offset3 * kernel ).r;
   
-  shade = shade - sign(pixel_depth - shadow_depth0 - SHADOW_BIAS) * (float(1) / float(4));
-  shade = shade - sign(pixel_depth - shadow_depth1 - SHADOW_BIAS) * (float(1) / float(4));
-  shade = shade - sign(pixel_depth - shadow_depth2 - SHADOW_BIAS) * (float(1) / float(4));
-  shade = shade - sign(pixel_depth - shadow_depth3 - SHADOW_BIAS) * (float(1) / float(4));
+  shade = shade - sign(pixel_depth - shadow_depth0 - shadows_bias) * (float(1) / float(4));
+  shade = shade - sign(pixel_depth - shadow_depth1 - shadows_bias) * (float(1) / float(4));
+  shade = shade - sign(pixel_depth - shadow_depth2 - shadows_bias) * (float(1) / float(4));
+  shade = shade - sign(pixel_depth - shadow_depth3 - shadows_bias) * (float(1) / float(4));
+  
+  return shade;
+  
+}
+
+#define LIGHT_SHADOW_BIAS 0.05
+
+float linear_depth(float depth, float near, float far) {
+  return (2.0 * near * far) / (far + near - depth * (far - near));
+}
+
+float spot_shadow_amount(vec3 position, mat4 light_view, mat4 light_proj, sampler2D light_depth, float light_far, float kernel, vec2 seed) {
+  
+  vec4 light_pos = light_proj * light_view * vec4(position, 1.0);
+  light_pos = light_pos / light_pos.w;
+  
+  float pixel_depth = linear_depth(light_pos.z, shadow_clip_near, light_far);
+  vec2  pixel_coords = vec2(light_pos.x, light_pos.y) / 2.0 + 0.5;
+  
+  float shade = 1.0;
+  
+  for (int i = 0; i < 4; i++) {
+    vec2 offset = reflect(SHADOW_SAMPLE_SPHERE[i].xy, seed);
+    float shadow_depth = texture2D( light_depth, pixel_coords + offset * kernel ).r;
+    shadow_depth = linear_depth(shadow_depth * 2.0 - 1.0, shadow_clip_near, light_far);
+    shade = shade - step(shadow_depth + LIGHT_SHADOW_BIAS, pixel_depth) * (float(1) / float(4));
+  }
+  
+  return shade;
+  
+}
+
+float point_shadow_amount(vec3 position, vec3 light_position, samplerCube light_depth, float light_far, float kernel, vec3 seed) {
+  
+  vec3 light_vector = position - light_position;
+  vec3 light_abs = abs(light_vector);
+  
+  float pixel_depth = max(max(light_abs.x, light_abs.y), light_abs.z);
+  
+  float shade = 1.0;
+  
+  for (int i = 0; i < 4; i++) {
+    vec3 offset = reflect(SHADOW_SAMPLE_SPHERE[i], seed);
+    float shadow_depth = textureCube( light_depth, light_vector + offset * kernel * pixel_depth ).r;
+    shadow_depth = linear_depth(shadow_depth * 2.0 - 1.0, shadow_clip_near, light_far);
+    shade = shade - step(shadow_depth + LIGHT_SHADOW_BIAS, pixel_depth) * (float(1) / float(4));
+  }
   
   return shade;
   
 }
 
+vec3 spot_cookie_amount(vec3 position, mat4 light_view, mat4 light_proj, sampler2D cookie) {
+  
+  vec4 light_pos = light_proj * light_view * vec4(position, 1.0);
+  light_pos = light_pos / light_pos.w;
+  
+  vec2 cookie_coords = vec2(light_pos.x, light_pos.y) / 2.0 + 0.5;
+  
+  return texture2D(cookie, cookie_coords).rgb;
+  
+}
+
 float when_eq(float x, float y) {
   return 1.0 - abs(sign(x - y));
 }
@@ -111,6 +201,7 @@ vec3 from_gamma(vec3 color) {
 }
 
 float perspective_depth(float depth, float near, float far) {
+  if (clip_orthographic == 1) { return depth; }
   return (((2.0 * near) / depth) - far - near) / (near - far);
 }
 
@@ -143,12 +234,27 @@ void main() {
     abs(normal.z) * texture2D(random_texture, position.xy * noise_tile).rgb;
   random = normalize(random * 2.0 - 1.0);
   
-  float shadow0 = shadow_amount(position.xyz, light_view[0], light_proj[0], shadows_texture0, 0.00075, random.xy);
-  float shadow1 = shadow_amount(position.xyz, light_view[1], light_proj[1], shadows_texture1, 0.00075, random.xy);
-  float shadow2 = shadow_amount(position.xyz, light_view[2], light_proj[2], shadows_texture2, 0.00100, random.xy);
-	float shadow = depth > light_start[2] ? shadow2 : (depth > light_start[1] ? shadow1 : shadow0);
+  vec3 shadow_position = position.xyz + normal * shadows_normal_offset;
+  
+  float shadow = 1.0;
+  int cascade = 0;
+  
+  if (shadows_num > 3 && depth > light_start[3]) {
+    shadow = shadow_amount(shadow_position, light_view[3], light_proj[3], shadows_texture3, 0.00100, random.xy);
+    cascade = 3;
+  } else if (shadows_num > 2 && depth > light_start[2]) {
+    shadow = shadow_amount(shadow_position, light_view[2], light_proj[2], shadows_texture2, 0.00100, random.xy);
+    cascade = 2;
+  } else if (shadows_num > 1 && depth > light_start[1]) {
+    shadow = shadow_amount(shadow_position, light_view[1], light_proj[1], shadows_texture1, 0.00075, random.xy);
+    cascade = 1;
+  } else if (shadows_num > 0) {
+    shadow = shadow_amount(shadow_position, light_view[0], light_proj[0], shadows_texture0, 0.00075, random.xy);
+    cascade = 0;
+  }
   
   vec3 eye_dir = normalize(camera_position - position.xyz);
+  if (clip_orthographic == 1) { eye_dir = -camera_direction; }
   float n_dot_c = dot(normal, eye_dir);
   
   vec3 ssao = texture2D(ssao_texture, fTexcoord).rgb;
@@ -196,6 +302,41 @@ void main() {
     float power = light_power[i] / pow(length(light_vector), light_falloff[i]);
     
     vec3 light_dir = normalize(light_position[i] - light_target[i]);
+    
+    float light_shadow = clamp(shadow + i, 0, 1);
+    vec3  light_color = vec3(1.0, 1.0, 1.0);
+    
+    if (light_cone_outer[i] > -1.0) {
+      
+      float cone = dot(normalize(-light_vector), normalize(light_target[i] - light_position[i]));
+      power *= smoothstep(light_cone_outer[i], light_cone_inner[i], cone);
+      light_dir = normalize(light_vector);
+      light_shadow = 1.0;
+      
+      if (light_spot[i] == 0.0) {
+        light_color = spot_cookie_amount(position.xyz, spot_view[0], spot_proj[0], spot_cookie_texture0);
+        if (spot_shadows[0] == 1.0) {
+          light_shadow = spot_shadow_amount(position.xyz, spot_view[0], spot_proj[0], spot_shadows_texture0, spot_clip_far[0], 0.001, random.xy);
+        }
+      }
+      
+      if (light_spot[i] == 1.0) {
+        light_color = spot_cookie_amount(position.xyz, spot_view[1], spot_proj[1], spot_cookie_texture1);
+        if (spot_shadows[1] == 1.0) {
+          light_shadow = spot_shadow_amount(position.xyz, spot_view[1], spot_proj[1], spot_shadows_texture1, spot_clip_far[1], 0.001, random.xy);
+        }
+      }
+      
+    }
+    
+    if (light_point[i] == 0.0 && point_shadows[0] == 1.0) {
+      light_shadow = point_shadow_amount(position.xyz, light_position[i], point_shadows_texture0, point_clip_far[0], 0.002, random);
+    }
+    
+    if (light_point[i] == 1.0 && point_shadows[1] == 1.0) {
+      light_shadow = point_shadow_amount(position.xyz, light_position[i], point_shadows_texture1, point_clip_far[1], 0.002, random);
+    }
+    
     vec3 light_half = normalize(light_dir + eye_dir);
     
     float n_dot_l = dot(normal, light_dir);
@@ -214,14 +355,14 @@ void main() {
       light_diff = texture2D( skin_lookup, clamp(vec2(n_dot_l * 0.5 + 0.5, curvature), 0.1, 0.9)).rgb;
     }
    
-    light_diff *= clamp(shadow + i, 0, 1);
-    light_spec *= clamp(shadow + i, 0, 1);
-    light_inrim *= clamp(shadow + i, 0, 1);
-    light_outrim *= clamp(shadow + i, 0, 1);
+    light_diff *= light_shadow;
+    light_spec *= light_shadow;
+    light_inrim *= light_shadow;
+    light_outrim *= light_shadow;
     
     ambient    += power * light_ambient[i];
-    diffuse    += power * light_diffuse[i]  * light_diff;
-    specular   += power * light_specular[i] * light_spec;
+    diffuse    += power * light_diffuse[i]  * light_diff * light_color;
+    specular   += power * light_specular[i] * light_spec * light_color;
     reflection += power * light_ambient[i]  * light_refl;
     inner_rim  += power * light_specular[i] * light_inrim;
     outer_rim  += power * light_specular[i] * light_outrim;
@@ -236,5 +377,9 @@ void main() {
   outer_rim  = outer_rim  * spec_amount * outer_rim_amount;
   
   gl_FragColor.rgb = ambient + diffuse + specular + reflection + inner_rim + outer_rim; 
+  
+  if (shadows_debug == 1 && shadows_num > 0) {
+    gl_FragColor.rgb *= SHADOW_CASCADE_COLORS[cascade];
+  }
 
 } 
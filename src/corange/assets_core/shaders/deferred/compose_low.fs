@@ -14,6 +14,7 @@ uniform mat4 inv_view;
 uniform mat4 inv_proj;
 uniform float clip_near;
 uniform float clip_far;
+uniform int clip_orthographic;
 
 uniform sampler2D diffuse_texture;
 uniform sampler2D depth_texture;
@@ -31,6 +32,7 @@ uniform vec3  light_specular;
 varying vec2 fTexcoord;
 
 float perspective_depth(float depth, float near, float far) {
+  if (clip_orthographic == 1) { return depth; }
   return (near - 0.5 * far * near - 0.5 * depth * far) / (-0.5 * far * (far-near));
 }
 
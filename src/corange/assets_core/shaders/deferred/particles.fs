@@ -11,6 +11,7 @@ uniform float particle_scattering;
 
 uniform float clip_near;
 uniform float clip_far;
+uniform int clip_orthographic;
 
 uniform float light_power;
 uniform vec3 light_direction;
@@ -23,6 +24,7 @@ varying vec4 fColor;
 varying mat4 fTBN;
 
 float linear_depth(float depth, float near, float far){
+  if (clip_orthographic == 1) { return depth; }
   return (2.0 * near) / (far + near - depth * (far - near));
 }
 
@@ -17,6 +17,7 @@ uniform vec3 light_specular;
 
 uniform float clip_near;
 uniform float clip_far;
+uniform int clip_orthographic;
 
 uniform vec3 camera_position;
 
@@ -27,6 +28,7 @@ varying vec3 fNormal;
 varying vec4 fScreen;
 
 float linear_depth(float depth, float near, float far) {
+  if (clip_orthographic == 1) { return depth; }
   return (2.0 * near) / (far + near - depth * (far - near));
 }
 
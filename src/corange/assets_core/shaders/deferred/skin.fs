@@ -11,6 +11,7 @@ uniform int material;
 
 uniform float near;
 uniform float far;
+uniform int clip_orthographic;
 
 varying vec2 fTexcoord;
 varying vec3 fColor;
@@ -49,6 +50,7 @@ vec3 swap_red_green_inv(vec3 color) {
 }
 
 float linear_depth(float depth, float near, float far){
+  if (clip_orthographic == 1) { return depth; }
   return (2.0 * near) / (far + near - depth * (far - near));
 }
 
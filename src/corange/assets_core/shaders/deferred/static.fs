@@ -12,6 +12,7 @@ uniform int material;
 
 uniform float clip_near;
 uniform float clip_far;
+uniform int clip_orthographic;
 
 varying vec2 fTexcoord;
 varying vec3 fColor;
@@ -42,6 +43,7 @@ vec3 swap_red_green_inv(vec3 color) {
 }
 
 float linear_depth(float depth, float near, float far){
+  if (clip_orthographic == 1) { return depth; }
   return (2.0 * near) / (far + near - depth * (far - near));
 }
 
@@ -17,6 +17,7 @@ uniform float size_y;
 
 uniform float clip_near;
 uniform float clip_far;
+uniform int clip_orthographic;
 
 varying vec3 fPosition;
 varying mat4 fTBN;
@@ -37,6 +38,7 @@ vec3 swap_red_green_inv(vec3 color) {
 }
 
 float linear_depth(float depth, float near, float far){
+  if (clip_orthographic == 1) { return depth; }
   return (2.0 * near) / (far + near - depth * (far - near));
 }
 
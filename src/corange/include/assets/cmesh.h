@@ -22,30 +22,41 @@ bool ctri_inside_plane(ctri t, plane p);
 bool ctri_outside_plane(ctri t, plane p);
 bool ctri_intersects_plane(ctri t, plane p);
 
+/* Node of the bounding volume hierarchy, in mesh space */
+typedef struct {
+  vec3 min, max;
+  /* Leaves cover triangles [first, first + count), inner nodes have count zero and children first and first + 1 */
+  int first;
+  int count;
+} cmesh_node;
+
 typedef struct cmesh {
 
-  bool is_leaf;
+  ctri* triangles;
+  int triangles_num;
 
-  union {
-    struct {
-      plane division;
-      struct cmesh* front;
-      struct cmesh* back;
-    };
+  cmesh_node* nodes;
+  int nodes_num;
 
-    struct {
-      ctri* triangles;
-      int triangles_num;
-      sphere bound;
-    };
-  };
+  sphere bound;
 
 } cmesh;
 
-cmesh* col_load_file(char* filename);
+cmesh* cmesh_new();
+cmesh* cmesh_from_mesh(mesh* m);
+cmesh* cmesh_from_model(model* m);
 void cmesh_delete(cmesh* cm);
 
+cmesh* col_load_file(char* filename);
+void col_save_file(cmesh* cm, char* filename);
+
 sphere cmesh_bound(cmesh* cm);
-void cmesh_subdivide(cmesh* cm, int iterations);
+
+/* Merges vertices closer than about cell_size and drops collapsed triangles */
+void cmesh_simplify(cmesh* cm, float cell_size);
+cmesh* cmesh_convex_hull(cmesh* cm);
+
+/* Reorders the triangles and builds the hierarchy, call after changing them */
+void cmesh_build_bvh(cmesh* cm);
 
 #endif
@@ -106,6 +106,9 @@ long image_mask_count(image* i);
 void image_mask_median(image* i, int* u, int* v);
 void image_mask_random(image* i, int* u, int* v);
 
+/* Add a loader used by image_read_from_file for files with the given extension */
+void image_handler_add(const char* extension, image* image_loader(const char* filename));
+
 image* image_read_from_file(char* filename);
 image* image_tga_load_file(char* filename);
 image* image_bmp_load_file(char* filename);
@@ -54,6 +54,8 @@ material* material_new();
 void material_delete(material* m);
 
 material* mat_load_file(char* filename);
+/* Read the entries of a .mat without generating their shader programs. Shaders and textures it names are still loaded */
+material* mat_read_file(char* filename);
 
 material_entry* material_get_entry(material* m, int index);
 material_entry* material_add_entry(material* m);
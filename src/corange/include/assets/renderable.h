@@ -13,6 +13,7 @@
 
 #include "../cengine.h"
 #include "../assets/material.h"
+#include "../assets/cmesh.h"
 
 typedef struct {
   int bone_ids[3];
@@ -44,14 +45,27 @@ void renderable_delete(renderable* r);
 void renderable_add_mesh(renderable* r, mesh* m);
 void renderable_add_model(renderable* r, model* m);
 void renderable_set_material(renderable* r, asset_hndl mat);
+sphere renderable_bound(renderable* r);
 
 model* renderable_to_model(renderable* r);
+cmesh* renderable_to_cmesh(renderable* r);
+
+/* Read surface i back from its vertex buffers, weights are filled for rigged renderables when given */
+mesh* renderable_surface_to_mesh(renderable* r, int i, vertex_weight* weights);
+/* Replace surface i with new vertex buffers built from the mesh */
+void renderable_set_surface(renderable* r, int i, mesh* m, vertex_weight* weights);
 
 renderable* bmf_load_file(char* filename);
 renderable* obj_load_file(char* filename);
 renderable* smd_load_file(char* filename);
 renderable* ply_load_file(char* filename);
 
+/* The meshes the loaders above build their surfaces from, read without GL and without writing a .bmf */
+model* obj_load_model(char* filename);
+/* Sets weights to one array of vertex weights per mesh, free each and the array */
+model* smd_load_model(char* filename, vertex_weight*** weights);
+model* ply_load_model(char* filename);
+
 void bmf_save_file(renderable* r, char* filename);
 
 
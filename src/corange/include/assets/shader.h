@@ -20,6 +20,9 @@ shader* gs_load_file(char* filename);
 shader* tcs_load_file(char* filename);
 shader* tes_load_file(char* filename);
 
+/* Compile a shader file without loading it, writing the compiler log to log */
+bool shader_file_compiles(char* filename, char* log, int log_size);
+
 void shader_delete(shader* s);
 void shader_print_log(shader* s);
 GLuint shader_handle(shader* s);
@@ -20,6 +20,7 @@ typedef struct {
 } texture;
 
 texture* texture_new();
+texture* texture_new_empty(int width, int height);
 texture* texture_new_handle(GLuint h);
 void texture_delete(texture* t);
 
@@ -37,6 +38,9 @@ void texture_set_filtering_anisotropic(texture* t);
 texture* bmp_load_file( char* filename );
 texture* tga_load_file( char* filename );
 texture* dds_load_file( char* filename );
+/* Upload the levels that follow the header of a checked .dds file. format is DXT1, DXT3, DXT5,
+   BGRA8, BGR8, BGR5A1, BGR565 or INDEX8 numbered from 0, pitch is only used by INDEX8. */
+texture* dds_load_levels( const void* data, int size, int width, int height, int mipmaps, int pitch, int format, bool cubemap );
 texture* lut_load_file( char* filename );
 texture* acv_load_file( char* filename );
 
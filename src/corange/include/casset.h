@@ -55,6 +55,24 @@ void asset_add_path_variable(fpath variable, fpath mapping);
 fpath asset_map_filename(fpath filename);
 fpath asset_unmap_filename(fpath filename);
 
+/* Like asset_map_filename without resolving links, so the file needn't exist */
+fpath asset_map_cleanpath(fpath filename);
+
+/* 
+** Mount in-memory files, e.g. from an archive, below a folder such as '$CORANGE'.
+** Later mounts take precedence and files not in any mount are read from disk.
+** File data is not copied and must stay alive until the mount is removed.
+*/
+int asset_mount(fpath folder);
+void asset_mount_file(int mount, fpath name, const void* data, int size);
+void asset_unmount(int mount);
+
+/* Data of a mounted file at a mapped path, or NULL */
+const void* asset_mounted_data(fpath filename, int* size);
+
+/* Open a mapped path, from its mount if it has one, or from disk */
+SDL_RWops* asset_open(fpath filename, const char* mode);
+
 /* Create handler for asset type. Requires type, file extension, and load/unload functions. */
 #define asset_handler(type, extension, loader, deleter) \
   asset_handler_cast(typeid(type), extension, \
@@ -66,8 +84,9 @@ void asset_handler_cast(
   asset* asset_loader(const char* filename) , 
   void asset_deleter(asset* asset) );
 
-/* Load/Reload/Unload assets at path or folder */
+/* Load/Add/Reload/Unload assets at path or folder */
 void file_load(fpath filename);
+void file_add(fpath filename, asset* a);
 void file_unload(fpath filename);
 void file_reload(fpath filename);
 bool file_isloaded(fpath path);
@@ -89,6 +108,9 @@ asset* asset_get_as_type(fpath path, type_id type);
 void asset_reload_type_id(type_id type);
 void asset_reload_all(void);
 
+/* Call func with the full path of every loaded asset, func must not load or unload assets */
+void asset_map_paths(void func(char* path));
+
 /* Get path or typename of asset at ptr */
 char* asset_ptr_path(asset* a);
 char* asset_ptr_typename(asset* a);
@@ -39,6 +39,11 @@ collision ellipsoid_collide_mesh(ellipsoid e, vec3 v, cmesh* m, mat4 world, mat3
 collision ellipsoid_collide_point(ellipsoid e, vec3 v, vec3 p);
 collision ellipsoid_collide_sphere(ellipsoid e, vec3 v, sphere s);
 
+/* Test every triangle without the hierarchy, for checking it against */
+collision point_collide_mesh_brute(vec3 p, vec3 v, cmesh* m, mat4 world, mat3 world_normal);
+collision sphere_collide_mesh_brute(sphere s, vec3 v, cmesh* m, mat4 world, mat3 world_normal);
+collision ellipsoid_collide_mesh_brute(ellipsoid e, vec3 v, cmesh* m, mat4 world, mat3 world_normal);
+
 void collision_response_slide(void* x, vec3* position, vec3* velocity, collision (*colfunc)(void* x, vec3* pos, vec3* vel) );
 
 #endif
@@ -17,6 +17,9 @@ typedef struct {
   float fov;
   float near_clip;
   float far_clip;
+  float roll;
+  bool orthographic;
+  float ortho_width;
 } camera;
 
 camera* camera_new();
@@ -25,8 +28,12 @@ void camera_delete(camera* cam);
 vec3 camera_direction(camera* c);
 mat4 camera_view_matrix(camera* c);
 mat4 camera_proj_matrix(camera* c);
+mat4 camera_proj_matrix_ratio(camera* c, float ratio);
 mat4 camera_view_proj_matrix(camera* c);
 
+void camera_set_perspective(camera* c, float fov);
+void camera_set_orthographic(camera* c, float width);
+
 void camera_normalize_target(camera* c);
 void camera_control_orbit(camera* c, SDL_Event e);
 void camera_control_freecam(camera* c, float timestep);
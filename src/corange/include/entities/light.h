@@ -14,6 +14,13 @@
 #define light_h
 
 #include "../cengine.h"
+#include "../casset.h"
+
+#define DEFAULT_LIGHT_NEAR_CLIP 0.1
+#define DEFAULT_LIGHT_FAR_CLIP 8192.0
+
+/* Fraction of a light's power below which it no longer lights anything */
+#define LIGHT_CUTOFF (1.0 / 256.0)
 
 enum {
   LIGHT_TYPE_POINT        = 0,
@@ -52,6 +59,11 @@ typedef struct {
   float fov;
   float aspect_ratio;
 
+  /* Spot Lights */
+  float inner_angle;
+  float outer_angle;
+  asset_hndl cookie;
+
 } light;
 
 light* light_new();
@@ -62,9 +74,14 @@ light* light_new_type(vec3 position, int type);
 void light_delete(light* l);
 
 void light_set_type(light* l, int type);
+void light_set_cone(light* l, float inner_angle, float outer_angle);
+void light_set_cookie(light* l, asset_hndl cookie);
 
 vec3 light_direction(light* l);
 
+/* Distance at which the light's falloff reaches LIGHT_CUTOFF, used as the far plane of its shadow maps */
+float light_radius(light* l);
+
 mat4 light_view_matrix(light* l);
 mat4 light_proj_matrix(light* l);
 
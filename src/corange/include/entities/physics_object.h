@@ -7,8 +7,11 @@
 ***   physics and movement data such
 ***   as velocity and acceleration
 ***
-***   Some WIP functions to collide such
-***   objects with static objects.
+***   Functions to collide such objects
+***   with static objects. Objects are
+***   collided against their bounding
+***   sphere. For simulating many objects
+***   together see the physics world.
 ***
 **/
 
@@ -40,13 +43,17 @@ typedef struct {
   bool cast_shadows;
 
   asset_hndl renderable;
-  //asset_hndl collision_body;
+  asset_hndl collision_body;
 
 } physics_object;
 
 physics_object* physics_object_new();
 void physics_object_delete(physics_object* po);
 
+mat4 physics_object_world(physics_object* po);
+mat3 physics_object_world_normal(physics_object* po);
+
+/* Call before update, clips this timestep's movement against the static object */
 void physics_object_collide_static(physics_object* po, static_object* so, float timestep);
 void physics_object_update(physics_object* po, float timestep);
 
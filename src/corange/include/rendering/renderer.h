@@ -29,8 +29,52 @@
 #include "../rendering/sky.h"
 
 enum {
-  RENDERER_MAX_LIGHTS     = 16,
-  RENDERER_MAX_DYN_LIGHTS = 13,
+  RENDERER_MAX_LIGHTS        = 16,
+  RENDERER_MAX_CASCADES      = 4,
+  RENDERER_MAX_DYN_LIGHTS    = 13,
+  RENDERER_MAX_SPOT_SHADOWS  = 2,
+  RENDERER_MAX_POINT_SHADOWS = 2,
+  RENDERER_MAX_SHADOWS       = RENDERER_MAX_CASCADES + RENDERER_MAX_SPOT_SHADOWS + 6 * RENDERER_MAX_POINT_SHADOWS,
+};
+
+/* Buffers for one output size, one set for the screen and one for render targets */
+typedef struct {
+  
+  int width;
+  int height;
+  
+  GLuint gfbo;
+  GLuint gdepth_buffer;
+  GLuint gdiffuse_buffer;
+  GLuint gnormals_buffer;
+
+  GLuint gdiffuse_texture;
+  GLuint gnormals_texture;
+  GLuint gdepth_texture;
+
+  GLuint ssao_fbo;
+  GLuint ssao_buffer;
+  GLuint ssao_texture;
+
+  GLuint hdr_fbo;
+  GLuint hdr_buffer;
+  GLuint hdr_texture;
+
+  GLuint ldr_front_fbo;
+  GLuint ldr_front_buffer;
+  GLuint ldr_front_texture;
+
+  GLuint ldr_back_fbo;
+  GLuint ldr_back_buffer;
+  GLuint ldr_back_texture;
+  
+} render_buffers;
+
+enum {
+  SHADOW_SPLIT_MANUAL      = 0,
+  SHADOW_SPLIT_LINEAR      = 1,
+  SHADOW_SPLIT_LOGARITHMIC = 2,
+  SHADOW_SPLIT_BLENDED     = 3,
 };
 
 enum {
@@ -158,40 +202,52 @@ typedef struct {
   asset_hndl tex_skin_lookup;
 
   /* Buffers */
-  GLuint gfbo;
-  GLuint gdepth_buffer;
-  GLuint gdiffuse_buffer;
-  GLuint gnormals_buffer;
-
-  GLuint gdiffuse_texture;
-  GLuint gnormals_texture;
-  GLuint gdepth_texture;
-
-  GLuint ssao_fbo;
-  GLuint ssao_buffer;
-  GLuint ssao_texture;
+  render_buffers screen_buffers;
+  render_buffers target_buffers;
+  render_buffers* buffers;
+  
+  GLuint target_fbo;
 
-  GLuint hdr_fbo;
-  GLuint hdr_buffer;
-  GLuint hdr_texture;
+  GLuint shadows_fbo[RENDERER_MAX_CASCADES];
+  GLuint shadows_buffer[RENDERER_MAX_CASCADES];
+  GLuint shadows_texture[RENDERER_MAX_CASCADES];
 
-  GLuint ldr_front_fbo;
-  GLuint ldr_front_buffer;
-  GLuint ldr_front_texture;
-
-  GLuint ldr_back_fbo;
-  GLuint ldr_back_buffer;
-  GLuint ldr_back_texture;
+  GLuint spot_shadows_fbo[RENDERER_MAX_SPOT_SHADOWS];
+  GLuint spot_shadows_buffer[RENDERER_MAX_SPOT_SHADOWS];
+  GLuint spot_shadows_texture[RENDERER_MAX_SPOT_SHADOWS];
 
-  GLuint shadows_fbo[3];
-  GLuint shadows_buffer[3];
-  GLuint shadows_texture[3];
+  GLuint point_shadows_fbo[RENDERER_MAX_POINT_SHADOWS];
+  GLuint point_shadows_texture[RENDERER_MAX_POINT_SHADOWS];
 
   /* Shadows */
-  float shadows_start[3];
-  float shadows_end[3];
-  int shadows_widths[3];
-  int shadows_heights[3];
+  int shadows_num;
+  int shadows_split;
+  float shadows_split_blend;
+  float shadows_distance;
+  float shadows_snap;
+  float shadows_bias;
+  float shadows_normal_offset;
+  bool shadows_debug;
+  float shadows_start[RENDERER_MAX_CASCADES];
+  float shadows_end[RENDERER_MAX_CASCADES];
+  int shadows_widths[RENDERER_MAX_CASCADES];
+  int shadows_heights[RENDERER_MAX_CASCADES];
+  int spot_shadows_width;
+  int spot_shadows_height;
+  int point_shadows_size;
+  int point_shadows_budget;
+  int point_shadows_limit;
+  
+  /* Fragment shader texture units, the fourth cascade needs more than 16 */
+  int texture_units;
+
+  /* Spot Lights */
+  int spot_lights_num;
+  light* spot_light[RENDERER_MAX_SPOT_SHADOWS];
+
+  /* Point Lights */
+  int point_lights_num;
+  light* point_light[RENDERER_MAX_POINT_SHADOWS];
 
   /* Variables */
   int seed;
@@ -203,11 +259,17 @@ typedef struct {
   float exposure_target;
   bool skydome_enabled;
   bool sea_enabled;
+  bool culling_enabled;
+  texture* target;
 
   /* Objects */
   int render_objects_num;
   render_object* render_objects;
 
+  /* Statistics, objects, surfaces and landscape chunks culled last frame */
+  int culled_num;
+  int shadows_culled_num;
+
   /* Preprocessed */
 
   mat4  camera_view;
@@ -216,13 +278,14 @@ typedef struct {
   mat4  camera_inv_proj;
   float camera_near;
   float camera_far;
+  bool  camera_orthographic;
   box   camera_frustum;
 
-  mat4  shadow_view[3];
-  mat4  shadow_proj[3];
-  float shadow_near[3];
-  float shadow_far[3];
-  box   shadow_frustum[3];
+  mat4  shadow_view[RENDERER_MAX_SHADOWS];
+  mat4  shadow_proj[RENDERER_MAX_SHADOWS];
+  float shadow_near[RENDERER_MAX_SHADOWS];
+  float shadow_far[RENDERER_MAX_SHADOWS];
+  box   shadow_frustum[RENDERER_MAX_SHADOWS];
 
 } renderer;
 
@@ -235,11 +298,21 @@ void renderer_set_vignetting(renderer* dr, asset_hndl v);
 void renderer_set_glitch(renderer* dr, float glitch);
 void renderer_set_skydome_enabled(renderer* dr, bool enabled);
 void renderer_set_sea_enabled(renderer* dr, bool enabled);
+void renderer_set_culling_enabled(renderer* dr, bool enabled);
+void renderer_set_point_shadows(renderer* dr, int num);
+int renderer_max_shadow_cascades(renderer* dr);
+/* Cascade sizes are rounded up to multiples of snap world units, 0 to fit them exactly */
+void renderer_set_shadow_snap(renderer* dr, float snap);
+void renderer_set_shadow_cascades(renderer* dr, int num, int split, float blend, float distance);
+void renderer_set_shadow_bias(renderer* dr, float bias, float normal_offset);
+void renderer_set_shadow_resolution(renderer* dr, int cascade, int width, int height);
+void renderer_set_shadow_debug(renderer* dr, bool enabled);
 void renderer_set_tod(renderer* dr, float tod, int seed);
 
 void renderer_add(renderer* dr, render_object ro);
 void renderer_add_dyn_light(renderer* dr, light* l);
 
 void renderer_render(renderer* dr);
+void renderer_render_to_texture(renderer* dr, camera* cam, texture* t);
 
 #endif
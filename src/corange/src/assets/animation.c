@@ -1,4 +1,5 @@
 #include "assets/animation.h"
+#include "casset.h"
 
 animation* animation_new() {
   
@@ -123,7 +124,7 @@ animation* ani_load_file(char* filename) {
   skeleton* base = skeleton_new();
   frame* f = NULL;
   
-  SDL_RWops* file = SDL_RWFromFile(filename, "r");
+  SDL_RWops* file = asset_open(P(filename), "r");
   
   if(file == NULL) {
     error("Could not load file %s", filename);
@@ -1,4 +1,5 @@
 #include "assets/cmesh.h"
+#include "casset.h"
 
 #include "data/vertex_list.h"
 
@@ -61,25 +62,13 @@ ctri ctri_transform_space(ctri t, mat3 s, mat3 sn) {
 }
 
 void cmesh_delete(cmesh* cm) {
-  
-  if (cm->is_leaf) {
-    free(cm->triangles);
-  } else {
-    cmesh_delete(cm->front);
-    cmesh_delete(cm->back);
-  }
-
+  free(cm->triangles);
+  free(cm->nodes);
   free(cm);
-  
 }
 
 static vec3 cmesh_center(cmesh* cm) {
 
-  if (!cm->is_leaf) {
-    error("cmesh is not leaf type!");
-    return vec3_zero();
-  }
-
   if (cm->triangles_num == 0) {
     error("Can't find center of mesh with no triangles");
     return vec3_zero();
@@ -99,11 +88,6 @@ static vec3 cmesh_center(cmesh* cm) {
 
 static float cmesh_radius(cmesh* cm) {
   
-  if (!cm->is_leaf) {
-    error("cmesh is not leaf type!");
-    return 0;
-  }
-  
   vec3 center = cmesh_center(cm);
   float radius = 0;
   for (int i = 0; i < cm->triangles_num; i++) {
@@ -116,173 +100,406 @@ static float cmesh_radius(cmesh* cm) {
   
 }
 
-static box cmesh_box(cmesh* cm) {
+sphere cmesh_bound(cmesh* cm) {
+  return cm->bound;
+}
 
-  if (!cm->is_leaf) {
-    error("cmesh is not leaf type!");
-    return box_new(0,0,0,0,0,0);
-  }
+#define CMESH_LEAF_TRIANGLES 4
+
+static vec3 ctri_center(ctri t) {
+  return vec3_div(vec3_add(vec3_add(t.a, t.b), t.c), 3);
+}
+
+static vec3 vec3_min(vec3 a, vec3 b) {
+  return vec3_new(min(a.x, b.x), min(a.y, b.y), min(a.z, b.z));
+}
+
+static vec3 vec3_max(vec3 a, vec3 b) {
+  return vec3_new(max(a.x, b.x), max(a.y, b.y), max(a.z, b.z));
+}
+
+static float vec3_axis(vec3 v, int axis) {
+  return axis == 0 ? v.x : (axis == 1 ? v.y : v.z);
+}
+
+static void cmesh_build_node(cmesh* cm, int index, int first, int count) {
   
-  if (cm->triangles_num == 0) {
-    error("Can't find box of mesh with no triangles");
-    return box_new(0,0,0,0,0,0);
+  cmesh_node* node = &cm->nodes[index];
+  
+  vec3 lower = cm->triangles[first].a;
+  vec3 upper = cm->triangles[first].a;
+  vec3 center_lower = ctri_center(cm->triangles[first]);
+  vec3 center_upper = center_lower;
+  
+  for (int i = first; i < first + count; i++) {
+    ctri t = cm->triangles[i];
+    lower = vec3_min(lower, vec3_min(t.a, vec3_min(t.b, t.c)));
+    upper = vec3_max(upper, vec3_max(t.a, vec3_max(t.b, t.c)));
+    center_lower = vec3_min(center_lower, ctri_center(t));
+    center_upper = vec3_max(center_upper, ctri_center(t));
   }
   
-  float x_min = 0; float x_max = 0;
-  float y_min = 0; float y_max = 0;
-  float z_min = 0; float z_max = 0;
+  /* Pad so that queries rounding differently to the triangle tests are never culled */
+  float pad = vec3_dist(lower, upper) * 1e-4 + 1e-6;
+  node->min = vec3_sub(lower, vec3_new(pad, pad, pad));
+  node->max = vec3_add(upper, vec3_new(pad, pad, pad));
   
-  for(int i = 0; i < cm->triangles_num; i++) {
-    x_min = min(x_min, cm->triangles[i].a.x);
-    x_max = max(x_max, cm->triangles[i].a.x);
-    y_min = min(y_min, cm->triangles[i].a.y);
-    y_max = max(y_max, cm->triangles[i].a.y);
-    z_min = min(z_min, cm->triangles[i].a.z);
-    z_max = max(z_max, cm->triangles[i].a.z);
-    
-    x_min = min(x_min, cm->triangles[i].b.x);
-    x_max = max(x_max, cm->triangles[i].b.x);
-    y_min = min(y_min, cm->triangles[i].b.y);
-    y_max = max(y_max, cm->triangles[i].b.y);
-    z_min = min(z_min, cm->triangles[i].b.z);
-    z_max = max(z_max, cm->triangles[i].b.z);
-    
-    x_min = min(x_min, cm->triangles[i].c.x);
-    x_max = max(x_max, cm->triangles[i].c.x);
-    y_min = min(y_min, cm->triangles[i].c.y);
-    y_max = max(y_max, cm->triangles[i].c.y);
-    z_min = min(z_min, cm->triangles[i].c.z);
-    z_max = max(z_max, cm->triangles[i].c.z);
+  if (count <= CMESH_LEAF_TRIANGLES) {
+    node->first = first;
+    node->count = count;
+    return;
   }
   
-  return box_new(x_min, x_max, y_min, y_max, z_min, z_max);
-
+  /* Split at the middle of the longest axis of the triangle centers */
+  vec3 extent = vec3_sub(center_upper, center_lower);
+  int axis = 0;
+  if (extent.y > vec3_axis(extent, axis)) { axis = 1; }
+  if (extent.z > vec3_axis(extent, axis)) { axis = 2; }
+  float split = (vec3_axis(center_lower, axis) + vec3_axis(center_upper, axis)) / 2;
+  
+  int i = first;
+  int j = first + count - 1;
+  while (i <= j) {
+    if (vec3_axis(ctri_center(cm->triangles[i]), axis) < split) {
+      i++;
+    } else {
+      ctri t = cm->triangles[i];
+      cm->triangles[i] = cm->triangles[j];
+      cm->triangles[j] = t;
+      j--;
+    }
+  }
+  
+  /* All centers coincide on the axis, any split is as good as another */
+  int num_back = i - first;
+  if (num_back == 0 || num_back == count) { num_back = count / 2; }
+  
+  int children = cm->nodes_num;
+  cm->nodes_num += 2;
+  
+  node->first = children;
+  node->count = 0;
+  
+  cmesh_build_node(cm, children+0, first, num_back);
+  cmesh_build_node(cm, children+1, first + num_back, count - num_back);
+  
 }
 
-static plane cmesh_division(cmesh* cm) {
+void cmesh_build_bvh(cmesh* cm) {
   
-  box bb = cmesh_box(cm);
+  free(cm->nodes);
+  cm->nodes = NULL;
+  cm->nodes_num = 0;
   
-  plane p;
-  p.position = cmesh_center(cm);
-    
-  float x_diff = bb.left.position.x  - bb.right.position.x;
-  float y_diff = bb.top.position.y   - bb.bottom.position.y;
-  float z_diff = bb.front.position.z - bb.back.position.z;
-  
-  if ((x_diff >= y_diff) && 
-      (x_diff >= z_diff)) {
-    p.direction = vec3_new(1,0,0);
-    
-  } else if ((y_diff >= x_diff) && 
-             (y_diff >= z_diff)) {
-    p.direction = vec3_new(0,1,0);
-    
-  } else if ((z_diff >= x_diff) && 
-             (z_diff >= y_diff)) {
-    p.direction = vec3_new(0,0,1);
-  } else {
-    p.direction = vec3_new(1,0,0);
+  if (cm->triangles_num == 0) {
+    cm->bound = sphere_new(vec3_zero(), 0);
+    return;
   }
   
-  return p;
+  cm->bound = sphere_new(cmesh_center(cm), cmesh_radius(cm));
+  
+  /* A binary tree over n triangles has at most 2n - 1 nodes */
+  cm->nodes = malloc(sizeof(cmesh_node) * (cm->triangles_num * 2 - 1));
+  cm->nodes_num = 1;
+  cmesh_build_node(cm, 0, 0, cm->triangles_num);
   
 }
 
-sphere cmesh_bound(cmesh* cm) {
+cmesh* cmesh_new() {
+  cmesh* cm = malloc(sizeof(cmesh));
+  cm->triangles = NULL;
+  cm->triangles_num = 0;
+  cm->nodes = NULL;
+  cm->nodes_num = 0;
+  cm->bound = sphere_new(vec3_zero(), 0);
+  return cm;
+}
+
+static void cmesh_push_triangle(cmesh* cm, vec3 a, vec3 b, vec3 c) {
   
-  if (!cm->is_leaf) {
-    error("cmesh is not leaf type!");
-    return sphere_new(vec3_zero(), 0);
-  }
+  /* Degenerate triangles have no normal to collide with */
+  vec3 cross = vec3_cross(vec3_sub(b, a), vec3_sub(c, a));
+  if (vec3_length_sqrd(cross) == 0) { return; }
   
-  return sphere_new(cmesh_center(cm), cmesh_radius(cm));
+  cm->triangles_num++;
+  cm->triangles = realloc(cm->triangles, sizeof(ctri) * cm->triangles_num);
+  cm->triangles[cm->triangles_num-1] = ctri_new(a, b, c, vec3_normalize(cross));
   
 }
 
-void cmesh_subdivide(cmesh* cm, int iterations) {
+static void cmesh_add_mesh(cmesh* cm, mesh* m) {
+  for (int i = 0; i < m->num_triangles; i++) {
+    cmesh_push_triangle(cm,
+      m->verticies[m->triangles[i*3+0]].position,
+      m->verticies[m->triangles[i*3+1]].position,
+      m->verticies[m->triangles[i*3+2]].position);
+  }
+}
+
+cmesh* cmesh_from_mesh(mesh* m) {
+  cmesh* cm = cmesh_new();
+  cmesh_add_mesh(cm, m);
+  cmesh_build_bvh(cm);
+  return cm;
+}
+
+cmesh* cmesh_from_model(model* m) {
+  cmesh* cm = cmesh_new();
+  for (int i = 0; i < m->num_meshes; i++) {
+    cmesh_add_mesh(cm, m->meshes[i]);
+  }
+  cmesh_build_bvh(cm);
+  return cm;
+}
+
+typedef struct {
+  int x, y, z;
+  int vertex;
+} cmesh_cell;
+
+static int cmesh_cell_compare(const void* a, const void* b) {
+  const cmesh_cell* ca = a;
+  const cmesh_cell* cb = b;
+  if (ca->x != cb->x) { return ca->x < cb->x ? -1 : 1; }
+  if (ca->y != cb->y) { return ca->y < cb->y ? -1 : 1; }
+  if (ca->z != cb->z) { return ca->z < cb->z ? -1 : 1; }
+  return ca->vertex - cb->vertex;
+}
+
+static bool cmesh_cell_equal(cmesh_cell a, cmesh_cell b) {
+  return a.x == b.x && a.y == b.y && a.z == b.z;
+}
+
+static vec3 ctri_corner(ctri t, int corner) {
+  return corner == 0 ? t.a : (corner == 1 ? t.b : t.c);
+}
+
+void cmesh_simplify(cmesh* cm, float cell_size) {
   
-  if (!cm->is_leaf) {
-    error("Attempt to subdivide non-leaf bsp tree!");
+  if (cell_size <= 0) {
+    error("Cannot simplify cmesh with cell size %f", cell_size);
     return;
   }
   
-  if (iterations == 0) { return; }
-  if (cm->triangles_num < 10) { return; }
+  /* Cluster vertices on a grid and move each one to the average of its cell */
+  int verts_num = cm->triangles_num * 3;
+  cmesh_cell* cells = malloc(sizeof(cmesh_cell) * verts_num);
   
-  plane division = cmesh_division(cm);
+  for (int i = 0; i < verts_num; i++) {
+    vec3 p = ctri_corner(cm->triangles[i / 3], i % 3);
+    cells[i].x = (int)floorf(p.x / cell_size);
+    cells[i].y = (int)floorf(p.y / cell_size);
+    cells[i].z = (int)floorf(p.z / cell_size);
+    cells[i].vertex = i;
+  }
   
-  int num_front = 0;
-  int num_back  = 0;
+  qsort(cells, verts_num, sizeof(cmesh_cell), cmesh_cell_compare);
   
-  for(int i = 0; i < cm->triangles_num; i++) {
-    ctri t = cm->triangles[i];
+  vec3* positions = malloc(sizeof(vec3) * verts_num);
+  int* clusters = malloc(sizeof(int) * verts_num);
+  
+  int start = 0;
+  int cluster = 0;
+  while (start < verts_num) {
     
-    if (ctri_inside_plane(t, division)) {
-      num_back++;
-    } else if (ctri_outside_plane(t, division)) {
-      num_front++;
-    } else { 
-      num_back++; 
-      num_front++; 
+    int end = start;
+    vec3 total = vec3_zero();
+    while (end < verts_num && cmesh_cell_equal(cells[start], cells[end])) {
+      total = vec3_add(total, ctri_corner(cm->triangles[cells[end].vertex / 3], cells[end].vertex % 3));
+      end++;
     }
+    
+    vec3 average = vec3_div(total, end - start);
+    for (int i = start; i < end; i++) {
+      positions[cells[i].vertex] = average;
+      clusters[cells[i].vertex] = cluster;
+    }
+    
+    cluster++;
+    start = end;
   }
   
-  if (num_front > cm->triangles_num * 0.75) { return; }
-  if (num_back  > cm->triangles_num * 0.75) { return; }
+  /* Triangles with two corners in one cell collapse and are dropped */
+  ctri* triangles = cm->triangles;
+  int triangles_num = cm->triangles_num;
+  cm->triangles = NULL;
+  cm->triangles_num = 0;
+  
+  for (int i = 0; i < triangles_num; i++) {
+    int a = clusters[i*3+0];
+    int b = clusters[i*3+1];
+    int c = clusters[i*3+2];
+    if (a == b || b == c || c == a) { continue; }
+    cmesh_push_triangle(cm, positions[i*3+0], positions[i*3+1], positions[i*3+2]);
+  }
   
-  cmesh* front = malloc(sizeof(cmesh));
-  front->is_leaf = true;
-  front->triangles_num = num_front;
-  front->triangles = malloc(sizeof(ctri) * num_front);
+  free(triangles);
+  free(positions);
+  free(clusters);
+  free(cells);
   
-  cmesh* back = malloc(sizeof(cmesh));
-  back->is_leaf = true;
-  back->triangles_num = num_back;
-  back->triangles = malloc(sizeof(ctri) * num_back);
+  cmesh_build_bvh(cm);
   
-  int i_front = 0;
-  int i_back  = 0;
+}
+
+typedef struct {
+  int a, b, c;
+  plane p;
+} cmesh_face;
+
+static cmesh_face cmesh_face_new(vec3* points, int a, int b, int c) {
+  cmesh_face f;
+  f.a = a; f.b = b; f.c = c;
+  vec3 norm = vec3_normalize(vec3_cross(
+    vec3_sub(points[b], points[a]),
+    vec3_sub(points[c], points[a])));
+  f.p = plane_new(points[a], norm);
+  return f;
+}
+
+static float cmesh_face_distance(cmesh_face f, vec3 p) {
+  return vec3_dot(f.p.direction, vec3_sub(p, f.p.position));
+}
+
+static int cmesh_farthest(vec3* points, int points_num, float (*distance)(vec3 p, void* x), void* x) {
+  int farthest = 0;
+  for (int i = 1; i < points_num; i++) {
+    if (distance(points[i], x) > distance(points[farthest], x)) { farthest = i; }
+  }
+  return farthest;
+}
+
+static float cmesh_point_distance(vec3 p, void* x) {
+  return vec3_dist(p, *(vec3*)x);
+}
+
+static float cmesh_line_distance(vec3 p, void* x) {
+  vec3* line = x;
+  vec3 d = vec3_normalize(vec3_sub(line[1], line[0]));
+  vec3 o = vec3_sub(p, line[0]);
+  return vec3_length(vec3_sub(o, vec3_mul(d, vec3_dot(o, d))));
+}
+
+static float cmesh_plane_distance(vec3 p, void* x) {
+  return fabs(cmesh_face_distance(*(cmesh_face*)x, p));
+}
+
+static cmesh* cmesh_copy(cmesh* cm) {
+  cmesh* copy = cmesh_new();
+  for (int i = 0; i < cm->triangles_num; i++) {
+    cmesh_push_triangle(copy, cm->triangles[i].a, cm->triangles[i].b, cm->triangles[i].c);
+  }
+  cmesh_build_bvh(copy);
+  return copy;
+}
+
+cmesh* cmesh_convex_hull(cmesh* cm) {
   
-  for(int i = 0; i < cm->triangles_num; i++) {
+  if (cm->triangles_num < 2) {
+    warning("Cannot compute convex hull of flat cmesh, keeping its triangles");
+    return cmesh_copy(cm);
+  }
+  
+  int points_num = cm->triangles_num * 3;
+  vec3* points = malloc(sizeof(vec3) * points_num);
+  for (int i = 0; i < points_num; i++) {
+    points[i] = ctri_corner(cm->triangles[i / 3], i % 3);
+  }
+  
+  float epsilon = cm->bound.radius * 1e-5;
+  
+  /* Start from the largest tetrahedron spanned by extreme points */
+  int i0 = 0;
+  for (int i = 1; i < points_num; i++) {
+    if (points[i].x < points[i0].x) { i0 = i; }
+  }
+  int i1 = cmesh_farthest(points, points_num, cmesh_point_distance, &points[i0]);
+  vec3 line[2] = { points[i0], points[i1] };
+  int i2 = cmesh_farthest(points, points_num, cmesh_line_distance, line);
+  cmesh_face base = cmesh_face_new(points, i0, i1, i2);
+  int i3 = cmesh_farthest(points, points_num, cmesh_plane_distance, &base);
+  
+  if (fabs(cmesh_face_distance(base, points[i3])) <= epsilon) {
+    warning("Cannot compute convex hull of flat cmesh, keeping its triangles");
+    free(points);
+    return cmesh_copy(cm);
+  }
+  
+  if (cmesh_face_distance(base, points[i3]) > 0) {
+    int t = i1; i1 = i2; i2 = t;
+  }
+  
+  int faces_num = 4;
+  cmesh_face* faces = malloc(sizeof(cmesh_face) * faces_num);
+  faces[0] = cmesh_face_new(points, i0, i1, i2);
+  faces[1] = cmesh_face_new(points, i0, i3, i1);
+  faces[2] = cmesh_face_new(points, i1, i3, i2);
+  faces[3] = cmesh_face_new(points, i2, i3, i0);
+  
+  bool* visible = NULL;
+  int* horizon = NULL;
+  
+  for (int i = 0; i < points_num; i++) {
     
-    ctri t = cm->triangles[i];
+    vec3 p = points[i];
     
-    if (ctri_inside_plane(t, division)) {
-      back->triangles[i_back] = t;
-      i_back++;
-    } else if (ctri_outside_plane(t, division)) {
-      front->triangles[i_front] = t;
-      i_front++;
-    } else { 
-      back->triangles[i_back] = t;
-      front->triangles[i_front] = t;
-      i_back++; 
-      i_front++; 
+    visible = realloc(visible, sizeof(bool) * faces_num);
+    int visible_num = 0;
+    for (int j = 0; j < faces_num; j++) {
+      visible[j] = cmesh_face_distance(faces[j], p) > epsilon;
+      if (visible[j]) { visible_num++; }
+    }
+    
+    if (visible_num == 0) { continue; }
+    
+    /* Edges of visible faces whose twin belongs to a hidden face form the horizon */
+    horizon = realloc(horizon, sizeof(int) * visible_num * 6);
+    int horizon_num = 0;
+    
+    for (int j = 0; j < faces_num; j++) {
+      if (!visible[j]) { continue; }
+      int edges[6] = { faces[j].a, faces[j].b, faces[j].b, faces[j].c, faces[j].c, faces[j].a };
+      for (int e = 0; e < 6; e += 2) {
+        bool shared = false;
+        for (int k = 0; k < faces_num && !shared; k++) {
+          if (!visible[k] || k == j) { continue; }
+          int twins[6] = { faces[k].a, faces[k].b, faces[k].b, faces[k].c, faces[k].c, faces[k].a };
+          for (int t = 0; t < 6; t += 2) {
+            if (twins[t] == edges[e+1] && twins[t+1] == edges[e]) { shared = true; }
+          }
+        }
+        if (!shared) {
+          horizon[horizon_num++] = edges[e];
+          horizon[horizon_num++] = edges[e+1];
+        }
+      }
+    }
+    
+    int kept = 0;
+    for (int j = 0; j < faces_num; j++) {
+      if (!visible[j]) { faces[kept++] = faces[j]; }
+    }
+    
+    faces_num = kept + horizon_num / 2;
+    faces = realloc(faces, sizeof(cmesh_face) * faces_num);
+    for (int e = 0; e < horizon_num; e += 2) {
+      faces[kept++] = cmesh_face_new(points, horizon[e], horizon[e+1], i);
     }
     
   }
   
-  if (front->triangles_num > 0) {
-    front->bound = cmesh_bound(front);
-  } else {
-    front->bound = sphere_new(vec3_zero(), 0);
-  }
-  
-  if (back->triangles_num > 0) {
-    back->bound = cmesh_bound(back);
-  } else {
-    back->bound = sphere_new(vec3_zero(), 0);
+  cmesh* hull = cmesh_new();
+  for (int i = 0; i < faces_num; i++) {
+    cmesh_push_triangle(hull, points[faces[i].a], points[faces[i].b], points[faces[i].c]);
   }
+  cmesh_build_bvh(hull);
   
-  cmesh_subdivide(front, iterations-1);
-  cmesh_subdivide(back, iterations-1);
+  free(visible);
+  free(horizon);
+  free(faces);
+  free(points);
   
-  free(cm->triangles);
-  cm->is_leaf = false;
-  cm->division = division;
-  cm->front = front;
-  cm->back = back;
+  return hull;
   
 }
 
@@ -319,13 +536,12 @@ static int SDL_RWreadline(SDL_RWops* file, char* buffer, int buffersize) {
 
 cmesh* col_load_file(char* filename) {
     
-  cmesh* cm = malloc(sizeof(cmesh));
-  cm->is_leaf = true;
+  cmesh* cm = cmesh_new();
   
   vertex_list* vert_positions = vertex_list_new();
   vertex_list* vert_triangles = vertex_list_new();
   
-  SDL_RWops* file = SDL_RWFromFile(filename, "r");
+  SDL_RWops* file = asset_open(P(filename), "r");
   
   if(file == NULL) {
     error("Could not load file %s", filename);
@@ -446,13 +662,65 @@ cmesh* col_load_file(char* filename) {
   vertex_list_delete(vert_positions);
   vertex_list_delete(vert_triangles);
   
-  if (cm->triangles_num != 0) {
-    cm->bound = cmesh_bound(cm);
-  } else {
-    cm->bound = sphere_new(vec3_zero(), 0);
+  cmesh_build_bvh(cm);
+  
+  return cm;
+}
+
+typedef struct {
+  vec3 position;
+  int vertex;
+} cmesh_position;
+
+static int cmesh_position_compare(const void* a, const void* b) {
+  const cmesh_position* pa = a;
+  const cmesh_position* pb = b;
+  if (pa->position.x != pb->position.x) { return pa->position.x < pb->position.x ? -1 : 1; }
+  if (pa->position.y != pb->position.y) { return pa->position.y < pb->position.y ? -1 : 1; }
+  if (pa->position.z != pb->position.z) { return pa->position.z < pb->position.z ? -1 : 1; }
+  return pa->vertex - pb->vertex;
+}
+
+void col_save_file(cmesh* cm, char* filename) {
+  
+  SDL_RWops* file = SDL_RWFromFile(filename, "w");
+  
+  if (file == NULL) {
+    error("Could not save file %s", filename);
   }
   
-  cmesh_subdivide(cm, 5);
+  /* Share identical positions between triangles */
+  int verts_num = cm->triangles_num * 3;
+  cmesh_position* positions = malloc(sizeof(cmesh_position) * max(verts_num, 1));
+  int* indices = malloc(sizeof(int) * max(verts_num, 1));
+  
+  for (int i = 0; i < verts_num; i++) {
+    positions[i].position = ctri_corner(cm->triangles[i / 3], i % 3);
+    positions[i].vertex = i;
+  }
+  
+  qsort(positions, verts_num, sizeof(cmesh_position), cmesh_position_compare);
+  
+  char line[1024];
+  int written = 0;
+  for (int i = 0; i < verts_num; i++) {
+    if (i == 0 || !vec3_equ(positions[i].position, positions[i-1].position)) {
+      vec3 p = positions[i].position;
+      snprintf(line, 1024, "v %.9g %.9g %.9g\n", p.x, p.y, p.z);
+      SDL_RWwrite(file, line, strlen(line), 1);
+      written++;
+    }
+    indices[positions[i].vertex] = written;
+  }
+  
+  for (int i = 0; i < cm->triangles_num; i++) {
+    snprintf(line, 1024, "f %i %i %i\n", indices[i*3+0], indices[i*3+1], indices[i*3+2]);
+    SDL_RWwrite(file, line, strlen(line), 1);
+  }
+  
+  free(positions);
+  free(indices);
+  
+  SDL_RWclose(file);
   
-  return cm;
 }
@@ -33,7 +33,7 @@ static int SDL_RWreadline(SDL_RWops* file, char* buffer, int buffersize) {
 
 config* cfg_load_file(const char* filename) {
 
-  SDL_RWops* file = SDL_RWFromFile(filename, "r");
+  SDL_RWops* file = asset_open(P(filename), "r");
   if(file == NULL) {
     error("Cannot load file %s", filename);
   }
@@ -42,7 +42,7 @@ font* font_load_file(char* filename) {
   f->sizes = malloc( sizeof(vec2) * 256 );
   f->offsets = malloc( sizeof(vec2) * 256 );
   
-  SDL_RWops* file = SDL_RWFromFile(filename, "r");
+  SDL_RWops* file = asset_open(P(filename), "r");
   
   if(file == NULL) {
     error("Could not load file %s", filename);
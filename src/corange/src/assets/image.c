@@ -1,4 +1,5 @@
 #include "assets/image.h"
+#include "casset.h"
 
 #include "data/int_list.h"
 
@@ -841,19 +842,61 @@ void image_bmp_save_file(image* i, char* filename) {
   
 }
 
+enum {
+  MAX_IMAGE_HANDLERS = 64
+};
+
+typedef struct {
+  char* extension;
+  image* (*load_func)(const char*);
+} image_handler;
+
+static image_handler image_handlers[MAX_IMAGE_HANDLERS];
+static int num_image_handlers = 0;
+
+void image_handler_add(const char* extension, image* image_loader(const char* filename)) {
+  
+  for (int i = 0; i < num_image_handlers; i++) {
+    if (strcmp(image_handlers[i].extension, extension) == 0) {
+      image_handlers[i].load_func = image_loader;
+      return;
+    }
+  }
+  
+  if (num_image_handlers == MAX_IMAGE_HANDLERS) {
+    warning("Max number of image handlers reached. Handler for extension '%s' not added.", extension);
+    return;
+  }
+  
+  image_handler h;
+  h.extension = malloc(strlen(extension) + 1);
+  strcpy(h.extension, extension);
+  h.load_func = image_loader;
+  
+  image_handlers[num_image_handlers] = h;
+  num_image_handlers++;
+  
+}
+
 image* image_read_from_file(char* filename) {
   
   fpath ext;
   SDL_PathFileExtension(ext.ptr, filename);
   
+  for (int i = 0; i < num_image_handlers; i++) {
+    if (strcmp(ext.ptr, image_handlers[i].extension) == 0) {
+      return image_handlers[i].load_func(filename);
+    }
+  }
+  
        if ( strcmp(ext.ptr, "tga") == 0 ) { return image_tga_load_file(filename); }
   else if ( strcmp(ext.ptr, "bmp") == 0 ) { return image_bmp_load_file(filename); } 
-  else { error("Cannot save texture to %s, unknown file extension %s. Try .tga!\n", filename, ext.ptr); return NULL; }
+  else { error("Cannot load image %s, unknown file extension %s. Try .tga!\n", filename, ext.ptr); return NULL; }
 }
 
 image* image_tga_load_file(char* filename) {
 
-  SDL_RWops* file = SDL_RWFromFile(filename, "rb");
+  SDL_RWops* file = asset_open(P(filename), "rb");
   
 	if (file == NULL) {
 		error("Cannot open file %s", filename);
@@ -928,7 +971,7 @@ image* image_tga_load_file(char* filename) {
 
 image* image_bmp_load_file(char* filename) {
   
-  SDL_Surface *surface = SDL_LoadBMP(filename);
+  SDL_Surface *surface = SDL_LoadBMP_RW(asset_open(P(filename), "rb"), 1);
   
   if (!surface) { error("Could not load file %s\n", filename); }
   
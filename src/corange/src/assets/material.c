@@ -1,7 +1,9 @@
 #include "assets/material.h"
 
 void material_entry_delete(material_entry* me) {
-  shader_program_delete(me->program);
+  if (me->program != NULL) {
+    shader_program_delete(me->program);
+  }
   for(int i = 0; i < me->num_items; i++) {
     free(me->names[i]);
   }
@@ -137,9 +139,9 @@ static int SDL_RWreadline(SDL_RWops* file, char* buffer, int buffersize) {
   
 }
 
-material* mat_load_file(char* filename) {
+material* mat_read_file(char* filename) {
   
-  SDL_RWops* file = SDL_RWFromFile(filename, "r");
+  SDL_RWops* file = asset_open(P(filename), "r");
   if(file == NULL) {
     error("Cannot load file %s", filename);
   }
@@ -176,6 +178,11 @@ material* mat_load_file(char* filename) {
     char* end;
     float f0, f1, f2, f3;
     
+    /* Vector components are separated by spaces, so read them from the whole line */
+    char* equals = strchr(line, '=');
+    if (equals == NULL) continue;
+    char* components = equals + 1;
+    
     if (strcmp(type, "shader") == 0) {
     
       mi.as_asset = asset_hndl_new_load(P(value));
@@ -198,20 +205,20 @@ material* mat_load_file(char* filename) {
       
     } else if (strcmp(type, "vec2") == 0) {
     
-      f0 = strtod(value, &end); f1 = strtod(end, NULL);
+      f0 = strtod(components, &end); f1 = strtod(end, NULL);
       mi.as_vec2 = vec2_new(f0, f1);
       type_id = mat_item_vec2;
       
     } else if (strcmp(type, "vec3") == 0) {
       
-      f0 = strtod(value, &end); f1 = strtod(end, &end);
+      f0 = strtod(components, &end); f1 = strtod(end, &end);
       f2 = strtod(end, NULL);
       mi.as_vec3 = vec3_new(f0, f1, f2);
       type_id = mat_item_vec3;
       
     } else if (strcmp(type, "vec4") == 0) {
     
-      f0 = strtod(value, &end); f1 = strtod(end, &end);
+      f0 = strtod(components, &end); f1 = strtod(end, &end);
       f2 = strtod(end, &end); f3 = strtod(end, NULL);
       mi.as_vec4 = vec4_new(f0, f1, f2, f3);
       type_id = mat_item_vec4;
@@ -227,6 +234,13 @@ material* mat_load_file(char* filename) {
   
   SDL_RWclose(file);
   
+  return m;
+}
+
+material* mat_load_file(char* filename) {
+  
+  material* m = mat_read_file(filename);
+  
   material_generate_programs(m);
   
   SDL_GL_CheckError();
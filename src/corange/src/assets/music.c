@@ -1,16 +1,17 @@
 
 #include "assets/music.h"
+#include "casset.h"
 
 music* mp3_load_file(char* filename) {
   music* m = malloc(sizeof(music));
-  m->handle = Mix_LoadMUS(filename);
+  m->handle = Mix_LoadMUS_RW(asset_open(P(filename), "rb"), 1);
   if (!m->handle) { error("Couldn't load music '%s' : %s", filename, Mix_GetError()); }
   return m;
 }
 
 music* ogg_load_file(char* filename) {
   music* m = malloc(sizeof(music));
-  m->handle = Mix_LoadMUS(filename);
+  m->handle = Mix_LoadMUS_RW(asset_open(P(filename), "rb"), 1);
   if (!m->handle) { error("Couldn't load music '%s' : %s", filename, Mix_GetError()); }
   return m;
 }
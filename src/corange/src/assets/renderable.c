@@ -48,6 +48,23 @@ void renderable_set_material(renderable* r, asset_hndl mat) {
   r->material = mat;
 }
 
+sphere renderable_bound(renderable* r) {
+  
+  if (r->num_surfaces == 0) { return sphere_point(); }
+  
+  sphere bound = r->surfaces[0]->bound;
+  
+  for (int i = 1; i < r->num_surfaces; i++) {
+    sphere s = r->surfaces[i]->bound;
+    float dist = vec3_dist(bound.center, s.center);
+    if (dist + s.radius <= bound.radius) { continue; }
+    if (dist + bound.radius <= s.radius) { bound = s; continue; }
+    bound = sphere_merge(bound, s);
+  }
+  
+  return bound;
+}
+
 model* renderable_to_model(renderable* r) {
 
   if (r->is_rigged) {
@@ -119,6 +136,121 @@ model* renderable_to_model(renderable* r) {
   return m;
 }
 
+cmesh* renderable_to_cmesh(renderable* r) {
+  
+  /* Only positions are needed, so rigged surfaces can be read too */
+  const int vertsize = r->is_rigged ? 24 : 18;
+  
+  mesh* me = mesh_new();
+  
+  for(int i = 0; i < r->num_surfaces; i++) {
+    
+    renderable_surface* s = r->surfaces[i];
+    
+    float* vb_data = malloc(sizeof(float) * s->num_verticies * vertsize);
+    uint32_t* ib_data = malloc(sizeof(uint32_t) * s->num_triangles * 3);
+    
+    glBindBuffer(GL_ARRAY_BUFFER, s->vertex_vbo);
+    glGetBufferSubData(GL_ARRAY_BUFFER, 0, sizeof(float) * s->num_verticies * vertsize, vb_data);
+    
+    glBindBuffer(GL_ELEMENT_ARRAY_BUFFER, s->triangle_vbo);
+    glGetBufferSubData(GL_ELEMENT_ARRAY_BUFFER, 0, sizeof(uint32_t) * s->num_triangles * 3, ib_data);
+    
+    int offset = me->num_verts;
+    me->num_verts += s->num_verticies;
+    me->verticies = realloc(me->verticies, sizeof(vertex) * me->num_verts);
+    
+    for(int j = 0; j < s->num_verticies; j++) {
+      me->verticies[offset+j] = vertex_new();
+      me->verticies[offset+j].position.x = vb_data[(j*vertsize)+0];
+      me->verticies[offset+j].position.y = vb_data[(j*vertsize)+1];
+      me->verticies[offset+j].position.z = vb_data[(j*vertsize)+2];
+    }
+    
+    int first = me->num_triangles * 3;
+    me->num_triangles += s->num_triangles;
+    me->triangles = realloc(me->triangles, sizeof(uint32_t) * me->num_triangles * 3);
+    
+    for(int j = 0; j < s->num_triangles * 3; j++) {
+      me->triangles[first+j] = offset + ib_data[j];
+    }
+    
+    free(vb_data);
+    free(ib_data);
+  
+  }
+  
+  glBindBuffer(GL_ELEMENT_ARRAY_BUFFER, 0);
+  glBindBuffer(GL_ARRAY_BUFFER, 0);
+  
+  cmesh* cm = cmesh_from_mesh(me);
+  mesh_delete(me);
+  
+  return cm;
+}
+
+mesh* renderable_surface_to_mesh(renderable* r, int i, vertex_weight* weights) {
+  
+  const int vertsize = r->is_rigged ? 24 : 18;
+  renderable_surface* s = r->surfaces[i];
+  
+  float* vb_data = malloc(sizeof(float) * s->num_verticies * vertsize);
+  
+  mesh* me = mesh_new();
+  me->num_verts = s->num_verticies;
+  me->num_triangles = s->num_triangles;
+  me->verticies = realloc(me->verticies, sizeof(vertex) * me->num_verts);
+  me->triangles = realloc(me->triangles, sizeof(uint32_t) * me->num_triangles * 3);
+  
+  glBindBuffer(GL_ARRAY_BUFFER, s->vertex_vbo);
+  glGetBufferSubData(GL_ARRAY_BUFFER, 0, sizeof(float) * s->num_verticies * vertsize, vb_data);
+  
+  glBindBuffer(GL_ELEMENT_ARRAY_BUFFER, s->triangle_vbo);
+  glGetBufferSubData(GL_ELEMENT_ARRAY_BUFFER, 0, sizeof(uint32_t) * s->num_triangles * 3, me->triangles);
+  
+  for(int j = 0; j < me->num_verts; j++) {
+    float* v = vb_data + j * vertsize;
+    me->verticies[j].position = vec3_new(v[0], v[1], v[2]);
+    me->verticies[j].normal   = vec3_new(v[3], v[4], v[5]);
+    me->verticies[j].tangent  = vec3_new(v[6], v[7], v[8]);
+    me->verticies[j].binormal = vec3_new(v[9], v[10], v[11]);
+    me->verticies[j].uvs      = vec2_new(v[12], v[13]);
+    
+    /* Rigged surfaces store the color alpha first */
+    if (r->is_rigged) {
+      me->verticies[j].color = vec4_new(v[15], v[16], v[17], v[14]);
+      if (weights != NULL) {
+        for(int k = 0; k < 3; k++) {
+          weights[j].bone_ids[k] = (int)v[18+k];
+          weights[j].bone_weights[k] = v[21+k];
+        }
+      }
+    } else {
+      me->verticies[j].color = vec4_new(v[14], v[15], v[16], v[17]);
+    }
+  }
+  
+  free(vb_data);
+  
+  glBindBuffer(GL_ELEMENT_ARRAY_BUFFER, 0);
+  glBindBuffer(GL_ARRAY_BUFFER, 0);
+  
+  return me;
+}
+
+void renderable_set_surface(renderable* r, int i, mesh* m, vertex_weight* weights) {
+  
+  renderable_surface_delete(r->surfaces[i]);
+  
+  if (r->is_rigged) {
+    r->surfaces[i] = renderable_surface_new_rigged(m, weights);
+    r->surfaces[i]->bound = mesh_bounding_sphere(m);
+  } else {
+    r->surfaces[i] = renderable_surface_new(m);
+  }
+  
+}
+
 renderable_surface* renderable_surface_new(mesh* m) {
 
   renderable_surface* s = malloc(sizeof(renderable_surface));
@@ -290,7 +422,7 @@ renderable* bmf_load_file(char* filename) {
 
   renderable* r = malloc(sizeof(renderable));
   
-  SDL_RWops* file = SDL_RWFromFile(filename, "rb");
+  SDL_RWops* file = asset_open(P(filename), "rb");
   
   if(file == NULL) {
     error("Could not load file %s", filename);
@@ -457,7 +589,7 @@ static int SDL_RWreadline(SDL_RWops* file, char* buffer, int buffersize) {
   
 }
 
-renderable* obj_load_file(char* filename) {
+model* obj_load_model(char* filename) {
     
   model* obj_model = malloc(sizeof(model));
   obj_model->num_meshes = 0;
@@ -478,7 +610,7 @@ renderable* obj_load_file(char* filename) {
   bool has_normal_data = false;
   bool has_texcoord_data = false;
   
-  SDL_RWops* file = SDL_RWFromFile(filename, "r");
+  SDL_RWops* file = asset_open(P(filename), "r");
   
   if(file == NULL) {
     error("Could not load file %s", filename);
@@ -888,6 +1020,13 @@ renderable* obj_load_file(char* filename) {
   
   model_generate_tangents(obj_model);
   
+  return obj_model;
+}
+
+renderable* obj_load_file(char* filename) {
+  
+  model* obj_model = obj_load_model(filename);
+  
   renderable* renderable = renderable_new();
   renderable_add_model(renderable, obj_model);
   model_delete(obj_model);
@@ -924,12 +1063,23 @@ static void renderable_add_mesh_rigged(renderable* r, mesh* m, vertex_weight* we
   
 }
 
+static void smd_add_mesh(model* smd_model, vertex_weight*** mesh_weights, mesh* m, vertex_weight* weights) {
+  
+  smd_model->num_meshes++;
+  smd_model->meshes = realloc(smd_model->meshes, sizeof(mesh*) * smd_model->num_meshes);
+  smd_model->meshes[smd_model->num_meshes-1] = m;
+  
+  *mesh_weights = realloc(*mesh_weights, sizeof(vertex_weight*) * smd_model->num_meshes);
+  (*mesh_weights)[smd_model->num_meshes-1] = weights;
+  
+}
+
 enum {
   STATE_LOAD_EMPTY     = 0,
   STATE_LOAD_TRIANGLES = 1,
 };
 
-renderable* smd_load_file(char* filename) {
+model* smd_load_model(char* filename, vertex_weight*** mesh_weights) {
   
   int state = STATE_LOAD_EMPTY;
   char state_material[1024];
@@ -943,14 +1093,14 @@ renderable* smd_load_file(char* filename) {
   
   int vert_index = 0;
   
-  SDL_RWops* file = SDL_RWFromFile(filename, "r");
+  SDL_RWops* file = asset_open(P(filename), "r");
   
   if(file == NULL) {
     error("Could not load file %s", filename);
   }
   
-  renderable* r = renderable_new();
-  r->is_rigged = true;
+  model* smd_model = model_new();
+  *mesh_weights = NULL;
   
   char line[1024];
   while(SDL_RWreadline(file, line, 1024)) {
@@ -1095,8 +1245,7 @@ renderable* smd_load_file(char* filename) {
           }
           
           mesh_generate_tangents(m);
-          renderable_add_mesh_rigged(r, m, weights);
-          mesh_delete(m);
+          smd_add_mesh(smd_model, mesh_weights, m, weights);
           
           vertex_hashtable_delete(hashes);
           vertex_list_delete(vert_list);
@@ -1108,7 +1257,7 @@ renderable* smd_load_file(char* filename) {
           tri_list = int_list_new();
           
           allocated_weights = 1024;
-          weights = realloc(weights, sizeof(vertex_weight) * 1024);
+          weights = malloc(sizeof(vertex_weight) * allocated_weights);
           
         }
         
@@ -1137,13 +1286,30 @@ renderable* smd_load_file(char* filename) {
   }
   
   mesh_generate_tangents(m);  
-  renderable_add_mesh_rigged(r, m, weights);
-  mesh_delete(m);
+  smd_add_mesh(smd_model, mesh_weights, m, weights);
   
   vertex_hashtable_delete(hashes);
   vertex_list_delete(vert_list);
   int_list_delete(tri_list);
+  
+  return smd_model;
+}
+
+renderable* smd_load_file(char* filename) {
+  
+  vertex_weight** weights = NULL;
+  model* smd_model = smd_load_model(filename, &weights);
+  
+  renderable* r = renderable_new();
+  r->is_rigged = true;
+  
+  for (int i = 0; i < smd_model->num_meshes; i++) {
+    renderable_add_mesh_rigged(r, smd_model->meshes[i], weights[i]);
+    free(weights[i]);
+  }
+  
   free(weights);
+  model_delete(smd_model);
 
   fpath mat_file;
   fpath bmf_file;
@@ -1167,15 +1333,25 @@ renderable* smd_load_file(char* filename) {
   return r;
 }
 
-renderable* ply_load_file(char* filename) {
+static void ply_add_mesh(model* ply_model, mesh* m) {
   
-  SDL_RWops* file = SDL_RWFromFile(filename, "r");
+  mesh_generate_tangents(m);
+  
+  ply_model->num_meshes++;
+  ply_model->meshes = realloc(ply_model->meshes, sizeof(mesh*) * ply_model->num_meshes);
+  ply_model->meshes[ply_model->num_meshes-1] = m;
+  
+}
+
+model* ply_load_model(char* filename) {
+  
+  SDL_RWops* file = asset_open(P(filename), "r");
   
   if(file == NULL) {
     error("Could not load file %s", filename);
   }
   
-  renderable* r = renderable_new();
+  model* ply_model = model_new();
   
   mesh* curr_mesh = NULL;
   int vert_id = 0;
@@ -1187,9 +1363,7 @@ renderable* ply_load_file(char* filename) {
     if (strstr(line, "ply")) {
       
       if (curr_mesh != NULL) {
-        mesh_generate_tangents(curr_mesh);
-        renderable_add_mesh(r, curr_mesh);
-        mesh_delete(curr_mesh);
+        ply_add_mesh(ply_model, curr_mesh);
       }
     
       curr_mesh = mesh_new();
@@ -1235,11 +1409,20 @@ renderable* ply_load_file(char* filename) {
   SDL_RWclose(file);
   
   if (curr_mesh != NULL) {
-    mesh_generate_tangents(curr_mesh);
-    renderable_add_mesh(r, curr_mesh);
-    mesh_delete(curr_mesh);
+    ply_add_mesh(ply_model, curr_mesh);
   }
   
+  return ply_model;
+}
+
+renderable* ply_load_file(char* filename) {
+  
+  model* ply_model = ply_load_model(filename);
+  
+  renderable* r = renderable_new();
+  renderable_add_model(r, ply_model);
+  model_delete(ply_model);
+  
   fpath mat_file;
   fpath bmf_file;
   fpath fileid;
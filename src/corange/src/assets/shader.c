@@ -5,7 +5,7 @@ static shader* load_shader_file(char* filename, GLenum type) {
 
   shader* new_shader = malloc(sizeof(shader));
   
-  SDL_RWops* file = SDL_RWFromFile(filename, "r");
+  SDL_RWops* file = asset_open(P(filename), "r");
   if(file == NULL) {
     error("Cannot load file %s", filename);
   }
@@ -58,6 +58,56 @@ shader* tes_load_file(char* filename) {
   return load_shader_file(filename, GL_TESS_EVALUATION_SHADER);
 }
 
+bool shader_file_compiles(char* filename, char* log, int log_size) {
+  
+  log[0] = '\0';
+  
+  fpath ext;
+  SDL_PathFileExtension(ext.ptr, filename);
+  
+  GLenum type;
+  if (strcmp(ext.ptr, "vs") == 0) { type = GL_VERTEX_SHADER; }
+  else if (strcmp(ext.ptr, "fs") == 0) { type = GL_FRAGMENT_SHADER; }
+  else if (strcmp(ext.ptr, "gs") == 0) { type = GL_GEOMETRY_SHADER; }
+  else if (strcmp(ext.ptr, "tcs") == 0) { type = GL_TESS_CONTROL_SHADER; }
+  else if (strcmp(ext.ptr, "tes") == 0) { type = GL_TESS_EVALUATION_SHADER; }
+  else {
+    snprintf(log, log_size, "Unknown shader extension '%s'", ext.ptr);
+    return false;
+  }
+  
+  SDL_RWops* file = asset_open(P(filename), "r");
+  if(file == NULL) {
+    snprintf(log, log_size, "Cannot load file %s", filename);
+    return false;
+  }
+  
+  long size = SDL_RWseek(file,0,SEEK_END);
+  char* contents = malloc(size+1);
+  contents[size] = '\0';
+  
+  SDL_RWseek(file, 0, SEEK_SET);
+  SDL_RWread(file, contents, size, 1);
+  SDL_RWclose(file);
+  
+  GLuint handle = glCreateShader(type);
+  glShaderSource(handle, 1, (const char**)&contents, NULL);
+  glCompileShader(handle);
+  
+  free(contents);
+  
+  int compile_error = 0;
+  glGetShaderiv(handle, GL_COMPILE_STATUS, &compile_error);
+  if (compile_error == GL_FALSE) {
+    glGetShaderInfoLog(handle, log_size, NULL, log);
+  }
+  
+  glDeleteShader(handle);
+  SDL_GL_CheckError();
+  
+  return compile_error != GL_FALSE;
+}
+
 shader_program* shader_program_new() {
   shader_program* program = malloc(sizeof(shader_program));  
   *program = glCreateProgram();
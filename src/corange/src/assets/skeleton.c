@@ -1,4 +1,5 @@
 #include "assets/skeleton.h"
+#include "casset.h"
 
 frame* frame_new() {
   frame* f = malloc(sizeof(frame));
@@ -229,7 +230,7 @@ skeleton* skl_load_file(char* filename) {
   
   skeleton* s =  skeleton_new();
   
-  SDL_RWops* file = SDL_RWFromFile(filename, "r");
+  SDL_RWops* file = asset_open(P(filename), "r");
   
   if(file == NULL) {
     error("Could not load file %s", filename);
@@ -1,8 +1,9 @@
 #include "assets/sound.h"
+#include "casset.h"
 
 sound* wav_load_file(char* filename) {
   sound* s = malloc(sizeof(sound));
-  s->sample = Mix_LoadWAV(filename);
+  s->sample = Mix_LoadWAV_RW(asset_open(P(filename), "rb"), 1);
   
   if (!s->sample) { error("Couldn't load sound '%s' : %s", filename, Mix_GetError()); }
   
@@ -1,4 +1,5 @@
 #include "assets/terrain.h"
+#include "casset.h"
 
 #include "cnet.h"
 
@@ -228,7 +229,8 @@ static void terrain_new_chunk(terrain* ter, int i) {
   }
   
   tc->colmesh = malloc(sizeof(cmesh));
-  tc->colmesh->is_leaf = true;
+  tc->colmesh->nodes = NULL;
+  tc->colmesh->nodes_num = 0;
   tc->colmesh->triangles_num = (tc->width/4) * (tc->height/4) * 2;
   tc->colmesh->triangles = malloc(sizeof(ctri) * tc->colmesh->triangles_num);
   
@@ -254,9 +256,7 @@ static void terrain_new_chunk(terrain* ter, int i) {
   
   }
   
-  tc->colmesh->bound = cmesh_bound(tc->colmesh);
-  
-  cmesh_subdivide(tc->colmesh, 5);
+  cmesh_build_bvh(tc->colmesh);
 
   ter->chunks[i] = tc;
 
@@ -283,7 +283,7 @@ static const float MAX_HEIGHT = 128;
 
 terrain* raw_load_file(char* filename) {
   
-  SDL_RWops* file = SDL_RWFromFile(filename, "rb");
+  SDL_RWops* file = asset_open(P(filename), "rb");
   
   if (!file) {
     error("Could not load file %s\n", filename);
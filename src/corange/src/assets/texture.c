@@ -1,4 +1,5 @@
 #include "assets/texture.h"
+#include "casset.h"
 
 #include "data/spline.h"
 
@@ -11,6 +12,19 @@ texture* texture_new() {
   return t;
 }
 
+texture* texture_new_empty(int width, int height) {
+  
+  texture* t = texture_new();
+  glBindTexture(GL_TEXTURE_2D, texture_handle(t));
+  glTexImage2D(GL_TEXTURE_2D, 0, GL_RGBA, width, height, 0, GL_RGBA, GL_UNSIGNED_BYTE, NULL);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
+  
+  return t;
+}
+
 void texture_delete(texture* t) {
   glDeleteTextures(1, &t->handle);
   free(t);
@@ -204,7 +218,7 @@ void texture_write_to_file(texture* t, char* filename){
 
 texture* lut_load_file( char* filename ) {
   
-  SDL_RWops* file = SDL_RWFromFile(filename, "r");
+  SDL_RWops* file = asset_open(P(filename), "r");
   if(file == NULL) {
     error("Cannot load file %s", filename);
   }
@@ -418,52 +432,23 @@ static bool is_power_of_two(unsigned int x) {
   return (x == 1);
 }
 
-texture* dds_load_file( char* filename ) {
-  
-  DdsLoadInfo loadInfoDXT1 =   { true,  false, false, 4, 8,  GL_COMPRESSED_RGBA_S3TC_DXT1 };
-  DdsLoadInfo loadInfoDXT3 =   { true,  false, false, 4, 16, GL_COMPRESSED_RGBA_S3TC_DXT3 };
-  DdsLoadInfo loadInfoDXT5 =   { true,  false, false, 4, 16, GL_COMPRESSED_RGBA_S3TC_DXT5 };
-  DdsLoadInfo loadInfoBGRA8 =  { false, false, false, 1, 4,  GL_RGBA8,   GL_BGRA, GL_UNSIGNED_BYTE };
-  DdsLoadInfo loadInfoBGR8 =   { false, false, false, 1, 3,  GL_RGB8,    GL_BGR,  GL_UNSIGNED_BYTE };
-  DdsLoadInfo loadInfoBGR5A1 = { false, true,  false, 1, 2,  GL_RGB5_A1, GL_BGRA, GL_UNSIGNED_SHORT_1_5_5_5_REV };
-  DdsLoadInfo loadInfoBGR565 = { false, true,  false, 1, 2,  GL_RGB5,    GL_RGB,  GL_UNSIGNED_SHORT_5_6_5 };
-  DdsLoadInfo loadInfoIndex8 = { false, false, true,  1, 1,  GL_RGB8,    GL_BGRA, GL_UNSIGNED_BYTE };
-  
-  SDL_RWops* f = SDL_RWFromFile(filename, "rb");
-  
-  if (f == NULL) {
-    error("Cannot load file %s", filename);
-  }
-  
-  DDS_header hdr;
-  SDL_RWread(f, &hdr, 1, sizeof(DDS_header));
-  
-  if( hdr.dwMagic != DDS_MAGIC || hdr.dwSize != 124 ||
-    !(hdr.dwFlags & DDSD_PIXELFORMAT) || !(hdr.dwFlags & DDSD_CAPS) ) {
-    error("Cannot Load File %s: Does not appear to be a .dds file.\n", filename);
-  }
+/* In the order of the formats dds_load_levels takes */
+static DdsLoadInfo dds_load_infos[] = {
+  { true,  false, false, 4, 8,  GL_COMPRESSED_RGBA_S3TC_DXT1 },
+  { true,  false, false, 4, 16, GL_COMPRESSED_RGBA_S3TC_DXT3 },
+  { true,  false, false, 4, 16, GL_COMPRESSED_RGBA_S3TC_DXT5 },
+  { false, false, false, 1, 4,  GL_RGBA8,   GL_BGRA, GL_UNSIGNED_BYTE },
+  { false, false, false, 1, 3,  GL_RGB8,    GL_BGR,  GL_UNSIGNED_BYTE },
+  { false, true,  false, 1, 2,  GL_RGB5_A1, GL_BGRA, GL_UNSIGNED_SHORT_1_5_5_5_REV },
+  { false, true,  false, 1, 2,  GL_RGB5,    GL_RGB,  GL_UNSIGNED_SHORT_5_6_5 },
+  { false, false, true,  1, 1,  GL_RGB8,    GL_BGRA, GL_UNSIGNED_BYTE },
+};
 
-  int x = hdr.dwWidth;
-  int y = hdr.dwHeight;
-  int mip_map_num = (hdr.dwFlags & DDSD_MIPMAPCOUNT) ? hdr.dwMipMapCount : 1;
-  
-  if (!is_power_of_two(x)) { error("Texture %s with is %i pixels which is not a power of two!", filename, x); }
-  if (!is_power_of_two(y)) { error("Texture %s height is %i pixels which is not a power of two!", filename, y); }
-  
-  DdsLoadInfo* li = &loadInfoDXT1;
-  if      (PF_IS_DXT1(hdr.sPixelFormat  )) { li = &loadInfoDXT1;   }
-  else if (PF_IS_DXT3(hdr.sPixelFormat  )) { li = &loadInfoDXT3;   }
-  else if (PF_IS_DXT5(hdr.sPixelFormat  )) { li = &loadInfoDXT5;   } 
-  else if (PF_IS_BGRA8(hdr.sPixelFormat )) { li = &loadInfoBGRA8;  }
-  else if (PF_IS_BGR8(hdr.sPixelFormat  )) { li = &loadInfoBGR8;   }
-  else if (PF_IS_BGR5A1(hdr.sPixelFormat)) { li = &loadInfoBGR5A1; }
-  else if (PF_IS_BGR565(hdr.sPixelFormat)) { li = &loadInfoBGR565; } 
-  else if (PF_IS_INDEX8(hdr.sPixelFormat)) { li = &loadInfoIndex8; }
-  else { error("Cannot Load File %s: Unknown DDS File format type.", filename); }
+static texture* dds_upload( SDL_RWops* f, DdsLoadInfo* li, int width, int height, int mip_map_num, int pitch, bool cubemap ) {
   
   texture* t = texture_new();
   
-  if (hdr.sCaps.dwCaps2 & DDSCAPS2_CUBEMAP) {
+  if (cubemap) {
     t->type = GL_TEXTURE_CUBE_MAP;
     glBindTexture(GL_TEXTURE_CUBE_MAP, texture_handle(t));
     glTexParameteri(GL_TEXTURE_CUBE_MAP, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
@@ -491,9 +476,8 @@ texture* dds_load_file( char* filename ) {
       target = GL_TEXTURE_CUBE_MAP_POSITIVE_X + i;
     }
         
-    int x = hdr.dwWidth;
-    int y = hdr.dwHeight;
-    int mip_map_num = (hdr.dwFlags & DDSD_MIPMAPCOUNT) ? hdr.dwMipMapCount : 1;
+    int x = width;
+    int y = height;
     
     if ( li->compressed ) {
       
@@ -515,7 +499,7 @@ texture* dds_load_file( char* filename ) {
       
     } else if ( li->palette ) {
       
-      size_t size = hdr.dwPitchOrLinearSize * y;
+      size_t size = pitch * y;
       char* data = malloc(size);
       int palette[256];
       int* unpacked = malloc(size * sizeof(int));
@@ -567,9 +551,58 @@ texture* dds_load_file( char* filename ) {
     
   }
   
+  SDL_GL_CheckError();
+  
+  return t;
+  
+}
+
+texture* dds_load_file( char* filename ) {
+  
+  SDL_RWops* f = asset_open(P(filename), "rb");
+  
+  if (f == NULL) {
+    error("Cannot load file %s", filename);
+  }
+  
+  DDS_header hdr;
+  SDL_RWread(f, &hdr, 1, sizeof(DDS_header));
+  
+  if( hdr.dwMagic != DDS_MAGIC || hdr.dwSize != 124 ||
+    !(hdr.dwFlags & DDSD_PIXELFORMAT) || !(hdr.dwFlags & DDSD_CAPS) ) {
+    error("Cannot Load File %s: Does not appear to be a .dds file.\n", filename);
+  }
+
+  int x = hdr.dwWidth;
+  int y = hdr.dwHeight;
+  int mip_map_num = (hdr.dwFlags & DDSD_MIPMAPCOUNT) ? hdr.dwMipMapCount : 1;
+  
+  if (!is_power_of_two(x)) { error("Texture %s with is %i pixels which is not a power of two!", filename, x); }
+  if (!is_power_of_two(y)) { error("Texture %s height is %i pixels which is not a power of two!", filename, y); }
+  
+  DdsLoadInfo* li = &dds_load_infos[0];
+  if      (PF_IS_DXT1(hdr.sPixelFormat  )) { li = &dds_load_infos[0]; }
+  else if (PF_IS_DXT3(hdr.sPixelFormat  )) { li = &dds_load_infos[1]; }
+  else if (PF_IS_DXT5(hdr.sPixelFormat  )) { li = &dds_load_infos[2]; } 
+  else if (PF_IS_BGRA8(hdr.sPixelFormat )) { li = &dds_load_infos[3]; }
+  else if (PF_IS_BGR8(hdr.sPixelFormat  )) { li = &dds_load_infos[4]; }
+  else if (PF_IS_BGR5A1(hdr.sPixelFormat)) { li = &dds_load_infos[5]; }
+  else if (PF_IS_BGR565(hdr.sPixelFormat)) { li = &dds_load_infos[6]; } 
+  else if (PF_IS_INDEX8(hdr.sPixelFormat)) { li = &dds_load_infos[7]; }
+  else { error("Cannot Load File %s: Unknown DDS File format type.", filename); }
+  
+  texture* t = dds_upload(f, li, x, y, mip_map_num, hdr.dwPitchOrLinearSize, hdr.sCaps.dwCaps2 & DDSCAPS2_CUBEMAP);
   SDL_RWclose(f);
   
-  SDL_GL_CheckError();
+  return t;
+  
+}
+
+texture* dds_load_levels( const void* data, int size, int width, int height, int mipmaps, int pitch, int format, bool cubemap ) {
+  
+  SDL_RWops* f = SDL_RWFromConstMem(data, size);
+  texture* t = dds_upload(f, &dds_load_infos[format], width, height, mipmaps, pitch, cubemap);
+  SDL_RWclose(f);
   
   return t;
   
@@ -8,7 +8,8 @@ static uint32_t asset_timestamp = 0;
 
 enum {
   MAX_ASSET_HANDLERS = 512,
-  MAX_PATH_VARIABLES = 512
+  MAX_PATH_VARIABLES = 512,
+  MAX_MOUNTS = 512
 };
 
 typedef struct {
@@ -29,6 +30,19 @@ typedef struct {
 static path_variable path_variables[MAX_PATH_VARIABLES];
 static int num_path_variables = 0;
 
+typedef struct {
+  int id;
+  fpath folder;
+  int num_files;
+  char** names;
+  const void** data;
+  int* sizes;
+} mount;
+
+static mount mounts[MAX_MOUNTS];
+static int num_mounts = 0;
+static int next_mount_id = 0;
+
 void asset_add_path_variable(fpath variable, fpath mapping) {
   
   if (num_path_variables == MAX_PATH_VARIABLES) {
@@ -58,40 +72,51 @@ static fpath asset_map_shortpath(fpath filename) {
   return out;
 }
 
-fpath asset_unmap_filename(fpath filename) {
+/* Absolute path without '.', '..' or repeated slashes, for files which may not exist */
+static fpath asset_map_absolute(fpath filename) {
   
-  fpath fullpath = asset_map_fullpath(filename);
+  fpath in = filename;
+  SDL_PathForwardSlashes(in.ptr);
   
-  for (int i = 0; i < num_path_variables; i++) {
-    
-    fpath variable = path_variables[i].variable;
-    fpath mapping  = path_variables[i].mapping; 
-    fpath fullmapping = asset_map_fullpath(mapping);
-    
-    char* subptr = strstr(fullpath.ptr, fullmapping.ptr);
-    
-    if (subptr) {
-    
-      fpath sub; strcpy(sub.ptr, subptr);
-    
-      int replace_len = strlen(variable.ptr);
-      int start_len = strlen(fullpath.ptr) - strlen(sub.ptr);
-      int ext_len = strlen(sub.ptr) - strlen(fullmapping.ptr);
-      
-      fullpath.ptr[start_len] = '\0';
-      strcat(fullpath.ptr, variable.ptr);
-      strcat(fullpath.ptr, "/");
-      strcat(fullpath.ptr, sub.ptr + strlen(fullmapping.ptr));
-      
+  fpath out;
+  char* rest = in.ptr;
+  
+  if (in.ptr[0] == '/') {
+    strcpy(out.ptr, "");
+  } else if (in.ptr[0] != '\0' && in.ptr[1] == ':') {
+    strncpy(out.ptr, in.ptr, 2);
+    out.ptr[2] = '\0';
+    rest = in.ptr + 2;
+  } else {
+    strcpy(out.ptr, SDL_GetWorkingDir());
+    SDL_PathForwardSlashes(out.ptr);
+    int len = strlen(out.ptr);
+    if (len > 0 && out.ptr[len-1] == '/') {
+      out.ptr[len-1] = '\0';
     }
-    
   }
   
-  return asset_map_shortpath(fullpath);
+  char* part = strtok(rest, "/");
+  while (part != NULL) {
+    if (strcmp(part, "..") == 0) {
+      char* last = strrchr(out.ptr, '/');
+      if (last != NULL) { *last = '\0'; }
+    } else if (strcmp(part, ".") != 0) {
+      strcat(out.ptr, "/");
+      strcat(out.ptr, part);
+    }
+    part = strtok(NULL, "/");
+  }
   
+  return out;
 }
 
-fpath asset_map_filename(fpath filename) {
+static bool asset_path_below(char* path, char* folder) {
+  int len = strlen(folder);
+  return (strncmp(path, folder, len) == 0) && (path[len] == '/' || path[len] == '\0');
+}
+
+static fpath asset_map_variables(fpath filename) {
   
   fpath out = filename;
   
@@ -116,8 +141,258 @@ fpath asset_map_filename(fpath filename) {
     }
   
   }
+  
+  return out;
+}
+
+fpath asset_map_cleanpath(fpath filename) {
+  return asset_map_absolute(asset_map_variables(filename));
+}
+
+int asset_mount(fpath folder) {
+  
+  if (num_mounts == MAX_MOUNTS) {
+    error("Already reached maximum num of mounts (%i)", MAX_MOUNTS);
+  }
+  
+  mount m;
+  m.id = next_mount_id++;
+  m.folder = asset_map_cleanpath(folder);
+  m.num_files = 0;
+  m.names = NULL;
+  m.data = NULL;
+  m.sizes = NULL;
+  
+  mounts[num_mounts] = m;
+  num_mounts++;
+  
+  return m.id;
+}
+
+static mount* asset_mount_get(int id) {
+  for (int i = 0; i < num_mounts; i++) {
+    if (mounts[i].id == id) { return &mounts[i]; }
+  }
+  error("No mount with id %i", id);
+  return NULL;
+}
+
+void asset_mount_file(int id, fpath name, const void* data, int size) {
+  
+  mount* m = asset_mount_get(id);
+  
+  m->num_files++;
+  m->names = realloc(m->names, sizeof(char*) * m->num_files);
+  m->data = realloc(m->data, sizeof(void*) * m->num_files);
+  m->sizes = realloc(m->sizes, sizeof(int) * m->num_files);
+  
+  m->names[m->num_files-1] = malloc(strlen(name.ptr) + 1);
+  strcpy(m->names[m->num_files-1], name.ptr);
+  m->data[m->num_files-1] = data;
+  m->sizes[m->num_files-1] = size;
+  
+}
+
+void asset_unmount(int id) {
+  
+  for (int i = 0; i < num_mounts; i++) {
+    if (mounts[i].id == id) {
+      for (int j = 0; j < mounts[i].num_files; j++) {
+        free(mounts[i].names[j]);
+      }
+      free(mounts[i].names);
+      free(mounts[i].data);
+      free(mounts[i].sizes);
+      memmove(&mounts[i], &mounts[i+1], sizeof(mount) * (num_mounts - i - 1));
+      num_mounts--;
+      return;
+    }
+  }
+  
+}
+
+/* Part of a clean path below the folder of a mount, "" for the folder itself and NULL outside it */
+static char* asset_mount_relative(mount* m, char* path) {
+  if (!asset_path_below(path, m->folder.ptr)) { return NULL; }
+  char* rest = path + strlen(m->folder.ptr);
+  return rest[0] == '/' ? rest + 1 : rest;
+}
+
+/* The newest mount holding the file at a clean path, with the index of the file in it */
+static mount* asset_mount_find(char* path, int* index) {
+  
+  for (int i = num_mounts-1; i >= 0; i--) {
+    char* name = asset_mount_relative(&mounts[i], path);
+    if (name == NULL) { continue; }
+    for (int j = 0; j < mounts[i].num_files; j++) {
+      if (strcmp(mounts[i].names[j], name) == 0) {
+        *index = j;
+        return &mounts[i];
+      }
+    }
+  }
+  
+  return NULL;
+}
+
+/* Whether a clean path is the folder of a mount or holds mounted files */
+static bool asset_mount_has_folder(char* path) {
+  
+  for (int i = 0; i < num_mounts; i++) {
+    char* name = asset_mount_relative(&mounts[i], path);
+    if (name == NULL) { continue; }
+    
+    int len = strlen(name);
+    if (len == 0) { return true; }
+    
+    for (int j = 0; j < mounts[i].num_files; j++) {
+      if (strncmp(mounts[i].names[j], name, len) == 0 && mounts[i].names[j][len] == '/') {
+        return true;
+      }
+    }
+  }
+  
+  return false;
+}
+
+const void* asset_mounted_data(fpath filename, int* size) {
+  
+  if (num_mounts == 0) { return NULL; }
+  
+  fpath clean = asset_map_absolute(filename);
+  int index;
+  mount* m = asset_mount_find(clean.ptr, &index);
+  if (m == NULL) { return NULL; }
+  
+  *size = m->sizes[index];
+  return m->data[index];
+}
+
+SDL_RWops* asset_open(fpath filename, const char* mode) {
+  int size;
+  const void* data = asset_mounted_data(filename, &size);
+  return data ? SDL_RWFromConstMem(data, size) : SDL_RWFromFile(filename.ptr, mode);
+}
+
+/* Add a name to a list of names if it isn't there yet */
+static void asset_names_add(list* names, char* name) {
+  for (int i = 0; i < names->num_items; i++) {
+    if (strcmp(list_get(names, i), name) == 0) { return; }
+  }
+  char* copy = malloc(strlen(name) + 1);
+  strcpy(copy, name);
+  list_push_back(names, copy);
+}
 
-  return asset_map_fullpath(out);
+/* Names of the files and folders in a mapped folder, from its mounts and from disk, each once.
+   False when the folder is in neither. */
+static bool asset_folder_names(fpath folder, list* names) {
+  
+  bool found = false;
+  
+  if (num_mounts > 0) {
+    fpath clean = asset_map_absolute(folder);
+    for (int i = num_mounts-1; i >= 0; i--) {
+      char* inside = asset_mount_relative(&mounts[i], clean.ptr);
+      if (inside == NULL) { continue; }
+      
+      int len = strlen(inside);
+      if (len == 0) { found = true; }
+      
+      for (int j = 0; j < mounts[i].num_files; j++) {
+        char* name = mounts[i].names[j];
+        if (len > 0) {
+          if (strncmp(name, inside, len) != 0 || name[len] != '/') { continue; }
+          name += len + 1;
+        }
+        found = true;
+        
+        fpath entry;
+        strcpy(entry.ptr, name);
+        char* slash = strchr(entry.ptr, '/');
+        if (slash) { *slash = '\0'; }
+        asset_names_add(names, entry.ptr);
+      }
+    }
+  }
+  
+  DIR* dir = opendir(folder.ptr);
+  if (dir) {
+    found = true;
+    struct dirent* ent;
+    while ((ent = readdir(dir)) != NULL) {
+      if ((strcmp(ent->d_name,".") != 0) && 
+          (strcmp(ent->d_name,"..") != 0)) {
+        asset_names_add(names, ent->d_name);
+      }
+    }
+    closedir(dir);
+  }
+  
+  return found;
+}
+
+static bool asset_folder_exists(fpath folder) {
+  
+  if (num_mounts > 0) {
+    fpath clean = asset_map_absolute(folder);
+    if (asset_mount_has_folder(clean.ptr)) { return true; }
+  }
+  
+  DIR* dir = opendir(folder.ptr);
+  if (dir) { closedir(dir); }
+  return dir != NULL;
+}
+
+/* Full path of a file, mounted files are left as clean paths since they may not exist on disk */
+static fpath asset_map_resolved(fpath filename) {
+  
+  if (num_mounts > 0) {
+    fpath clean = asset_map_absolute(filename);
+    int index;
+    if (asset_mount_find(clean.ptr, &index) || asset_mount_has_folder(clean.ptr)) {
+      return clean;
+    }
+  }
+  
+  return asset_map_fullpath(filename);
+}
+
+fpath asset_unmap_filename(fpath filename) {
+  
+  fpath fullpath = asset_map_resolved(filename);
+  
+  for (int i = 0; i < num_path_variables; i++) {
+    
+    fpath variable = path_variables[i].variable;
+    fpath mapping  = path_variables[i].mapping; 
+    fpath fullmapping = asset_map_resolved(mapping);
+    
+    char* subptr = strstr(fullpath.ptr, fullmapping.ptr);
+    
+    if (subptr) {
+    
+      fpath sub; strcpy(sub.ptr, subptr);
+    
+      int replace_len = strlen(variable.ptr);
+      int start_len = strlen(fullpath.ptr) - strlen(sub.ptr);
+      int ext_len = strlen(sub.ptr) - strlen(fullmapping.ptr);
+      
+      fullpath.ptr[start_len] = '\0';
+      strcat(fullpath.ptr, variable.ptr);
+      strcat(fullpath.ptr, "/");
+      strcat(fullpath.ptr, sub.ptr + strlen(fullmapping.ptr));
+      
+    }
+    
+  }
+  
+  return asset_map_shortpath(fullpath);
+  
+}
+
+fpath asset_map_filename(fpath filename) {
+  return asset_map_resolved(asset_map_variables(filename));
 }
 
 asset_hndl asset_hndl_null() {
@@ -289,10 +564,23 @@ void file_load(fpath filename) {
   
 }
 
+void file_add(fpath filename, asset* a) {
+  
+  filename = asset_map_filename(filename);
+  
+  if (dict_contains(asset_dict, filename.ptr)) {
+    error("Asset '%s' already loaded", filename.ptr);
+  }
+  
+  debug("Adding: '%s'", filename.ptr);
+  dict_set(asset_dict, filename.ptr, a);
+  
+}
+
 bool file_exists(fpath filename) {
 
   filename = asset_map_filename(filename);
-  SDL_RWops* file = SDL_RWFromFile(filename.ptr, "r");
+  SDL_RWops* file = asset_open(filename, "r");
   if (file) {
     SDL_RWclose(file);
     return true;
@@ -307,33 +595,28 @@ void folder_load(fpath folder) {
   folder = asset_map_filename(folder);
   debug("Loading Folder: '%s'", folder.ptr);
   
-  DIR* dir = opendir(folder.ptr);
-  if (dir == NULL) {
+  list* names = list_new();
+  if (!asset_folder_names(folder, names)) {
     error("Could not open directory '%s' to load.", folder.ptr);
   }
   
-  struct dirent* ent;
-  while ((ent = readdir(dir)) != NULL) {
-  
-    if ((strcmp(ent->d_name,".") != 0) && 
-        (strcmp(ent->d_name,"..") != 0)) {
+  for (int i = 0; i < names->num_items; i++) {
     
-      fpath filename = folder;
-      
-      // If does not end in "/" then copy it.
-      if (folder.ptr[strlen(folder.ptr)-1] != '/') {
-        strcat(filename.ptr, "/");
-      }
-      
-      strcat(filename.ptr, ent->d_name);
-      
-      if (!file_isloaded(filename)) {
-        file_load(filename);
-      }
-    } 
+    fpath filename = folder;
+    
+    // If does not end in "/" then copy it.
+    if (folder.ptr[strlen(folder.ptr)-1] != '/') {
+      strcat(filename.ptr, "/");
+    }
+    
+    strcat(filename.ptr, list_get(names, i));
+    
+    if (!file_isloaded(filename)) {
+      file_load(filename);
+    }
   }
   
-  closedir(dir);
+  list_delete_with(names, free);
 }
 
 void folder_load_recursive(fpath folder) {
@@ -341,38 +624,32 @@ void folder_load_recursive(fpath folder) {
   folder = asset_map_filename(folder);
   debug("Loading Folder: '%s'", folder.ptr);
   
-  DIR* dir = opendir(folder.ptr);
-  if (dir == NULL) {
+  list* names = list_new();
+  if (!asset_folder_names(folder, names)) {
     error("Could not open directory '%s' to load.", folder.ptr);
   }
   
-  struct dirent* ent;
-  while ((ent = readdir(dir)) != NULL) {
-  
-    if ((strcmp(ent->d_name,".") != 0) && 
-        (strcmp(ent->d_name,"..") != 0)) {
+  for (int i = 0; i < names->num_items; i++) {
     
-      fpath filename = folder;
-      
-      // If does not end in "/" then copy it.
-      if (folder.ptr[strlen(folder.ptr)-1] != '/') {
-        strcat(filename.ptr, "/");
-      }
-      
-      strcat(filename.ptr, ent->d_name);
-      
-      DIR* sub = opendir(filename.ptr);
-      if (sub) {
-        folder_load_recursive(filename);
-      }
-      
-      if (!file_isloaded(filename)) {
-        file_load(filename);
-      }
-    } 
+    fpath filename = folder;
+    
+    // If does not end in "/" then copy it.
+    if (folder.ptr[strlen(folder.ptr)-1] != '/') {
+      strcat(filename.ptr, "/");
+    }
+    
+    strcat(filename.ptr, list_get(names, i));
+    
+    if (asset_folder_exists(filename)) {
+      folder_load_recursive(filename);
+    }
+    
+    if (!file_isloaded(filename)) {
+      file_load(filename);
+    }
   }
   
-  closedir(dir);
+  list_delete_with(names, free);
 
 }
 
@@ -412,28 +689,29 @@ void folder_unload(fpath folder) {
   folder = asset_map_filename(folder);
   
   debug("Unloading Folder: '%s'", folder.ptr);
-  DIR* dir = opendir(folder.ptr);
   
-  if (dir == NULL) {
+  list* names = list_new();
+  if (!asset_folder_names(folder, names)) {
     error("Could not open directory '%s' to unload.\n", folder.ptr);
   }
   
-  struct dirent* ent;
-  while ((ent = readdir(dir)) != NULL) {
-  
-    if ((strcmp(ent->d_name,".") != 0) && 
-        (strcmp(ent->d_name,"..") != 0)) {
+  for (int i = 0; i < names->num_items; i++) {
     
-      fpath filename = folder;
-      strcat(filename.ptr, ent->d_name);
-      
-      if(dict_contains(asset_dict, filename.ptr) ) {
-        file_unload(filename);
-      }
-      
-    } 
+    fpath filename = folder;
+    
+    // If does not end in "/" then copy it.
+    if (folder.ptr[strlen(folder.ptr)-1] != '/') {
+      strcat(filename.ptr, "/");
+    }
+    
+    strcat(filename.ptr, list_get(names, i));
+    
+    if(dict_contains(asset_dict, filename.ptr) ) {
+      file_unload(filename);
+    }
   }
-  closedir(dir);
+  
+  list_delete_with(names, free);
 }
 
 bool file_isloaded(fpath path) {
@@ -543,6 +821,16 @@ void asset_reload_all() {
   asset_cache_flush();
 }
 
+void asset_map_paths(void func(char* path)) {
+  for(int i = 0; i < asset_dict->size; i++) {
+    struct bucket* b = asset_dict->buckets[i];
+    while(b != NULL) {
+      func(b->key);
+      b = b->next;
+    }
+  }
+}
+
 char* asset_ptr_path(asset* a) {
   char* path = dict_find(asset_dict, a);
   if (path == NULL) {
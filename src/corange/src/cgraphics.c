@@ -147,7 +147,7 @@ void graphics_viewport_set_title(const char* title) {
 }
 
 void graphics_viewport_set_icon(fpath icon) {
-  SDL_Surface* window_icon = SDL_LoadBMP(asset_hndl_new(icon).path.ptr);
+  SDL_Surface* window_icon = SDL_LoadBMP_RW(asset_open(asset_hndl_new(icon).path, "rb"), 1);
   SDL_SetWindowIcon(screen, window_icon);
   SDL_FreeSurface(window_icon);
 }
@@ -195,43 +195,177 @@ collision point_collide_ctri(vec3 p, vec3 v, ctri ct) {
 
 }
 
-static collision point_collide_mesh_space(vec3 p, vec3 v, cmesh* cm, mat4 world, mat3 world_normal, mat3 space, mat3 space_normal) {
+/* Point or sphere swept against a mesh in the space its triangles are tested in */
+typedef struct {
+  bool point;
+  sphere s;
+  vec3 v;
+  mat4 world;
+  mat3 world_normal;
+  mat3 space;
+  mat3 space_normal;
+  /* The sweep mapped back into mesh space where the hierarchy is built */
+  bool cull;
+  vec3 local_center;
+  vec3 local_v;
+  vec3 local_extent;
+} mesh_sweep;
+
+static bool vec3_finite(vec3 v) {
+  return isfinite(v.x) && isfinite(v.y) && isfinite(v.z);
+}
+
+static mesh_sweep mesh_sweep_new(bool point, sphere s, vec3 v, mat4 world, mat3 world_normal, mat3 space, mat3 space_normal) {
+  
+  mesh_sweep ms;
+  ms.point = point;
+  ms.s = s;
+  ms.v = v;
+  ms.world = world;
+  ms.world_normal = world_normal;
+  ms.space = space;
+  ms.space_normal = space_normal;
+  
+  mat3 linear = mat3_mul_mat3(space, mat4_to_mat3(world));
+  mat3 inv = mat3_inverse(linear);
+  vec3 offset = mat3_mul_vec3(space, vec3_new(world.xw, world.yw, world.zw));
+  
+  ms.local_center = mat3_mul_vec3(inv, vec3_sub(s.center, offset));
+  ms.local_v = mat3_mul_vec3(inv, v);
+  
+  /* The sphere becomes an ellipsoid whose box is given by the rows of the inverse */
+  ms.local_extent = vec3_new(
+    s.radius * vec3_length(vec3_new(inv.xx, inv.xy, inv.xz)),
+    s.radius * vec3_length(vec3_new(inv.yx, inv.yy, inv.yz)),
+    s.radius * vec3_length(vec3_new(inv.zx, inv.zy, inv.zz)));
+  
+  /* Edge and vertex roots are only accurate to around the square root of float
+     precision, so grazing hits can be reported slightly off the triangle */
+  float tolerance = vec3_length(ms.local_v) * 1e-3;
+  ms.local_extent = vec3_add(ms.local_extent, vec3_new(tolerance, tolerance, tolerance));
+  
+  /* Degenerate transforms can't be mapped back, so every triangle gets tested */
+  ms.cull = (mat3_det(linear) != 0)
+    && vec3_finite(ms.local_center)
+    && vec3_finite(ms.local_v)
+    && vec3_finite(ms.local_extent);
+  
+  return ms;
+  
+}
+
+static collision mesh_sweep_ctri(mesh_sweep* ms, ctri ct) {
+  
+  ct = ctri_transform(ct, ms->world, ms->world_normal);
+  ct = ctri_transform_space(ct, ms->space, ms->space_normal);
+  
+  if (ms->point) {
+    return point_collide_ctri(ms->s.center, ms->v, ct);
+  } else {
+    return sphere_collide_ctri(ms->s, ms->v, ct);
+  }
   
-  if ( !cm->is_leaf ) {
+}
+
+static float vec3_axis(vec3 v, int axis) {
+  return axis == 0 ? v.x : (axis == 1 ? v.y : v.z);
+}
+
+/* Time the sweep enters a node, false if it misses it before time limit */
+static bool mesh_sweep_enters(mesh_sweep* ms, cmesh_node n, float limit, float* entry) {
   
-    plane div = cm->division;
-    div = plane_transform(div, world, world_normal);
-    div = plane_transform_space(div, space, space_normal);
+  float t0 = 0;
+  float t1 = limit;
   
-         if ( point_swept_inside_plane(p, v, div)  ) { return point_collide_mesh_space(p, v, cm->back,  world, world_normal, space, space_normal); }
-    else if ( point_swept_outside_plane(p, v, div) ) { return point_collide_mesh_space(p, v, cm->front, world, world_normal, space, space_normal); }
-    else {
+  for (int axis = 0; axis < 3; axis++) {
     
-      collision c0 = point_collide_mesh_space(p, v, cm->back,  world, world_normal, space, space_normal);
-      collision c1 = point_collide_mesh_space(p, v, cm->front, world, world_normal, space, space_normal);
-      return collision_merge(c0, c1);
-      
+    float p = vec3_axis(ms->local_center, axis);
+    float v = vec3_axis(ms->local_v, axis);
+    float r = vec3_axis(ms->local_extent, axis);
+    float lower = vec3_axis(n.min, axis) - r;
+    float upper = vec3_axis(n.max, axis) + r;
+    
+    if (v == 0) {
+      if (p < lower || p > upper) { return false; }
+      continue;
     }
+    
+    float s0 = (lower - p) / v;
+    float s1 = (upper - p) / v;
+    t0 = max(t0, min(s0, s1));
+    t1 = min(t1, max(s0, s1));
+    
+    if (t0 > t1) { return false; }
   }
   
+  *entry = t0;
+  return true;
+  
+}
+
+static collision mesh_sweep_collide_node(mesh_sweep* ms, cmesh* cm, int index, collision col) {
+  
+  cmesh_node n = cm->nodes[index];
+  
+  if (n.count > 0) {
+    for (int i = n.first; i < n.first + n.count; i++) {
+      col = collision_merge(col, mesh_sweep_ctri(ms, cm->triangles[i]));
+    }
+    return col;
+  }
+  
+  float t0, t1;
+  bool hit0 = mesh_sweep_enters(ms, cm->nodes[n.first+0], min(col.time, 1), &t0);
+  bool hit1 = mesh_sweep_enters(ms, cm->nodes[n.first+1], min(col.time, 1), &t1);
+  
+  /* Visit the nearer child first, the other is skipped if only entered after the hit */
+  if (hit0 && hit1 && t1 < t0) {
+    col = mesh_sweep_collide_node(ms, cm, n.first+1, col);
+    if (t0 <= col.time) { col = mesh_sweep_collide_node(ms, cm, n.first+0, col); }
+  } else {
+    if (hit0) { col = mesh_sweep_collide_node(ms, cm, n.first+0, col); }
+    if (hit1 && t1 <= col.time) { col = mesh_sweep_collide_node(ms, cm, n.first+1, col); }
+  }
+  
+  return col;
+  
+}
+
+static collision mesh_sweep_collide_brute(mesh_sweep* ms, cmesh* cm) {
+  
   collision col = collision_none();
   
   for (int i = 0; i < cm->triangles_num; i++) {
-    ctri ct = cm->triangles[i];
-    ct = ctri_transform(ct, world, world_normal);
-    ct = ctri_transform_space(ct, space, space_normal);
-    
-    /* This does not work for some reason */
-    //if (point_swept_outside_sphere(p, v, ct.bound)) continue;
-    col = collision_merge(col, point_collide_ctri(p, v, ct));
+    col = collision_merge(col, mesh_sweep_ctri(ms, cm->triangles[i]));
   }
   
   return col;
+  
+}
 
+static collision mesh_sweep_collide(mesh_sweep* ms, cmesh* cm) {
+  
+  if (cm->nodes_num == 0 || !ms->cull) {
+    return mesh_sweep_collide_brute(ms, cm);
+  }
+  
+  float entry;
+  if (!mesh_sweep_enters(ms, cm->nodes[0], 1, &entry)) {
+    return collision_none();
+  }
+  
+  return mesh_sweep_collide_node(ms, cm, 0, collision_none());
+  
 }
 
 collision point_collide_mesh(vec3 p, vec3 v, cmesh* m, mat4 world, mat3 world_normal) {
-  return point_collide_mesh_space(p, v, m, world, world_normal, mat3_id(), mat3_id());
+  mesh_sweep ms = mesh_sweep_new(true, sphere_new(p, 0), v, world, world_normal, mat3_id(), mat3_id());
+  return mesh_sweep_collide(&ms, m);
+}
+
+collision point_collide_mesh_brute(vec3 p, vec3 v, cmesh* m, mat4 world, mat3 world_normal) {
+  mesh_sweep ms = mesh_sweep_new(true, sphere_new(p, 0), v, world, world_normal, mat3_id(), mat3_id());
+  return mesh_sweep_collide_brute(&ms, m);
 }
 
 collision sphere_collide_face(sphere s, vec3 v, ctri ct) {
@@ -382,58 +516,30 @@ collision ellipsoid_collide_point(ellipsoid e, vec3 v, vec3 p) {
 
 collision ellipsoid_collide_sphere(ellipsoid e, vec3 v, sphere s) {
   
-  error("Unimplemented!");
+  /* Approximate the sum of both shapes as an ellipsoid, exact when e is a sphere */
+  e.radiuses = vec3_add(e.radiuses, vec3_new(s.radius, s.radius, s.radius));
   
-  s.center = vec3_sub(s.center, e.center);
+  collision c = ellipsoid_collide_point(e, v, s.center);
   
-  collision c = sphere_collide_sphere(sphere_unit(), v, s);
-  
-  c.point = vec3_add(c.point, e.center);
+  if (c.collided) {
+    c.point = vec3_add(s.center, vec3_mul(c.norm, s.radius));
+  }
   
   return c;
   
 }
 
-static collision sphere_collide_mesh_space(sphere s, vec3 v, cmesh* cm, mat4 world, mat3 world_normal, mat3 space, mat3 space_normal) {
-  
-  if ( !cm->is_leaf ) {
-  
-    plane div = cm->division;
-    div = plane_transform(div, world, world_normal);
-    div = plane_transform_space(div, space, space_normal);
-  
-         if ( sphere_swept_inside_plane(s, v, div)  ) { return sphere_collide_mesh_space(s, v, cm->back,  world, world_normal, space, space_normal); }
-    else if ( sphere_swept_outside_plane(s, v, div) ) { return sphere_collide_mesh_space(s, v, cm->front, world, world_normal, space, space_normal); }
-    else {
-    
-      collision c0 = sphere_collide_mesh_space(s, v, cm->back,  world, world_normal, space, space_normal);
-      collision c1 = sphere_collide_mesh_space(s, v, cm->front, world, world_normal, space, space_normal);
-      return collision_merge(c0, c1);
-      
-    }
-  }
-  
-  collision col = collision_none();
-  
-  for (int i = 0; i < cm->triangles_num; i++) {
-    ctri ct = cm->triangles[i];
-    ct = ctri_transform(ct, world, world_normal);
-    ct = ctri_transform_space(ct, space, space_normal);
-    
-    /* This does not work for some reason */
-    //if (sphere_swept_outside_sphere(s, v, ct.bound)) continue;
-    col = collision_merge(col, sphere_collide_ctri(s, v, ct));
-  }
-  
-  return col;
-
+collision sphere_collide_mesh(sphere s, vec3 v, cmesh* m, mat4 world, mat3 world_normal) {
+  mesh_sweep ms = mesh_sweep_new(false, s, v, world, world_normal, mat3_id(), mat3_id());
+  return mesh_sweep_collide(&ms, m);
 }
 
-collision sphere_collide_mesh(sphere s, vec3 v, cmesh* m, mat4 world, mat3 world_normal) {
-  return sphere_collide_mesh_space(s, v, m, world, world_normal, mat3_id(), mat3_id());
+collision sphere_collide_mesh_brute(sphere s, vec3 v, cmesh* m, mat4 world, mat3 world_normal) {
+  mesh_sweep ms = mesh_sweep_new(false, s, v, world, world_normal, mat3_id(), mat3_id());
+  return mesh_sweep_collide_brute(&ms, m);
 }
 
-collision ellipsoid_collide_mesh(ellipsoid e, vec3 v, cmesh* m, mat4 world, mat3 world_normal) {
+static collision ellipsoid_collide_mesh_sweep(ellipsoid e, vec3 v, cmesh* m, mat4 world, mat3 world_normal, bool brute) {
   
   world.xw -= e.center.x;
   world.yw -= e.center.y;
@@ -444,7 +550,8 @@ collision ellipsoid_collide_mesh(ellipsoid e, vec3 v, cmesh* m, mat4 world, mat3
   
   v = mat3_mul_vec3(space, v);
   
-  collision c = sphere_collide_mesh_space(sphere_unit(), v, m, world, world_normal, space, mat3_transpose(space_inv));
+  mesh_sweep ms = mesh_sweep_new(false, sphere_unit(), v, world, world_normal, space, mat3_transpose(space_inv));
+  collision c = brute ? mesh_sweep_collide_brute(&ms, m) : mesh_sweep_collide(&ms, m);
   
   c.point = mat3_mul_vec3(space_inv, c.point);
   c.point = vec3_add(c.point, e.center);
@@ -455,6 +562,14 @@ collision ellipsoid_collide_mesh(ellipsoid e, vec3 v, cmesh* m, mat4 world, mat3
   
 }
 
+collision ellipsoid_collide_mesh(ellipsoid e, vec3 v, cmesh* m, mat4 world, mat3 world_normal) {
+  return ellipsoid_collide_mesh_sweep(e, v, m, world, world_normal, false);
+}
+
+collision ellipsoid_collide_mesh_brute(ellipsoid e, vec3 v, cmesh* m, mat4 world, mat3 world_normal) {
+  return ellipsoid_collide_mesh_sweep(e, v, m, world, world_normal, true);
+}
+
 void collision_response_slide(void* x, vec3* position, vec3* velocity, collision (*colfunc)(void* x, vec3* pos, vec3* vel) ) {
   
   collision col = colfunc(x, position, velocity);
@@ -11,6 +11,9 @@ camera* camera_new() {
   c->fov = 0.785398163;
   c->near_clip = 0.1;
   c->far_clip = 512.0;
+  c->roll = 0.0;
+  c->orthographic = false;
+  c->ortho_width = 64.0;
   
   return c;
 }
@@ -24,17 +27,52 @@ vec3 camera_direction(camera* c) {
 }
 
 mat4 camera_view_matrix(camera* c) {
-  return mat4_view_look_at(c->position, c->target, vec3_new(0.0f,1.0f,0.0f) );
+  vec3 up = vec3_new(0.0f,1.0f,0.0f);
+  if (fabs(vec3_dot(camera_direction(c), up)) > 0.999) {
+    up = vec3_new(0.0f,0.0f,-1.0f);
+  }
+  if (c->roll != 0.0) {
+    up = mat3_mul_vec3(mat3_rotation_angle_axis(c->roll, camera_direction(c)), up);
+  }
+  return mat4_view_look_at(c->position, c->target, up);
 }
 
 mat4 camera_proj_matrix(camera* c) {
-  return mat4_perspective(c->fov, c->near_clip, c->far_clip, graphics_viewport_ratio());
+  return camera_proj_matrix_ratio(c, graphics_viewport_ratio());
+}
+
+/* Ratio is height over width of the output, like graphics_viewport_ratio */
+mat4 camera_proj_matrix_ratio(camera* c, float ratio) {
+  
+  if (c->orthographic) {
+    
+    /* Mirrored in x and mapping depth to -1..1 to match mat4_perspective */
+    mat4 m = mat4_id();
+    m.xx = -1.0 / c->ortho_width;
+    m.yy =  1.0 / (c->ortho_width * ratio);
+    m.zz = -2.0 / (c->far_clip - c->near_clip);
+    m.zw = -(c->far_clip + c->near_clip) / (c->far_clip - c->near_clip);
+    return m;
+    
+  }
+  
+  return mat4_perspective(c->fov, c->near_clip, c->far_clip, ratio);
 }
 
 mat4 camera_view_proj_matrix(camera* c) {
   mat4 view = camera_view_matrix(c);
   mat4 proj = camera_proj_matrix(c);
-  return mat4_mul_mat4(view, proj);
+  return mat4_mul_mat4(proj, view);
+}
+
+void camera_set_perspective(camera* c, float fov) {
+  c->orthographic = false;
+  c->fov = fov;
+}
+
+void camera_set_orthographic(camera* c, float width) {
+  c->orthographic = true;
+  c->ortho_width = width;
 }
 
 void camera_normalize_target(camera* c) {
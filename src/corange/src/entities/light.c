@@ -1,7 +1,5 @@
 #include "entities/light.h"
 
-#define DEFAULT_LIGHT_NEAR_CLIP 0.1
-#define DEFAULT_LIGHT_FAR_CLIP 8192.0
 #define DEFAULT_FOV 0.785398163
 
 light* light_new() {
@@ -46,6 +44,10 @@ void light_set_type(light* l, int type) {
     l->fov = -1;
     l->aspect_ratio = -1;
     
+    l->inner_angle = -1;
+    l->outer_angle = -1;
+    l->cookie = asset_hndl_null();
+    
     l->shadow_color = vec3_zero();
     l->shadow_map_width = -1;
     l->shadow_map_height = -1;
@@ -67,6 +69,10 @@ void light_set_type(light* l, int type) {
     l->fov = -1;
     l->aspect_ratio = -1;
     
+    l->inner_angle = -1;
+    l->outer_angle = -1;
+    l->cookie = asset_hndl_null();
+    
     l->shadow_color = vec3_zero();
     l->shadow_map_width = -1;
     l->shadow_map_height = -1;
@@ -94,6 +100,10 @@ void light_set_type(light* l, int type) {
     l->fov = -1;
     l->aspect_ratio = -1;
     
+    l->inner_angle = -1;
+    l->outer_angle = -1;
+    l->cookie = asset_hndl_null();
+    
     l->shadow_color = vec3_zero();
     l->shadow_map_width = 2048;
     l->shadow_map_height = 2048;
@@ -115,6 +125,10 @@ void light_set_type(light* l, int type) {
     l->fov = DEFAULT_FOV;
     l->aspect_ratio = 1.0;
     
+    l->inner_angle = DEFAULT_FOV / 2;
+    l->outer_angle = DEFAULT_FOV;
+    l->cookie = asset_hndl_null();
+    
     l->shadow_color = vec3_zero();
     l->shadow_map_width = 1024;
     l->shadow_map_height = 1024;
@@ -123,6 +137,16 @@ void light_set_type(light* l, int type) {
 
 }
 
+void light_set_cone(light* l, float inner_angle, float outer_angle) {
+  l->inner_angle = min(inner_angle, outer_angle);
+  l->outer_angle = outer_angle;
+  l->fov = outer_angle;
+}
+
+void light_set_cookie(light* l, asset_hndl cookie) {
+  l->cookie = cookie;
+}
+
 void light_delete(light* l) {
   free(l);
 }
@@ -131,6 +155,15 @@ vec3 light_direction(light* l) {
   return vec3_normalize(  vec3_sub( l->target, l->position ) );
 }
 
+float light_radius(light* l) {
+  
+  if (l->falloff <= 0 || l->power <= 0) { return DEFAULT_LIGHT_FAR_CLIP; }
+  
+  float radius = pow(l->power / LIGHT_CUTOFF, 1.0 / l->falloff);
+  return clamp(radius, DEFAULT_LIGHT_NEAR_CLIP * 10, DEFAULT_LIGHT_FAR_CLIP);
+  
+}
+
 mat4 light_view_matrix(light* l) {
 
   return mat4_view_look_at( l->position, l->target, vec3_new(0,1,0) );
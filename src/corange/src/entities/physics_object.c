@@ -1,6 +1,7 @@
 #include "entities/physics_object.h"
 
 #include "assets/cmesh.h"
+#include "cphysics.h"
 
 physics_object* physics_object_new() {
 
@@ -26,7 +27,7 @@ physics_object* physics_object_new() {
   po->cast_shadows = true;
   
   po->renderable = asset_hndl_null();
-  //po->collision_body = asset_hndl_null();
+  po->collision_body = asset_hndl_null();
   
   return po;
 }
@@ -35,6 +36,14 @@ void physics_object_delete(physics_object* po) {
   free(po);
 }
 
+mat4 physics_object_world(physics_object* po) {
+  return mat4_world(po->position, po->scale, po->rotation);
+}
+
+mat3 physics_object_world_normal(physics_object* po) {
+  return mat3_transpose(mat3_inverse(mat4_to_mat3(physics_object_world(po))));
+}
+
 void physics_object_update(physics_object* po, float timestep) {
   
   po->previous_position = po->position;
@@ -53,58 +62,29 @@ void physics_object_update(physics_object* po, float timestep) {
 
 void physics_object_collide_static(physics_object* po, static_object* so, float timestep) {
   
-  /*
-  collision_body* po_col = asset_hndl_ptr(po->collision_body);
-  collision_body* so_col = asset_hndl_ptr(so->collision_body);
-  
-  if ((po_col == NULL) || (so_col == NULL)) {
-    error("Cannot collide objects. One or more has no collision body - First: %p Second: %p).", po_col, so_col);
+  if (asset_hndl_isnull(&po->collision_body) || asset_hndl_isnull(&so->collision_body)) {
+    error("Cannot collide objects. One or more has no collision body - First: %p Second: %p).", po, so);
   }
   
-  mat4 po_world = mat4_world(po->position, po->scale, po->rotation);
-  mat4 so_world = mat4_world(so->position, so->scale, so->rotation);
-  
-  sphere po_sphere = sphere_transform(po_col->collision_sphere, po_world);
-  sphere so_sphere = sphere_transform(so_col->collision_sphere, so_world);
-  
-  box po_box = box_transform(po_col->collision_box, po_world);
-  box so_box = box_transform(so_col->collision_box, so_world);
-  
-  collision c;
-  int col_count = 0;
-  while(col_count < 2) {
-    
-    c.collided = false;
-    c.time = timestep;
-    
-    if (!sphere_contains_sphere(so_sphere, po_sphere)) break;
-    
-    if (po_col->collision_type == collision_type_sphere) {
-    
-      if (so_col->collision_type == collision_type_sphere) {
-        sphere_collide_sphere(&c, po_sphere, po->velocity, so_sphere, timestep);
-        if (!c.collided) break;
-      }
-    
-      if (so_col->collision_type == collision_type_box) {
-        sphere_collide_box(&c, po_sphere, po->velocity, so_box, timestep);
-        if (!c.collided) break;
-      }
-      
-      if (so_col->collision_type == collision_type_mesh) {
-        sphere_collide_mesh(&c, po_sphere, po->velocity, so_col->collision_mesh, so_world, timestep);
-        if (!c.collided) break;
-      }
-    }
-    
-    if (c.collided) {
-      po->position = c.object_position;
-      po->velocity = vec3_mul(po->velocity, 0.25);
-      po->velocity = vec3_reflect(po->velocity, c.surface_normal);
-      col_count++;
-    }
-    
-  }
-  */
+  cmesh* po_col = asset_hndl_ptr(&po->collision_body);
+  cmesh* so_col = asset_hndl_ptr(&so->collision_body);
+  
+  sphere po_sphere = sphere_transform(cmesh_bound(po_col), physics_object_world(po));
+  vec3 movement = vec3_mul(po->velocity, timestep);
+  
+  collision c = sphere_collide_mesh(po_sphere, movement, so_col, 
+    static_object_world(so), static_object_world_normal(so));
+  
+  if (!c.collided) { return; }
+  
+  /* Move up to the contact, then bounce and slide along the surface */
+  po->position = vec3_add(po->position, vec3_mul(movement, c.time));
+  
+  vec3 normal_velocity = vec3_mul(c.norm, vec3_dot(po->velocity, c.norm));
+  vec3 tangent_velocity = vec3_sub(po->velocity, normal_velocity);
+  
+  po->velocity = vec3_add(
+    vec3_mul(tangent_velocity, 1 - po->friction),
+    vec3_mul(normal_velocity, -po->elasticity));
   
 }
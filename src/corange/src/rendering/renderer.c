@@ -143,6 +143,178 @@ static float quad_texcoord[] = {
   1, 1
 };
 
+static void render_buffers_new(renderer* dr, render_buffers* b, int width, int height) {
+  
+  b->width = width;
+  b->height = height;
+  
+  int gwidth  = width  * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
+  int gheight = height * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
+  
+  glGenFramebuffers(1, &b->gfbo);
+  glBindFramebuffer(GL_FRAMEBUFFER, b->gfbo);
+  
+  glGenRenderbuffers(1, &b->gdiffuse_buffer);
+  glBindRenderbuffer(GL_RENDERBUFFER, b->gdiffuse_buffer);
+  glRenderbufferStorage(GL_RENDERBUFFER, GL_RGBA, gwidth, gheight);
+  glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_RENDERBUFFER, b->gdiffuse_buffer);   
+  
+  glGenRenderbuffers(1, &b->gnormals_buffer);  
+  glBindRenderbuffer(GL_RENDERBUFFER, b->gnormals_buffer);
+  glRenderbufferStorage(GL_RENDERBUFFER, GL_RGBA16F, gwidth, gheight);
+  glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT1, GL_RENDERBUFFER, b->gnormals_buffer);  
+  
+  glGenRenderbuffers(1, &b->gdepth_buffer);
+  glBindRenderbuffer(GL_RENDERBUFFER, b->gdepth_buffer);
+  glRenderbufferStorage(GL_RENDERBUFFER, GL_DEPTH_COMPONENT24, gwidth, gheight);
+  glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_DEPTH_ATTACHMENT, GL_RENDERBUFFER, b->gdepth_buffer);  
+  
+  glGenTextures(1, &b->gdiffuse_texture);
+  glBindTexture(GL_TEXTURE_2D, b->gdiffuse_texture);
+  glTexImage2D(GL_TEXTURE_2D, 0, GL_RGBA, gwidth, gheight, 0, GL_RGBA, GL_UNSIGNED_BYTE, NULL);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
+  glFramebufferTexture2D(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_TEXTURE_2D, b->gdiffuse_texture, 0);
+  
+  glGenTextures(1, &b->gnormals_texture);
+  glBindTexture(GL_TEXTURE_2D, b->gnormals_texture);
+  glTexImage2D(GL_TEXTURE_2D, 0, GL_RGBA16F, gwidth, gheight, 0, GL_RGBA, GL_FLOAT, NULL);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
+  glFramebufferTexture2D(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT1, GL_TEXTURE_2D, b->gnormals_texture, 0);
+  
+  glGenTextures(1, &b->gdepth_texture);
+  glBindTexture(GL_TEXTURE_2D, b->gdepth_texture);
+  glTexImage2D(GL_TEXTURE_2D, 0, GL_DEPTH_COMPONENT24, gwidth, gheight, 0, GL_DEPTH_COMPONENT, GL_FLOAT, NULL);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
+  glFramebufferTexture2D(GL_FRAMEBUFFER, GL_DEPTH_ATTACHMENT, GL_TEXTURE_2D, b->gdepth_texture, 0);
+
+  /* SSAO Buffer */
+  
+  int ssaowidth  = width  / option_graphics_int(asset_hndl_ptr(&dr->options), "ssao", 1, 2, 4);
+  int ssaoheight = height / option_graphics_int(asset_hndl_ptr(&dr->options), "ssao", 1, 2, 4);
+  
+  glGenFramebuffers(1, &b->ssao_fbo);
+  glBindFramebuffer(GL_FRAMEBUFFER, b->ssao_fbo);
+  
+  glGenRenderbuffers(1, &b->ssao_buffer);
+  glBindRenderbuffer(GL_RENDERBUFFER, b->ssao_buffer);
+  glRenderbufferStorage(GL_RENDERBUFFER, GL_RGBA, ssaowidth, ssaoheight);
+  glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_RENDERBUFFER, b->ssao_buffer);   
+  
+  glGenTextures(1, &b->ssao_texture);
+  glBindTexture(GL_TEXTURE_2D, b->ssao_texture);
+  glTexImage2D(GL_TEXTURE_2D, 0, GL_RGBA, ssaowidth, ssaoheight, 0, GL_RGBA, GL_UNSIGNED_BYTE, NULL);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
+  glFramebufferTexture2D(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_TEXTURE_2D, b->ssao_texture, 0);
+  
+  /* HDR Buffer */
+  
+  int hdrwidth  = width  * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
+  int hdrheight = height * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
+  
+  glGenFramebuffers(1, &b->hdr_fbo);
+  glBindFramebuffer(GL_FRAMEBUFFER, b->hdr_fbo);
+  
+  glGenRenderbuffers(1, &b->hdr_buffer);
+  glBindRenderbuffer(GL_RENDERBUFFER, b->hdr_buffer);
+  glRenderbufferStorage(GL_RENDERBUFFER, GL_RGBA16F, hdrwidth, hdrheight);
+  glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_RENDERBUFFER, b->hdr_buffer);   
+  
+  glGenTextures(1, &b->hdr_texture);
+  glBindTexture(GL_TEXTURE_2D, b->hdr_texture);
+  glTexImage2D(GL_TEXTURE_2D, 0, GL_RGBA16F, hdrwidth, hdrheight, 0, GL_RGBA, GL_FLOAT, NULL);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
+  glFramebufferTexture2D(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_TEXTURE_2D, b->hdr_texture, 0);
+
+  /* LDR front buffer */
+  
+  glGenFramebuffers(1, &b->ldr_front_fbo);
+  glBindFramebuffer(GL_FRAMEBUFFER, b->ldr_front_fbo);
+  
+  glGenRenderbuffers(1, &b->ldr_front_buffer);
+  glBindRenderbuffer(GL_RENDERBUFFER, b->ldr_front_buffer);
+  glRenderbufferStorage(GL_RENDERBUFFER, GL_RGBA, width, height);
+  glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_RENDERBUFFER, b->ldr_front_buffer);   
+  
+  glGenTextures(1, &b->ldr_front_texture);
+  glBindTexture(GL_TEXTURE_2D, b->ldr_front_texture);
+  glTexImage2D(GL_TEXTURE_2D, 0, GL_RGBA, width, height, 0, GL_RGBA, GL_UNSIGNED_BYTE, NULL);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
+  glFramebufferTexture2D(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_TEXTURE_2D, b->ldr_front_texture, 0);
+ 
+  /* LDR back buffer */
+  
+  glGenFramebuffers(1, &b->ldr_back_fbo);
+  glBindFramebuffer(GL_FRAMEBUFFER, b->ldr_back_fbo);
+  
+  glGenRenderbuffers(1, &b->ldr_back_buffer);
+  glBindRenderbuffer(GL_RENDERBUFFER, b->ldr_back_buffer);
+  glRenderbufferStorage(GL_RENDERBUFFER, GL_RGBA, width, height);
+  glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_RENDERBUFFER, b->ldr_back_buffer);   
+  
+  glGenTextures(1, &b->ldr_back_texture);
+  glBindTexture(GL_TEXTURE_2D, b->ldr_back_texture);
+  glTexImage2D(GL_TEXTURE_2D, 0, GL_RGBA, width, height, 0, GL_RGBA, GL_UNSIGNED_BYTE, NULL);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
+  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
+  glFramebufferTexture2D(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_TEXTURE_2D, b->ldr_back_texture, 0);
+  
+}
+
+/* Height over width of the buffers being rendered to */
+static float render_ratio(renderer* dr) {
+  return (float)dr->buffers->height / (float)dr->buffers->width;
+}
+
+static void render_buffers_delete(render_buffers* b) {
+  
+  glDeleteFramebuffers(1, &b->gfbo);
+  
+  glDeleteRenderbuffers(1, &b->gdiffuse_buffer);
+  glDeleteRenderbuffers(1, &b->gnormals_buffer);
+  glDeleteRenderbuffers(1, &b->gdepth_buffer);
+  
+  glDeleteTextures(1, &b->gdiffuse_texture);
+  glDeleteTextures(1, &b->gnormals_texture);
+  glDeleteTextures(1, &b->gdepth_texture);
+  
+  glDeleteFramebuffers(1, &b->ssao_fbo);
+  glDeleteRenderbuffers(1, &b->ssao_buffer);
+  glDeleteTextures(1, &b->ssao_texture);
+  
+  glDeleteFramebuffers(1, &b->hdr_fbo);
+  glDeleteRenderbuffers(1, &b->hdr_buffer);
+  glDeleteTextures(1, &b->hdr_texture);
+  
+  glDeleteFramebuffers(1, &b->ldr_front_fbo);
+  glDeleteRenderbuffers(1, &b->ldr_front_buffer);
+  glDeleteTextures(1, &b->ldr_front_texture);
+  
+  glDeleteFramebuffers(1, &b->ldr_back_fbo);
+  glDeleteRenderbuffers(1, &b->ldr_back_buffer);
+  glDeleteTextures(1, &b->ldr_back_texture);
+  
+}
+
 renderer* renderer_new(asset_hndl options) {
   
   renderer* dr = malloc(sizeof(renderer));
@@ -222,153 +394,38 @@ renderer* renderer_new(asset_hndl options) {
   
   /* Buffers */
   
-  int width = graphics_viewport_width();
-  int height = graphics_viewport_height();
-  
-  int gwidth  = width  * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
-  int gheight = height * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
-  
-  glGenFramebuffers(1, &dr->gfbo);
-  glBindFramebuffer(GL_FRAMEBUFFER, dr->gfbo);
-  
-  glGenRenderbuffers(1, &dr->gdiffuse_buffer);
-  glBindRenderbuffer(GL_RENDERBUFFER, dr->gdiffuse_buffer);
-  glRenderbufferStorage(GL_RENDERBUFFER, GL_RGBA, gwidth, gheight);
-  glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_RENDERBUFFER, dr->gdiffuse_buffer);   
-  
-  glGenRenderbuffers(1, &dr->gnormals_buffer);  
-  glBindRenderbuffer(GL_RENDERBUFFER, dr->gnormals_buffer);
-  glRenderbufferStorage(GL_RENDERBUFFER, GL_RGBA16F, gwidth, gheight);
-  glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT1, GL_RENDERBUFFER, dr->gnormals_buffer);  
-  
-  glGenRenderbuffers(1, &dr->gdepth_buffer);
-  glBindRenderbuffer(GL_RENDERBUFFER, dr->gdepth_buffer);
-  glRenderbufferStorage(GL_RENDERBUFFER, GL_DEPTH_COMPONENT24, gwidth, gheight);
-  glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_DEPTH_ATTACHMENT, GL_RENDERBUFFER, dr->gdepth_buffer);  
-  
-  glGenTextures(1, &dr->gdiffuse_texture);
-  glBindTexture(GL_TEXTURE_2D, dr->gdiffuse_texture);
-  glTexImage2D(GL_TEXTURE_2D, 0, GL_RGBA, gwidth, gheight, 0, GL_RGBA, GL_UNSIGNED_BYTE, NULL);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
-  glFramebufferTexture2D(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_TEXTURE_2D, dr->gdiffuse_texture, 0);
-  
-  glGenTextures(1, &dr->gnormals_texture);
-  glBindTexture(GL_TEXTURE_2D, dr->gnormals_texture);
-  glTexImage2D(GL_TEXTURE_2D, 0, GL_RGBA16F, gwidth, gheight, 0, GL_RGBA, GL_FLOAT, NULL);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
-  glFramebufferTexture2D(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT1, GL_TEXTURE_2D, dr->gnormals_texture, 0);
+  render_buffers_new(dr, &dr->screen_buffers, graphics_viewport_width(), graphics_viewport_height());
+  dr->target_buffers.width = 0;
+  dr->target_buffers.height = 0;
+  dr->buffers = &dr->screen_buffers;
   
-  glGenTextures(1, &dr->gdepth_texture);
-  glBindTexture(GL_TEXTURE_2D, dr->gdepth_texture);
-  glTexImage2D(GL_TEXTURE_2D, 0, GL_DEPTH_COMPONENT24, gwidth, gheight, 0, GL_DEPTH_COMPONENT, GL_FLOAT, NULL);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
-  glFramebufferTexture2D(GL_FRAMEBUFFER, GL_DEPTH_ATTACHMENT, GL_TEXTURE_2D, dr->gdepth_texture, 0);
-
-  /* SSAO Buffer */
-  
-  int ssaowidth  = width  / option_graphics_int(asset_hndl_ptr(&dr->options), "ssao", 1, 2, 4);
-  int ssaoheight = height / option_graphics_int(asset_hndl_ptr(&dr->options), "ssao", 1, 2, 4);
-  
-  glGenFramebuffers(1, &dr->ssao_fbo);
-  glBindFramebuffer(GL_FRAMEBUFFER, dr->ssao_fbo);
-  
-  glGenRenderbuffers(1, &dr->ssao_buffer);
-  glBindRenderbuffer(GL_RENDERBUFFER, dr->ssao_buffer);
-  glRenderbufferStorage(GL_RENDERBUFFER, GL_RGBA, ssaowidth, ssaoheight);
-  glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_RENDERBUFFER, dr->ssao_buffer);   
-  
-  glGenTextures(1, &dr->ssao_texture);
-  glBindTexture(GL_TEXTURE_2D, dr->ssao_texture);
-  glTexImage2D(GL_TEXTURE_2D, 0, GL_RGBA, ssaowidth, ssaoheight, 0, GL_RGBA, GL_UNSIGNED_BYTE, NULL);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
-  glFramebufferTexture2D(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_TEXTURE_2D, dr->ssao_texture, 0);
-  
-  /* HDR Buffer */
-  
-  int hdrwidth  = width  * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
-  int hdrheight = height * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
-  
-  glGenFramebuffers(1, &dr->hdr_fbo);
-  glBindFramebuffer(GL_FRAMEBUFFER, dr->hdr_fbo);
-  
-  glGenRenderbuffers(1, &dr->hdr_buffer);
-  glBindRenderbuffer(GL_RENDERBUFFER, dr->hdr_buffer);
-  glRenderbufferStorage(GL_RENDERBUFFER, GL_RGBA16F, hdrwidth, hdrheight);
-  glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_RENDERBUFFER, dr->hdr_buffer);   
-  
-  glGenTextures(1, &dr->hdr_texture);
-  glBindTexture(GL_TEXTURE_2D, dr->hdr_texture);
-  glTexImage2D(GL_TEXTURE_2D, 0, GL_RGBA16F, hdrwidth, hdrheight, 0, GL_RGBA, GL_FLOAT, NULL);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
-  glFramebufferTexture2D(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_TEXTURE_2D, dr->hdr_texture, 0);
-
-  /* LDR front buffer */
-  
-  glGenFramebuffers(1, &dr->ldr_front_fbo);
-  glBindFramebuffer(GL_FRAMEBUFFER, dr->ldr_front_fbo);
-  
-  glGenRenderbuffers(1, &dr->ldr_front_buffer);
-  glBindRenderbuffer(GL_RENDERBUFFER, dr->ldr_front_buffer);
-  glRenderbufferStorage(GL_RENDERBUFFER, GL_RGBA, width, height);
-  glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_RENDERBUFFER, dr->ldr_front_buffer);   
-  
-  glGenTextures(1, &dr->ldr_front_texture);
-  glBindTexture(GL_TEXTURE_2D, dr->ldr_front_texture);
-  glTexImage2D(GL_TEXTURE_2D, 0, GL_RGBA, width, height, 0, GL_RGBA, GL_UNSIGNED_BYTE, NULL);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
-  glFramebufferTexture2D(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_TEXTURE_2D, dr->ldr_front_texture, 0);
- 
-  /* LDR back buffer */
-  
-  glGenFramebuffers(1, &dr->ldr_back_fbo);
-  glBindFramebuffer(GL_FRAMEBUFFER, dr->ldr_back_fbo);
-  
-  glGenRenderbuffers(1, &dr->ldr_back_buffer);
-  glBindRenderbuffer(GL_RENDERBUFFER, dr->ldr_back_buffer);
-  glRenderbufferStorage(GL_RENDERBUFFER, GL_RGBA, width, height);
-  glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_RENDERBUFFER, dr->ldr_back_buffer);   
-  
-  glGenTextures(1, &dr->ldr_back_texture);
-  glBindTexture(GL_TEXTURE_2D, dr->ldr_back_texture);
-  glTexImage2D(GL_TEXTURE_2D, 0, GL_RGBA, width, height, 0, GL_RGBA, GL_UNSIGNED_BYTE, NULL);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
-  glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
-  glFramebufferTexture2D(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_TEXTURE_2D, dr->ldr_back_texture, 0);
+  glGenFramebuffers(1, &dr->target_fbo);
+  dr->target = NULL;
 
   /* Shadow Buffers */
   
   int shadow_width  = option_graphics_int(asset_hndl_ptr(&dr->options), "shadows", 4096, 2048, 1024);
   int shadow_height = option_graphics_int(asset_hndl_ptr(&dr->options), "shadows", 4096, 2048, 1024);
   
+  glGetIntegerv(GL_MAX_TEXTURE_IMAGE_UNITS, &dr->texture_units);
+  
+  dr->shadows_num = 3;
+  dr->shadows_split = SHADOW_SPLIT_MANUAL;
+  dr->shadows_split_blend = 0.5;
+  dr->shadows_distance = 0.2;
+  dr->shadows_bias = 0.001;
+  dr->shadows_normal_offset = 0.0;
+  dr->shadows_debug = false;
+  
   dr->shadows_start[0] = 0.000; dr->shadows_end[0] = 0.060;
   dr->shadows_start[1] = 0.060; dr->shadows_end[1] = 0.070;
   dr->shadows_start[2] = 0.070; dr->shadows_end[2] = 0.200;
+  dr->shadows_start[3] = 0.200; dr->shadows_end[3] = 0.500;
+  dr->shadows_snap = 10;
   
-  dr->shadows_widths[0] = shadow_width; dr->shadows_heights[0] = shadow_height;
-  dr->shadows_widths[1] = shadow_width; dr->shadows_heights[1] = shadow_height;
-  dr->shadows_widths[2] = shadow_width; dr->shadows_heights[2] = shadow_height;
-  
-  for (int i = 0; i < 3; i++) {
+  for (int i = 0; i < RENDERER_MAX_CASCADES; i++) {
+    
+    dr->shadows_widths[i] = shadow_width; dr->shadows_heights[i] = shadow_height;
     
     glGenFramebuffers(1, &dr->shadows_fbo[i]);
     glBindFramebuffer(GL_FRAMEBUFFER, dr->shadows_fbo[i]);
@@ -392,8 +449,78 @@ renderer* renderer_new(asset_hndl options) {
     
   }
   
+  /* Spot Shadow Buffers */
+  
+  dr->spot_shadows_width  = option_graphics_int(asset_hndl_ptr(&dr->options), "shadows", 2048, 1024, 512);
+  dr->spot_shadows_height = option_graphics_int(asset_hndl_ptr(&dr->options), "shadows", 2048, 1024, 512);
+  
+  for (int i = 0; i < RENDERER_MAX_SPOT_SHADOWS; i++) {
+    
+    glGenFramebuffers(1, &dr->spot_shadows_fbo[i]);
+    glBindFramebuffer(GL_FRAMEBUFFER, dr->spot_shadows_fbo[i]);
+    glDrawBuffer(GL_NONE);
+    glReadBuffer(GL_NONE);
+    
+    glGenRenderbuffers(1, &dr->spot_shadows_buffer[i]);
+    glBindRenderbuffer(GL_RENDERBUFFER, dr->spot_shadows_buffer[i]);
+    glRenderbufferStorage(GL_RENDERBUFFER, GL_DEPTH_COMPONENT, dr->spot_shadows_width, dr->spot_shadows_height);
+    glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_DEPTH_ATTACHMENT, GL_RENDERBUFFER, dr->spot_shadows_buffer[i]);
+    
+    glGenTextures(1, &dr->spot_shadows_texture[i]);
+    glBindTexture(GL_TEXTURE_2D, dr->spot_shadows_texture[i]);
+    glTexImage2D(GL_TEXTURE_2D, 0, GL_DEPTH_COMPONENT, dr->spot_shadows_width, dr->spot_shadows_height, 0, GL_DEPTH_COMPONENT, GL_FLOAT, NULL);
+    glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR);
+    glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
+    glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
+    glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
+    glTexParameteri(GL_TEXTURE_2D, GL_DEPTH_TEXTURE_MODE, GL_INTENSITY);
+    glFramebufferTexture2D(GL_FRAMEBUFFER, GL_DEPTH_ATTACHMENT, GL_TEXTURE_2D, dr->spot_shadows_texture[i], 0);
+    
+  }
+  
+  /* Point Shadow Buffers */
+  
+  dr->point_shadows_size   = option_graphics_int(asset_hndl_ptr(&dr->options), "shadows", 1024, 512, 256);
+  dr->point_shadows_budget = option_graphics_int(asset_hndl_ptr(&dr->options), "shadows", RENDERER_MAX_POINT_SHADOWS, 1, 0);
+  dr->point_shadows_limit  = dr->point_shadows_budget;
+  
+  for (int i = 0; i < RENDERER_MAX_POINT_SHADOWS; i++) {
+    
+    glGenFramebuffers(1, &dr->point_shadows_fbo[i]);
+    glBindFramebuffer(GL_FRAMEBUFFER, dr->point_shadows_fbo[i]);
+    glDrawBuffer(GL_NONE);
+    glReadBuffer(GL_NONE);
+    
+    glGenTextures(1, &dr->point_shadows_texture[i]);
+    glBindTexture(GL_TEXTURE_CUBE_MAP, dr->point_shadows_texture[i]);
+    for (int j = 0; j < 6; j++) {
+      glTexImage2D(GL_TEXTURE_CUBE_MAP_POSITIVE_X + j, 0, GL_DEPTH_COMPONENT, dr->point_shadows_size, dr->point_shadows_size, 0, GL_DEPTH_COMPONENT, GL_FLOAT, NULL);
+    }
+    glTexParameteri(GL_TEXTURE_CUBE_MAP, GL_TEXTURE_MIN_FILTER, GL_LINEAR);
+    glTexParameteri(GL_TEXTURE_CUBE_MAP, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
+    glTexParameteri(GL_TEXTURE_CUBE_MAP, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
+    glTexParameteri(GL_TEXTURE_CUBE_MAP, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
+    glTexParameteri(GL_TEXTURE_CUBE_MAP, GL_TEXTURE_WRAP_R, GL_CLAMP_TO_EDGE);
+    glTexParameteri(GL_TEXTURE_CUBE_MAP, GL_DEPTH_TEXTURE_MODE, GL_INTENSITY);
+    glFramebufferTexture2D(GL_FRAMEBUFFER, GL_DEPTH_ATTACHMENT, GL_TEXTURE_CUBE_MAP_POSITIVE_X, dr->point_shadows_texture[i], 0);
+    
+  }
+  
+  glBindTexture(GL_TEXTURE_CUBE_MAP, 0);
   glBindFramebuffer(GL_FRAMEBUFFER, 0);
   
+  /* Spot Lights */
+  dr->spot_lights_num = 0;
+  for (int i = 0; i < RENDERER_MAX_SPOT_SHADOWS; i++) {
+    dr->spot_light[i] = NULL;
+  }
+  
+  /* Point Lights */
+  dr->point_lights_num = 0;
+  for (int i = 0; i < RENDERER_MAX_POINT_SHADOWS; i++) {
+    dr->point_light[i] = NULL;
+  }
+  
   /* Variables */
   dr->seed = 0;
   dr->glitch = 0.0;
@@ -404,10 +531,15 @@ renderer* renderer_new(asset_hndl options) {
   dr->exposure_target = 0.4;
   dr->skydome_enabled = true;
   dr->sea_enabled = false;
+  dr->culling_enabled = true;
   
   /* Objects */
   dr->render_objects_num = 0;
   dr->render_objects = NULL;
+  
+  /* Statistics */
+  dr->culled_num = 0;
+  dr->shadows_culled_num = 0;
     
   SDL_GL_CheckError();
   SDL_GL_CheckFrameBuffer();
@@ -418,35 +550,23 @@ renderer* renderer_new(asset_hndl options) {
 
 void renderer_delete(renderer* dr) {
   
-  glDeleteFramebuffers(1, &dr->gfbo);
-  
-  glDeleteRenderbuffers(1, &dr->gdiffuse_buffer);
-  glDeleteRenderbuffers(1, &dr->gnormals_buffer);
-  glDeleteRenderbuffers(1, &dr->gdepth_buffer);
-  
-  glDeleteTextures(1, &dr->gdiffuse_texture);
-  glDeleteTextures(1, &dr->gnormals_texture);
-  glDeleteTextures(1, &dr->gdepth_texture);
-  
-  glDeleteFramebuffers(1, &dr->ssao_fbo);
-  glDeleteRenderbuffers(1, &dr->ssao_buffer);
-  glDeleteTextures(1, &dr->ssao_texture);
+  render_buffers_delete(&dr->screen_buffers);
+  if (dr->target_buffers.width > 0) {
+    render_buffers_delete(&dr->target_buffers);
+  }
   
-  glDeleteFramebuffers(1, &dr->hdr_fbo);
-  glDeleteRenderbuffers(1, &dr->hdr_buffer);
-  glDeleteTextures(1, &dr->hdr_texture);
+  glDeleteFramebuffers(1, &dr->target_fbo);
   
-  glDeleteFramebuffers(1, &dr->ldr_front_fbo);
-  glDeleteRenderbuffers(1, &dr->ldr_front_buffer);
-  glDeleteTextures(1, &dr->ldr_front_texture);
+  glDeleteFramebuffers(RENDERER_MAX_CASCADES, dr->shadows_fbo);
+  glDeleteRenderbuffers(RENDERER_MAX_CASCADES, dr->shadows_buffer);
+  glDeleteTextures(RENDERER_MAX_CASCADES, dr->shadows_texture);
   
-  glDeleteFramebuffers(1, &dr->ldr_back_fbo);
-  glDeleteRenderbuffers(1, &dr->ldr_back_buffer);
-  glDeleteTextures(1, &dr->ldr_back_texture);
+  glDeleteFramebuffers(RENDERER_MAX_SPOT_SHADOWS, dr->spot_shadows_fbo);
+  glDeleteRenderbuffers(RENDERER_MAX_SPOT_SHADOWS, dr->spot_shadows_buffer);
+  glDeleteTextures(RENDERER_MAX_SPOT_SHADOWS, dr->spot_shadows_texture);
   
-  glDeleteFramebuffers(3, dr->shadows_fbo);
-  glDeleteRenderbuffers(3, dr->shadows_buffer);
-  glDeleteTextures(3, dr->shadows_texture);
+  glDeleteFramebuffers(RENDERER_MAX_POINT_SHADOWS, dr->point_shadows_fbo);
+  glDeleteTextures(RENDERER_MAX_POINT_SHADOWS, dr->point_shadows_texture);
   
   free(dr->render_objects);
     
@@ -481,6 +601,63 @@ void renderer_set_sea_enabled(renderer* dr, bool enabled) {
   dr->sea_enabled = enabled;
 }
 
+void renderer_set_culling_enabled(renderer* dr, bool enabled) {
+  dr->culling_enabled = enabled;
+}
+
+void renderer_set_point_shadows(renderer* dr, int num) {
+  if (num > dr->point_shadows_budget) {
+    warning("Point shadows limited to %i by shadow quality setting.", dr->point_shadows_budget);
+  }
+  dr->point_shadows_limit = clamp(num, 0, dr->point_shadows_budget);
+}
+
+int renderer_max_shadow_cascades(renderer* dr) {
+  return dr->texture_units > 16 ? RENDERER_MAX_CASCADES : RENDERER_MAX_CASCADES - 1;
+}
+
+void renderer_set_shadow_cascades(renderer* dr, int num, int split, float blend, float distance) {
+  int max = renderer_max_shadow_cascades(dr);
+  if (num < 1 || num > max) {
+    warning("Shadow cascades must be between 1 and %i.", max);
+  }
+  dr->shadows_num = clamp(num, 1, max);
+  dr->shadows_split = split;
+  dr->shadows_split_blend = saturate(blend);
+  dr->shadows_distance = saturate(distance);
+}
+
+void renderer_set_shadow_snap(renderer* dr, float snap) {
+  dr->shadows_snap = max(snap, 0);
+}
+
+void renderer_set_shadow_bias(renderer* dr, float bias, float normal_offset) {
+  dr->shadows_bias = bias;
+  dr->shadows_normal_offset = normal_offset;
+}
+
+void renderer_set_shadow_resolution(renderer* dr, int cascade, int width, int height) {
+  
+  if (cascade < 0 || cascade >= RENDERER_MAX_CASCADES) {
+    warning("Cannot set resolution of shadow cascade %i. Maximum cascades is %i.", cascade, RENDERER_MAX_CASCADES);
+    return;
+  }
+  
+  dr->shadows_widths[cascade] = width;
+  dr->shadows_heights[cascade] = height;
+  
+  glBindRenderbuffer(GL_RENDERBUFFER, dr->shadows_buffer[cascade]);
+  glRenderbufferStorage(GL_RENDERBUFFER, GL_DEPTH_COMPONENT, width, height);
+  
+  glBindTexture(GL_TEXTURE_2D, dr->shadows_texture[cascade]);
+  glTexImage2D(GL_TEXTURE_2D, 0, GL_DEPTH_COMPONENT, width, height, 0, GL_DEPTH_COMPONENT, GL_FLOAT, NULL);
+  
+}
+
+void renderer_set_shadow_debug(renderer* dr, bool enabled) {
+  dr->shadows_debug = enabled;
+}
+
 void renderer_set_tod(renderer* dr, float tod, int seed) {
   dr->time_of_day = tod;
   sky_update(dr->sky, dr->time_of_day, 0);
@@ -504,8 +681,77 @@ void renderer_add(renderer* dr, render_object ro) {
   dr->render_objects[dr->render_objects_num-1] = ro;
 }
 
-static int round_to(float x, int multiple) {
-  return (int)(x / multiple) * multiple;
+/* Animated objects are bounded by their rest pose, so allow them some slack */
+static const float ANIMATED_BOUND_SLACK = 1.5;
+
+/* Whether culling is enabled and the bound is outside the frustum, counting it if so */
+static bool renderer_culled(renderer* dr, sphere bound, box frustum, int* culled) {
+  if (!dr->culling_enabled) { return false; }
+  if (!sphere_outside_box(bound, frustum)) { return false; }
+  (*culled)++;
+  return true;
+}
+
+static bool render_object_culled(renderer* dr, render_object ro, box frustum) {
+  
+  if (!dr->culling_enabled) { return false; }
+  
+  if (ro.type == RO_TYPE_STATIC) {
+    static_object* so = ro.static_object;
+    renderable* r = asset_hndl_ptr(&so->renderable);
+    if (r->num_surfaces == 0) { return false; }
+    mat4 world = mat4_world(so->position, so->scale, so->rotation);
+    return sphere_outside_box(sphere_transform(renderable_bound(r), world), frustum);
+  }
+  
+  if (ro.type == RO_TYPE_ANIMATED) {
+    animated_object* ao = ro.animated_object;
+    renderable* r = asset_hndl_ptr(&ao->renderable);
+    if (r->num_surfaces == 0) { return false; }
+    mat4 world = mat4_world(ao->position, ao->scale, ao->rotation);
+    sphere bound = sphere_scale(renderable_bound(r), ANIMATED_BOUND_SLACK);
+    return sphere_outside_box(sphere_transform(bound, world), frustum);
+  }
+  
+  if (ro.type == RO_TYPE_INSTANCE) {
+    return sphere_outside_box(ro.instance_object->bound, frustum);
+  }
+  
+  return false;
+}
+
+/* Smallest half-size of a cascade, so tiny near cascades still get a valid projection */
+#define SHADOW_MIN_RANGE 0.5
+
+static float round_up_to(float x, float multiple) {
+  return ceilf(x / multiple) * multiple;
+}
+
+static void shadow_mapper_splits(renderer* dr) {
+  
+  if (dr->shadows_split == SHADOW_SPLIT_MANUAL) { return; }
+  
+  float near = dr->camera->near_clip;
+  float far  = dr->camera->far_clip;
+  float end  = near + (far - near) * dr->shadows_distance;
+  
+  float splits[RENDERER_MAX_CASCADES+1];
+  
+  for (int i = 0; i <= dr->shadows_num; i++) {
+    float t = (float)i / dr->shadows_num;
+    float linear = near + (end - near) * t;
+    float logarithmic = near * powf(end / near, t);
+    float d = linear;
+    if (dr->shadows_split == SHADOW_SPLIT_LOGARITHMIC) { d = logarithmic; }
+    if (dr->shadows_split == SHADOW_SPLIT_BLENDED) { d = lerp(linear, logarithmic, dr->shadows_split_blend); }
+    splits[i] = (d - near) / (far - near);
+  }
+  
+  for (int i = 0; i < dr->shadows_num; i++) {
+    dr->shadows_start[i] = splits[i];
+    dr->shadows_end[i] = splits[i+1];
+  }
+  
 }
 
 static void shadow_mapper_transforms(renderer* dr, int i, mat4* view, mat4* proj, float* nearclip, float* farclip) {
@@ -532,11 +778,16 @@ static void shadow_mapper_transforms(renderer* dr, int i, mat4* view, mat4* proj
   float rangex = max(maximums.x, -minimums.x);
   float rangey = max(maximums.y, -minimums.y);
   float rangez = max(maximums.z, -minimums.z);
-  float range  = round_to(max(max(rangex, rangey), rangez), 10);
+  float range  = max(max(rangex, rangey), rangez);
+  
+  /* Snapping the size keeps it steady as the camera turns, which stops shadow edges shimmering */
+  if (dr->shadows_snap > 0) { range = round_up_to(range, dr->shadows_snap); }
+  range = max(range, SHADOW_MIN_RANGE);
   
-  float pixel = dr->shadows_widths[i] / range;
+  /* Moving the center in whole texels does the same as the camera moves */
+  float texel = 2 * range / dr->shadows_widths[i];
   
-  vec3 offset = vec3_fmod(center, pixel);
+  vec3 offset = vec3_fmod(center, texel);
   center = vec3_sub(center, offset);
   
   if (dr->sky->is_day) {
@@ -553,7 +804,7 @@ static void shadow_mapper_transforms(renderer* dr, int i, mat4* view, mat4* proj
 
 static void render_shadows_vegetation(renderer* dr, int i, instance_object* io) {
   
-  if (sphere_outside_box(io->bound, dr->camera_frustum)) { return; }
+  if (renderer_culled(dr, io->bound, dr->camera_frustum, &dr->shadows_culled_num)) { return; }
   
   shader_program* shader = material_first_program(asset_hndl_ptr(&dr->mat_depth_veg));
   shader_program_enable(shader);
@@ -628,7 +879,7 @@ static void render_shadows_static(renderer* dr, int i, static_object* s) {
     
     renderable_surface* s = r->surfaces[j];
     
-    if (sphere_outside_box(sphere_transform(s->bound, world), dr->shadow_frustum[i])) { continue; }
+    if (renderer_culled(dr, sphere_transform(s->bound, world), dr->shadow_frustum[i], &dr->shadows_culled_num)) { continue; }
     
     material_entry* me = material_get_entry(asset_hndl_ptr(&r->material), j);
     
@@ -661,7 +912,7 @@ static void render_shadows_static(renderer* dr, int i, static_object* s) {
 
 static void render_shadows_instance(renderer* dr, int i, instance_object* io) {
   
-  if (sphere_outside_box(io->bound, dr->camera_frustum)) { return; }
+  if (renderer_culled(dr, io->bound, dr->camera_frustum, &dr->shadows_culled_num)) { return; }
   
   shader_program* shader = material_first_program(asset_hndl_ptr(&dr->mat_depth_ins));
   shader_program_enable(shader);
@@ -793,7 +1044,7 @@ static void render_shadows_animated(renderer* dr, int i, animated_object* ao) {
 
 static void render_shadows_landscape_blobtree(renderer* dr, int i, shader* shader, landscape_blobtree* lbt, terrain* terr) {
 
-  if (sphere_outside_box(lbt->bound, dr->shadow_frustum[i])) { return; }
+  if (renderer_culled(dr, lbt->bound, dr->shadow_frustum[i], &dr->shadows_culled_num)) { return; }
   
   if (!lbt->is_leaf) {
     render_shadows_landscape_blobtree(dr, i, shader, lbt->child0, terr);
@@ -842,14 +1093,137 @@ static void render_shadows_landscape(renderer* dr, int i, landscape* l) {
 
 }
 
+static void render_shadows_casters(renderer* dr, int i) {
+  
+  for ( int j = 0; j < dr->render_objects_num; j++) {
+    
+    if (render_object_culled(dr, dr->render_objects[j], dr->shadow_frustum[i])) {
+      dr->shadows_culled_num++;
+      continue;
+    }
+    
+    // HACK ALERT
+    bool veg_found = false;
+    if (dr->render_objects[j].type == RO_TYPE_INSTANCE) {
+      renderable* r = asset_hndl_ptr(&dr->render_objects[j].instance_object->renderable);
+      material* m = asset_hndl_ptr(&r->material);        
+      
+      for (int k = 0; k < m->num_entries; k++) {
+        if (material_entry_item(m->entries[k], "material").as_int == 6) {
+          render_shadows_vegetation(dr, i, dr->render_objects[j].instance_object);
+          veg_found = true;
+          break;
+        }
+      }
+      
+    }
+    
+    if (veg_found) continue;
+    
+    if (dr->render_objects[j].type == RO_TYPE_STATIC) { render_shadows_static(dr, i, dr->render_objects[j].static_object); }
+    if (dr->render_objects[j].type == RO_TYPE_INSTANCE) { render_shadows_instance(dr, i, dr->render_objects[j].instance_object); }
+    if (dr->render_objects[j].type == RO_TYPE_ANIMATED) { render_shadows_animated(dr, i, dr->render_objects[j].animated_object); }
+    if (dr->render_objects[j].type == RO_TYPE_LANDSCAPE) { render_shadows_landscape(dr, i, dr->render_objects[j].landscape); }
+    
+  }
+  
+}
+
+static void render_shadows_map(renderer* dr, int i, GLuint fbo, int width, int height) {
+  
+  glBindFramebuffer(GL_FRAMEBUFFER, fbo);  
+  glViewport( 0, 0, width, height);
+  glClearDepth(1.0f);  
+  glClear(GL_DEPTH_BUFFER_BIT);
+  
+  glEnable(GL_DEPTH_TEST);
+  glEnable(GL_CULL_FACE);
+  glCullFace(GL_FRONT);
+  
+  render_shadows_casters(dr, i);
+  
+  glCullFace(GL_BACK);
+  glDisable(GL_CULL_FACE);
+  glDisable(GL_DEPTH_TEST);
+  
+  glViewport( 0, 0, dr->buffers->width, dr->buffers->height);
+  glBindFramebuffer(GL_FRAMEBUFFER, 0);
+  
+}
+
+static void spot_mapper_transforms(renderer* dr, light* l, mat4* view, mat4* proj, float* nearclip, float* farclip) {
+  
+  vec3 up = fabs(vec3_dot(light_direction(l), vec3_up())) > 0.99 ? vec3_new(1,0,0) : vec3_up();
+  
+  *view = mat4_view_look_at(l->position, l->target, up);
+  *proj = mat4_perspective(l->outer_angle, DEFAULT_LIGHT_NEAR_CLIP, light_radius(l), 1.0);
+  *nearclip = DEFAULT_LIGHT_NEAR_CLIP;
+  *farclip = light_radius(l);
+  
+}
+
+static const vec3 point_face_forward[6] = {
+  {  1,  0,  0 }, { -1,  0,  0 },
+  {  0,  1,  0 }, {  0, -1,  0 },
+  {  0,  0,  1 }, {  0,  0, -1 },
+};
+
+static const vec3 point_face_up[6] = {
+  {  0, -1,  0 }, {  0, -1,  0 },
+  {  0,  0,  1 }, {  0,  0, -1 },
+  {  0, -1,  0 }, {  0, -1,  0 },
+};
+
+static void point_mapper_transforms(renderer* dr, light* l, int face, mat4* view, mat4* proj, float* nearclip, float* farclip) {
+  
+  *view = mat4_view_look_at(l->position, vec3_add(l->position, point_face_forward[face]), point_face_up[face]);
+  *proj = mat4_perspective(M_PI / 4, DEFAULT_LIGHT_NEAR_CLIP, light_radius(l), 1.0);
+  *nearclip = DEFAULT_LIGHT_NEAR_CLIP;
+  *farclip = light_radius(l);
+  
+}
+
 static void render_shadows(renderer* dr) {
   
   dr->camera_inv_view = mat4_inverse(camera_view_matrix(dr->camera));
-  dr->camera_inv_proj = mat4_inverse(camera_proj_matrix(dr->camera));
+  dr->camera_inv_proj = mat4_inverse(camera_proj_matrix_ratio(dr->camera, render_ratio(dr)));
+  
+  dr->spot_lights_num = 0;
+  for (int i = 0; i < dr->dyn_lights_num; i++) {
+    light* l = dr->dyn_light[i];
+    if (l->type != LIGHT_TYPE_SPOT || !l->enabled) { continue; }
+    if (dr->spot_lights_num == RENDERER_MAX_SPOT_SHADOWS) {
+      warning("Cannot map extra spot light. Maximum spot lights reached!");
+      break;
+    }
+    
+    int k = dr->spot_lights_num;
+    dr->spot_light[k] = l;
+    dr->spot_lights_num++;
+    
+    int s = RENDERER_MAX_CASCADES + k;
+    
+    spot_mapper_transforms(dr, l,
+      &dr->shadow_view[s], &dr->shadow_proj[s],
+      &dr->shadow_near[s], &dr->shadow_far[s]);
+    
+    dr->shadow_frustum[s] = box_invert_depth(frustum_box(frustum_new_camera(dr->shadow_view[s], dr->shadow_proj[s])));
+  }
+  
+  dr->point_lights_num = 0;
+  for (int i = 0; i < dr->dyn_lights_num; i++) {
+    light* l = dr->dyn_light[i];
+    if (l->type != LIGHT_TYPE_POINT || !l->enabled || !l->cast_shadows) { continue; }
+    if (dr->point_lights_num == dr->point_shadows_limit) { break; }
+    dr->point_light[dr->point_lights_num] = l;
+    dr->point_lights_num++;
+  }
   
   if (config_int(asset_hndl_ptr(&dr->options), "shadows") == 0) return;
   
-  for (int i = 0; i < 3; i++) {
+  shadow_mapper_splits(dr);
+  
+  for (int i = 0; i < dr->shadows_num; i++) {
     
     shadow_mapper_transforms(dr, i,
       &dr->shadow_view[i], &dr->shadow_proj[i],
@@ -857,49 +1231,36 @@ static void render_shadows(renderer* dr) {
     
     dr->shadow_frustum[i] = box_invert_depth(box_invert(frustum_box(frustum_new_camera(dr->shadow_view[i], dr->shadow_proj[i]))));
     
-    glBindFramebuffer(GL_FRAMEBUFFER, dr->shadows_fbo[i]);  
-    glViewport( 0, 0, dr->shadows_widths[i], dr->shadows_heights[i]);
-    glClearDepth(1.0f);  
-    glClear(GL_DEPTH_BUFFER_BIT);
+    render_shadows_map(dr, i, dr->shadows_fbo[i], dr->shadows_widths[i], dr->shadows_heights[i]);
     
-    glEnable(GL_DEPTH_TEST);
-    glEnable(GL_CULL_FACE);
-    glCullFace(GL_FRONT);
+  }
+  
+  for (int k = 0; k < dr->spot_lights_num; k++) {
+    
+    light* l = dr->spot_light[k];
+    if (!l->cast_shadows) { continue; }
+    
+    render_shadows_map(dr, RENDERER_MAX_CASCADES + k, dr->spot_shadows_fbo[k], dr->spot_shadows_width, dr->spot_shadows_height);
     
-    for ( int j = 0; j < dr->render_objects_num; j++) {
+  }
+  
+  for (int k = 0; k < dr->point_lights_num; k++) {
+    for (int face = 0; face < 6; face++) {
       
-      // HACK ALERT
-      bool veg_found = false;
-      if (dr->render_objects[j].type == RO_TYPE_INSTANCE) {
-        renderable* r = asset_hndl_ptr(&dr->render_objects[j].instance_object->renderable);
-        material* m = asset_hndl_ptr(&r->material);        
-        
-        for (int k = 0; k < m->num_entries; k++) {
-          if (material_entry_item(m->entries[k], "material").as_int == 6) {
-            render_shadows_vegetation(dr, i, dr->render_objects[j].instance_object);
-            veg_found = true;
-            break;
-          }
-        }
-        
-      }
+      int i = RENDERER_MAX_CASCADES + RENDERER_MAX_SPOT_SHADOWS + k * 6 + face;
       
-      if (veg_found) continue;
+      point_mapper_transforms(dr, dr->point_light[k], face,
+        &dr->shadow_view[i], &dr->shadow_proj[i],
+        &dr->shadow_near[i], &dr->shadow_far[i]);
       
-      if (dr->render_objects[j].type == RO_TYPE_STATIC) { render_shadows_static(dr, i, dr->render_objects[j].static_object); }
-      if (dr->render_objects[j].type == RO_TYPE_INSTANCE) { render_shadows_instance(dr, i, dr->render_objects[j].instance_object); }
-      if (dr->render_objects[j].type == RO_TYPE_ANIMATED) { render_shadows_animated(dr, i, dr->render_objects[j].animated_object); }
-      if (dr->render_objects[j].type == RO_TYPE_LANDSCAPE) { render_shadows_landscape(dr, i, dr->render_objects[j].landscape); }
+      dr->shadow_frustum[i] = box_invert_depth(frustum_box(frustum_new_camera(dr->shadow_view[i], dr->shadow_proj[i])));
+      
+      glBindFramebuffer(GL_FRAMEBUFFER, dr->point_shadows_fbo[k]);
+      glFramebufferTexture2D(GL_FRAMEBUFFER, GL_DEPTH_ATTACHMENT, GL_TEXTURE_CUBE_MAP_POSITIVE_X + face, dr->point_shadows_texture[k], 0);
+      
+      render_shadows_map(dr, i, dr->point_shadows_fbo[k], dr->point_shadows_size, dr->point_shadows_size);
       
     }
-    
-    glCullFace(GL_BACK);
-    glDisable(GL_CULL_FACE);
-    glDisable(GL_DEPTH_TEST);
-    
-    glViewport( 0, 0, graphics_viewport_width(), graphics_viewport_height());
-    glBindFramebuffer(GL_FRAMEBUFFER, 0);
-  
   }
     
 }
@@ -909,9 +1270,9 @@ void render_plane(renderer* dr, plane p);
 
 static void render_clear(renderer* dr) {
 
-  glBindFramebuffer(GL_FRAMEBUFFER, dr->gfbo);
+  glBindFramebuffer(GL_FRAMEBUFFER, dr->buffers->gfbo);
   glDrawBuffers(2, (GLenum[]){ GL_COLOR_ATTACHMENT0, GL_COLOR_ATTACHMENT1 });
-  glViewport( 0, 0, graphics_viewport_width(), graphics_viewport_height());
+  glViewport( 0, 0, dr->buffers->width, dr->buffers->height);
   glClearColor(0.2, 0.2, 0.2, 1.0f);
   glClearDepth(1.0f);
   glClear( GL_COLOR_BUFFER_BIT | GL_DEPTH_BUFFER_BIT );
@@ -957,13 +1318,7 @@ static fpath cmesh_pallet[14] = {
   {"$CORANGE/textures/solid/grey.dds"},
 };
 
-static void render_cmesh(renderer* dr, cmesh* cm, mat4 world) {
-  
-  if (!cm->is_leaf) {
-    render_cmesh(dr, cm->front, world);
-    render_cmesh(dr, cm->back,  world);
-    return;
-  }
+static void render_cmesh_triangles(renderer* dr, ctri* triangles, int triangles_num, mat4 world) {
   
   shader_program* shader = material_first_program(asset_hndl_ptr(&dr->mat_static));
   shader_program_enable(shader);
@@ -972,6 +1327,7 @@ static void render_cmesh(renderer* dr, cmesh* cm, mat4 world) {
   shader_program_set_mat4(shader, "proj", dr->camera_proj);
   shader_program_set_float(shader, "clip_near", dr->camera_near);
   shader_program_set_float(shader, "clip_far",  dr->camera_far);
+  shader_program_set_int(shader, "clip_orthographic", dr->camera_orthographic);
   
   material_entry* me = material_get_entry(asset_get_load(P("$CORANGE/shaders/basic.mat")), 0);
   
@@ -986,11 +1342,11 @@ static void render_cmesh(renderer* dr, cmesh* cm, mat4 world) {
   shader_program_set_float(shader, "alpha_test", 0);
   shader_program_set_int(shader, "material", material_entry_item(me, "material").as_int);
   
-  vec3* positions = malloc(sizeof(vec3) * cm->triangles_num * 3);
-  vec3* normals   = malloc(sizeof(vec3) * cm->triangles_num * 3);
+  vec3* positions = malloc(sizeof(vec3) * triangles_num * 3);
+  vec3* normals   = malloc(sizeof(vec3) * triangles_num * 3);
   
-  for (int i = 0; i < cm->triangles_num * 3; i += 3) {
-    ctri t = cm->triangles[i / 3];
+  for (int i = 0; i < triangles_num * 3; i += 3) {
+    ctri t = triangles[i / 3];
     
     positions[i+0] = t.a;
     positions[i+1] = t.b;
@@ -1009,7 +1365,7 @@ static void render_cmesh(renderer* dr, cmesh* cm, mat4 world) {
   shader_program_enable_attribute(shader, "vBinormal",  3, 3, normals);
   shader_program_enable_attribute(shader, "vTexcoord",  2, 2, normals);
     
-    glDrawArrays(GL_TRIANGLES, 0, cm->triangles_num * 3);
+    glDrawArrays(GL_TRIANGLES, 0, triangles_num * 3);
   
   shader_program_disable_attribute(shader, "vPosition");
   shader_program_disable_attribute(shader, "vNormal");
@@ -1026,6 +1382,34 @@ static void render_cmesh(renderer* dr, cmesh* cm, mat4 world) {
     
 }
 
+/* Subtrees with up to this many triangles are drawn in a single color */
+#define CMESH_RENDER_TRIANGLES 64
+
+static void render_cmesh_node(renderer* dr, cmesh* cm, int index, mat4 world) {
+  
+  /* Triangles of a subtree are contiguous, from its leftmost to its rightmost leaf */
+  int first = index;
+  int last = index;
+  while (cm->nodes[first].count == 0) { first = cm->nodes[first].first; }
+  while (cm->nodes[last].count  == 0) { last  = cm->nodes[last].first + 1; }
+  
+  int start = cm->nodes[first].first;
+  int end = cm->nodes[last].first + cm->nodes[last].count;
+  
+  if (cm->nodes[index].count == 0 && end - start > CMESH_RENDER_TRIANGLES) {
+    render_cmesh_node(dr, cm, cm->nodes[index].first+0, world);
+    render_cmesh_node(dr, cm, cm->nodes[index].first+1, world);
+  } else {
+    render_cmesh_triangles(dr, cm->triangles + start, end - start, world);
+  }
+  
+}
+
+static void render_cmesh(renderer* dr, cmesh* cm, mat4 world) {
+  if (cm->nodes_num == 0) { return; }
+  render_cmesh_node(dr, cm, 0, world);
+}
+
 static void render_static(renderer* dr, static_object* so) {
   
   mat4 world = mat4_world( so->position, so->scale, so->rotation );
@@ -1051,12 +1435,13 @@ static void render_static(renderer* dr, static_object* so) {
   shader_program_set_mat4(shader, "proj", dr->camera_proj);
   shader_program_set_float(shader, "clip_near", dr->camera_near);
   shader_program_set_float(shader, "clip_far",  dr->camera_far);
+  shader_program_set_int(shader, "clip_orthographic", dr->camera_orthographic);
   
   for(int i=0; i < r->num_surfaces; i++) {
     
     renderable_surface* s = r->surfaces[i];
     
-    if (sphere_outside_box(sphere_transform(s->bound, world), dr->camera_frustum)) { continue; }
+    if (renderer_culled(dr, sphere_transform(s->bound, world), dr->camera_frustum, &dr->culled_num)) { continue; }
     
     material_entry* me = material_get_entry(asset_hndl_ptr(&r->material), i);
     
@@ -1101,7 +1486,7 @@ static void render_static(renderer* dr, static_object* so) {
 
 static void render_skin(renderer* dr, instance_object* io) {
   
-  if (sphere_outside_box(io->bound, dr->camera_frustum)) { return; }
+  if (renderer_culled(dr, io->bound, dr->camera_frustum, &dr->culled_num)) { return; }
   
   if (config_bool(asset_hndl_ptr(&dr->options), "render_colmeshes")) {
     if (!file_isloaded(io->collision_body.path)) {
@@ -1120,6 +1505,7 @@ static void render_skin(renderer* dr, instance_object* io) {
   shader_program_set_mat4(shader, "proj", dr->camera_proj);
   shader_program_set_float(shader, "clip_near", dr->camera_near);
   shader_program_set_float(shader, "clip_far",  dr->camera_far);
+  shader_program_set_int(shader, "clip_orthographic", dr->camera_orthographic);
   
   for(int i=0; i < r->num_surfaces; i++) {
     
@@ -1173,7 +1559,7 @@ static void render_skin(renderer* dr, instance_object* io) {
 
 static void render_instance(renderer* dr, instance_object* io) {
   
-  if (sphere_outside_box(io->bound, dr->camera_frustum)) { return; }
+  if (renderer_culled(dr, io->bound, dr->camera_frustum, &dr->culled_num)) { return; }
   
   if (config_bool(asset_hndl_ptr(&dr->options), "render_colmeshes")) {
     if (!file_isloaded(io->collision_body.path)) {
@@ -1197,6 +1583,7 @@ static void render_instance(renderer* dr, instance_object* io) {
   shader_program_set_mat4(shader, "proj", dr->camera_proj);
   shader_program_set_float(shader, "clip_near", dr->camera_near);
   shader_program_set_float(shader, "clip_far",  dr->camera_far);
+  shader_program_set_int(shader, "clip_orthographic", dr->camera_orthographic);
   
   for(int i=0; i < r->num_surfaces; i++) {
     
@@ -1250,7 +1637,7 @@ static void render_instance(renderer* dr, instance_object* io) {
 
 static void render_vegetation(renderer* dr, instance_object* io) {
   
-  if (sphere_outside_box(io->bound, dr->camera_frustum)) { return; }  
+  if (renderer_culled(dr, io->bound, dr->camera_frustum, &dr->culled_num)) { return; }
   
   float fade = option_graphics_float(asset_hndl_ptr(&dr->options), "vegetation",
         1.0,
@@ -1281,6 +1668,7 @@ static void render_vegetation(renderer* dr, instance_object* io) {
   shader_program_set_mat4(shader, "proj", dr->camera_proj);
   shader_program_set_float(shader, "clip_near", dr->camera_near);
   shader_program_set_float(shader, "clip_far",  dr->camera_far);
+  shader_program_set_int(shader, "clip_orthographic", dr->camera_orthographic);
   shader_program_set_float(shader, "time", dr->time);
   shader_program_set_float(shader, "fade", fade);
   
@@ -1468,6 +1856,7 @@ static void render_animated(renderer* dr, animated_object* ao) {
   shader_program_set_mat4(shader, "proj", dr->camera_proj);
   shader_program_set_float(shader, "clip_near", dr->camera_near);
   shader_program_set_float(shader, "clip_far",  dr->camera_far);
+  shader_program_set_int(shader, "clip_orthographic", dr->camera_orthographic);
   shader_program_set_vec4_array(shader, "quat_reals", quat_reals, skel->joint_count);
   shader_program_set_vec4_array(shader, "quat_duals", quat_duals, skel->joint_count);
   
@@ -1527,7 +1916,7 @@ static void render_animated(renderer* dr, animated_object* ao) {
 
 static void render_landscape_blobtree(renderer* dr, shader* shader, landscape_blobtree* lbt, terrain* terr) {
   
-  if (sphere_outside_box(lbt->bound, dr->camera_frustum)) { return; }
+  if (renderer_culled(dr, lbt->bound, dr->camera_frustum, &dr->culled_num)) { return; }
   
   if (!lbt->is_leaf) {
     render_landscape_blobtree(dr, shader, lbt->child0, terr);
@@ -1588,6 +1977,7 @@ static void render_landscape(renderer* dr, landscape* l) {
   shader_program_set_mat4(shader, "proj", dr->camera_proj);
   shader_program_set_float(shader, "clip_near", dr->camera_near);
   shader_program_set_float(shader, "clip_far",  dr->camera_far);
+  shader_program_set_int(shader, "clip_orthographic", dr->camera_orthographic);
   
   if (quality != 0) {
     shader_program_set_float(shader, "size_x", l->size_x);
@@ -1637,7 +2027,7 @@ void render_light(renderer* dr, light* l) {
   
   vec3 light_pos = l->position;
   light_pos = mat4_mul_vec3(camera_view_matrix(dr->camera), light_pos);
-  light_pos = mat4_mul_vec3(camera_proj_matrix(dr->camera), light_pos);
+  light_pos = mat4_mul_vec3(camera_proj_matrix_ratio(dr->camera, render_ratio(dr)), light_pos);
   
   shader_program* shader = material_first_program(asset_hndl_ptr(&dr->mat_ui));
   shader_program_enable(shader);
@@ -1645,12 +2035,12 @@ void render_light(renderer* dr, light* l) {
   shader_program_set_float(shader, "alpha_test", 0.5);
   shader_program_set_mat4(shader, "world", mat4_id());
   shader_program_set_mat4(shader, "view", mat4_id());
-  shader_program_set_mat4(shader, "proj", mat4_orthographic(0, graphics_viewport_width(), graphics_viewport_height(), 0, -1, 1));
+  shader_program_set_mat4(shader, "proj", mat4_orthographic(0, dr->buffers->width, dr->buffers->height, 0, -1, 1));
   
-  float top = ((-light_pos.y + 1) / 2) * graphics_viewport_height() - 8;
-  float bot = ((-light_pos.y + 1) / 2) * graphics_viewport_height() + 8;
-  float left = ((light_pos.x + 1) / 2) * graphics_viewport_width() - 8;
-  float right = ((light_pos.x + 1) / 2) * graphics_viewport_width() + 8;
+  float top = ((-light_pos.y + 1) / 2) * dr->buffers->height - 8;
+  float bot = ((-light_pos.y + 1) / 2) * dr->buffers->height + 8;
+  float left = ((light_pos.x + 1) / 2) * dr->buffers->width - 8;
+  float right = ((light_pos.x + 1) / 2) * dr->buffers->width + 8;
   
   // TODO: Implement
   
@@ -1910,18 +2300,19 @@ static void render_gbuffer(renderer* dr) {
   */
   
   dr->camera_view = camera_view_matrix(dr->camera);
-  dr->camera_proj = camera_proj_matrix(dr->camera);
+  dr->camera_proj = camera_proj_matrix_ratio(dr->camera, render_ratio(dr));
   dr->camera_near = dr->camera->near_clip;
   dr->camera_far  = dr->camera->far_clip;
+  dr->camera_orthographic = dr->camera->orthographic;
   dr->camera_frustum = box_invert_depth(frustum_box(frustum_new_camera(dr->camera_view, dr->camera_proj)));
   
-  int width = graphics_viewport_width();
-  int height = graphics_viewport_height();
+  int width = dr->buffers->width;
+  int height = dr->buffers->height;
   
   int gwidth  = width  * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
   int gheight = height * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
   
-  glBindFramebuffer(GL_FRAMEBUFFER, dr->gfbo);
+  glBindFramebuffer(GL_FRAMEBUFFER, dr->buffers->gfbo);
   glDrawBuffers(2, (GLenum[]){ GL_COLOR_ATTACHMENT0, GL_COLOR_ATTACHMENT1 });
   glViewport( 0, 0, gwidth, gheight);
   
@@ -1930,6 +2321,11 @@ static void render_gbuffer(renderer* dr) {
   
   for ( int j = 0; j < dr->render_objects_num; j++) {
     
+    if (render_object_culled(dr, dr->render_objects[j], dr->camera_frustum)) {
+      dr->culled_num++;
+      continue;
+    }
+    
     // HACK ALERT
     bool veg_found = false;
     if (dr->render_objects[j].type == RO_TYPE_INSTANCE) {
@@ -2020,13 +2416,13 @@ static void render_ssao(renderer* dr) {
   
   if (config_int(asset_hndl_ptr(&dr->options), "ssao") == 0) return;
   
-  int width = graphics_viewport_width();
-  int height = graphics_viewport_height();
+  int width = dr->buffers->width;
+  int height = dr->buffers->height;
   
   int ssaowidth  = width  * option_graphics_int(asset_hndl_ptr(&dr->options), "ssao", 1, 0.5, 0.25);
   int ssaoheight = height * option_graphics_int(asset_hndl_ptr(&dr->options), "ssao", 1, 0.5, 0.25);
   
-  glBindFramebuffer(GL_FRAMEBUFFER, dr->ssao_fbo);
+  glBindFramebuffer(GL_FRAMEBUFFER, dr->buffers->ssao_fbo);
   glViewport(0, 0, ssaowidth, ssaoheight);
   glClearColor(1, 1, 1, 1);
   glClear( GL_COLOR_BUFFER_BIT );
@@ -2038,11 +2434,11 @@ static void render_ssao(renderer* dr) {
   shader_program_set_mat4(shader, "proj", mat4_orthographic(-1, 1, -1, 1, -1, 1));
   
   shader_program_set_texture(shader, "random_texture", 0, dr->tex_random);
-  shader_program_set_texture_id(shader, "depth_texture", 1, dr->gdepth_texture);
-  shader_program_set_texture_id(shader, "normals_texture", 2, dr->gnormals_texture);  
+  shader_program_set_texture_id(shader, "depth_texture", 1, dr->buffers->gdepth_texture);
+  shader_program_set_texture_id(shader, "normals_texture", 2, dr->buffers->gnormals_texture);  
 
-  shader_program_set_int(shader, "width", graphics_viewport_width());
-  shader_program_set_int(shader, "height", graphics_viewport_height());
+  shader_program_set_int(shader, "width", dr->buffers->width);
+  shader_program_set_int(shader, "height", dr->buffers->height);
   shader_program_set_float(shader, "clip_far", dr->camera->far_clip);
   shader_program_set_float(shader, "clip_near", dr->camera->near_clip);
   
@@ -2055,7 +2451,7 @@ static void render_ssao(renderer* dr) {
   shader_program_disable_attribute(shader, "vTexcoord");
   shader_program_disable(shader);
   
-  glViewport(0, 0, graphics_viewport_width(), graphics_viewport_height());
+  glViewport(0, 0, dr->buffers->width, dr->buffers->height);
   glBindFramebuffer(GL_FRAMEBUFFER, 0);
 }
 
@@ -2063,23 +2459,29 @@ static void render_skies(renderer* dr) {
   
   if (!dr->skydome_enabled) { return; }
     
-  int width = graphics_viewport_width();
-  int height = graphics_viewport_height();
+  int width = dr->buffers->width;
+  int height = dr->buffers->height;
   
   int hdrwidth  = width  * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
   int hdrheight = height * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
   
-  glBindFramebuffer(GL_FRAMEBUFFER, dr->hdr_fbo);
+  glBindFramebuffer(GL_FRAMEBUFFER, dr->buffers->hdr_fbo);
   glViewport(0, 0, hdrwidth, hdrheight);
   glDepthMask(GL_FALSE);
   
+  /* Sky is infinitely far away so is always seen in perspective */
+  mat4 sky_proj = dr->camera_proj;
+  if (dr->camera_orthographic) {
+    sky_proj = mat4_perspective(dr->camera->fov, dr->camera_near, dr->camera_far, render_ratio(dr));
+  }
+  
   {
   
     shader_program* shader = material_first_program(asset_hndl_ptr(&dr->mat_skydome));
     shader_program_enable(shader);
     shader_program_set_mat4(shader, "world", mat4_world(dr->camera->position, vec3_new(200, 200, 200), quat_id()));
     shader_program_set_mat4(shader, "view", dr->camera_view);
-    shader_program_set_mat4(shader, "proj", dr->camera_proj);
+    shader_program_set_mat4(shader, "proj", sky_proj);
     shader_program_set_vec3(shader, "light_direction", dr->sky->sun_direction);
     //shader_program_set_vec3(shader, "camera_position", dr->camera->position);
     
@@ -2111,7 +2513,7 @@ static void render_skies(renderer* dr) {
     shader_program_enable(shader);
     shader_program_set_mat4(shader, "world", mat4_world(dr->camera->position, vec3_one(), mat4_to_quat(dr->sky->world_sun)));
     shader_program_set_mat4(shader, "view", dr->camera_view);
-    shader_program_set_mat4(shader, "proj", dr->camera_proj);
+    shader_program_set_mat4(shader, "proj", sky_proj);
     shader_program_set_float(shader, "sun_brightness", 1.5);
     shader_program_set_vec4(shader, "sun_color", vec4_one());
     shader_program_set_texture(shader, "sun_texture", 0, dr->sky->sun_tex);
@@ -2148,7 +2550,7 @@ static void render_skies(renderer* dr) {
     shader_program_enable(shader);
     shader_program_set_mat4(shader, "world", mat4_world(dr->camera->position, vec3_new(10, 10, 10), quat_id()));
     shader_program_set_mat4(shader, "view", dr->camera_view);
-    shader_program_set_mat4(shader, "proj", dr->camera_proj);
+    shader_program_set_mat4(shader, "proj", sky_proj);
     shader_program_set_float(shader, "time", dr->time);
     shader_program_set_float(shader, "wind", vec3_length(dr->sky->wind));
     shader_program_set_vec3(shader, "cloud_color", vec3_one());
@@ -2195,13 +2597,13 @@ static void render_sea(renderer* dr) {
   
   //glPolygonMode(GL_FRONT_AND_BACK, GL_LINE);
   
-  int width = graphics_viewport_width();
-  int height = graphics_viewport_height();
+  int width = dr->buffers->width;
+  int height = dr->buffers->height;
   
   int hdrwidth  = width  * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
   int hdrheight = height * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
   
-  glBindFramebuffer(GL_FRAMEBUFFER, dr->hdr_fbo);
+  glBindFramebuffer(GL_FRAMEBUFFER, dr->buffers->hdr_fbo);
   glViewport(0, 0, hdrwidth, hdrheight);
   
   glEnable(GL_BLEND);
@@ -2217,6 +2619,7 @@ static void render_sea(renderer* dr) {
   shader_program_set_mat4(shader, "proj", dr->camera_proj);
   shader_program_set_float(shader, "clip_near", dr->camera_near);
   shader_program_set_float(shader, "clip_far",  dr->camera_far);
+  shader_program_set_int(shader, "clip_orthographic", dr->camera_orthographic);
   shader_program_set_float(shader, "time", dr->time);
   
   shader_program_set_float(shader, "light_power", dr->sky->sun_power);
@@ -2226,7 +2629,7 @@ static void render_sea(renderer* dr) {
   shader_program_set_vec3(shader, "light_specular", dr->sky->sun_specular);
   shader_program_set_vec3(shader, "camera_position", dr->camera->position);
   
-  shader_program_set_texture_id(shader, "depth", 0, dr->gdepth_texture);
+  shader_program_set_texture_id(shader, "depth", 0, dr->buffers->gdepth_texture);
   shader_program_set_texture(shader, "bump0", 1, dr->tex_sea_bump0);
   shader_program_set_texture(shader, "bump1", 2, dr->tex_sea_bump1);
   shader_program_set_texture(shader, "bump2", 3, dr->tex_sea_bump2);
@@ -2260,13 +2663,13 @@ static void render_sea(renderer* dr) {
 
 static void render_compose_low(renderer* dr) {
   
-  int width = graphics_viewport_width();
-  int height = graphics_viewport_height();
+  int width = dr->buffers->width;
+  int height = dr->buffers->height;
   
   int hdrwidth  = width  * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
   int hdrheight = height * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
   
-  glBindFramebuffer(GL_FRAMEBUFFER, dr->hdr_fbo);
+  glBindFramebuffer(GL_FRAMEBUFFER, dr->buffers->hdr_fbo);
   glViewport(0, 0, hdrwidth, hdrheight);
   
   shader_program* shader = material_first_program(asset_hndl_ptr(&dr->mat_compose));
@@ -2278,10 +2681,11 @@ static void render_compose_low(renderer* dr) {
   shader_program_set_mat4(shader, "inv_proj", dr->camera_inv_proj);
   shader_program_set_float(shader, "clip_near", dr->camera_near);
   shader_program_set_float(shader, "clip_far", dr->camera_far);
+  shader_program_set_int(shader, "clip_orthographic", dr->camera_orthographic);
   
-  shader_program_set_texture_id(shader, "diffuse_texture", 2, dr->gdiffuse_texture);
-  shader_program_set_texture_id(shader, "depth_texture", 3, dr->gdepth_texture);
-  shader_program_set_texture_id(shader, "normals_texture", 4, dr->gnormals_texture);
+  shader_program_set_texture_id(shader, "diffuse_texture", 2, dr->buffers->gdiffuse_texture);
+  shader_program_set_texture_id(shader, "depth_texture", 3, dr->buffers->gdepth_texture);
+  shader_program_set_texture_id(shader, "normals_texture", 4, dr->buffers->gnormals_texture);
   
   shader_program_set_vec3(shader, "camera_position", dr->camera->position);
 
@@ -2308,13 +2712,13 @@ static void render_compose_low(renderer* dr) {
 
 static void render_compose_high(renderer* dr) {
   
-  int width = graphics_viewport_width();
-  int height = graphics_viewport_height();
+  int width = dr->buffers->width;
+  int height = dr->buffers->height;
   
   int hdrwidth  = width  * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
   int hdrheight = height * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
   
-  glBindFramebuffer(GL_FRAMEBUFFER, dr->hdr_fbo);
+  glBindFramebuffer(GL_FRAMEBUFFER, dr->buffers->hdr_fbo);
   glViewport(0, 0, hdrwidth, hdrheight);
   
   shader_program* shader = material_first_program(asset_hndl_ptr(&dr->mat_compose));
@@ -2326,24 +2730,36 @@ static void render_compose_high(renderer* dr) {
   shader_program_set_mat4(shader, "inv_proj", dr->camera_inv_proj);
   shader_program_set_float(shader, "clip_near", dr->camera_near);
   shader_program_set_float(shader, "clip_far", dr->camera_far);
+  shader_program_set_int(shader, "clip_orthographic", dr->camera_orthographic);
   
   shader_program_set_texture(shader, "env_texture", 0, dr->tex_cube_field);  
   shader_program_set_texture(shader, "random_texture", 1, dr->tex_random);
-  shader_program_set_texture_id(shader, "diffuse_texture", 2, dr->gdiffuse_texture);
-  shader_program_set_texture_id(shader, "depth_texture", 3, dr->gdepth_texture);
-  shader_program_set_texture_id(shader, "normals_texture", 4, dr->gnormals_texture);
-  shader_program_set_texture_id(shader, "ssao_texture", 5, dr->ssao_texture);
+  shader_program_set_texture_id(shader, "diffuse_texture", 2, dr->buffers->gdiffuse_texture);
+  shader_program_set_texture_id(shader, "depth_texture", 3, dr->buffers->gdepth_texture);
+  shader_program_set_texture_id(shader, "normals_texture", 4, dr->buffers->gnormals_texture);
+  shader_program_set_texture_id(shader, "ssao_texture", 5, dr->buffers->ssao_texture);
   shader_program_set_texture_id(shader, "shadows_texture0", 6, dr->shadows_texture[0]);
   shader_program_set_texture_id(shader, "shadows_texture1", 7, dr->shadows_texture[1]);
   shader_program_set_texture_id(shader, "shadows_texture2", 8, dr->shadows_texture[2]);
   shader_program_set_texture(shader, "skin_lookup", 9, dr->tex_skin_lookup);
   
+  /* The samplers above use all 16 units GL guarantees, so the fourth cascade needs a 17th */
+  if (dr->texture_units > 16) {
+    shader_program_set_texture_id(shader, "shadows_texture3", 16, dr->shadows_texture[3]);
+  } else {
+    shader_program_set_int(shader, "shadows_texture3", 8);
+  }
+  
   shader_program_set_vec3(shader, "camera_position", dr->camera->position);
+  shader_program_set_vec3(shader, "camera_direction", camera_direction(dr->camera));
   //shader_program_set_float_array(shader, "light_clip_near", dr->shadow_near, 3);
   //shader_program_set_float_array(shader, "light_clip_far", dr->shadow_far, 3);
-  shader_program_set_mat4_array(shader, "light_view", dr->shadow_view, 3);
-  shader_program_set_mat4_array(shader, "light_proj", dr->shadow_proj, 3);
-  shader_program_set_float_array(shader, "light_start", dr->shadows_start, 3);
+  shader_program_set_mat4_array(shader, "light_view", dr->shadow_view, RENDERER_MAX_CASCADES);
+  shader_program_set_mat4_array(shader, "light_proj", dr->shadow_proj, RENDERER_MAX_CASCADES);
+  shader_program_set_float_array(shader, "light_start", dr->shadows_start, RENDERER_MAX_CASCADES);
+  shader_program_set_float(shader, "shadows_bias", dr->shadows_bias);
+  shader_program_set_float(shader, "shadows_normal_offset", dr->shadows_normal_offset);
+  shader_program_set_int(shader, "shadows_debug", dr->shadows_debug);
   
   float light_power[RENDERER_MAX_LIGHTS];
   float light_falloff[RENDERER_MAX_LIGHTS];
@@ -2352,6 +2768,17 @@ static void render_compose_high(renderer* dr) {
   vec3 light_diffuse[RENDERER_MAX_LIGHTS];
   vec3 light_ambient[RENDERER_MAX_LIGHTS];
   vec3 light_specular[RENDERER_MAX_LIGHTS];
+  float light_cone_inner[RENDERER_MAX_LIGHTS];
+  float light_cone_outer[RENDERER_MAX_LIGHTS];
+  float light_spot[RENDERER_MAX_LIGHTS];
+  float light_point[RENDERER_MAX_LIGHTS];
+  
+  for (int i = 0; i < RENDERER_MAX_LIGHTS; i++) {
+    light_cone_inner[i] = -1;
+    light_cone_outer[i] = -1;
+    light_spot[i] = -1;
+    light_point[i] = -1;
+  }
   
   if (dr->sky->is_day) {
     light_power[0]    = dr->sky->sun_power;
@@ -2396,8 +2823,70 @@ static void render_compose_high(renderer* dr) {
     light_diffuse[i+ln]  = dr->dyn_light[i]->diffuse_color;
     light_ambient[i+ln]  = dr->dyn_light[i]->ambient_color;
     light_specular[i+ln] = dr->dyn_light[i]->specular_color;
+    
+    if (dr->dyn_light[i]->type == LIGHT_TYPE_SPOT) {
+      light_cone_inner[i+ln] = cos(dr->dyn_light[i]->inner_angle);
+      light_cone_outer[i+ln] = cos(dr->dyn_light[i]->outer_angle);
+    }
+    
+    for (int k = 0; k < dr->spot_lights_num; k++) {
+      if (dr->spot_light[k] == dr->dyn_light[i]) { light_spot[i+ln] = k; }
+    }
+    
+    for (int k = 0; k < dr->point_lights_num; k++) {
+      if (dr->point_light[k] == dr->dyn_light[i]) { light_point[i+ln] = k; }
+    }
+  }
+  
+  bool shadows = config_int(asset_hndl_ptr(&dr->options), "shadows") != 0;
+  float spot_shadows[RENDERER_MAX_SPOT_SHADOWS];
+  
+  for (int k = 0; k < RENDERER_MAX_SPOT_SHADOWS; k++) {
+    
+    char spot_shadows_texture[32]; sprintf(spot_shadows_texture, "spot_shadows_texture%i", k);
+    char spot_cookie_texture[32];  sprintf(spot_cookie_texture, "spot_cookie_texture%i", k);
+    
+    shader_program_set_texture_id(shader, spot_shadows_texture, 10 + k, dr->spot_shadows_texture[k]);
+    
+    if (k < dr->spot_lights_num && !asset_hndl_isnull(&dr->spot_light[k]->cookie)) {
+      shader_program_set_texture(shader, spot_cookie_texture, 10 + RENDERER_MAX_SPOT_SHADOWS + k, dr->spot_light[k]->cookie);
+    } else {
+      shader_program_set_texture(shader, spot_cookie_texture, 10 + RENDERER_MAX_SPOT_SHADOWS + k, dr->tex_white);
+    }
+    
+    spot_shadows[k] = (k < dr->spot_lights_num && shadows && dr->spot_light[k]->cast_shadows) ? 1 : 0;
+  }
+  
+  shader_program_set_mat4_array(shader, "spot_view", &dr->shadow_view[RENDERER_MAX_CASCADES], RENDERER_MAX_SPOT_SHADOWS);
+  shader_program_set_mat4_array(shader, "spot_proj", &dr->shadow_proj[RENDERER_MAX_CASCADES], RENDERER_MAX_SPOT_SHADOWS);
+  shader_program_set_float_array(shader, "spot_shadows", spot_shadows, RENDERER_MAX_SPOT_SHADOWS);
+  
+  float point_shadows[RENDERER_MAX_POINT_SHADOWS];
+  
+  for (int k = 0; k < RENDERER_MAX_POINT_SHADOWS; k++) {
+    char point_shadows_texture[32]; sprintf(point_shadows_texture, "point_shadows_texture%i", k);
+    int index = 10 + 2 * RENDERER_MAX_SPOT_SHADOWS + k;
+    glActiveTexture(GL_TEXTURE0 + index);
+    glBindTexture(GL_TEXTURE_CUBE_MAP, dr->point_shadows_texture[k]);
+    shader_program_set_int(shader, point_shadows_texture, index);
+    point_shadows[k] = (k < dr->point_lights_num && shadows) ? 1 : 0;
   }
   
+  shader_program_set_float_array(shader, "point_shadows", point_shadows, RENDERER_MAX_POINT_SHADOWS);
+  shader_program_set_int(shader, "shadows_num", shadows ? dr->shadows_num : 0);
+  shader_program_set_float(shader, "shadow_clip_near", DEFAULT_LIGHT_NEAR_CLIP);
+  
+  float spot_clip_far[RENDERER_MAX_SPOT_SHADOWS];
+  float point_clip_far[RENDERER_MAX_POINT_SHADOWS];
+  for (int k = 0; k < RENDERER_MAX_SPOT_SHADOWS; k++) {
+    spot_clip_far[k] = dr->shadow_far[RENDERER_MAX_CASCADES + k];
+  }
+  for (int k = 0; k < RENDERER_MAX_POINT_SHADOWS; k++) {
+    point_clip_far[k] = dr->shadow_far[RENDERER_MAX_CASCADES + RENDERER_MAX_SPOT_SHADOWS + k * 6];
+  }
+  shader_program_set_float_array(shader, "spot_clip_far", spot_clip_far, RENDERER_MAX_SPOT_SHADOWS);
+  shader_program_set_float_array(shader, "point_clip_far", point_clip_far, RENDERER_MAX_POINT_SHADOWS);
+  
   shader_program_set_int(shader, "lights_num", dr->dyn_lights_num + ln);
   shader_program_set_float_array(shader, "light_power", light_power, dr->dyn_lights_num + ln);
   shader_program_set_float_array(shader, "light_falloff", light_falloff, dr->dyn_lights_num + ln);
@@ -2406,6 +2895,10 @@ static void render_compose_high(renderer* dr) {
   shader_program_set_vec3_array(shader, "light_diffuse", light_diffuse, dr->dyn_lights_num + ln);
   shader_program_set_vec3_array(shader, "light_ambient", light_ambient, dr->dyn_lights_num + ln);
   shader_program_set_vec3_array(shader, "light_specular", light_specular, dr->dyn_lights_num + ln);
+  shader_program_set_float_array(shader, "light_cone_inner", light_cone_inner, dr->dyn_lights_num + ln);
+  shader_program_set_float_array(shader, "light_cone_outer", light_cone_outer, dr->dyn_lights_num + ln);
+  shader_program_set_float_array(shader, "light_spot", light_spot, dr->dyn_lights_num + ln);
+  shader_program_set_float_array(shader, "light_point", light_point, dr->dyn_lights_num + ln);
   
   shader_program_enable_attribute(shader, "vPosition",  3, 3, quad_position);
   shader_program_enable_attribute(shader, "vTexcoord",  2, 2, quad_texcoord);
@@ -2438,13 +2931,13 @@ static void render_particles(renderer* dr) {
   
   //glPolygonMode(GL_FRONT_AND_BACK, GL_LINE);
   
-  int width = graphics_viewport_width();
-  int height = graphics_viewport_height();
+  int width = dr->buffers->width;
+  int height = dr->buffers->height;
   
   int hdrwidth  = width  * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
   int hdrheight = height * option_graphics_int(asset_hndl_ptr(&dr->options), "msaa", 4, 2, 1);
   
-  glBindFramebuffer(GL_FRAMEBUFFER, dr->hdr_fbo);
+  glBindFramebuffer(GL_FRAMEBUFFER, dr->buffers->hdr_fbo);
   glViewport(0, 0, hdrwidth, hdrheight);
   
   glEnable(GL_BLEND);
@@ -2455,6 +2948,7 @@ static void render_particles(renderer* dr) {
   shader_program_set_mat4(shader, "proj", dr->camera_proj);
   shader_program_set_float(shader, "clip_near", dr->camera_near);
   shader_program_set_float(shader, "clip_far",  dr->camera_far);
+  shader_program_set_int(shader, "clip_orthographic", dr->camera_orthographic);
 
   shader_program_set_float(shader, "light_power", dr->sky->sun_power);
   shader_program_set_vec3(shader, "light_direction", dr->sky->sun_direction);
@@ -2478,7 +2972,7 @@ static void render_particles(renderer* dr) {
     shader_program_set_mat4(shader, "world", mat4_world(p->position, p->scale, p->rotation));
     shader_program_set_texture(shader, "particle_diffuse", 0, e->texture);
     shader_program_set_texture(shader, "particle_normals", 1, e->texture_nm);
-    shader_program_set_texture_id(shader, "depth", 2, dr->gdepth_texture);
+    shader_program_set_texture_id(shader, "depth", 2, dr->buffers->gdepth_texture);
     
     glBindBuffer(GL_ARRAY_BUFFER, p->vertex_buff);
     
@@ -2517,8 +3011,8 @@ static void render_particles(renderer* dr) {
 
 static void render_tonemap(renderer* dr) {
   
-  glBindFramebuffer(GL_FRAMEBUFFER, dr->ldr_back_fbo);
-  glViewport(0, 0, graphics_viewport_width(), graphics_viewport_height());
+  glBindFramebuffer(GL_FRAMEBUFFER, dr->buffers->ldr_back_fbo);
+  glViewport(0, 0, dr->buffers->width, dr->buffers->height);
 
   shader_program* shader = material_first_program(asset_hndl_ptr(&dr->mat_tonemap));
   shader_program_enable(shader);
@@ -2526,7 +3020,7 @@ static void render_tonemap(renderer* dr) {
   shader_program_set_mat4(shader, "view", mat4_id());
   shader_program_set_mat4(shader, "proj", mat4_orthographic(-1, 1, -1, 1, -1, 1));
   shader_program_set_float(shader, "exposure", dr->exposure);
-  shader_program_set_texture_id(shader, "hdr_texture", 0, dr->hdr_texture);
+  shader_program_set_texture_id(shader, "hdr_texture", 0, dr->buffers->hdr_texture);
   
   shader_program_enable_attribute(shader, "vPosition",  3, 3, quad_position);
   shader_program_enable_attribute(shader, "vTexcoord",  2, 2, quad_texcoord);
@@ -2544,7 +3038,7 @@ static void render_tonemap(renderer* dr) {
   int level = -1; int width = 0; int height = 0;
   
   glActiveTexture(GL_TEXTURE0 + 0 );
-  glBindTexture(GL_TEXTURE_2D, dr->ldr_back_texture);
+  glBindTexture(GL_TEXTURE_2D, dr->buffers->ldr_back_texture);
   glGenerateMipmap(GL_TEXTURE_2D);
   
   do {
@@ -2563,7 +3057,7 @@ static void render_tonemap(renderer* dr) {
   dr->exposure = 3.0;
   
   glBindFramebuffer(GL_FRAMEBUFFER, 0);
-  glViewport(0, 0, graphics_viewport_width(), graphics_viewport_height());
+  glViewport(0, 0, dr->buffers->width, dr->buffers->height);
   
   SDL_GL_CheckError();
   
@@ -2571,8 +3065,8 @@ static void render_tonemap(renderer* dr) {
 
 static void render_post0(renderer* dr) {
   
-  glBindFramebuffer(GL_FRAMEBUFFER, dr->ldr_front_fbo);
-  glViewport(0, 0, graphics_viewport_width(), graphics_viewport_height());
+  glBindFramebuffer(GL_FRAMEBUFFER, dr->buffers->ldr_front_fbo);
+  glViewport(0, 0, dr->buffers->width, dr->buffers->height);
   glClearColor(1.0, 0.0, 0.0, 1.0);
   glClear(GL_COLOR_BUFFER_BIT | GL_DEPTH_BUFFER_BIT);
   
@@ -2581,20 +3075,20 @@ static void render_post0(renderer* dr) {
   shader_program_set_mat4(shader, "world", mat4_id());
   shader_program_set_mat4(shader, "view", mat4_id());
   shader_program_set_mat4(shader, "proj", mat4_orthographic(-1, 1, -1, 1, -1, 1));
-  shader_program_set_texture_id(shader, "ldr_texture", 0, dr->ldr_back_texture);
+  shader_program_set_texture_id(shader, "ldr_texture", 0, dr->buffers->ldr_back_texture);
   //shader_program_set_texture(shader, "random_texture", 1, dr->tex_random);
   
   /*
   glActiveTexture(GL_TEXTURE0 + 2 );
-  glBindTexture(GL_TEXTURE_2D, dr->gdepth_texture);
+  glBindTexture(GL_TEXTURE_2D, dr->buffers->gdepth_texture);
   glGenerateMipmap(GL_TEXTURE_2D);
   glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_BASE_LEVEL, 3);
   glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAX_LEVEL, 3);
   shader_program_set_int(shader, "depth_texture", 2);
   */
   
-  //shader_program_set_int(shader, "width", graphics_viewport_width());
-  //shader_program_set_int(shader, "height", graphics_viewport_height());
+  //shader_program_set_int(shader, "width", dr->buffers->width);
+  //shader_program_set_int(shader, "height", dr->buffers->height);
   
   shader_program_enable_attribute(shader, "vPosition",  3, 3, quad_position);
   shader_program_enable_attribute(shader, "vTexcoord",  2, 2, quad_texcoord);
@@ -2613,9 +3107,16 @@ static void render_post0(renderer* dr) {
 }
 
 static void render_post1(renderer* dr) {
-    
-  glBindFramebuffer(GL_FRAMEBUFFER, 0);
-  glViewport(0, 0, graphics_viewport_width(), graphics_viewport_height());
+  
+  if (dr->target) {
+    glBindFramebuffer(GL_FRAMEBUFFER, dr->target_fbo);
+    glFramebufferTexture2D(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_TEXTURE_2D, texture_handle(dr->target), 0);
+  } else {
+    glBindFramebuffer(GL_FRAMEBUFFER, 0);
+  }
+  
+  glViewport(0, 0, dr->buffers->width, dr->buffers->height);
+  
   glClearDepth(1.0);
   glClearColor(1.0, 0.0, 0.0, 1.0);
   glClear(GL_COLOR_BUFFER_BIT | GL_DEPTH_BUFFER_BIT);
@@ -2626,15 +3127,15 @@ static void render_post1(renderer* dr) {
   shader_program_set_mat4(shader, "view", mat4_id());
   shader_program_set_mat4(shader, "proj", mat4_orthographic(-1, 1, -1, 1, -1, 1));
   
-  shader_program_set_texture_id(shader, "ldr_texture", 0, dr->ldr_front_texture);
+  shader_program_set_texture_id(shader, "ldr_texture", 0, dr->buffers->ldr_front_texture);
   shader_program_set_texture(shader, "random_perlin", 1, dr->tex_random_perlin);
   shader_program_set_texture(shader, "vignetting_texture", 2, dr->tex_vignetting);
   shader_program_set_texture(shader, "lut", 3, dr->tex_color_correction);
   
   shader_program_set_float(shader, "glitch", dr->glitch);
   shader_program_set_float(shader, "time", dr->time);
-  shader_program_set_int(shader, "width", graphics_viewport_width());
-  shader_program_set_int(shader, "height", graphics_viewport_height());
+  shader_program_set_int(shader, "width", dr->buffers->width);
+  shader_program_set_int(shader, "height", dr->buffers->height);
   shader_program_set_int(shader, "fxaa_quality", config_int(asset_hndl_ptr(&dr->options), "fxaa"));
   
   shader_program_enable_attribute(shader, "vPosition",  3, 3, quad_position);
@@ -2653,9 +3154,7 @@ static void render_post1(renderer* dr) {
 
 
 
-void renderer_render(renderer* dr) {
-  
-  dr->time += frame_time();
+static void render_pipeline(renderer* dr) {
   
   //timer t = timer_start(0, "Rendering Start");
   
@@ -2678,6 +3177,48 @@ void renderer_render(renderer* dr) {
 
   glTexEnvf(GL_TEXTURE_FILTER_CONTROL, GL_TEXTURE_LOD_BIAS, 0.0);
   
+}
+
+void renderer_render_to_texture(renderer* dr, camera* cam, texture* t) {
+  
+  int width, height;
+  glBindTexture(GL_TEXTURE_2D, texture_handle(t));
+  glGetTexLevelParameteriv(GL_TEXTURE_2D, 0, GL_TEXTURE_WIDTH, &width);
+  glGetTexLevelParameteriv(GL_TEXTURE_2D, 0, GL_TEXTURE_HEIGHT, &height);
+  
+  /* Targets render at their own size, reallocating only when it changes */
+  if ((dr->target_buffers.width != width) || (dr->target_buffers.height != height)) {
+    if (dr->target_buffers.width > 0) {
+      render_buffers_delete(&dr->target_buffers);
+    }
+    render_buffers_new(dr, &dr->target_buffers, width, height);
+  }
+  
+  camera* main = dr->camera;
+  
+  dr->camera = cam;
+  dr->target = t;
+  dr->buffers = &dr->target_buffers;
+  
+  render_pipeline(dr);
+  
+  dr->camera = main;
+  dr->target = NULL;
+  dr->buffers = &dr->screen_buffers;
+  
+  glBindFramebuffer(GL_FRAMEBUFFER, 0);
+  
+}
+
+void renderer_render(renderer* dr) {
+  
+  dr->time += frame_time();
+  
+  dr->culled_num = 0;
+  dr->shadows_culled_num = 0;
+  
+  render_pipeline(dr);
+  
   dr->render_objects_num = 0;
   dr->dyn_lights_num = 0;
   
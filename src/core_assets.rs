@@ -0,0 +1,52 @@
+//! The core assets of `src/corange/assets_core/`, compiled into the library
+//!
+//! `build.rs` lists every file of the folder with `include_bytes!`. `mount` mounts them in memory
+//! on a folder that isn't on disk, which `engine::initialize_with_embedded_assets` maps `$CORANGE`
+//! to, so nothing is written out. `engine::initialize` reads them from disk instead, to work on
+//! shaders.
+
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use archive;
+
+include!(concat!(env!("OUT_DIR"), "/assets_core.rs"));
+
+/// Folder the core assets are mounted on, which `$CORANGE` is mapped to
+pub const FOLDER: &'static str = "/corange/assets_core/";
+
+lazy_static! {
+    // Whether the assets are mounted, once per process
+    static ref MOUNTED: Mutex<bool> = Mutex::new(false);
+}
+
+/// Names relative to `assets_core/` and contents of the core assets, sorted by name
+pub fn files() -> &'static [(&'static str, &'static [u8])] {
+    FILES
+}
+
+pub fn file(name: &str) -> Option<&'static [u8]> {
+    FILES.iter().find(|file| file.0 == name).map(|file| file.1)
+}
+
+/// Write the core assets below `folder`
+pub fn unpack_to(folder: &Path) -> Result<(), String> {
+    for &(name, contents) in FILES {
+        let path = folder.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| format!("{}: {}", parent.display(), err))?;
+        }
+        fs::write(&path, contents).map_err(|err| format!("{}: {}", path.display(), err))?;
+    }
+    Ok(())
+}
+
+/// Mount the core assets on `FOLDER` the first time, returning it to map `$CORANGE` to
+pub fn mount() -> Result<&'static str, String> {
+    let mut mounted = MOUNTED.lock().unwrap();
+    if !*mounted {
+        archive::mount_static(FILES, "assets_core", FOLDER)?;
+        *mounted = true;
+    }
+    Ok(FOLDER)
+}
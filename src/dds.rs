@@ -0,0 +1,169 @@
+//! DDS header checks
+//!
+//! `.dds` textures are uploaded as they are stored, so there is nothing to decode, but
+//! `dds_load_file` exits on a file it can't read and reads past the end of a short one. `parse`
+//! checks a file the way the loader reads it, including that the data covers every level, and
+//! `Header::to_texture` uploads a checked file without reading its header again.
+
+use libc::{c_int, c_void};
+use corange::*;
+
+const MAGIC: u32 = 0x20534444;
+const HEADER_SIZE: usize = 128;
+
+/// Larger sides are refused rather than trusted to size the data
+const MAX_SIZE: usize = 1 << 16;
+
+const DDSD_CAPS: u32 = 0x1;
+const DDSD_PIXELFORMAT: u32 = 0x1000;
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_INDEXED: u32 = 0x20;
+const DDPF_RGB: u32 = 0x40;
+
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+
+/// Pixel formats the engine's loader knows, in the order `dds_load_levels` numbers them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Dxt1,
+    Dxt3,
+    Dxt5,
+    Bgra8,
+    Bgr8,
+    Bgr5A1,
+    Bgr565,
+    /// 8 bit indices into a palette of 256 colours
+    Index8
+}
+
+impl Format {
+    pub fn compressed(self) -> bool {
+        matches!(self, Format::Dxt1 | Format::Dxt3 | Format::Dxt5)
+    }
+
+    /// Bytes per 4x4 block for compressed formats, per pixel otherwise
+    fn block_bytes(self) -> usize {
+        match self {
+            Format::Dxt1 => 8,
+            Format::Dxt3 | Format::Dxt5 => 16,
+            Format::Bgra8 => 4,
+            Format::Bgr8 => 3,
+            Format::Bgr5A1 | Format::Bgr565 => 2,
+            Format::Index8 => 1
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Header {
+    pub width: usize,
+    pub height: usize,
+    pub mipmaps: usize,
+    /// Six faces follow each other, each with all its levels
+    pub cubemap: bool,
+    pub format: Format,
+    pitch: usize
+}
+
+impl Header {
+    /// Bytes the loader reads after the header
+    pub fn data_size(&self) -> usize {
+        let mut face = 0;
+        let (mut x, mut y) = (self.width, self.height);
+        for level in 0..self.mipmaps {
+            face += if self.format.compressed() {
+                (x.max(4) / 4) * (y.max(4) / 4) * self.format.block_bytes()
+            } else if self.format == Format::Index8 && level == 0 {
+                // The palette comes first and the first level is read by its pitch
+                256 * 4 + self.pitch * y
+            } else {
+                x * y * self.format.block_bytes()
+            };
+            x = (x + 1) >> 1;
+            y = (y + 1) >> 1;
+        }
+        face * if self.cubemap { 6 } else { 1 }
+    }
+
+    /// Upload the levels of `bytes`, the file this header was parsed from. Needs a GL context,
+    /// the texture is freed with `texture_delete`.
+    pub fn to_texture(&self, bytes: &[u8]) -> *mut texture {
+        let data = &bytes[HEADER_SIZE..];
+        unsafe {
+            dds_load_levels(data.as_ptr() as *const c_void, data.len() as c_int, self.width as c_int, self.height as c_int,
+                self.mipmaps as c_int, self.pitch as c_int, self.format as c_int, self.cubemap as u8)
+        }
+    }
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    bytes[offset] as u32 | (bytes[offset + 1] as u32) << 8 | (bytes[offset + 2] as u32) << 16 | (bytes[offset + 3] as u32) << 24
+}
+
+pub fn is_dds(bytes: &[u8]) -> bool {
+    bytes.len() >= 4 && u32_at(bytes, 0) == MAGIC
+}
+
+/// Check the header and length of a `.dds` file
+pub fn parse(bytes: &[u8]) -> Result<Header, String> {
+    if bytes.len() < HEADER_SIZE || !is_dds(bytes) {
+        return Err("not a DDS file".to_string());
+    }
+    let flags = u32_at(bytes, 8);
+    if u32_at(bytes, 4) != 124 || flags & DDSD_PIXELFORMAT == 0 || flags & DDSD_CAPS == 0 {
+        return Err("DDS header is corrupt".to_string());
+    }
+
+    let (height, width) = (u32_at(bytes, 12) as usize, u32_at(bytes, 16) as usize);
+    if !width.is_power_of_two() || !height.is_power_of_two() {
+        return Err(format!("DDS is {}x{}, which isn't a power of two", width, height));
+    }
+    if width > MAX_SIZE || height > MAX_SIZE {
+        return Err(format!("DDS is too large at {}x{}", width, height));
+    }
+    let mipmaps = if flags & DDSD_MIPMAPCOUNT != 0 { u32_at(bytes, 28) as usize } else { 1 };
+    if mipmaps == 0 || mipmaps > 32 {
+        return Err(format!("DDS has an invalid mipmap count {}", mipmaps));
+    }
+
+    let pixel_flags = u32_at(bytes, 80);
+    let (four_cc, bits) = (u32_at(bytes, 84), u32_at(bytes, 88));
+    let masks = (u32_at(bytes, 92), u32_at(bytes, 96), u32_at(bytes, 100), u32_at(bytes, 104));
+    let rgb = pixel_flags & DDPF_RGB != 0;
+    let alpha = pixel_flags & DDPF_ALPHAPIXELS != 0;
+    let format = if pixel_flags & DDPF_FOURCC != 0 && four_cc == 0x31545844 {
+        Format::Dxt1
+    } else if pixel_flags & DDPF_FOURCC != 0 && four_cc == 0x33545844 {
+        Format::Dxt3
+    } else if pixel_flags & DDPF_FOURCC != 0 && four_cc == 0x35545844 {
+        Format::Dxt5
+    } else if rgb && alpha && bits == 32 && masks == (0xff0000, 0xff00, 0xff, 0xff000000) {
+        Format::Bgra8
+    } else if rgb && !alpha && bits == 24 && (masks.0, masks.1, masks.2) == (0xff0000, 0xff00, 0xff) {
+        Format::Bgr8
+    } else if rgb && alpha && bits == 16 && masks == (0x7c00, 0x3e0, 0x1f, 0x8000) {
+        Format::Bgr5A1
+    } else if rgb && !alpha && bits == 16 && (masks.0, masks.1, masks.2) == (0xf800, 0x7e0, 0x1f) {
+        Format::Bgr565
+    } else if pixel_flags & DDPF_INDEXED != 0 && bits == 8 {
+        Format::Index8
+    } else {
+        return Err("DDS uses an unknown pixel format".to_string());
+    };
+
+    let header = Header {
+        width: width,
+        height: height,
+        mipmaps: mipmaps,
+        cubemap: u32_at(bytes, 112) & DDSCAPS2_CUBEMAP != 0,
+        format: format,
+        pitch: u32_at(bytes, 20) as usize
+    };
+    if bytes.len() - HEADER_SIZE < header.data_size() {
+        return Err(format!("DDS ends early, it has {} of {} bytes of image data", bytes.len() - HEADER_SIZE, header.data_size()));
+    }
+    Ok(header)
+}
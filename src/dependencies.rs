@@ -0,0 +1,255 @@
+//! The assets asset files reference, and checking them before shipping
+//!
+//! `Graph::scan` reads every file below some folders with the parsers of `formats`, without
+//! loading anything, and follows what they reference: the material of a `.bmf`, the shaders and
+//! textures of a `.mat`, the textures of an `.effect` and `.fnt`, the buffers and images of a glTF
+//! file and the `.mat` an `.obj`, `.smd` or `.ply` picks up next to it. References are mapped with
+//! `asset_map_filename` like the loaders do, so `$CORANGE` and mounted archives resolve and
+//! relative paths are relative to the working directory, except in `.fnt` and glTF files.
+//!
+//! The graph lists missing files, files nothing uses and circular references, which the engine's
+//! loaders exit or recurse forever on, and can be written in Graphviz dot format.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ffi::{CStr, CString};
+use std::path::Path;
+use corange::*;
+use archive;
+use formats::bmf::Bmf;
+use formats::effect::Effect;
+use formats::mat::Mat;
+use gltf;
+use hot_reload;
+
+/// Meshes which use the `.mat` of the same name next to them
+const MESH_SOURCES: &'static [&'static str] = &["obj", "smd", "ply"];
+
+/// Textures loaded only through the engine's own loaders
+const ENGINE_TEXTURES: &'static [&'static str] = &["lut", "acv"];
+
+/// A path named in an asset file
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reference {
+    /// As written in the file
+    pub written: String,
+    /// Full path the engine maps it to
+    pub path: String,
+    pub exists: bool
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Graph {
+    /// Every file read by full path, with its references in file order
+    pub assets: BTreeMap<String, Vec<Reference>>,
+    /// Full paths of the files below the scanned folders
+    pub scanned: BTreeSet<String>,
+    /// Files which couldn't be read, with the error
+    pub errors: Vec<String>
+}
+
+fn mapped(filename: &str) -> String {
+    unsafe {
+        let filename = CString::new(filename).unwrap();
+        let mapped = asset_map_filename(P(filename.as_ptr()));
+        CStr::from_ptr(mapped.ptr.as_ptr()).to_string_lossy().into_owned()
+    }
+}
+
+/// Full path without following links, for files which don't exist
+fn cleaned(filename: &str) -> String {
+    unsafe {
+        let filename = CString::new(filename).unwrap();
+        let cleaned = asset_map_cleanpath(P(filename.as_ptr()));
+        CStr::from_ptr(cleaned.ptr.as_ptr()).to_string_lossy().into_owned()
+    }
+}
+
+/// A full path as it would be written, starting with `$CORANGE` or `./` where it can
+pub fn short_path(path: &str) -> String {
+    unsafe {
+        let path = CString::new(path).unwrap();
+        let unmapped = asset_unmap_filename(P(path.as_ptr()));
+        CStr::from_ptr(unmapped.ptr.as_ptr()).to_string_lossy().into_owned()
+    }
+}
+
+fn extension(path: &str) -> String {
+    Path::new(path).extension().and_then(|extension| extension.to_str()).unwrap_or("").to_lowercase()
+}
+
+/// Whether some asset format references files with the extension, so a file nothing references
+/// is unused. Other files are loaded by name from code.
+fn referenced_kind(path: &str) -> bool {
+    let extension = extension(path);
+    let extension = extension.as_str();
+    extension == "mat" || extension == "bin"
+        || gltf::TEXTURE_EXTENSIONS.contains(&extension)
+        || ENGINE_TEXTURES.contains(&extension)
+        || hot_reload::SHADER_EXTENSIONS.contains(&extension)
+}
+
+fn reference(written: &str) -> Reference {
+    let path = mapped(written);
+    if archive::is_file(&path) {
+        Reference { written: written.to_string(), path: path, exists: true }
+    } else {
+        Reference { written: written.to_string(), path: cleaned(written), exists: false }
+    }
+}
+
+/// Texture pages of a `.fnt`, relative to its folder like `font_load_file` reads them
+fn font_pages(path: &str) -> Result<Vec<String>, String> {
+    let bytes = archive::read_file(path).map_err(|err| format!("{}: {}", path, err))?;
+    let text = String::from_utf8_lossy(&bytes);
+    let folder = &path[..path.rfind('/').map_or(0, |slash| slash + 1)];
+    Ok(text.lines()
+        .filter(|line| line.starts_with("page id="))
+        .filter_map(|line| line.split_whitespace().find(|word| word.starts_with("file=")))
+        .map(|word| format!("{}{}", folder, word["file=".len()..].trim_matches('"')))
+        .collect())
+}
+
+/// Paths named in the file at the full path `path`, as written
+fn written_references(path: &str) -> Result<Vec<String>, String> {
+    let extension = extension(path);
+    match extension.as_str() {
+        "bmf" => Ok(vec![Bmf::load(path)?.material]),
+        "mat" => Ok(Mat::load(path)?.paths().into_iter().map(String::from).collect()),
+        "effect" => {
+            let effect = Effect::load(path)?;
+            Ok(effect.texture.into_iter().chain(effect.texture_nm).collect())
+        }
+        "fnt" => font_pages(path),
+        "gltf" | "glb" => gltf::references(path),
+        extension if MESH_SOURCES.contains(&extension) => {
+            let material = Path::new(path).with_extension("mat");
+            Ok(if archive::is_file(&material.to_string_lossy()) { vec![material.to_string_lossy().into_owned()] } else { Vec::new() })
+        }
+        _ => Ok(Vec::new())
+    }
+}
+
+fn quoted(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl Graph {
+    /// Read every file below `folders`, on disk or in archives mounted over them, and the files
+    /// they reference, wherever those are. Needs
+    /// `$CORANGE` mapped, by `engine::initialize` or `asset_add_path_variable` in tools.
+    pub fn scan(folders: &[&str]) -> Result<Graph, String> {
+        let mut graph = Graph::default();
+        let mut queue = Vec::new();
+        for folder in folders {
+            let files = archive::folder_tree(&mapped(folder)).map_err(|err| format!("{}: {}", folder, err))?;
+            for file in files {
+                let path = mapped(&file);
+                graph.scanned.insert(path.clone());
+                queue.push(path);
+            }
+        }
+        queue.reverse();
+
+        while let Some(path) = queue.pop() {
+            if graph.assets.contains_key(&path) {
+                continue;
+            }
+            let references: Vec<Reference> = match written_references(&path) {
+                Ok(written) => written.iter().map(|written| reference(written)).collect(),
+                Err(err) => { graph.errors.push(err); Vec::new() }
+            };
+            queue.extend(references.iter().rev().filter(|r| r.exists).map(|r| r.path.clone()));
+            graph.assets.insert(path, references);
+        }
+        Ok(graph)
+    }
+
+    /// References to files which don't exist, with the full path of the file naming them
+    pub fn missing(&self) -> Vec<(&str, &Reference)> {
+        self.assets.iter()
+            .flat_map(|(path, references)| references.iter().filter(|r| !r.exists).map(move |r| (path.as_str(), r)))
+            .collect()
+    }
+
+    /// Scanned files of the kinds assets reference, like textures, shaders and materials, which
+    /// no scanned file and none of `roots` use, directly or through other files. `roots` are the
+    /// files the game loads by name from code.
+    pub fn unused(&self, roots: &[&str]) -> Vec<&str> {
+        let mut kept = BTreeSet::new();
+        let mut stack: Vec<String> = self.scanned.iter().filter(|path| !referenced_kind(path)).cloned().collect();
+        stack.extend(roots.iter().map(|root| mapped(root)));
+        while let Some(path) = stack.pop() {
+            if let Some(references) = self.assets.get(&path) {
+                if kept.insert(path) {
+                    stack.extend(references.iter().filter(|r| r.exists).map(|r| r.path.clone()));
+                }
+            }
+        }
+        self.scanned.iter().filter(|path| referenced_kind(path) && !kept.contains(*path)).map(|path| path.as_str()).collect()
+    }
+
+    /// Files referencing each other in a circle, each circle in the order they are reached
+    pub fn cycles(&self) -> Vec<Vec<&str>> {
+        // Tarjan's strongly connected components, of which those with more than one file or a
+        // file referencing itself are circular
+        struct Search<'a> {
+            graph: &'a Graph,
+            index: HashMap<&'a str, usize>,
+            lowest: HashMap<&'a str, usize>,
+            stack: Vec<&'a str>,
+            cycles: Vec<Vec<&'a str>>
+        }
+
+        fn visit<'a>(search: &mut Search<'a>, path: &'a str) {
+            let index = search.index.len();
+            search.index.insert(path, index);
+            search.lowest.insert(path, index);
+            search.stack.push(path);
+            let graph = search.graph;
+            let references = &graph.assets[path];
+            for r in references.iter().filter(|r| r.exists && graph.assets.contains_key(&r.path)) {
+                let used = r.path.as_str();
+                if !search.index.contains_key(used) {
+                    visit(search, used);
+                    let lowest = search.lowest[path].min(search.lowest[used]);
+                    search.lowest.insert(path, lowest);
+                } else if search.stack.contains(&used) {
+                    let lowest = search.lowest[path].min(search.index[used]);
+                    search.lowest.insert(path, lowest);
+                }
+            }
+            if search.lowest[path] == index {
+                let start = search.stack.iter().position(|&other| other == path).unwrap();
+                let component = search.stack.split_off(start);
+                if component.len() > 1 || references.iter().any(|r| r.path == path) {
+                    search.cycles.push(component);
+                }
+            }
+        }
+
+        let mut search = Search { graph: self, index: HashMap::new(), lowest: HashMap::new(), stack: Vec::new(), cycles: Vec::new() };
+        for path in self.assets.keys() {
+            if !search.index.contains_key(path.as_str()) {
+                visit(&mut search, path);
+            }
+        }
+        search.cycles
+    }
+
+    /// The references in Graphviz dot format, with missing files in red. Files without
+    /// references to or from them are left out.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph assets {\n  rankdir=LR;\n  node [shape=box];\n");
+        for (_, r) in self.missing() {
+            dot.push_str(&format!("  {} [color=red, fontcolor=red];\n", quoted(&r.written)));
+        }
+        for (path, references) in &self.assets {
+            for r in references {
+                let to = if r.exists { short_path(&r.path) } else { r.written.clone() };
+                dot.push_str(&format!("  {} -> {};\n", quoted(&short_path(path)), quoted(&to)));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
@@ -1,22 +1,56 @@
+use std::ffi::CString;
 use std::mem;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use sdl2_sys::event::*;
 use sdl2_sys::keycode::*;
 use corange::*;
 use viewport::Viewport;
-use renderer::Renderer;
-use camera::{Camera, CameraType};
+use renderer::{Renderer, Shadows};
+use camera::{Camera, CameraType, CameraController, Input, Projection};
+use render_target::RenderTarget;
+use picking;
+use picking::Hit;
+use overlay;
+use overlay::{Attachment, ScreenPosition, UiElement};
+use statistics::Statistics;
+use gltf;
+use image_formats;
+use hot_reload::{Reload, Watcher};
+use loading;
+use loading::{Loader, LoadingScreen, Progress};
+use core_assets;
+
+/// Called with every hot reload, see `enable_hot_reload`
+pub type ReloadCallback = Arc<Fn(&Reload) + Send + Sync>;
 
 lazy_static! {
     pub static ref FRAME:Arc<Mutex<u64>> = Arc::new(Mutex::new(0u64));
     pub static ref VIEWPORT:Arc<Mutex<Viewport>> = Arc::new(Mutex::new(Viewport::default()));
     pub static ref RENDERER:Arc<Mutex<Renderer>> = Arc::new(Mutex::new(Renderer::default()));
     pub static ref CAMERA:Arc<Mutex<Camera>> = Arc::new(Mutex::new(Camera::default()));
+    pub static ref STATISTICS:Arc<Mutex<Statistics>> = Arc::new(Mutex::new(Statistics::default()));
+    pub static ref RENDER_TARGETS:Arc<Mutex<Vec<RenderTarget>>> = Arc::new(Mutex::new(Vec::new()));
+    pub static ref ATTACHMENTS:Arc<Mutex<Vec<Attachment>>> = Arc::new(Mutex::new(Vec::new()));
+    pub static ref HOT_RELOAD:Arc<Mutex<Option<(Watcher, ReloadCallback)>>> = Arc::new(Mutex::new(None));
+    pub static ref LOADER:Arc<Mutex<Option<Loader>>> = Arc::new(Mutex::new(None));
+    pub static ref LOADING_SCREEN:Arc<Mutex<Option<LoadingScreen>>> = Arc::new(Mutex::new(None));
+}
+
+/// Initialize the Corange engine and load default assets/shaders, reading `$CORANGE` from
+/// `assets_path`, such as `./src/corange/assets_core/` to edit shaders without rebuilding
+pub fn initialize(assets_path:&str) {
+    let assets_path = CString::new(assets_path).unwrap();
+    unsafe { corange_init(assets_path.as_ptr()) }
+    gltf::register();
+    image_formats::register();
 }
 
-/// Initialize the Corange engine and load default assets/shaders
-pub fn initialize(assets_path:&'static str) {
-    unsafe { corange_init(str(assets_path)) }
+/// Initialize the Corange engine with the core assets compiled into the library for `$CORANGE`,
+/// read from memory
+pub fn initialize_with_embedded_assets() {
+    let folder = core_assets::mount().unwrap_or_else(|err| panic!("Failed to mount the core assets: {}", err));
+    initialize(folder);
 }
 
 fn frame() -> u64 {
@@ -38,7 +72,8 @@ pub fn set_viewport(title:String, width:usize, height:usize, fullscreen:bool, an
 
 /// Reconfigure renderer
 pub fn set_renderer(configuration: String, color_correction_texture: Option<String>, vignetting_texture: Option<String>, texture_noise: f32, sea_enabled: bool, sky_enabled: bool, sky_time: f32) {
-    *RENDERER.lock().unwrap() = Renderer {
+    let mut renderer = RENDERER.lock().unwrap();
+    *renderer = Renderer {
         configuration: configuration,
         color_correction_texture: color_correction_texture,
         vignetting_texture: vignetting_texture,
@@ -46,10 +81,44 @@ pub fn set_renderer(configuration: String, color_correction_texture: Option<Stri
         sea_enabled: sea_enabled,
         sky_enabled: sky_enabled,
         sky_time: sky_time,
-        frame: frame() + 1
+        frame: frame() + 1,
+        ..renderer.clone()
     };
 }
 
+/// Enable or disable frustum culling of submitted render objects
+pub fn set_culling_enabled(enabled: bool) {
+    let mut renderer = RENDERER.lock().unwrap();
+    renderer.culling_enabled = enabled;
+    renderer.frame = frame() + 1;
+}
+
+/// Limit the number of shadow casting point lights per frame (capped by the shadow quality setting)
+pub fn set_point_shadows(num: usize) {
+    let mut renderer = RENDERER.lock().unwrap();
+    renderer.point_shadows = Some(num);
+    renderer.frame = frame() + 1;
+}
+
+/// Reconfigure the sun shadow cascades
+pub fn set_shadows(shadows: Shadows) {
+    let mut renderer = RENDERER.lock().unwrap();
+    renderer.shadows = shadows;
+    renderer.frame = frame() + 1;
+}
+
+/// Tint each shadow cascade a different color
+pub fn set_shadow_debug(enabled: bool) {
+    let mut renderer = RENDERER.lock().unwrap();
+    renderer.shadows.debug = enabled;
+    renderer.frame = frame() + 1;
+}
+
+/// Statistics gathered while rendering the last frame
+pub fn statistics() -> Statistics {
+    STATISTICS.lock().unwrap().clone()
+}
+
 /// Reconfigure camera
 pub fn set_camera(position: vec3, target: vec3, fov: f32, near_clip: f32, far_clip: f32, movement: CameraType) {
     *CAMERA.lock().unwrap() = Camera {
@@ -58,11 +127,148 @@ pub fn set_camera(position: vec3, target: vec3, fov: f32, near_clip: f32, far_cl
         fov: fov,
         near_clip: near_clip,
         far_clip: far_clip,
+        roll: 0.0,
+        projection: Projection::Perspective,
         movement: movement,
         frame: frame() + 1
     };
 }
 
+/// Render the scene from a secondary camera into a texture registered at `path`
+pub fn add_render_target(path: String, width: usize, height: usize, camera: Camera) {
+    let mut targets = RENDER_TARGETS.lock().unwrap();
+    targets.retain(|target| target.path != path);
+    targets.push(RenderTarget {
+        path: path,
+        width: width,
+        height: height,
+        camera: camera,
+        enabled: true,
+        frame: frame() + 1
+    });
+}
+
+/// Move the camera of an existing render target
+pub fn set_render_target_camera(path: &str, camera: Camera) {
+    for target in RENDER_TARGETS.lock().unwrap().iter_mut().filter(|target| target.path == path) {
+        target.camera = camera.clone();
+        target.frame = frame() + 1;
+    }
+}
+
+/// Pause or resume rendering into an existing render target
+pub fn set_render_target_enabled(path: &str, enabled: bool) {
+    for target in RENDER_TARGETS.lock().unwrap().iter_mut().filter(|target| target.path == path) {
+        target.enabled = enabled;
+        target.frame = frame() + 1;
+    }
+}
+
+/// Stop rendering into a render target and unload its texture
+pub fn remove_render_target(path: &str) {
+    RENDER_TARGETS.lock().unwrap().retain(|target| target.path != path);
+}
+
+/// Switch the camera between perspective and orthographic projection
+pub fn set_camera_projection(projection: Projection) {
+    let mut camera = CAMERA.lock().unwrap();
+    camera.projection = projection;
+    camera.frame = frame() + 1;
+}
+
+/// Drive the camera with a user-defined controller
+pub fn set_camera_controller<C: CameraController + 'static>(controller: C) {
+    let mut camera = CAMERA.lock().unwrap();
+    camera.movement = CameraType::Custom(Arc::new(Mutex::new(controller)));
+    camera.frame = frame() + 1;
+}
+
+/// Nearest object under viewport pixel `(x, y)` as seen by the main camera
+pub fn pick(x: i32, y: i32) -> Option<Hit> {
+    unsafe {
+        if entity_exists(str("camera")) == 0 {
+            return None;
+        }
+        picking::pick(entity_get(str("camera")) as *mut camera, x, y)
+    }
+}
+
+/// Viewport pixel position of `position` as seen by the main camera
+pub fn world_to_screen(position: vec3) -> Option<ScreenPosition> {
+    unsafe {
+        if entity_exists(str("camera")) == 0 {
+            return None;
+        }
+        Some(overlay::world_to_screen(entity_get(str("camera")) as *mut camera, position))
+    }
+}
+
+/// Keep a UI element over the screen position of its target every frame, replacing any previous attachment of the element
+pub fn attach_ui(attachment: Attachment) {
+    let mut attachments = ATTACHMENTS.lock().unwrap();
+    attachments.retain(|a| a.element != attachment.element);
+    attachments.push(attachment);
+}
+
+/// Stop moving a UI element with its target. It stays where it was last placed, and hidden if
+/// it was hidden behind the camera.
+pub fn detach_ui(element: &UiElement) {
+    ATTACHMENTS.lock().unwrap().retain(|a| a.element != *element);
+}
+
+/// Reload assets whose files change on disk between frames, passing each reload to `callback`,
+/// e.g. `hot_reload::toast`. Fails where files can't be watched.
+pub fn enable_hot_reload<F: Fn(&Reload) + Send + Sync + 'static>(callback: F) -> Result<(), String> {
+    let watcher = Watcher::new()?;
+    *HOT_RELOAD.lock().unwrap() = Some((watcher, Arc::new(callback)));
+    Ok(())
+}
+
+/// Stop watching asset files. Changes made from now on are not reloaded, and the callback is dropped.
+pub fn disable_hot_reload() {
+    *HOT_RELOAD.lock().unwrap() = None;
+}
+
+/// The background loader, started on first use
+fn with_loader<T, F: FnOnce(&mut Loader) -> T>(f: F) -> T {
+    let mut loader = LOADER.lock().unwrap();
+    f(loader.get_or_insert_with(|| Loader::new(loading::default_threads())))
+}
+
+/// Load the files in `folder` like `folder_load`, reading them on worker threads and adding them
+/// between frames. Fails when the folder can't be read.
+pub fn load_folder_in_background(folder: &str) -> Result<(), String> {
+    with_loader(|loader| loader.load_folder(folder))
+}
+
+/// Load `filename` like `file_load`, reading it on a worker thread and adding it between frames
+pub fn load_file_in_background(filename: &str) {
+    with_loader(|loader| loader.load_file(filename))
+}
+
+/// How far the files queued for background loading have got, errors included
+pub fn loading_progress() -> Progress {
+    LOADER.lock().unwrap().as_ref().map_or(Progress::default(), |loader| loader.progress())
+}
+
+/// Add every file queued for background loading now, blocking until they are read
+pub fn wait_for_loading() -> Progress {
+    with_loader(|loader| loader.wait())
+}
+
+/// Time spent adding loaded files to the engine per frame, `loading::DEFAULT_BUDGET` by default
+pub fn set_loading_budget(budget: Duration) {
+    with_loader(|loader| loader.set_budget(budget))
+}
+
+/// Show a `LoadingScreen` until the files queued for background loading are loaded
+pub fn show_loading_screen() {
+    let mut screen = LOADING_SCREEN.lock().unwrap();
+    if screen.is_none() {
+        *screen = Some(LoadingScreen::new("loading"));
+    }
+}
+
 /// Assert Corange viewport settings matches user-submitted settings
 fn update_viewport() {
     let configuration = VIEWPORT.lock().unwrap().clone();
@@ -79,14 +285,82 @@ fn update_renderer(renderer:*mut renderer) {
     }
 }
 
-// Assert Corange renderer settings matches user-submitted settings
-fn update_camera(camera:*mut camera) -> CameraType {
+/// Assert Corange camera settings matches user-submitted settings, returns true when they changed
+fn update_camera(camera:*mut camera) -> bool {
     let configuration = CAMERA.lock().unwrap().clone();
-    let camera_type = configuration.movement.clone();
     if configuration.frame == frame() {
         configuration.apply(camera);
+        true
+    } else {
+        false
+    }
+}
+
+/// Controller for the user-submitted camera movement
+fn camera_controller() -> Option<Arc<Mutex<CameraController>>> {
+    CAMERA.lock().unwrap().movement.controller()
+}
+
+/// Assert Corange render targets match user-submitted render targets
+fn update_render_targets(targets:&mut Vec<(RenderTarget, *mut camera, *mut texture)>) {
+    let configurations = RENDER_TARGETS.lock().unwrap().clone();
+
+    // Remove targets which were removed or resized
+    let mut i = 0;
+    while i < targets.len() {
+        let keep = configurations.iter().any(|configuration| {
+            configuration.path == targets[i].0.path &&
+            configuration.width == targets[i].0.width &&
+            configuration.height == targets[i].0.height
+        });
+        if keep {
+            i += 1;
+        } else {
+            let (target, camera, _) = targets.remove(i);
+            target.delete(camera);
+        }
+    }
+
+    for configuration in configurations {
+        match targets.iter().position(|target| target.0.path == configuration.path) {
+            Some(i) => {
+                if configuration.frame == frame() {
+                    configuration.clone().apply(targets[i].1);
+                    targets[i].0 = configuration;
+                }
+            }
+            None => {
+                let (camera, texture) = configuration.clone().initialize();
+                targets.push((configuration, camera, texture));
+            }
+        }
+    }
+}
+
+/// Reload changed assets when hot reloading is enabled
+fn reload_changed_assets() {
+    let (reloads, callback) = match *HOT_RELOAD.lock().unwrap() {
+        Some((ref mut watcher, ref callback)) => (watcher.poll(), callback.clone()),
+        None => return
+    };
+    // The callback may enable or disable hot reloading, so it runs without the lock
+    for reload in &reloads {
+        callback(reload);
+    }
+}
+
+/// Add files loaded in the background within the budget, and update the loading screen
+fn upload_loaded_assets() {
+    let progress = match *LOADER.lock().unwrap() {
+        Some(ref mut loader) => { loader.upload(); loader.progress() }
+        None => Progress::default()
+    };
+    let mut screen = LOADING_SCREEN.lock().unwrap();
+    if progress.done() {
+        *screen = None;
+    } else if let Some(ref mut screen) = *screen {
+        screen.update(&progress);
     }
-    camera_type
 }
 
 /// Enter main rendering loop
@@ -102,12 +376,21 @@ pub fn run(event_handler:Option<&Fn(SDL_Event)>, update_handler:Option<&Fn(f64,
         // Initialize camera
         let camera = entity_new_type_id(str("camera"), *CAMERA_TYPE) as *mut camera;
         update_camera(camera);
+        let mut controller = camera_controller();
+
+        // Secondary cameras rendering into textures
+        let mut targets = Vec::new();
 
         'main: loop {
             // Synchronize engine component settings
             update_viewport();
             update_renderer(renderer);
-            let camera_type = update_camera(camera);
+            if update_camera(camera) {
+                controller = camera_controller();
+            }
+            update_render_targets(&mut targets);
+            reload_changed_assets();
+            upload_loaded_assets();
 
             // Initialize frame
             frame_begin();
@@ -129,12 +412,10 @@ pub fn run(event_handler:Option<&Fn(SDL_Event)>, update_handler:Option<&Fn(f64,
                             _ => ()
                         }
 
-                        // Update camera
-                        let event:SDL_Event = mem::transmute_copy(&raw);
-                        match camera_type {
-                            CameraType::Orbit => camera_control_orbit(camera, event),
-                            CameraType::Free => camera_control_freecam(camera, frame_time() as f32),
-                            CameraType::Manual => ()
+                        // Forward events to camera controller
+                        if let Some(ref controller) = controller {
+                            let event:SDL_Event = mem::transmute_copy(&raw);
+                            controller.lock().unwrap().event(camera, event);
                         }
 
                         // Foward events to UI controller
@@ -151,14 +432,28 @@ pub fn run(event_handler:Option<&Fn(SDL_Event)>, update_handler:Option<&Fn(f64,
                 }
             }
 
+            // Update camera controller
+            if let Some(ref controller) = controller {
+                controller.lock().unwrap().update(camera, &Input::capture(), frame_time() as f32);
+            }
+
             // Call user-defined frame update handler
             if let Some(handler) = update_handler {
                 handler(frame_time(), renderer);
             }
 
-            // Render scene
+            // Move attached UI elements to their targets
+            for attachment in ATTACHMENTS.lock().unwrap().iter() {
+                attachment.update(camera);
+            }
+
+            // Render scene into render targets, then to the screen
+            for &(ref target, target_camera, texture) in targets.iter() {
+                target.render(renderer, target_camera, texture);
+            }
             renderer_set_camera(renderer, camera);
             renderer_render(renderer);
+            *STATISTICS.lock().unwrap() = Statistics::collect(renderer, frame());
 
             // Render UI
             ui_update();
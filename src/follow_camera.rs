@@ -0,0 +1,152 @@
+use sdl2_sys::event::{SDL_Event, SDL_MOUSEWHEEL};
+use sdl2_sys::mouse::SDL_BUTTON_LMASK;
+use corange;
+use corange::*;
+use camera::{CameraController, Input};
+
+/// Entity or point trailed by a follow camera
+#[derive(Clone, Copy)]
+pub enum FollowTarget {
+    Point(vec3),
+    Static(*mut static_object),
+    Animated(*mut animated_object),
+    Physics(*mut physics_object)
+}
+
+impl FollowTarget {
+    pub fn position(&self) -> vec3 {
+        unsafe {
+            match *self {
+                FollowTarget::Point(position) => position,
+                FollowTarget::Static(object) => (*object).position,
+                FollowTarget::Animated(object) => (*object).position,
+                FollowTarget::Physics(object) => (*object).position
+            }
+        }
+    }
+}
+
+/// Third-person camera trailing a target on a spring. Orbits while the left mouse
+/// button is held, zooms with the wheel and pulls in when colliders block the view.
+pub struct FollowController {
+    pub target: FollowTarget,
+    pub offset: vec3,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub sensitivity: f32,
+    pub stiffness: f32,
+    pub damping: f32,
+    pub radius: f32,
+    pub colliders: Vec<*mut static_object>,
+    position: Option<vec3>,
+    velocity: vec3
+}
+
+// Lives in the engine's `CAMERA` global as a custom controller. Its target and colliders are only
+// dereferenced in `engine::run`, on the thread that owns the GL context.
+unsafe impl Send for FollowController {}
+
+/// Largest step the spring is integrated with, to stay stable on long frames
+const FOLLOW_MAX_STEP: f32 = 1.0 / 60.0;
+
+impl FollowController {
+    pub fn new(target: FollowTarget) -> FollowController {
+        unsafe {
+            FollowController {
+                target: target,
+                offset: vec3_new(0.0, 2.0, 0.0),
+                distance: 10.0,
+                min_distance: 1.0,
+                max_distance: 50.0,
+                yaw: 0.0,
+                pitch: 0.3,
+                sensitivity: 0.005,
+                stiffness: 60.0,
+                damping: 15.0,
+                radius: 0.5,
+                colliders: Vec::new(),
+                position: None,
+                velocity: vec3_zero()
+            }
+        }
+    }
+
+    /// Static object whose collision body the camera cannot pass through
+    pub fn add_collider(&mut self, object: *mut static_object) {
+        self.colliders.push(object);
+    }
+
+    /// Fraction of `movement` a sphere at `start` can travel before hitting a collider
+    fn sweep(&self, start: vec3, movement: vec3) -> f32 {
+        unsafe {
+            let sweep = sphere_new(start, self.radius);
+            let reach = vec3_length(movement) + self.radius;
+            let mut time = 1.0f32;
+            for &object in &self.colliders {
+                if asset_hndl_isnull(&mut (*object).collision_body) != 0 { continue; }
+                let mesh = asset_hndl_ptr(&mut (*object).collision_body) as *mut cmesh;
+                let world = static_object_world(object);
+                let bound = sphere_transform(cmesh_bound(mesh), world);
+                if vec3_dist(bound.center, start) > bound.radius + reach { continue; }
+                let hit = sphere_collide_mesh(sweep, movement, mesh, world, static_object_world_normal(object));
+                if hit.collided != 0 {
+                    time = time.min(hit.time);
+                }
+            }
+            time
+        }
+    }
+}
+
+impl CameraController for FollowController {
+    fn event(&mut self, _camera: *mut corange::camera, mut event: SDL_Event) {
+        unsafe {
+            if *event.type_() == SDL_MOUSEWHEEL {
+                let wheel = (*event.wheel()).y as f32;
+                self.distance = (self.distance - wheel).max(self.min_distance).min(self.max_distance);
+            }
+        }
+    }
+
+    fn update(&mut self, camera: *mut corange::camera, input: &Input, timestep: f32) {
+        unsafe {
+            if input.button(SDL_BUTTON_LMASK) {
+                self.yaw -= input.mouse_dx as f32 * self.sensitivity;
+                self.pitch = (self.pitch + input.mouse_dy as f32 * self.sensitivity).max(-1.4).min(1.4);
+            }
+
+            let focus = vec3_add(self.target.position(), self.offset);
+            let direction = vec3_new(
+                self.pitch.cos() * self.yaw.sin(),
+                self.pitch.sin(),
+                self.pitch.cos() * self.yaw.cos());
+            let arm = vec3_mul(direction, self.distance);
+            let reach = self.distance * self.sweep(focus, arm);
+            let desired = vec3_add(focus, vec3_mul(direction, reach));
+
+            let mut position = self.position.unwrap_or(desired);
+            let mut remaining = timestep;
+            while remaining > 0.0 {
+                let step = remaining.min(FOLLOW_MAX_STEP);
+                let pull = vec3_mul(vec3_sub(desired, position), self.stiffness);
+                let drag = vec3_mul(self.velocity, self.damping);
+                self.velocity = vec3_add(self.velocity, vec3_mul(vec3_sub(pull, drag), step));
+                position = vec3_add(position, vec3_mul(self.velocity, step));
+                remaining -= step;
+            }
+
+            // Never let the spring lag the camera behind geometry
+            if vec3_dist(position, focus) > reach {
+                position = vec3_add(focus, vec3_mul(vec3_normalize(vec3_sub(position, focus)), reach));
+                self.velocity = vec3_zero();
+            }
+
+            self.position = Some(position);
+            (*camera).position = position;
+            (*camera).target = focus;
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! `.ani` animations, as read by `ani_load_file`
+//!
+//! The same layout as `.skl`, with a `time` entry starting each frame of the `skeleton` block.
+
+use super::{in_file, invalid, parse_skeleton_text, read_text, write_file, write_joints, write_transforms};
+use super::skl::{Joint, JointTransform};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub time: f32,
+    /// Joints left out keep the origin and no rotation
+    pub transforms: Vec<JointTransform>
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ani {
+    pub joints: Vec<Joint>,
+    pub frames: Vec<Frame>
+}
+
+impl Ani {
+    pub fn parse(text: &str) -> Result<Ani, String> {
+        let contents = parse_skeleton_text(text)?;
+        let mut frames = Vec::with_capacity(contents.frames.len());
+        for frame in contents.frames {
+            match frame.time {
+                Some(time) => frames.push(Frame { time: time, transforms: frame.transforms }),
+                None => return Err(invalid(frame.line, "joint transform before the first 'time'"))
+            }
+        }
+        Ok(Ani { joints: contents.joints, frames: frames })
+    }
+
+    pub fn load(filename: &str) -> Result<Ani, String> {
+        in_file(filename, Ani::parse(&read_text(filename)?))
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        write_joints(&mut text, &self.joints);
+        for frame in &self.frames {
+            text.push_str(&format!("time {}\n", frame.time));
+            write_transforms(&mut text, &frame.transforms);
+        }
+        text.push_str("end\n");
+        text
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), String> {
+        write_file(filename, self.to_text().as_bytes())
+    }
+}
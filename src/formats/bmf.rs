@@ -0,0 +1,237 @@
+//! `.bmf` binary meshes, as read by `bmf_load_file` and written by `bmf_save_file`
+//!
+//! Little endian: `BMF`, the version, a rigged byte, the material path and then per surface the
+//! vertex data and triangle indices. Vertices are 18 floats, 24 for rigged meshes which add three
+//! bone ids and three weights and store the color alpha first.
+
+use std::fmt::Display;
+use super::{in_file, read_bytes, write_file, MAX_PATH};
+
+/// The only version the engine reads
+pub const VERSION: u32 = 1;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub tangent: [f32; 3],
+    pub binormal: [f32; 3],
+    pub uvs: [f32; 2],
+    /// Red, green, blue and alpha, even though rigged files store alpha first
+    pub color: [f32; 4]
+}
+
+/// Bones influencing a vertex of a rigged mesh
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Weight {
+    pub bone_ids: [i32; 3],
+    pub bone_weights: [f32; 3]
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Surface {
+    pub vertices: Vec<Vertex>,
+    /// One per vertex for rigged meshes, empty otherwise
+    pub weights: Vec<Weight>,
+    /// Three per triangle
+    pub indices: Vec<u32>
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bmf {
+    pub rigged: bool,
+    /// Material path as stored, usually relative to the working directory or under `$CORANGE`
+    pub material: String,
+    pub surfaces: Vec<Surface>
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize
+}
+
+impl<'a> Reader<'a> {
+    fn error<T: Display>(&self, message: T) -> String {
+        format!("byte {}: {}", self.offset, message)
+    }
+
+    fn take(&mut self, count: usize, what: &str) -> Result<&'a [u8], String> {
+        if self.bytes.len() - self.offset < count {
+            return Err(self.error(format!("file ends in the middle of {}", what)));
+        }
+        let taken = &self.bytes[self.offset..self.offset + count];
+        self.offset += count;
+        Ok(taken)
+    }
+
+    fn u32(&mut self, what: &str) -> Result<u32, String> {
+        let b = self.take(4, what)?;
+        Ok(b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16 | (b[3] as u32) << 24)
+    }
+
+    fn f32(&mut self, what: &str) -> Result<f32, String> {
+        self.u32(what).map(f32::from_bits)
+    }
+
+    fn floats(&mut self, out: &mut [f32], what: &str) -> Result<(), String> {
+        for value in out.iter_mut() {
+            *value = self.f32(what)?;
+        }
+        Ok(())
+    }
+
+    /// A count of items `size` bytes each, checked against what is left so garbage can't allocate
+    fn count(&mut self, size: usize, what: &str) -> Result<usize, String> {
+        let start = self.offset;
+        let count = self.u32(&format!("the {} count", what))? as usize;
+        if count.saturating_mul(size) > self.bytes.len() - self.offset {
+            self.offset = start;
+            return Err(self.error(format!("{} {} don't fit in the rest of the file", count, what)));
+        }
+        Ok(count)
+    }
+}
+
+impl Bmf {
+    pub fn parse(bytes: &[u8]) -> Result<Bmf, String> {
+        let mut reader = Reader { bytes: bytes, offset: 0 };
+        if reader.take(3, "the magic number")? != b"BMF" {
+            return Err("byte 0: missing the BMF magic number".to_string());
+        }
+        let version = reader.u32("the version")?;
+        if version != VERSION {
+            reader.offset = 3;
+            return Err(reader.error(format!("version {} isn't supported, only version {}", version, VERSION)));
+        }
+        let rigged = match reader.take(1, "the rigged flag")?[0] {
+            0 => false,
+            1 => true,
+            flag => { reader.offset -= 1; return Err(reader.error(format!("rigged flag is {}, expected 0 or 1", flag))); }
+        };
+
+        let length = reader.count(1, "material path bytes")?;
+        if length > MAX_PATH {
+            reader.offset -= 4;
+            return Err(reader.error(format!("material path is {} bytes, the engine allows {}", length, MAX_PATH)));
+        }
+        let material = String::from_utf8(reader.take(length, "the material path")?.to_vec())
+            .map_err(|_| reader.error("material path is not valid UTF-8"))?;
+
+        let stride = if rigged { 24 } else { 18 };
+        let count = reader.count(8, "surfaces")?;
+        let mut surfaces = Vec::with_capacity(count);
+        for s in 0..count {
+            let mut surface = Surface::default();
+
+            let vertices = reader.count(stride * 4, "vertices")?;
+            for _ in 0..vertices {
+                let what = format!("surface {} vertices", s);
+                let mut v = Vertex::default();
+                reader.floats(&mut v.position, &what)?;
+                reader.floats(&mut v.normal, &what)?;
+                reader.floats(&mut v.tangent, &what)?;
+                reader.floats(&mut v.binormal, &what)?;
+                reader.floats(&mut v.uvs, &what)?;
+                reader.floats(&mut v.color, &what)?;
+                if rigged {
+                    v.color = [v.color[1], v.color[2], v.color[3], v.color[0]];
+                    let mut ids = [0.0; 3];
+                    let mut weight = Weight::default();
+                    reader.floats(&mut ids, &what)?;
+                    for (id, &value) in weight.bone_ids.iter_mut().zip(&ids) {
+                        if value.fract() != 0.0 || value < 0.0 || value > i32::MAX as f32 {
+                            reader.offset -= 4 * 3;
+                            return Err(reader.error(format!("surface {} has bone id {}, expected a joint index", s, value)));
+                        }
+                        *id = value as i32;
+                    }
+                    reader.floats(&mut weight.bone_weights, &what)?;
+                    surface.weights.push(weight);
+                }
+                surface.vertices.push(v);
+            }
+
+            let indices = reader.count(4, "indices")?;
+            if indices % 3 != 0 {
+                reader.offset -= 4;
+                return Err(reader.error(format!("surface {} has {} indices, not whole triangles", s, indices)));
+            }
+            for _ in 0..indices {
+                let index = reader.u32(&format!("surface {} indices", s))?;
+                if index as usize >= vertices {
+                    reader.offset -= 4;
+                    return Err(reader.error(format!("surface {} index {} is out of range of its {} vertices", s, index, vertices)));
+                }
+                surface.indices.push(index);
+            }
+
+            surfaces.push(surface);
+        }
+
+        if reader.offset != bytes.len() {
+            return Err(reader.error(format!("{} bytes left after the last surface", bytes.len() - reader.offset)));
+        }
+
+        Ok(Bmf { rigged: rigged, material: material, surfaces: surfaces })
+    }
+
+    pub fn load(filename: &str) -> Result<Bmf, String> {
+        in_file(filename, Bmf::parse(&read_bytes(filename)?))
+    }
+
+    /// The file `bmf_save_file` would write. Rigged meshes take their bones from `weights`,
+    /// vertices without one get none.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        fn u32(bytes: &mut Vec<u8>, value: u32) {
+            bytes.extend_from_slice(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+        }
+        fn floats(bytes: &mut Vec<u8>, values: &[f32]) {
+            for value in values {
+                u32(bytes, value.to_bits());
+            }
+        }
+
+        let mut bytes = b"BMF".to_vec();
+        u32(&mut bytes, VERSION);
+        bytes.push(self.rigged as u8);
+        u32(&mut bytes, self.material.len() as u32);
+        bytes.extend_from_slice(self.material.as_bytes());
+        u32(&mut bytes, self.surfaces.len() as u32);
+
+        for surface in &self.surfaces {
+            u32(&mut bytes, surface.vertices.len() as u32);
+            for (i, v) in surface.vertices.iter().enumerate() {
+                floats(&mut bytes, &v.position);
+                floats(&mut bytes, &v.normal);
+                floats(&mut bytes, &v.tangent);
+                floats(&mut bytes, &v.binormal);
+                floats(&mut bytes, &v.uvs);
+                if self.rigged {
+                    let weight = surface.weights.get(i).cloned().unwrap_or_default();
+                    floats(&mut bytes, &[v.color[3], v.color[0], v.color[1], v.color[2]]);
+                    floats(&mut bytes, &[weight.bone_ids[0] as f32, weight.bone_ids[1] as f32, weight.bone_ids[2] as f32]);
+                    floats(&mut bytes, &weight.bone_weights);
+                } else {
+                    floats(&mut bytes, &v.color);
+                }
+            }
+            u32(&mut bytes, surface.indices.len() as u32);
+            for &index in &surface.indices {
+                u32(&mut bytes, index);
+            }
+        }
+        bytes
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), String> {
+        write_file(filename, &self.to_bytes())
+    }
+
+    pub fn vertices(&self) -> usize {
+        self.surfaces.iter().map(|surface| surface.vertices.len()).sum()
+    }
+
+    pub fn triangles(&self) -> usize {
+        self.surfaces.iter().map(|surface| surface.indices.len() / 3).sum()
+    }
+}
@@ -0,0 +1,64 @@
+//! `.cfg` settings, as read by `cfg_load_file` and written by `cfg_save_file`
+//!
+//! One `key = value` pair per line, neither with spaces or `=` in them. Lines starting with `#`
+//! are comments.
+
+use super::{in_file, invalid, lines, read_text, write_file};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Cfg {
+    /// Pairs in file order
+    pub entries: Vec<(String, String)>
+}
+
+impl Cfg {
+    pub fn parse(text: &str) -> Result<Cfg, String> {
+        let mut cfg = Cfg::default();
+        for (number, line) in lines(text)? {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.splitn(2, '=');
+            let (key, value) = match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => (key.trim(), value.trim()),
+                _ => return Err(invalid(number, "expected 'key = value'"))
+            };
+            if key.is_empty() || value.is_empty() || key.contains(char::is_whitespace) {
+                return Err(invalid(number, "expected 'key = value'"));
+            }
+            if value.contains(char::is_whitespace) || value.contains('=') {
+                return Err(invalid(number, &format!("the value of '{}' can't contain spaces or '='", key)));
+            }
+            if cfg.get(key).is_some() {
+                return Err(invalid(number, &format!("'{}' is set twice", key)));
+            }
+            cfg.entries.push((key.to_string(), value.to_string()));
+        }
+        Ok(cfg)
+    }
+
+    pub fn load(filename: &str) -> Result<Cfg, String> {
+        in_file(filename, Cfg::parse(&read_text(filename)?))
+    }
+
+    pub fn to_text(&self) -> String {
+        self.entries.iter().map(|(key, value)| format!("{} = {}\n", key, value)).collect()
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), String> {
+        write_file(filename, self.to_text().as_bytes())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.iter().find(|entry| entry.0 == key).map(|entry| entry.1.as_str())
+    }
+
+    /// Replace the value of `key`, or add it at the end
+    pub fn set(&mut self, key: &str, value: &str) {
+        match self.entries.iter_mut().find(|entry| entry.0 == key) {
+            Some(entry) => entry.1 = value.to_string(),
+            None => self.entries.push((key.to_string(), value.to_string()))
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! `.col` collision meshes, as read by `col_load_file` and written by `col_save_file`
+//!
+//! A subset of `.obj`: `v` lines for positions and `f` lines for triangles. Texture coordinate and
+//! normal indices are allowed on faces but ignored, and everything else is skipped like in the
+//! engine.
+
+use super::{in_file, invalid, lines, parse_floats, parse_word, read_text, write_file};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Col {
+    pub positions: Vec<[f32; 3]>,
+    /// Indices into `positions`, counting from zero
+    pub triangles: Vec<[u32; 3]>
+}
+
+impl Col {
+    pub fn parse(text: &str) -> Result<Col, String> {
+        let mut col = Col::default();
+        for (number, line) in lines(text)? {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.first() {
+                Some(&"v") => {
+                    let values = parse_floats(number, &words[1..], 3)?;
+                    col.positions.push([values[0], values[1], values[2]]);
+                }
+                Some(&"f") => {
+                    let corners = words[1..].iter()
+                        .map(|word| col.corner(number, word))
+                        .collect::<Result<Vec<u32>, String>>()?;
+                    match corners.len() {
+                        3 => col.triangles.push([corners[0], corners[1], corners[2]]),
+                        // The engine only splits quads written without texture coordinates or normals
+                        4 if words[1..].iter().all(|word| word.ends_with("//")) => {
+                            col.triangles.push([corners[0], corners[1], corners[2]]);
+                            col.triangles.push([corners[0], corners[2], corners[3]]);
+                        }
+                        4 => return Err(invalid(number, "the engine only reads quads written as 'f a// b// c// d//'")),
+                        count => return Err(invalid(number, &format!("faces need 3 or 4 corners, found {}", count)))
+                    }
+                }
+                _ => continue
+            }
+        }
+        Ok(col)
+    }
+
+    /// Position index of a face corner like `3`, `3/1`, `3/1/2`, `3//2` or `3//`
+    fn corner(&self, number: usize, word: &str) -> Result<u32, String> {
+        let index: u32 = parse_word(number, word.split('/').next().unwrap(), "a position index")?;
+        if index == 0 || index as usize > self.positions.len() {
+            return Err(invalid(number, &format!("position {} isn't one of the {} before it", index, self.positions.len())));
+        }
+        Ok(index - 1)
+    }
+
+    pub fn load(filename: &str) -> Result<Col, String> {
+        in_file(filename, Col::parse(&read_text(filename)?))
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for p in &self.positions {
+            text.push_str(&format!("v {} {} {}\n", p[0], p[1], p[2]));
+        }
+        for t in &self.triangles {
+            text.push_str(&format!("f {} {} {}\n", t[0] + 1, t[1] + 1, t[2] + 1));
+        }
+        text
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), String> {
+        write_file(filename, self.to_text().as_bytes())
+    }
+}
@@ -0,0 +1,180 @@
+//! `.effect` particle effects, as read by `effect_load_file`
+//!
+//! Settings one per line, like `lifetime 2.5` or `blend_src src_alpha`, and `key` lines giving
+//! each property as `value:random` at a point of the particle's life. Lines starting with `#` are
+//! comments.
+
+use super::{in_file, invalid, lines, parse_floats, parse_word, read_text, scan_floats, write_file};
+
+/// Layout of `key` lines, every `%f` is a number
+const KEY: &'static str = "key time=%f rotation=%f:%f scale=(%f:%f,%f:%f,%f:%f) \
+    color=(%f:%f,%f:%f,%f:%f,%f:%f) force=(%f:%f,%f:%f,%f:%f)";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Blend {
+    One,
+    Zero,
+    SrcAlpha,
+    DstAlpha,
+    SrcInvAlpha,
+    DstInvAlpha
+}
+
+const BLENDS: &'static [(&'static str, Blend)] = &[
+    ("one", Blend::One),
+    ("zero", Blend::Zero),
+    ("src_alpha", Blend::SrcAlpha),
+    ("dst_alpha", Blend::DstAlpha),
+    ("src_inv_alpha", Blend::SrcInvAlpha),
+    ("dst_inv_alpha", Blend::DstInvAlpha)
+];
+
+impl Blend {
+    pub fn parse(name: &str) -> Option<Blend> {
+        BLENDS.iter().find(|blend| blend.0 == name).map(|blend| blend.1)
+    }
+
+    pub fn name(&self) -> &'static str {
+        BLENDS.iter().find(|blend| blend.1 == *self).unwrap().0
+    }
+}
+
+/// Particle properties at `time`, a fraction of the lifetime. The `_r` values are the random
+/// variation added to each particle.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Key {
+    pub time: f32,
+    pub rotation: f32,
+    pub rotation_r: f32,
+    pub scale: [f32; 3],
+    pub scale_r: [f32; 3],
+    pub color: [f32; 4],
+    pub color_r: [f32; 4],
+    pub force: [f32; 3],
+    pub force_r: [f32; 3]
+}
+
+/// An effect with the defaults of `effect_new` for anything the file leaves out
+#[derive(Clone, Debug, PartialEq)]
+pub struct Effect {
+    pub texture: Option<String>,
+    pub texture_nm: Option<String>,
+    pub blend_src: Blend,
+    pub blend_dst: Blend,
+    pub count: i32,
+    pub depth: f32,
+    pub thickness: f32,
+    pub bumpiness: f32,
+    pub scattering: f32,
+    pub lifetime: f32,
+    pub output: f32,
+    pub output_r: f32,
+    pub keys: Vec<Key>
+}
+
+impl Default for Effect {
+    fn default() -> Effect {
+        Effect {
+            texture: None,
+            texture_nm: None,
+            blend_src: Blend::One,
+            blend_dst: Blend::One,
+            count: 0,
+            depth: 1.0,
+            thickness: 1.0,
+            bumpiness: 0.5,
+            scattering: 0.3,
+            lifetime: 1.0,
+            output: 1.0,
+            output_r: 0.0,
+            keys: Vec::new()
+        }
+    }
+}
+
+impl Effect {
+    pub fn parse(text: &str) -> Result<Effect, String> {
+        let mut e = Effect::default();
+        for (number, line) in lines(text)? {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.is_empty() || words[0].starts_with('#') {
+                continue;
+            }
+            let single = || -> Result<&str, String> {
+                if words.len() == 2 { Ok(words[1]) } else { Err(invalid(number, &format!("expected a single value after '{}'", words[0]))) }
+            };
+            let float = || -> Result<f32, String> { parse_floats(number, &words[1..], 1).map(|values| values[0]) };
+            let blend = || -> Result<Blend, String> {
+                let name = single()?;
+                Blend::parse(name).ok_or_else(|| {
+                    let names: Vec<&str> = BLENDS.iter().map(|blend| blend.0).collect();
+                    invalid(number, &format!("unknown blend '{}', expected one of {}", name, names.join(", ")))
+                })
+            };
+            match words[0] {
+                "texture" => e.texture = Some(single()?.to_string()),
+                "texture_nm" => e.texture_nm = Some(single()?.to_string()),
+                "blend_src" => e.blend_src = blend()?,
+                "blend_dst" => e.blend_dst = blend()?,
+                "count" => e.count = parse_word(number, single()?, "an integer")?,
+                "depth" => e.depth = float()?,
+                "thickness" => e.thickness = float()?,
+                "bumpiness" => e.bumpiness = float()?,
+                "scattering" => e.scattering = float()?,
+                "lifetime" => e.lifetime = float()?,
+                "output" => {
+                    let values = scan_floats(line, "output %f:%f")
+                        .ok_or_else(|| invalid(number, "expected 'output value:random'"))?;
+                    e.output = values[0];
+                    e.output_r = values[1];
+                }
+                "key" => {
+                    let v = scan_floats(line, KEY).ok_or_else(|| invalid(number, &format!("expected '{}'", KEY)))?;
+                    e.keys.push(Key {
+                        time: v[0],
+                        rotation: v[1],
+                        rotation_r: v[2],
+                        scale: [v[3], v[5], v[7]],
+                        scale_r: [v[4], v[6], v[8]],
+                        color: [v[9], v[11], v[13], v[15]],
+                        color_r: [v[10], v[12], v[14], v[16]],
+                        force: [v[17], v[19], v[21]],
+                        force_r: [v[18], v[20], v[22]]
+                    });
+                }
+                other => return Err(invalid(number, &format!("unknown setting '{}'", other)))
+            }
+        }
+        Ok(e)
+    }
+
+    pub fn load(filename: &str) -> Result<Effect, String> {
+        in_file(filename, Effect::parse(&read_text(filename)?))
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        if let Some(ref texture) = self.texture {
+            text.push_str(&format!("texture {}\n", texture));
+        }
+        if let Some(ref texture_nm) = self.texture_nm {
+            text.push_str(&format!("texture_nm {}\n", texture_nm));
+        }
+        text.push_str(&format!("blend_src {}\nblend_dst {}\n", self.blend_src.name(), self.blend_dst.name()));
+        text.push_str(&format!("count {}\ndepth {}\nthickness {}\nbumpiness {}\nscattering {}\nlifetime {}\noutput {}:{}\n",
+            self.count, self.depth, self.thickness, self.bumpiness, self.scattering, self.lifetime, self.output, self.output_r));
+        for k in &self.keys {
+            text.push_str(&format!(
+                "key time={} rotation={}:{} scale=({}:{},{}:{},{}:{}) color=({}:{},{}:{},{}:{},{}:{}) force=({}:{},{}:{},{}:{})\n",
+                k.time, k.rotation, k.rotation_r,
+                k.scale[0], k.scale_r[0], k.scale[1], k.scale_r[1], k.scale[2], k.scale_r[2],
+                k.color[0], k.color_r[0], k.color[1], k.color_r[1], k.color[2], k.color_r[2], k.color[3], k.color_r[3],
+                k.force[0], k.force_r[0], k.force[1], k.force_r[1], k.force[2], k.force_r[2]));
+        }
+        text
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), String> {
+        write_file(filename, self.to_text().as_bytes())
+    }
+}
@@ -0,0 +1,50 @@
+//! `.lang` string tables, as read by `lang_load_file`
+//!
+//! One `id text` pair per line, where `\n` in the text stands for a line break. The engine turns
+//! it into a space followed by the break.
+
+use super::{in_file, invalid, lines, read_text, write_file};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Lang {
+    /// Ids and their text with line breaks decoded, in file order
+    pub entries: Vec<(String, String)>
+}
+
+impl Lang {
+    pub fn parse(text: &str) -> Result<Lang, String> {
+        let mut lang = Lang::default();
+        for (number, line) in lines(text)? {
+            let line = line.trim_start();
+            if line.trim().is_empty() {
+                continue;
+            }
+            let id = line.split_whitespace().next().unwrap();
+            let text = line[id.len()..].trim_start();
+            if text.is_empty() {
+                return Err(invalid(number, &format!("'{}' has no text", id)));
+            }
+            if lang.get(id).is_some() {
+                return Err(invalid(number, &format!("'{}' is defined twice", id)));
+            }
+            lang.entries.push((id.to_string(), text.replace("\\n", "\n")));
+        }
+        Ok(lang)
+    }
+
+    pub fn load(filename: &str) -> Result<Lang, String> {
+        in_file(filename, Lang::parse(&read_text(filename)?))
+    }
+
+    pub fn to_text(&self) -> String {
+        self.entries.iter().map(|(id, text)| format!("{} {}\n", id, text.replace('\n', "\\n"))).collect()
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), String> {
+        write_file(filename, self.to_text().as_bytes())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&str> {
+        self.entries.iter().find(|entry| entry.0 == id).map(|entry| entry.1.as_str())
+    }
+}
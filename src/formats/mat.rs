@@ -0,0 +1,148 @@
+//! `.mat` materials, as read by `mat_load_file`
+//!
+//! One `type name = value` item per line, with `submaterial` lines starting the next entry so each
+//! surface of a mesh can use its own. Lines starting with `#` are comments.
+
+use super::{in_file, invalid, lines, parse_floats, parse_word, read_text, write_file};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// Shader file, linked with the others of the entry into its program
+    Shader(String),
+    Texture(String),
+    Int(i32),
+    Float(f32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4])
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match *self {
+            Value::Shader(_) => "shader",
+            Value::Texture(_) => "texture",
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::Vec2(_) => "vec2",
+            Value::Vec3(_) => "vec3",
+            Value::Vec4(_) => "vec4"
+        }
+    }
+
+    /// Path of shader and texture items
+    pub fn path(&self) -> Option<&str> {
+        match *self {
+            Value::Shader(ref path) | Value::Texture(ref path) => Some(path),
+            _ => None
+        }
+    }
+
+    fn text(&self) -> String {
+        fn joined(values: &[f32]) -> String {
+            values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(" ")
+        }
+        match *self {
+            Value::Shader(ref path) | Value::Texture(ref path) => path.clone(),
+            Value::Int(value) => value.to_string(),
+            Value::Float(value) => value.to_string(),
+            Value::Vec2(ref values) => joined(values),
+            Value::Vec3(ref values) => joined(values),
+            Value::Vec4(ref values) => joined(values)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Item {
+    /// Uniform the value is bound to
+    pub name: String,
+    pub value: Value
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Entry {
+    /// Name after `submaterial`, which the engine ignores
+    pub name: Option<String>,
+    pub items: Vec<Item>
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mat {
+    pub entries: Vec<Entry>
+}
+
+impl Mat {
+    pub fn parse(text: &str) -> Result<Mat, String> {
+        let mut entries = vec![Entry::default()];
+        for (number, line) in lines(text)? {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.is_empty() || words[0].starts_with('#') {
+                continue;
+            }
+
+            if words[0] == "submaterial" {
+                if words.len() > 2 {
+                    return Err(invalid(number, "submaterial names can't contain spaces"));
+                }
+                // Like the engine, a submaterial line before any items names the current entry
+                if !entries.last().unwrap().items.is_empty() {
+                    entries.push(Entry::default());
+                }
+                entries.last_mut().unwrap().name = words.get(1).map(|name| name.to_string());
+                continue;
+            }
+
+            if words.len() < 4 || words[2] != "=" {
+                return Err(invalid(number, "expected 'type name = value'"));
+            }
+            let values = &words[3..];
+            let single = |expected: &str| -> Result<&str, String> {
+                if values.len() == 1 { Ok(values[0]) } else { Err(invalid(number, &format!("expected {} without spaces", expected))) }
+            };
+            let value = match words[0] {
+                "shader" => Value::Shader(single("a path")?.to_string()),
+                "texture" => Value::Texture(single("a path")?.to_string()),
+                "int" => Value::Int(parse_word(number, single("an integer")?, "an integer")?),
+                "float" => Value::Float(parse_word(number, single("a number")?, "a number")?),
+                "vec2" => { let v = parse_floats(number, values, 2)?; Value::Vec2([v[0], v[1]]) }
+                "vec3" => { let v = parse_floats(number, values, 3)?; Value::Vec3([v[0], v[1], v[2]]) }
+                "vec4" => { let v = parse_floats(number, values, 4)?; Value::Vec4([v[0], v[1], v[2], v[3]]) }
+                other => return Err(invalid(number, &format!("unknown material item type '{}'", other)))
+            };
+            entries.last_mut().unwrap().items.push(Item { name: words[1].to_string(), value: value });
+        }
+        Ok(Mat { entries: entries })
+    }
+
+    pub fn load(filename: &str) -> Result<Mat, String> {
+        in_file(filename, Mat::parse(&read_text(filename)?))
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                text.push('\n');
+            }
+            match entry.name {
+                Some(ref name) => text.push_str(&format!("submaterial {}\n", name)),
+                None if i > 0 => text.push_str("submaterial\n"),
+                None => {}
+            }
+            for item in &entry.items {
+                text.push_str(&format!("{} {} = {}\n", item.value.type_name(), item.name, item.value.text()));
+            }
+        }
+        text
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), String> {
+        write_file(filename, self.to_text().as_bytes())
+    }
+
+    /// Shader and texture paths of every entry, in order
+    pub fn paths(&self) -> Vec<&str> {
+        self.entries.iter().flat_map(|entry| entry.items.iter().filter_map(|item| item.value.path())).collect()
+    }
+}
@@ -0,0 +1,214 @@
+//! Readers and writers for the engine's own asset formats
+//!
+//! These work on plain data, without the C engine or a GL context, so tools can check and
+//! rewrite assets on their own. Each format has `parse` and `load`, which check the file the way
+//! the engine reads it, and `to_text` (`to_bytes` for `.bmf`) and `save` to write it back.
+//! Errors name the line they happened on, or the byte offset for `.bmf`, and `load` adds the file.
+//!
+//! Values are kept as they are written, conversions the engine's loaders apply afterwards (like
+//! the y and z swap of `.skl` and `.ani`) are left to the caller.
+
+pub mod bmf;
+pub mod skl;
+pub mod ani;
+pub mod col;
+pub mod mat;
+pub mod cfg;
+pub mod effect;
+pub mod lang;
+
+use std::fs::File;
+use std::io::Write;
+use std::str::FromStr;
+use archive;
+
+/// The engine reads text assets into a 1024 byte buffer, longer lines are cut up
+const MAX_LINE: usize = 1023;
+
+/// The engine's `fpath` holds 511 bytes and the terminator
+const MAX_PATH: usize = 511;
+
+fn read_bytes(filename: &str) -> Result<Vec<u8>, String> {
+    archive::read_file(filename).map_err(|err| format!("{}: {}", filename, err))
+}
+
+fn read_text(filename: &str) -> Result<String, String> {
+    String::from_utf8(read_bytes(filename)?).map_err(|_| format!("{}: not valid UTF-8", filename))
+}
+
+fn write_file(filename: &str, bytes: &[u8]) -> Result<(), String> {
+    File::create(filename)
+        .and_then(|mut file| file.write_all(bytes))
+        .map_err(|err| format!("{}: {}", filename, err))
+}
+
+/// Prefix parse errors with the file they came from
+fn in_file<T>(filename: &str, result: Result<T, String>) -> Result<T, String> {
+    result.map_err(|err| format!("{}: {}", filename, err))
+}
+
+fn invalid(number: usize, message: &str) -> String {
+    format!("line {}: {}", number, message)
+}
+
+/// Numbered lines without their line endings, failing on lines the engine can't read in one piece
+fn lines(text: &str) -> Result<Vec<(usize, &str)>, String> {
+    text.lines().enumerate().map(|(i, line)| {
+        if line.len() > MAX_LINE {
+            Err(invalid(i + 1, &format!("longer than the engine's limit of {} characters", MAX_LINE)))
+        } else {
+            Ok((i + 1, line))
+        }
+    }).collect()
+}
+
+fn parse_word<T: FromStr>(number: usize, word: &str, expected: &str) -> Result<T, String> {
+    word.parse().map_err(|_| invalid(number, &format!("expected {}, found '{}'", expected, word)))
+}
+
+/// Every word of `words` as a float, `count` of them
+fn parse_floats(number: usize, words: &[&str], count: usize) -> Result<Vec<f32>, String> {
+    if words.len() != count {
+        return Err(invalid(number, &format!("expected {} numbers, found {}", count, words.len())));
+    }
+    words.iter().map(|word| parse_word(number, word, "a number")).collect()
+}
+
+/// Read the floats of `line` laid out like `template`, where each `%f` stands for a number and
+/// whitespace matches any amount of whitespace like in `sscanf`
+fn scan_floats(line: &str, template: &str) -> Option<Vec<f32>> {
+    let mut values = Vec::new();
+    let mut rest = line.trim_start();
+    let mut pattern = template;
+    while !pattern.is_empty() {
+        if pattern.starts_with("%f") {
+            let end = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '+'))
+                .unwrap_or(rest.len());
+            values.push(rest[..end].parse().ok()?);
+            rest = &rest[end..];
+            pattern = &pattern[2..];
+        } else if pattern.starts_with(' ') {
+            rest = rest.trim_start();
+            pattern = pattern.trim_start();
+        } else {
+            let c = pattern.chars().next().unwrap();
+            if !rest.starts_with(c) {
+                return None;
+            }
+            rest = &rest[c.len_utf8()..];
+            pattern = &pattern[c.len_utf8()..];
+        }
+    }
+    if rest.trim().is_empty() { Some(values) } else { None }
+}
+
+/// Contents shared by `.skl` and `.ani`, which are the node and skeleton blocks of an SMD file
+struct SkeletonText {
+    joints: Vec<skl::Joint>,
+    frames: Vec<SkeletonFrame>
+}
+
+struct SkeletonFrame {
+    /// Line of the `time` entry, or of the first transform when there is none
+    line: usize,
+    time: Option<f32>,
+    transforms: Vec<skl::JointTransform>
+}
+
+fn parse_skeleton_text(text: &str) -> Result<SkeletonText, String> {
+    enum Section { None, Nodes, Skeleton }
+
+    let mut contents = SkeletonText { joints: Vec::new(), frames: Vec::new() };
+    let mut section = Section::None;
+    let mut last = 0;
+    for (number, line) in lines(text)? {
+        last = number;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() {
+            continue;
+        }
+        match section {
+            Section::None => match words[0] {
+                "version" => {
+                    let version: i32 = parse_word(number, words.get(1).cloned().unwrap_or(""), "a version")?;
+                    if version != 1 || words.len() != 2 {
+                        return Err(invalid(number, &format!("version {} isn't supported, only version 1", version)));
+                    }
+                }
+                "nodes" if words.len() == 1 => section = Section::Nodes,
+                "skeleton" if words.len() == 1 => section = Section::Skeleton,
+                _ => return Err(invalid(number, &format!("expected version, nodes or skeleton, found '{}'", line.trim())))
+            },
+            Section::Nodes => {
+                if words == ["end"] {
+                    section = Section::None;
+                    continue;
+                }
+                contents.joints.push(parse_joint(number, line, contents.joints.len())?);
+            }
+            Section::Skeleton => {
+                if words == ["end"] {
+                    section = Section::None;
+                } else if words[0] == "time" {
+                    let time = parse_floats(number, &words[1..], 1)?[0];
+                    contents.frames.push(SkeletonFrame { line: number, time: Some(time), transforms: Vec::new() });
+                } else {
+                    let id: usize = parse_word(number, words[0], "a joint id")?;
+                    if id >= contents.joints.len() {
+                        return Err(invalid(number, &format!("joint {} isn't one of the {} nodes", id, contents.joints.len())));
+                    }
+                    let values = parse_floats(number, &words[1..], 6)?;
+                    if contents.frames.is_empty() {
+                        contents.frames.push(SkeletonFrame { line: number, time: None, transforms: Vec::new() });
+                    }
+                    contents.frames.last_mut().unwrap().transforms.push(skl::JointTransform {
+                        id: id,
+                        position: [values[0], values[1], values[2]],
+                        rotation: [values[3], values[4], values[5]]
+                    });
+                }
+            }
+        }
+    }
+    match section {
+        Section::None => Ok(contents),
+        _ => Err(invalid(last, "missing 'end'"))
+    }
+}
+
+/// A `nodes` entry, `id "name" parent`
+fn parse_joint(number: usize, line: &str, index: usize) -> Result<skl::Joint, String> {
+    let expected = "expected 'id \"name\" parent'";
+    let mut parts = line.splitn(3, '"');
+    let (id, name, parent) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(id), Some(name), Some(parent)) => (id.trim(), name, parent.trim()),
+        _ => return Err(invalid(number, expected))
+    };
+    let id: usize = parse_word(number, id, "a joint id")?;
+    let parent: i32 = parse_word(number, parent, "a parent id")?;
+    if id != index {
+        return Err(invalid(number, &format!("joint {} is out of order, expected joint {}", id, index)));
+    }
+    if parent < -1 || parent >= id as i32 {
+        return Err(invalid(number, &format!("parent {} of joint {} has to be -1 or an earlier joint", parent, id)));
+    }
+    if name.is_empty() || name.contains("end") {
+        return Err(invalid(number, &format!("the engine can't read a joint named '{}'", name)));
+    }
+    Ok(skl::Joint { name: name.to_string(), parent: parent })
+}
+
+fn write_joints(text: &mut String, joints: &[skl::Joint]) {
+    text.push_str("version 1\nnodes\n");
+    for (id, joint) in joints.iter().enumerate() {
+        text.push_str(&format!("{:3} \"{}\" {:3}\n", id, joint.name, joint.parent));
+    }
+    text.push_str("end\nskeleton\n");
+}
+
+fn write_transforms(text: &mut String, transforms: &[skl::JointTransform]) {
+    for t in transforms {
+        text.push_str(&format!("{:3} {:.6} {:.6} {:.6} {:.6} {:.6} {:.6}\n",
+            t.id, t.position[0], t.position[1], t.position[2], t.rotation[0], t.rotation[1], t.rotation[2]));
+    }
+}
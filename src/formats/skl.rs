@@ -0,0 +1,63 @@
+//! `.skl` skeletons, as read by `skl_load_file`
+//!
+//! A `nodes` block naming each joint and its parent, then a `skeleton` block with the rest pose.
+//! Positions are z up and rotations are euler angles in radians, as exported to SMD.
+
+use super::{in_file, invalid, parse_skeleton_text, read_text, write_file, write_joints, write_transforms};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Joint {
+    pub name: String,
+    /// Index of the parent joint, -1 for roots
+    pub parent: i32
+}
+
+/// Position and rotation of one joint, relative to its parent
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JointTransform {
+    pub id: usize,
+    pub position: [f32; 3],
+    pub rotation: [f32; 3]
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Skl {
+    pub joints: Vec<Joint>,
+    /// Joints left out keep the origin and no rotation
+    pub rest_pose: Vec<JointTransform>
+}
+
+impl Skl {
+    pub fn parse(text: &str) -> Result<Skl, String> {
+        let contents = parse_skeleton_text(text)?;
+        if let Some(frame) = contents.frames.get(1) {
+            return Err(invalid(frame.line, "a skeleton has a single rest pose, use an .ani file for more frames"));
+        }
+        Ok(Skl {
+            joints: contents.joints,
+            rest_pose: contents.frames.into_iter().next().map_or(Vec::new(), |frame| frame.transforms)
+        })
+    }
+
+    pub fn load(filename: &str) -> Result<Skl, String> {
+        in_file(filename, Skl::parse(&read_text(filename)?))
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        write_joints(&mut text, &self.joints);
+        text.push_str("time 0\n");
+        write_transforms(&mut text, &self.rest_pose);
+        text.push_str("end\n");
+        text
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), String> {
+        write_file(filename, self.to_text().as_bytes())
+    }
+
+    /// Index of the joint called `name`
+    pub fn joint_id(&self, name: &str) -> Option<usize> {
+        self.joints.iter().position(|joint| joint.name == name)
+    }
+}
@@ -0,0 +1,1273 @@
+//! glTF 2.0 loader for `.gltf` and `.glb` files
+//!
+//! `register` adds it as a renderable handler, so `folder_load` picks glTF files up like `.bmf`.
+//! Loading `model.gltf` also adds the assets derived from it:
+//!
+//! * `model.gltf.mat`, one entry per surface using the deferred static or animated shaders
+//! * `model.gltf.skl`, the first skin used by the scene
+//! * `model.gltf.<animation>.ani` for every animation, resampled at `ANIMATION_RATE`
+//!
+//! These are replaced when the glTF file is loaded again, so reload the glTF file rather than
+//! the derived assets. Images are loaded through the registered texture handlers; those in other
+//! formats fall back to plain textures with a warning.
+
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::{Mutex, Once};
+use libc;
+use libc::{c_char, c_int, c_void};
+use corange::*;
+use archive;
+use json;
+use json::Json;
+
+/// Extensions the loader is registered for
+pub const EXTENSIONS: &'static [&'static str] = &["gltf", "glb"];
+
+/// Extensions of the registered texture loaders
+pub const TEXTURE_EXTENSIONS: &'static [&'static str] = &["dds", "tga", "bmp", "png", "jpg", "jpeg"];
+
+/// Frames per second animations are resampled at
+pub const ANIMATION_RATE: f32 = 30.0;
+
+/// Bones the renderer uploads for an animated object
+const MAX_JOINTS: usize = 64;
+
+lazy_static! {
+    // Assets added while loading each glTF file, keyed by the glTF path
+    static ref DERIVED: Mutex<HashMap<String, Vec<String>>> = Mutex::new(HashMap::new());
+}
+
+static REGISTER: Once = Once::new();
+
+/// Register the `.gltf` and `.glb` handlers, called by `engine::initialize`
+pub fn register() {
+    REGISTER.call_once(|| unsafe {
+        for extension in EXTENSIONS {
+            let extension = CString::new(*extension).unwrap();
+            asset_handler_cast(*RENDERABLE_TYPE, extension.as_ptr(), gltf_load_file, gltf_delete);
+        }
+    });
+}
+
+unsafe extern "C" fn gltf_load_file(filename: *const c_char) -> *mut asset {
+    let filename = CStr::from_ptr(filename).to_string_lossy().into_owned();
+    match load_file(&filename) {
+        Ok(r) => r as *mut asset,
+        Err(err) => {
+            let message = CString::new(format!("[ERROR] ({}) {}", file!(), err.replace('\0', ""))).unwrap();
+            error_(message.as_ptr());
+            ptr::null_mut()
+        }
+    }
+}
+
+unsafe extern "C" fn gltf_delete(a: *mut asset) {
+    renderable_delete(a as *mut renderable);
+}
+
+fn warn(message: &str) {
+    let message = CString::new(format!("[WARNING] ({}) {}", file!(), message.replace('\0', ""))).unwrap();
+    unsafe { warning_(message.as_ptr()); }
+}
+
+/// Paths of the assets added when `filename` was loaded
+pub fn derived_assets(filename: &str) -> Vec<String> {
+    let filename = mapped(filename);
+    DERIVED.lock().unwrap().get(&filename).cloned().unwrap_or_default()
+}
+
+fn mapped(filename: &str) -> String {
+    unsafe {
+        let filename = CString::new(filename).unwrap();
+        let mapped = asset_map_filename(P(filename.as_ptr()));
+        CStr::from_ptr(mapped.ptr.as_ptr()).to_string_lossy().into_owned()
+    }
+}
+
+/// Load `filename` as a renderable and add the assets derived from it. Needs a GL context.
+pub fn load_file(filename: &str) -> Result<*mut renderable, String> {
+    let imported = import_file(filename)?;
+    Ok(load_imported(filename, &imported))
+}
+
+/// A glTF file read and imported into meshes, everything `load_file` does before it needs GL
+pub struct Imported {
+    scene: Scene,
+    /// One per primitive of the scene
+    meshes: Vec<*mut mesh>
+}
+
+// The meshes are heap memory only `Imported` points to, so a file can be imported on one thread
+// and loaded on the thread owning the GL context.
+unsafe impl Send for Imported {}
+
+impl Drop for Imported {
+    fn drop(&mut self) {
+        for &m in &self.meshes {
+            unsafe { mesh_delete(m); }
+        }
+    }
+}
+
+/// Read and import `filename` without a GL context, for `load_imported`
+pub fn import_file(filename: &str) -> Result<Imported, String> {
+    let filename = &mapped(filename);
+    let scene = Document::open(Path::new(filename))
+        .and_then(|document| import(&document))
+        .map_err(|err| format!("{}: {}", filename, err))?;
+    let meshes = scene.primitives.iter().map(|primitive| unsafe { mesh_from(primitive) }).collect();
+    Ok(Imported { scene: scene, meshes: meshes })
+}
+
+/// Upload what `import_file` read from `filename` as a renderable and add the assets derived
+/// from it, like `load_file`. Needs a GL context.
+pub fn load_imported(filename: &str, imported: &Imported) -> *mut renderable {
+    let filename = &mapped(filename);
+    let previous = DERIVED.lock().unwrap().remove(filename).unwrap_or_default();
+    unsafe {
+        for path in previous {
+            let path = CString::new(path).unwrap();
+            if file_isloaded(P(path.as_ptr())) != 0 {
+                file_unload(P(path.as_ptr()));
+            }
+        }
+    }
+
+    let mut derived = Vec::new();
+    let r = unsafe { build(imported, filename, &mut derived) };
+    DERIVED.lock().unwrap().insert(filename.to_string(), derived);
+    unsafe { asset_cache_flush(); }
+    r
+}
+
+/// The meshes `load_file` builds its surfaces from, with their vertex weights if the file is
+/// rigged. Reads no GL and adds no assets; the caller deletes the meshes.
+pub fn load_meshes(filename: &str) -> Result<Vec<(*mut mesh, Option<Vec<vertex_weight>>)>, String> {
+    let filename = &mapped(filename);
+    let scene = Document::open(Path::new(filename))
+        .and_then(|document| import(&document))
+        .map_err(|err| format!("{}: {}", filename, err))?;
+    Ok(scene.primitives.iter().map(|primitive| (unsafe { mesh_from(primitive) }, primitive.weights.clone())).collect())
+}
+
+/// Read and import `filename` without loading anything, to find what `load_file` would fail on
+pub fn check_file(filename: &str) -> Result<(), String> {
+    let filename = &mapped(filename);
+    Document::open(Path::new(filename))
+        .and_then(|document| import(&document))
+        .map(|_| ())
+        .map_err(|err| format!("{}: {}", filename, err))
+}
+
+/// Files the buffers and images of `filename` are read from, without reading them
+pub fn references(filename: &str) -> Result<Vec<String>, String> {
+    let path = Path::new(filename);
+    let (json, _) = Document::read_json(path).map_err(|err| format!("{}: {}", filename, err))?;
+    let folder = path.parent().map_or(PathBuf::new(), Path::to_path_buf);
+    let uris = json.get("buffers").members().iter().chain(json.get("images").members().iter())
+        .filter_map(|member| member.get("uri").as_str())
+        .filter(|uri| !uri.starts_with("data:"))
+        .map(|uri| folder.join(percent_decode(uri)).to_string_lossy().into_owned())
+        .collect();
+    Ok(uris)
+}
+
+/// A parsed glTF file with its buffers
+struct Document {
+    json: Json,
+    buffers: Vec<Vec<u8>>,
+    folder: PathBuf
+}
+
+/// Values of an accessor, converted to floating point
+struct Accessor {
+    count: usize,
+    components: usize,
+    values: Vec<f64>
+}
+
+impl Accessor {
+    fn get(&self, i: usize, c: usize) -> f32 {
+        self.values[i * self.components + c] as f32
+    }
+
+    fn vec2(&self, i: usize) -> vec2 {
+        vec2 { x: self.get(i, 0), y: self.get(i, 1) }
+    }
+
+    fn vec3(&self, i: usize) -> vec3 {
+        vec3 { x: self.get(i, 0), y: self.get(i, 1), z: self.get(i, 2) }
+    }
+
+    fn mat4(&self, i: usize) -> mat4 {
+        let a: Vec<f32> = (0..16).map(|c| self.get(i, c)).collect();
+        column_major(&a)
+    }
+}
+
+const GLB_MAGIC: u32 = 0x46546C67;
+const GLB_JSON: u32 = 0x4E4F534A;
+const GLB_BIN: u32 = 0x004E4942;
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    bytes.get(offset..offset + 4).map(|b| b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16 | (b[3] as u32) << 24)
+}
+
+impl Document {
+    /// The JSON of a `.gltf` or `.glb` file with the binary chunk of a `.glb`
+    fn read_json(path: &Path) -> Result<(Json, Option<Vec<u8>>), String> {
+        let bytes = archive::read_file(&path.to_string_lossy()).map_err(|err| err.to_string())?;
+
+        let (text, binary) = if read_u32(&bytes, 0) == Some(GLB_MAGIC) {
+            Document::chunks(&bytes)?
+        } else {
+            (bytes, None)
+        };
+
+        let text = String::from_utf8(text).map_err(|_| "JSON is not valid UTF-8".to_string())?;
+        let json = json::parse(&text)?;
+
+        let version = json.get("asset").get("version").as_str().unwrap_or("");
+        if !version.starts_with("2.") {
+            return Err(format!("unsupported glTF version '{}', expected 2.x", version));
+        }
+        Ok((json, binary))
+    }
+
+    fn open(path: &Path) -> Result<Document, String> {
+        let (json, binary) = Document::read_json(path)?;
+        let folder = path.parent().map_or(PathBuf::new(), Path::to_path_buf);
+        let mut document = Document { json: Json::Null, buffers: Vec::new(), folder: folder };
+        let mut binary = binary;
+        for (i, buffer) in json.get("buffers").members().iter().enumerate() {
+            let length = buffer.get("byteLength").as_usize().ok_or_else(|| format!("buffer {} has no byteLength", i))?;
+            let data = match buffer.get("uri").as_str() {
+                Some(uri) => document.uri(uri)?,
+                None if i == 0 => binary.take().ok_or_else(|| "buffer 0 has no uri and there is no binary chunk".to_string())?,
+                None => return Err(format!("buffer {} has no uri", i))
+            };
+            if data.len() < length {
+                return Err(format!("buffer {} holds {} bytes, expected {}", i, data.len(), length));
+            }
+            document.buffers.push(data);
+        }
+        document.json = json;
+        Ok(document)
+    }
+
+    /// JSON and binary chunks of a `.glb` file
+    fn chunks(bytes: &[u8]) -> Result<(Vec<u8>, Option<Vec<u8>>), String> {
+        if read_u32(bytes, 4) != Some(2) {
+            return Err("unsupported binary glTF container version".to_string());
+        }
+        let length = (read_u32(bytes, 8).unwrap_or(0) as usize).min(bytes.len());
+        let mut text = None;
+        let mut binary = None;
+        let mut offset = 12;
+        while offset + 8 <= length {
+            let chunk_length = read_u32(bytes, offset).unwrap() as usize;
+            let chunk_type = read_u32(bytes, offset + 4).unwrap();
+            let data = bytes.get(offset + 8..offset + 8 + chunk_length).ok_or_else(|| "truncated chunk".to_string())?;
+            match chunk_type {
+                GLB_JSON if text.is_none() => text = Some(data.to_vec()),
+                GLB_BIN if binary.is_none() => binary = Some(data.to_vec()),
+                _ => {}
+            }
+            offset += 8 + ((chunk_length + 3) & !3);
+        }
+        Ok((text.ok_or_else(|| "no JSON chunk".to_string())?, binary))
+    }
+
+    /// Contents of a data URI or of a file relative to the glTF file
+    fn uri(&self, uri: &str) -> Result<Vec<u8>, String> {
+        if uri.starts_with("data:") {
+            let comma = uri.find(',').ok_or_else(|| "malformed data URI".to_string())?;
+            if !uri[..comma].ends_with(";base64") {
+                return Err("data URIs must be base64 encoded".to_string());
+            }
+            return base64(&uri[comma + 1..]);
+        }
+        let path = self.path(uri);
+        archive::read_file(&path.to_string_lossy()).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    fn path(&self, uri: &str) -> PathBuf {
+        self.folder.join(percent_decode(uri))
+    }
+
+    fn accessor(&self, index: usize) -> Result<Accessor, String> {
+        let accessor = self.json.get("accessors").at(index);
+        if accessor.is_null() {
+            return Err(format!("accessor {} does not exist", index));
+        }
+        let count = accessor.get("count").as_usize().ok_or_else(|| format!("accessor {} has no count", index))?;
+        let kind = accessor.get("componentType").as_usize().unwrap_or(0);
+        let size = component_size(kind).ok_or_else(|| format!("accessor {} has an unknown component type", index))?;
+        let components = match accessor.get("type").as_str().unwrap_or("") {
+            "SCALAR" => 1, "VEC2" => 2, "VEC3" => 3, "VEC4" => 4, "MAT2" => 4, "MAT3" => 9, "MAT4" => 16,
+            other => return Err(format!("accessor {} has unknown type '{}'", index, other))
+        };
+        let normalized = accessor.get("normalized").as_bool().unwrap_or(false);
+
+        let mut values = vec![0.0; count * components];
+        if let Some(view) = accessor.get("bufferView").as_usize() {
+            let offset = accessor.get("byteOffset").as_usize().unwrap_or(0);
+            let (data, stride) = self.view(view)?;
+            let stride = stride.unwrap_or(size * components);
+            for i in 0..count {
+                for c in 0..components {
+                    let at = offset + i * stride + c * size;
+                    let bytes = data.get(at..at + size)
+                        .ok_or_else(|| format!("accessor {} reads past the end of buffer view {}", index, view))?;
+                    values[i * components + c] = component(bytes, kind, normalized);
+                }
+            }
+        }
+
+        let sparse = accessor.get("sparse");
+        if !sparse.is_null() {
+            let changed = sparse.get("count").as_usize().unwrap_or(0);
+            let indices = sparse.get("indices");
+            let index_kind = indices.get("componentType").as_usize().unwrap_or(0);
+            let index_size = component_size(index_kind).ok_or_else(|| format!("accessor {} has malformed sparse indices", index))?;
+            let (index_data, _) = self.view(indices.get("bufferView").as_usize().unwrap_or(usize::MAX))?;
+            let index_offset = indices.get("byteOffset").as_usize().unwrap_or(0);
+            let (value_data, _) = self.view(sparse.get("values").get("bufferView").as_usize().unwrap_or(usize::MAX))?;
+            let value_offset = sparse.get("values").get("byteOffset").as_usize().unwrap_or(0);
+            for s in 0..changed {
+                let at = index_offset + s * index_size;
+                let target = index_data.get(at..at + index_size).map(|bytes| component(bytes, index_kind, false) as usize)
+                    .filter(|&target| target < count)
+                    .ok_or_else(|| format!("accessor {} has malformed sparse indices", index))?;
+                for c in 0..components {
+                    let at = value_offset + (s * components + c) * size;
+                    let bytes = value_data.get(at..at + size)
+                        .ok_or_else(|| format!("accessor {} has malformed sparse values", index))?;
+                    values[target * components + c] = component(bytes, kind, normalized);
+                }
+            }
+        }
+
+        Ok(Accessor { count: count, components: components, values: values })
+    }
+
+    /// Bytes of a buffer view and its stride
+    fn view(&self, index: usize) -> Result<(&[u8], Option<usize>), String> {
+        let view = self.json.get("bufferViews").at(index);
+        let buffer = view.get("buffer").as_usize().and_then(|buffer| self.buffers.get(buffer))
+            .ok_or_else(|| format!("buffer view {} does not exist or has no buffer", index))?;
+        let offset = view.get("byteOffset").as_usize().unwrap_or(0);
+        let length = view.get("byteLength").as_usize().unwrap_or(0);
+        let data = buffer.get(offset..offset + length).ok_or_else(|| format!("buffer view {} is out of range", index))?;
+        Ok((data, view.get("byteStride").as_usize()))
+    }
+}
+
+fn component_size(kind: usize) -> Option<usize> {
+    match kind {
+        5120 | 5121 => Some(1),
+        5122 | 5123 => Some(2),
+        5125 | 5126 => Some(4),
+        _ => None
+    }
+}
+
+fn component(bytes: &[u8], kind: usize, normalized: bool) -> f64 {
+    let unsigned = || bytes.iter().rev().fold(0u32, |value, &byte| value << 8 | byte as u32);
+    match (kind, normalized) {
+        (5120, false) => bytes[0] as i8 as f64,
+        (5120, true) => (bytes[0] as i8 as f64 / 127.0).max(-1.0),
+        (5121, false) => bytes[0] as f64,
+        (5121, true) => bytes[0] as f64 / 255.0,
+        (5122, false) => unsigned() as u16 as i16 as f64,
+        (5122, true) => (unsigned() as u16 as i16 as f64 / 32767.0).max(-1.0),
+        (5123, false) => unsigned() as f64,
+        (5123, true) => unsigned() as f64 / 65535.0,
+        (5125, _) => unsigned() as f64,
+        _ => f32::from_bits(unsigned()) as f64
+    }
+}
+
+fn base64(text: &str) -> Result<Vec<u8>, String> {
+    let mut data = Vec::with_capacity(text.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut count = 0;
+    for c in text.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' | b' ' | b'\n' | b'\r' | b'\t' => continue,
+            _ => return Err("malformed base64 in data URI".to_string())
+        };
+        bits = bits << 6 | value as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            data.push((bits >> count) as u8);
+        }
+    }
+    Ok(data)
+}
+
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = if bytes[i] == b'%' {
+            bytes.get(i + 1..i + 3)
+                .and_then(|digits| ::std::str::from_utf8(digits).ok())
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+        } else {
+            None
+        };
+        match escape {
+            Some(byte) => { decoded.push(byte); i += 3; }
+            None => { decoded.push(bytes[i]); i += 1; }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Matrix from the column major layout glTF uses
+fn column_major(a: &[f32]) -> mat4 {
+    unsafe {
+        mat4_new(a[0], a[4], a[8], a[12],
+                 a[1], a[5], a[9], a[13],
+                 a[2], a[6], a[10], a[14],
+                 a[3], a[7], a[11], a[15])
+    }
+}
+
+/// Rotation of a transform, ignoring scale, shear and mirroring
+fn rotation_of(m: mat4) -> quat {
+    unsafe {
+        let x = vec3_normalize(vec3_new(m.xx, m.yx, m.zx));
+        let mut y = vec3_new(m.xy, m.yy, m.zy);
+        y = vec3_normalize(vec3_sub(y, vec3_mul(x, vec3_dot(x, y))));
+        let z = vec3_cross(x, y);
+
+        let trace = x.x + y.y + z.z;
+        let q = if trace > 0.0 {
+            let s = 0.5 / (trace + 1.0).sqrt();
+            quat_new((y.z - z.y) * s, (z.x - x.z) * s, (x.y - y.x) * s, 0.25 / s)
+        } else if x.x > y.y && x.x > z.z {
+            let s = 2.0 * (1.0 + x.x - y.y - z.z).sqrt();
+            quat_new(0.25 * s, (y.x + x.y) / s, (z.x + x.z) / s, (y.z - z.y) / s)
+        } else if y.y > z.z {
+            let s = 2.0 * (1.0 + y.y - x.x - z.z).sqrt();
+            quat_new((y.x + x.y) / s, 0.25 * s, (z.y + y.z) / s, (z.x - x.z) / s)
+        } else {
+            let s = 2.0 * (1.0 + z.z - x.x - y.y).sqrt();
+            quat_new((z.x + x.z) / s, (z.y + y.z) / s, 0.25 * s, (x.y - y.x) / s)
+        };
+        quat_normalize(q)
+    }
+}
+
+fn translation_of(m: mat4) -> vec3 {
+    vec3 { x: m.xw, y: m.yw, z: m.zw }
+}
+
+/// Position and rotation of every joint relative to its parent, from their global transforms
+fn joint_pose(globals: &[mat4], parents: &[c_int]) -> Vec<(vec3, quat)> {
+    unsafe {
+        let rigid: Vec<mat4> = globals.iter()
+            .map(|&global| mat4_world(translation_of(global), vec3_one(), rotation_of(global)))
+            .collect();
+        rigid.iter().zip(parents).map(|(&joint, &parent)| {
+            let local = if parent < 0 { joint } else { mat4_mul_mat4(mat4_inverse(rigid[parent as usize]), joint) };
+            (translation_of(local), rotation_of(local))
+        }).collect()
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Transform {
+    translation: vec3,
+    rotation: quat,
+    scale: vec3,
+    matrix: Option<mat4>
+}
+
+impl Transform {
+    fn local(&self) -> mat4 {
+        match self.matrix {
+            Some(matrix) => matrix,
+            None => unsafe { mat4_world(self.translation, self.scale, self.rotation) }
+        }
+    }
+}
+
+struct Node {
+    name: Option<String>,
+    children: Vec<usize>,
+    transform: Transform,
+    mesh: Option<usize>,
+    skin: Option<usize>
+}
+
+/// The node hierarchy, with parents ordered before their children
+struct Hierarchy {
+    nodes: Vec<Node>,
+    parents: Vec<Option<usize>>,
+    order: Vec<usize>
+}
+
+impl Hierarchy {
+    fn new(json: &Json) -> Result<Hierarchy, String> {
+        let mut nodes = Vec::new();
+        for (i, node) in json.get("nodes").members().iter().enumerate() {
+            let vector = |key: &str, default: [f32; 4]| -> Result<[f32; 4], String> {
+                let value = node.get(key);
+                if value.is_null() { return Ok(default); }
+                let values = value.as_f32_vec().ok_or_else(|| format!("node {} has a malformed {}", i, key))?;
+                let mut out = default;
+                for (c, v) in values.into_iter().take(4).enumerate() { out[c] = v; }
+                Ok(out)
+            };
+            let t = vector("translation", [0.0, 0.0, 0.0, 0.0])?;
+            let r = vector("rotation", [0.0, 0.0, 0.0, 1.0])?;
+            let s = vector("scale", [1.0, 1.0, 1.0, 0.0])?;
+            let matrix = match node.get("matrix").as_f32_vec() {
+                Some(ref a) if a.len() == 16 => Some(column_major(a)),
+                Some(_) => return Err(format!("node {} has a malformed matrix", i)),
+                None => None
+            };
+            let children = node.get("children").members().iter()
+                .map(|child| child.as_usize().ok_or_else(|| format!("node {} has a malformed child", i)))
+                .collect::<Result<Vec<usize>, String>>()?;
+            nodes.push(Node {
+                name: node.get("name").as_str().map(str::to_string),
+                children: children,
+                transform: Transform {
+                    translation: vec3 { x: t[0], y: t[1], z: t[2] },
+                    rotation: quat { x: r[0], y: r[1], z: r[2], w: r[3] },
+                    scale: vec3 { x: s[0], y: s[1], z: s[2] },
+                    matrix: matrix
+                },
+                mesh: node.get("mesh").as_usize(),
+                skin: node.get("skin").as_usize()
+            });
+        }
+
+        let mut parents = vec![None; nodes.len()];
+        for (i, node) in nodes.iter().enumerate() {
+            for &child in &node.children {
+                if child >= nodes.len() || parents[child].is_some() || child == i {
+                    return Err(format!("node {} has an invalid child {}", i, child));
+                }
+                parents[child] = Some(i);
+            }
+        }
+
+        let mut order: Vec<usize> = (0..nodes.len()).filter(|&i| parents[i].is_none()).collect();
+        let mut next = 0;
+        while next < order.len() {
+            order.extend(nodes[order[next]].children.iter().cloned());
+            next += 1;
+        }
+        if order.len() != nodes.len() {
+            return Err("the node hierarchy contains a cycle".to_string());
+        }
+
+        Ok(Hierarchy { nodes: nodes, parents: parents, order: order })
+    }
+
+    /// Global transform of every node from their local transforms
+    fn globals(&self, transforms: &[Transform]) -> Vec<mat4> {
+        let mut globals = vec![unsafe { mat4_id() }; self.nodes.len()];
+        for &i in &self.order {
+            let local = transforms[i].local();
+            globals[i] = match self.parents[i] {
+                Some(parent) => unsafe { mat4_mul_mat4(globals[parent], local) },
+                None => local
+            };
+        }
+        globals
+    }
+
+    /// Nodes of the default scene in depth first order
+    fn scene(&self, json: &Json) -> Vec<usize> {
+        let scenes = json.get("scenes");
+        let roots: Vec<usize> = if scenes.members().is_empty() {
+            (0..self.nodes.len()).filter(|&i| self.parents[i].is_none()).collect()
+        } else {
+            let scene = scenes.at(json.get("scene").as_usize().unwrap_or(0));
+            scene.get("nodes").members().iter().filter_map(Json::as_usize).filter(|&i| i < self.nodes.len()).collect()
+        };
+        let mut visited = Vec::new();
+        let mut stack: Vec<usize> = roots.into_iter().rev().collect();
+        while let Some(i) = stack.pop() {
+            visited.push(i);
+            stack.extend(self.nodes[i].children.iter().rev().cloned());
+        }
+        visited
+    }
+}
+
+/// Shading values of a glTF material, mapped onto the deferred material items
+#[derive(Clone)]
+struct Shading {
+    diffuse_map: Option<String>,
+    base_color: [f32; 4],
+    bump_map: Option<String>,
+    normal_scale: f32,
+    roughness: f32,
+    metallic: f32,
+    alpha_cutoff: f32
+}
+
+impl Default for Shading {
+    fn default() -> Shading {
+        Shading {
+            diffuse_map: None,
+            base_color: [1.0, 1.0, 1.0, 1.0],
+            bump_map: None,
+            normal_scale: 1.0,
+            roughness: 1.0,
+            metallic: 1.0,
+            alpha_cutoff: 0.0
+        }
+    }
+}
+
+struct Primitive {
+    positions: Vec<vec3>,
+    normals: Option<Vec<vec3>>,
+    uvs: Vec<vec2>,
+    triangles: Vec<u32>,
+    weights: Option<Vec<vertex_weight>>,
+    shading: usize
+}
+
+struct Joint {
+    name: String,
+    parent: c_int,
+    position: vec3,
+    rotation: quat
+}
+
+struct Clip {
+    name: String,
+    frame_time: f32,
+    frames: Vec<Vec<(vec3, quat)>>
+}
+
+/// Everything needed to build the assets, read without touching GL
+struct Scene {
+    primitives: Vec<Primitive>,
+    shadings: Vec<Shading>,
+    joints: Vec<Joint>,
+    clips: Vec<Clip>
+}
+
+impl Scene {
+    fn rigged(&self) -> bool {
+        !self.joints.is_empty()
+    }
+}
+
+fn import(document: &Document) -> Result<Scene, String> {
+    let json = &document.json;
+    let hierarchy = Hierarchy::new(json)?;
+    let scene_nodes = hierarchy.scene(json);
+    let base: Vec<Transform> = hierarchy.nodes.iter().map(|node| node.transform).collect();
+    let globals = hierarchy.globals(&base);
+
+    let skin = scene_nodes.iter().filter(|&&i| hierarchy.nodes[i].mesh.is_some()).filter_map(|&i| hierarchy.nodes[i].skin).next();
+
+    let mut shadings: Vec<Shading> = json.get("materials").members().iter()
+        .map(|material| shading(document, material))
+        .collect();
+    let default_shading = shadings.len();
+    shadings.push(Shading::default());
+
+    let mut primitives = Vec::new();
+    for &i in &scene_nodes {
+        let node = &hierarchy.nodes[i];
+        let mesh = match node.mesh { Some(mesh) => mesh, None => continue };
+        let name = node.name.clone().unwrap_or_else(|| format!("node {}", i));
+        if node.skin != skin {
+            warn(&format!("skipping {}, only meshes using the first skin are loaded alongside it", name));
+            continue;
+        }
+        let world = if skin.is_some() { None } else { Some(globals[i]) };
+        let mesh = json.get("meshes").at(mesh);
+        for (p, primitive) in mesh.get("primitives").members().iter().enumerate() {
+            if let Some(primitive) = read_primitive(document, primitive, world, skin.is_some(), default_shading)
+                .map_err(|err| format!("{} primitive {}: {}", name, p, err))? {
+                primitives.push(primitive);
+            }
+        }
+    }
+
+    let (joints, clips) = match skin {
+        Some(skin) => read_skin(document, &hierarchy, skin)?,
+        None => {
+            if !json.get("animations").members().is_empty() {
+                warn("skipping animations, only skinned animation is supported");
+            }
+            (Vec::new(), Vec::new())
+        }
+    };
+
+    if primitives.is_empty() {
+        warn("no triangle meshes in the default scene");
+    }
+
+    for primitive in &primitives {
+        if let Some(ref weights) = primitive.weights {
+            if weights.iter().any(|weight| weight.bone_ids.iter().any(|&id| id as usize >= joints.len())) {
+                return Err("vertex weights refer to joints outside the skin".to_string());
+            }
+        }
+    }
+
+    Ok(Scene { primitives: primitives, shadings: shadings, joints: joints, clips: clips })
+}
+
+fn read_primitive(document: &Document, primitive: &Json, world: Option<mat4>, rigged: bool, default_shading: usize) -> Result<Option<Primitive>, String> {
+    let attributes = primitive.get("attributes");
+    let attribute = |name: &str| -> Result<Option<Accessor>, String> {
+        match attributes.get(name).as_usize() {
+            Some(index) => document.accessor(index).map(Some),
+            None => Ok(None)
+        }
+    };
+
+    let mode = primitive.get("mode").as_usize().unwrap_or(4);
+    if mode < 4 {
+        warn("skipping a primitive made of points or lines");
+        return Ok(None);
+    }
+
+    let positions = attribute("POSITION")?.ok_or_else(|| "no POSITION attribute".to_string())?;
+    let count = positions.count;
+    let normals = attribute("NORMAL")?;
+    let uvs = attribute("TEXCOORD_0")?;
+    for accessor in normals.iter().chain(uvs.iter()) {
+        if accessor.count != count {
+            return Err("attributes have different lengths".to_string());
+        }
+    }
+
+    let indices: Vec<u32> = match primitive.get("indices").as_usize() {
+        Some(index) => {
+            let indices = document.accessor(index)?;
+            (0..indices.count).map(|i| indices.values[i] as u32).collect()
+        }
+        None => (0..count as u32).collect()
+    };
+    if indices.iter().any(|&index| index as usize >= count) {
+        return Err("indices refer to vertices past the end".to_string());
+    }
+
+    let mut triangles = Vec::with_capacity(indices.len());
+    match mode {
+        4 => triangles.extend_from_slice(&indices[..indices.len() / 3 * 3]),
+        5 => for i in 2..indices.len() {
+            if i % 2 == 0 {
+                triangles.extend_from_slice(&[indices[i - 2], indices[i - 1], indices[i]]);
+            } else {
+                triangles.extend_from_slice(&[indices[i - 1], indices[i - 2], indices[i]]);
+            }
+        },
+        6 => for i in 2..indices.len() {
+            triangles.extend_from_slice(&[indices[0], indices[i - 1], indices[i]]);
+        },
+        _ => return Err(format!("unknown primitive mode {}", mode))
+    }
+    if triangles.is_empty() {
+        return Ok(None);
+    }
+
+    let mut positions: Vec<vec3> = (0..count).map(|i| positions.vec3(i)).collect();
+    let mut normals = normals.map(|normals| (0..count).map(|i| normals.vec3(i)).collect::<Vec<vec3>>());
+    // glTF puts the texture origin at the top left, Corange at the bottom left like .obj
+    let uvs = match uvs {
+        Some(uvs) => (0..count).map(|i| { let uv = uvs.vec2(i); vec2 { x: uv.x, y: 1.0 - uv.y } }).collect(),
+        None => vec![vec2 { x: 0.0, y: 0.0 }; count]
+    };
+
+    if let Some(world) = world {
+        unsafe {
+            let normal_world = mat3_transpose(mat3_inverse(mat4_to_mat3(world)));
+            for position in &mut positions {
+                *position = mat4_mul_vec3(world, *position);
+            }
+            if let Some(ref mut normals) = normals {
+                for normal in normals.iter_mut() {
+                    *normal = vec3_normalize(mat3_mul_vec3(normal_world, *normal));
+                }
+            }
+            if mat4_det(world) < 0.0 {
+                for triangle in triangles.chunks_mut(3) {
+                    triangle.swap(1, 2);
+                }
+            }
+        }
+    }
+
+    let weights = if rigged { Some(read_weights(&attribute, count)?) } else { None };
+
+    let shading = primitive.get("material").as_usize().filter(|&material| material < default_shading).unwrap_or(default_shading);
+
+    Ok(Some(Primitive {
+        positions: positions,
+        normals: normals,
+        uvs: uvs,
+        triangles: triangles,
+        weights: weights,
+        shading: shading
+    }))
+}
+
+/// The three strongest joint influences of every vertex, renormalized
+fn read_weights<F: Fn(&str) -> Result<Option<Accessor>, String>>(attribute: &F, count: usize) -> Result<Vec<vertex_weight>, String> {
+    let mut sets = Vec::new();
+    for set in 0.. {
+        match (attribute(&format!("JOINTS_{}", set))?, attribute(&format!("WEIGHTS_{}", set))?) {
+            (Some(joints), Some(weights)) => {
+                if joints.count != count || weights.count != count || joints.components != 4 || weights.components != 4 {
+                    return Err(format!("JOINTS_{0} and WEIGHTS_{0} don't match the vertices", set));
+                }
+                sets.push((joints, weights));
+            }
+            _ => break
+        }
+    }
+    if sets.is_empty() {
+        return Err("skinned mesh without JOINTS_0 and WEIGHTS_0".to_string());
+    }
+
+    Ok((0..count).map(|i| {
+        let mut influences: Vec<(f32, c_int)> = sets.iter()
+            .flat_map(|(joints, weights)| (0..4).map(move |c| (weights.get(i, c), joints.get(i, c) as c_int)))
+            .filter(|&(weight, _)| weight > 0.0)
+            .collect();
+        influences.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(::std::cmp::Ordering::Equal));
+        influences.truncate(3);
+
+        let total: f32 = influences.iter().map(|&(weight, _)| weight).sum();
+        let mut weight = vertex_weight::default();
+        if total <= 0.0 {
+            weight.bone_weights[0] = 1.0;
+        }
+        for (slot, &(amount, joint)) in influences.iter().enumerate() {
+            weight.bone_ids[slot] = joint;
+            weight.bone_weights[slot] = amount / total;
+        }
+        weight
+    }).collect())
+}
+
+fn shading(document: &Document, material: &Json) -> Shading {
+    let pbr = material.get("pbrMetallicRoughness");
+    let mut shading = Shading::default();
+    if let Some(color) = pbr.get("baseColorFactor").as_f32_vec() {
+        for (c, value) in color.into_iter().take(4).enumerate() { shading.base_color[c] = value; }
+    }
+    shading.roughness = pbr.get("roughnessFactor").as_f32().unwrap_or(1.0);
+    shading.metallic = pbr.get("metallicFactor").as_f32().unwrap_or(1.0);
+    shading.diffuse_map = texture(document, pbr.get("baseColorTexture"));
+    shading.bump_map = texture(document, material.get("normalTexture"));
+    shading.normal_scale = material.get("normalTexture").get("scale").as_f32().unwrap_or(1.0);
+    if material.get("alphaMode").as_str() == Some("MASK") {
+        shading.alpha_cutoff = material.get("alphaCutoff").as_f32().unwrap_or(0.5);
+    }
+    shading
+}
+
+/// Path of a texture's image if a registered texture loader can read it
+fn texture(document: &Document, info: &Json) -> Option<String> {
+    let index = info.get("index").as_usize()?;
+    let source = document.json.get("textures").at(index).get("source").as_usize()?;
+    let image = document.json.get("images").at(source);
+    let uri = match image.get("uri").as_str() {
+        Some(uri) if !uri.starts_with("data:") => uri,
+        _ => {
+            warn(&format!("image {} is embedded, which is not supported yet", source));
+            return None;
+        }
+    };
+    let path = document.path(uri);
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("").to_lowercase();
+    if !TEXTURE_EXTENSIONS.contains(&extension.as_str()) {
+        warn(&format!("{}: no texture loader for .{} images", path.display(), extension));
+        return None;
+    }
+    Some(path.to_string_lossy().into_owned())
+}
+
+fn read_skin(document: &Document, hierarchy: &Hierarchy, skin: usize) -> Result<(Vec<Joint>, Vec<Clip>), String> {
+    let json = document.json.get("skins").at(skin);
+    let nodes = json.get("joints").members().iter()
+        .map(|joint| joint.as_usize().filter(|&joint| joint < hierarchy.nodes.len()))
+        .collect::<Option<Vec<usize>>>()
+        .ok_or_else(|| format!("skin {} has invalid joints", skin))?;
+    if nodes.is_empty() {
+        return Err(format!("skin {} has no joints", skin));
+    }
+    if nodes.len() > MAX_JOINTS {
+        warn(&format!("skin {} has {} joints, the renderer supports {}", skin, nodes.len(), MAX_JOINTS));
+    }
+
+    // Nearest ancestor that is also a joint of the skin
+    let parents: Vec<c_int> = nodes.iter().map(|&node| {
+        let mut ancestor = hierarchy.parents[node];
+        while let Some(a) = ancestor {
+            if let Some(joint) = nodes.iter().position(|&n| n == a) {
+                return joint as c_int;
+            }
+            ancestor = hierarchy.parents[a];
+        }
+        -1
+    }).collect();
+
+    // The rest pose is the bind pose, so the renderer's bone matrices are exactly the glTF joint matrices
+    let rest: Vec<mat4> = match json.get("inverseBindMatrices").as_usize() {
+        Some(index) => {
+            let matrices = document.accessor(index)?;
+            if matrices.count < nodes.len() || matrices.components != 16 {
+                return Err(format!("skin {} has too few inverse bind matrices", skin));
+            }
+            (0..nodes.len()).map(|j| unsafe { mat4_inverse(matrices.mat4(j)) }).collect()
+        }
+        None => vec![unsafe { mat4_id() }; nodes.len()]
+    };
+
+    let joints = joint_pose(&rest, &parents).into_iter().enumerate().map(|(j, (position, rotation))| Joint {
+        name: hierarchy.nodes[nodes[j]].name.clone().unwrap_or_else(|| format!("joint{}", j)),
+        parent: parents[j],
+        position: position,
+        rotation: rotation
+    }).collect();
+
+    let mut clips: Vec<Clip> = Vec::new();
+    for (i, animation) in document.json.get("animations").members().iter().enumerate() {
+        let mut clip = read_animation(document, hierarchy, animation, &nodes, &parents)
+            .map_err(|err| format!("animation {}: {}", i, err))?;
+        clip.name = animation.get("name").as_str().map(sanitize).filter(|name| !name.is_empty()).unwrap_or_else(|| i.to_string());
+        if clips.iter().any(|other| other.name == clip.name) {
+            clip.name = format!("{}_{}", clip.name, i);
+        }
+        clips.push(clip);
+    }
+
+    Ok((joints, clips))
+}
+
+/// Keep names usable as part of an asset path
+fn sanitize(name: &str) -> String {
+    name.chars().map(|c| if c.is_alphanumeric() || c == '_' || c == '-' { c } else { '_' }).collect()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Interpolation {
+    Linear,
+    Step,
+    CubicSpline
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Target {
+    Translation,
+    Rotation,
+    Scale
+}
+
+struct Channel {
+    node: usize,
+    path: Target,
+    interpolation: Interpolation,
+    times: Vec<f32>,
+    values: Accessor
+}
+
+impl Channel {
+    /// Interpolated value at `time`, clamped to the keyframes
+    fn sample(&self, time: f32) -> [f32; 4] {
+        let n = self.values.components;
+        let keys = self.times.len();
+        let key = |k: usize, part: usize| -> [f32; 4] {
+            let i = if self.interpolation == Interpolation::CubicSpline { k * 3 + part } else { k };
+            let mut value = [0.0; 4];
+            for (c, v) in value.iter_mut().enumerate().take(n) { *v = self.values.get(i, c); }
+            value
+        };
+
+        let next = self.times.iter().position(|&t| t > time).unwrap_or(keys);
+        if next == 0 { return key(0, 1); }
+        if next == keys { return key(keys - 1, 1); }
+        let k = next - 1;
+        let span = self.times[next] - self.times[k];
+        let amount = if span > 0.0 { (time - self.times[k]) / span } else { 0.0 };
+
+        let mut value = match self.interpolation {
+            Interpolation::Step => key(k, 1),
+            Interpolation::Linear if self.path == Target::Rotation => unsafe {
+                let (a, b) = (key(k, 1), key(next, 1));
+                let q = quat_slerp(quat_new(a[0], a[1], a[2], a[3]), quat_new(b[0], b[1], b[2], b[3]), amount);
+                [q.x, q.y, q.z, q.w]
+            },
+            Interpolation::Linear => {
+                let (a, b) = (key(k, 1), key(next, 1));
+                let mut value = [0.0; 4];
+                for c in 0..4 { value[c] = a[c] + (b[c] - a[c]) * amount; }
+                value
+            }
+            Interpolation::CubicSpline => {
+                let (t, t2, t3) = (amount, amount * amount, amount * amount * amount);
+                let (v0, b0, a1, v1) = (key(k, 1), key(k, 2), key(next, 0), key(next, 1));
+                let mut value = [0.0; 4];
+                for c in 0..4 {
+                    value[c] = (2.0 * t3 - 3.0 * t2 + 1.0) * v0[c] + (t3 - 2.0 * t2 + t) * span * b0[c]
+                        + (-2.0 * t3 + 3.0 * t2) * v1[c] + (t3 - t2) * span * a1[c];
+                }
+                value
+            }
+        };
+        if self.path == Target::Rotation {
+            let length = value.iter().map(|c| c * c).sum::<f32>().sqrt();
+            if length > 0.0 { for c in &mut value { *c /= length; } }
+        }
+        value
+    }
+}
+
+fn read_animation(document: &Document, hierarchy: &Hierarchy, animation: &Json, nodes: &[usize], parents: &[c_int]) -> Result<Clip, String> {
+    let samplers = animation.get("samplers");
+    let mut channels = Vec::new();
+    for (i, channel) in animation.get("channels").members().iter().enumerate() {
+        let target = channel.get("target");
+        let node = match target.get("node").as_usize() {
+            Some(node) if node < hierarchy.nodes.len() => node,
+            _ => continue
+        };
+        let path = match target.get("path").as_str() {
+            Some("translation") => Target::Translation,
+            Some("rotation") => Target::Rotation,
+            Some("scale") => Target::Scale,
+            _ => continue
+        };
+        let sampler = samplers.at(channel.get("sampler").as_usize().unwrap_or(usize::MAX));
+        let interpolation = match sampler.get("interpolation").as_str().unwrap_or("LINEAR") {
+            "LINEAR" => Interpolation::Linear,
+            "STEP" => Interpolation::Step,
+            "CUBICSPLINE" => Interpolation::CubicSpline,
+            other => return Err(format!("channel {} has unknown interpolation {}", i, other))
+        };
+        let input = sampler.get("input").as_usize().ok_or_else(|| format!("channel {} has no sampler input", i))?;
+        let output = sampler.get("output").as_usize().ok_or_else(|| format!("channel {} has no sampler output", i))?;
+        let input = document.accessor(input)?;
+        let values = document.accessor(output)?;
+        let times: Vec<f32> = (0..input.count).map(|k| input.get(k, 0)).collect();
+        let expected = if interpolation == Interpolation::CubicSpline { times.len() * 3 } else { times.len() };
+        let components = if path == Target::Rotation { 4 } else { 3 };
+        if times.is_empty() || values.count != expected || values.components != components {
+            return Err(format!("channel {} has mismatched keyframes", i));
+        }
+        channels.push(Channel { node: node, path: path, interpolation: interpolation, times: times, values: values });
+    }
+
+    let duration = channels.iter().map(|channel| *channel.times.last().unwrap()).fold(0.0, f32::max);
+    let intervals = (duration * ANIMATION_RATE - 1e-3).ceil().max(0.0) as usize;
+    let frame_time = if intervals > 0 { duration / intervals as f32 } else { 1.0 / ANIMATION_RATE };
+
+    let base: Vec<Transform> = hierarchy.nodes.iter().map(|node| node.transform).collect();
+    let frames = (0..intervals + 1).map(|f| {
+        let time = f as f32 * frame_time;
+        let mut transforms = base.clone();
+        for channel in &channels {
+            let value = channel.sample(time);
+            let transform = &mut transforms[channel.node];
+            transform.matrix = None;
+            match channel.path {
+                Target::Translation => transform.translation = vec3 { x: value[0], y: value[1], z: value[2] },
+                Target::Rotation => transform.rotation = quat { x: value[0], y: value[1], z: value[2], w: value[3] },
+                Target::Scale => transform.scale = vec3 { x: value[0], y: value[1], z: value[2] }
+            }
+        }
+        let globals = hierarchy.globals(&transforms);
+        let joint_globals: Vec<mat4> = nodes.iter().map(|&node| globals[node]).collect();
+        joint_pose(&joint_globals, parents)
+    }).collect();
+
+    Ok(Clip { name: String::new(), frame_time: frame_time, frames: frames })
+}
+
+/// Create the renderable and its derived assets, recording their paths in `derived`
+unsafe fn build(imported: &Imported, filename: &str, derived: &mut Vec<String>) -> *mut renderable {
+    let scene = &imported.scene;
+    let r = renderable_new();
+    (*r).is_rigged = scene.rigged() as u8;
+
+    for (primitive, &m) in scene.primitives.iter().zip(&imported.meshes) {
+        let surface = match primitive.weights {
+            Some(ref weights) => {
+                let surface = renderable_surface_new_rigged(m, weights.as_ptr() as *mut vertex_weight);
+                (*surface).bound = mesh_bounding_sphere(m);
+                surface
+            }
+            None => renderable_surface_new(m)
+        };
+
+        (*r).num_surfaces += 1;
+        (*r).surfaces = libc::realloc((*r).surfaces as *mut c_void,
+            mem::size_of::<*mut renderable_surface>() * (*r).num_surfaces as usize) as *mut *mut renderable_surface;
+        *(*r).surfaces.offset((*r).num_surfaces as isize - 1) = surface;
+    }
+
+    (*r).material = build_material(scene, filename, derived);
+
+    if scene.rigged() {
+        let skeleton = skeleton_new();
+        for (j, joint) in scene.joints.iter().enumerate() {
+            let name = CString::new(joint.name.replace('\0', "")).unwrap();
+            skeleton_joint_add(skeleton, name.as_ptr() as *mut c_char, joint.parent);
+            *(*(*skeleton).rest_pose).joint_positions.offset(j as isize) = joint.position;
+            *(*(*skeleton).rest_pose).joint_rotations.offset(j as isize) = joint.rotation;
+        }
+        frame_gen_inv_transforms((*skeleton).rest_pose);
+        add_derived(format!("{}.skl", filename), skeleton as *mut asset, derived);
+
+        for clip in &scene.clips {
+            let a = animation_new();
+            (*a).frame_time = clip.frame_time;
+            for pose in &clip.frames {
+                let f = animation_add_frame(a, (*skeleton).rest_pose);
+                for (j, &(position, rotation)) in pose.iter().enumerate() {
+                    *(*f).joint_positions.offset(j as isize) = position;
+                    *(*f).joint_rotations.offset(j as isize) = rotation;
+                }
+            }
+            add_derived(format!("{}.{}.ani", filename, clip.name), a as *mut asset, derived);
+        }
+    }
+
+    r
+}
+
+unsafe fn add_derived(path: String, a: *mut asset, derived: &mut Vec<String>) -> asset_hndl {
+    let cpath = CString::new(path.clone()).unwrap();
+    file_add(P(cpath.as_ptr()), a);
+    derived.push(path);
+    asset_hndl_new(P(cpath.as_ptr()))
+}
+
+unsafe fn mesh_from(primitive: &Primitive) -> *mut mesh {
+    let m = mesh_new();
+    (*m).num_verts = primitive.positions.len() as c_int;
+    (*m).num_triangles = (primitive.triangles.len() / 3) as c_int;
+    (*m).verticies = libc::realloc((*m).verticies as *mut c_void,
+        mem::size_of::<vertex>() * primitive.positions.len()) as *mut vertex;
+    (*m).triangles = libc::realloc((*m).triangles as *mut c_void,
+        mem::size_of::<u32>() * primitive.triangles.len()) as *mut u32;
+
+    for (i, &position) in primitive.positions.iter().enumerate() {
+        *(*m).verticies.offset(i as isize) = vertex {
+            position: position,
+            normal: primitive.normals.as_ref().map_or(vec3_zero(), |normals| normals[i]),
+            tangent: vec3_zero(),
+            binormal: vec3_zero(),
+            color: vec4_one(),
+            uvs: primitive.uvs[i]
+        };
+    }
+    ptr::copy_nonoverlapping(primitive.triangles.as_ptr(), (*m).triangles, primitive.triangles.len());
+
+    if primitive.normals.is_none() {
+        mesh_generate_normals(m);
+    }
+    // Generated like the other loaders do, so they follow the engine's tangent convention
+    mesh_generate_tangents(m);
+    m
+}
+
+/// One material entry per surface, in the layout of the .mat files the deferred renderer reads
+unsafe fn build_material(scene: &Scene, filename: &str, derived: &mut Vec<String>) -> asset_hndl {
+    let vertex_shader = if scene.rigged() { "$CORANGE/shaders/deferred/animated.vs" } else { "$CORANGE/shaders/deferred/static.vs" };
+    let fragment_shader = "$CORANGE/shaders/deferred/static.fs";
+    let white = "$CORANGE/textures/white.dds";
+
+    let mut solid: HashMap<usize, asset_hndl> = HashMap::new();
+    let m = material_new();
+    for primitive in &scene.primitives {
+        let shading = &scene.shadings[primitive.shading];
+        let me = material_add_entry(m);
+
+        let vertex = load(vertex_shader);
+        let fragment = load(fragment_shader);
+        add_item(me, "vertex", mat_item_shader, |item| *item.as_asset() = vertex);
+        add_item(me, "fragment", mat_item_shader, |item| *item.as_asset() = fragment);
+
+        let diffuse = match shading.diffuse_map {
+            Some(ref path) => load(path),
+            None if shading.base_color.iter().all(|&c| c >= 1.0) => load(white),
+            None => *solid.entry(primitive.shading).or_insert_with(|| {
+                let path = format!("{}.{}.diffuse.dds", filename, primitive.shading);
+                add_derived(path, solid_texture(shading.base_color) as *mut asset, derived)
+            })
+        };
+        // Corange mixes towards a flat normal by the bumpiness, so a bumpiness of one disables the bump map
+        let (bump, bumpiness) = match shading.bump_map {
+            Some(ref path) => (load(path), 1.0 - shading.normal_scale),
+            None => (load(white), 1.0)
+        };
+        let smoothness = 1.0 - shading.roughness.clamp(0.0, 1.0);
+
+        add_item(me, "diffuse_map", mat_item_texture, |item| *item.as_asset() = diffuse);
+        add_item(me, "bump_map", mat_item_texture, |item| *item.as_asset() = bump);
+        add_item(me, "spec_map", mat_item_texture, |item| *item.as_asset() = load(white));
+        add_item(me, "glossiness", mat_item_float, |item| *item.as_float() = 1.0 + 99.0 * smoothness * smoothness);
+        add_item(me, "bumpiness", mat_item_float, |item| *item.as_float() = bumpiness);
+        add_item(me, "specular_level", mat_item_float, |item| *item.as_float() = 0.25 + 0.75 * smoothness);
+        add_item(me, "alpha_test", mat_item_float, |item| *item.as_float() = shading.alpha_cutoff);
+        // Reflective materials of compose.fs, major for metals and minor for everything else
+        add_item(me, "material", mat_item_int, |item| *item.as_int() = if shading.metallic >= 0.5 { 3 } else { 2 });
+
+        let mut vertex = vertex;
+        let mut fragment = fragment;
+        (*me).program = shader_program_new();
+        shader_program_attach_shader((*me).program, asset_hndl_ptr(&mut vertex) as *mut shader);
+        shader_program_attach_shader((*me).program, asset_hndl_ptr(&mut fragment) as *mut shader);
+        shader_program_link((*me).program);
+    }
+
+    add_derived(format!("{}.mat", filename), m as *mut asset, derived)
+}
+
+unsafe fn load(path: &str) -> asset_hndl {
+    let path = CString::new(path).unwrap();
+    asset_hndl_new_load(P(path.as_ptr()))
+}
+
+unsafe fn add_item<F: FnOnce(&mut material_item)>(me: *mut material_entry, name: &str, kind: c_int, set: F) {
+    let mut item = material_item::default();
+    set(&mut item);
+    let name = CString::new(name).unwrap();
+    material_entry_add_item(me, name.as_ptr() as *mut c_char, kind, item);
+}
+
+/// Single texel texture of a linear color, stored gamma encoded like image files are
+unsafe fn solid_texture(color: [f32; 4]) -> *mut texture {
+    let mut data: Vec<u8> = color.iter().enumerate().map(|(c, &value)| {
+        let value = value.clamp(0.0, 1.0);
+        let value = if c < 3 { value.powf(1.0 / 2.2) } else { value };
+        (value * 255.0 + 0.5) as u8
+    }).collect();
+    let i = image_new(1, 1, data.as_mut_ptr());
+    let t = texture_new();
+    texture_set_image(t, i);
+    texture_set_filtering_nearest(t);
+    image_delete(i);
+    t
+}
@@ -0,0 +1,302 @@
+//! Reference-counted asset handles and unloading the assets nobody holds
+//!
+//! The engine's `asset_hndl` is a plain struct copied around freely, so nothing knows when an
+//! asset stops being used. A `Handle` counts its clones instead, and `holders` finds the rest:
+//! entities with handles in their fields and loaded assets using others, like a mesh using its
+//! material and the material its textures and shaders. `unload_unused` unloads whatever none of
+//! these reach, e.g. the textures and meshes of a level whose entities were deleted.
+//!
+//! Other engine objects aren't searched. Hold the assets given to UI elements or kept behind raw
+//! pointers with a `Handle`. The core assets and the renderer's and render targets' assets are
+//! always kept.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::ptr;
+use std::sync::Mutex;
+use corange::*;
+use engine;
+use hot_reload;
+
+lazy_static! {
+    // Handles per full path
+    static ref COUNTS: Mutex<HashMap<String, usize>> = Mutex::new(HashMap::new());
+}
+
+fn mapped(filename: &str) -> String {
+    unsafe {
+        let filename = CString::new(filename).unwrap();
+        let mapped = asset_map_filename(P(filename.as_ptr()));
+        CStr::from_ptr(mapped.ptr.as_ptr()).to_string_lossy().into_owned()
+    }
+}
+
+fn hndl_path(hndl: &asset_hndl) -> Option<String> {
+    if hndl.path.ptr[0] == 0 {
+        return None;
+    }
+    unsafe { Some(CStr::from_ptr(hndl.path.ptr.as_ptr()).to_string_lossy().into_owned()) }
+}
+
+/// A counted hold on the asset at a path, which keeps it from `unload_unused`
+pub struct Handle {
+    path: String
+}
+
+impl Handle {
+    /// Hold the asset at `filename`, which needn't be loaded yet
+    pub fn new(filename: &str) -> Handle {
+        let path = mapped(filename);
+        *COUNTS.lock().unwrap().entry(path.clone()).or_insert(0) += 1;
+        Handle { path: path }
+    }
+
+    /// Hold the asset at `filename`, loading it first if it isn't loaded
+    pub fn load(filename: &str) -> Handle {
+        let handle = Handle::new(filename);
+        if !handle.is_loaded() {
+            let path = CString::new(handle.path.as_str()).unwrap();
+            unsafe { file_load(P(path.as_ptr())); }
+        }
+        handle
+    }
+
+    /// Full path of the asset
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        let path = CString::new(self.path.as_str()).unwrap();
+        unsafe { file_isloaded(P(path.as_ptr())) != 0 }
+    }
+
+    /// An engine handle to the asset, e.g. for an entity's `renderable`. Its copies aren't
+    /// counted, `holders` finds the entities and assets they are stored in.
+    pub fn hndl(&self) -> asset_hndl {
+        let path = CString::new(self.path.as_str()).unwrap();
+        unsafe { asset_hndl_new(P(path.as_ptr())) }
+    }
+}
+
+impl Clone for Handle {
+    fn clone(&self) -> Handle {
+        *COUNTS.lock().unwrap().get_mut(&self.path).unwrap() += 1;
+        Handle { path: self.path.clone() }
+    }
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        let mut counts = COUNTS.lock().unwrap();
+        let count = counts.get_mut(&self.path).unwrap();
+        *count -= 1;
+        if *count == 0 {
+            counts.remove(&self.path);
+        }
+    }
+}
+
+impl fmt::Debug for Handle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Handle({})", self.path)
+    }
+}
+
+/// Handles held on `filename`
+pub fn handle_count(filename: &str) -> usize {
+    COUNTS.lock().unwrap().get(&mapped(filename)).cloned().unwrap_or(0)
+}
+
+/// What keeps an asset loaded
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Holder {
+    /// Named entity with a handle to it in its fields
+    Entity(String),
+    /// Loaded asset using it, by full path
+    Asset(String),
+    /// Number of `Handle`s on it
+    Handles(usize),
+    /// A core asset, or used by the renderer or a render target
+    Engine
+}
+
+impl fmt::Display for Holder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Holder::Entity(ref name) => write!(f, "entity {}", name),
+            Holder::Asset(ref path) => write!(f, "{}", path),
+            Holder::Handles(1) => write!(f, "1 handle"),
+            Holder::Handles(count) => write!(f, "{} handles", count),
+            Holder::Engine => write!(f, "the engine")
+        }
+    }
+}
+
+/// Handles in the fields of every entity of the types storing them
+fn entity_handles() -> Vec<(String, asset_hndl)> {
+    type Fields = fn(*mut entity) -> Vec<asset_hndl>;
+    let types: [(i32, Fields); 7] = unsafe {[
+        (*STATIC_TYPE, |e| { let e = e as *mut static_object; vec![(*e).renderable, (*e).collision_body] }),
+        (*ANIMATED_TYPE, |e| { let e = e as *mut animated_object; vec![(*e).renderable, (*e).animation, (*e).skeleton] }),
+        (*PHYSICS_TYPE, |e| { let e = e as *mut physics_object; vec![(*e).renderable, (*e).collision_body] }),
+        (*INSTANCE_TYPE, |e| { let e = e as *mut instance_object; vec![(*e).renderable, (*e).collision_body] }),
+        (*PARTICLES_TYPE, |e| vec![(*(e as *mut particles)).effect]),
+        (*LIGHT_TYPE, |e| vec![(*(e as *mut light)).cookie]),
+        (*LANDSCAPE_TYPE, |e| {
+            let l = &*(e as *mut landscape);
+            vec![l.heightmap, l.attribmap, l.ground0, l.ground1, l.ground2, l.ground3,
+                 l.ground0_nm, l.ground1_nm, l.ground2_nm, l.ground3_nm]
+        })
+    ]};
+
+    let mut handles = Vec::new();
+    for &(type_id, fields) in types.iter() {
+        unsafe {
+            let mut entities = vec![ptr::null_mut(); entity_type_count_type_id(type_id) as usize];
+            let mut returned = 0;
+            entities_get_type_id(entities.as_mut_ptr(), &mut returned, type_id);
+            for &e in entities.iter().take(returned as usize) {
+                let name = CStr::from_ptr(entity_name(e)).to_string_lossy().into_owned();
+                handles.extend(fields(e).into_iter().map(|hndl| (name.clone(), hndl)));
+            }
+        }
+    }
+    handles
+}
+
+/// Handles stored in a loaded asset
+fn asset_handles(path: &str) -> Vec<asset_hndl> {
+    unsafe {
+        let filename = CString::new(path).unwrap();
+        let a = asset_get(P(filename.as_ptr()));
+        if a.is_null() {
+            return Vec::new();
+        }
+        let typename = asset_ptr_typename(a);
+        if typename.is_null() {
+            return Vec::new();
+        }
+        match CStr::from_ptr(typename).to_bytes() {
+            b"renderable" => vec![(*(a as *mut renderable)).material],
+            b"font" => vec![(*(a as *mut font)).texture_map],
+            b"effect" => vec![(*(a as *mut effect)).texture, (*(a as *mut effect)).texture_nm],
+            b"material" => {
+                let m = &*(a as *mut material);
+                let mut handles = Vec::new();
+                for i in 0..m.num_entries as isize {
+                    let entry = &**m.entries.offset(i);
+                    for j in 0..entry.num_items as isize {
+                        let kind = *entry.types.offset(j);
+                        if kind == mat_item_shader || kind == mat_item_texture {
+                            handles.push(*(*entry.items.offset(j)).as_asset());
+                        }
+                    }
+                }
+                handles
+            }
+            _ => Vec::new()
+        }
+    }
+}
+
+/// Full paths of the assets the engine keeps
+fn engine_paths() -> Vec<String> {
+    let renderer = engine::RENDERER.lock().unwrap().clone();
+    let mut paths = vec![renderer.configuration];
+    paths.extend(renderer.color_correction_texture);
+    paths.extend(renderer.vignetting_texture);
+    paths.extend(engine::RENDER_TARGETS.lock().unwrap().iter().map(|target| target.path.clone()));
+    paths.iter().map(|path| mapped(path)).collect()
+}
+
+/// What holds each loaded asset, and which assets each loaded asset uses
+fn graph() -> (BTreeMap<String, Vec<Holder>>, BTreeMap<String, Vec<String>>) {
+    let loaded = hot_reload::loaded_paths();
+    let mut holders: BTreeMap<String, Vec<Holder>> = loaded.iter().map(|path| (path.clone(), Vec::new())).collect();
+    let mut uses = BTreeMap::new();
+
+    let core = format!("{}/", mapped("$CORANGE"));
+    let engine = engine_paths();
+    let counts = COUNTS.lock().unwrap().clone();
+    for (path, list) in holders.iter_mut() {
+        if path.starts_with(&core) || engine.contains(path) {
+            list.push(Holder::Engine);
+        }
+        if let Some(&count) = counts.get(path) {
+            list.push(Holder::Handles(count));
+        }
+    }
+    for (name, hndl) in entity_handles() {
+        if let Some(list) = hndl_path(&hndl).and_then(|path| holders.get_mut(&path)) {
+            list.push(Holder::Entity(name));
+        }
+    }
+    for path in &loaded {
+        let used: Vec<String> = asset_handles(path).iter().filter_map(hndl_path).filter(|used| holders.contains_key(used)).collect();
+        for used in &used {
+            holders.get_mut(used).unwrap().push(Holder::Asset(path.clone()));
+        }
+        uses.insert(path.clone(), used);
+    }
+
+    for list in holders.values_mut() {
+        list.sort();
+        list.dedup();
+    }
+    (holders, uses)
+}
+
+/// Every loaded asset by full path, with what holds it
+pub fn holders() -> BTreeMap<String, Vec<Holder>> {
+    graph().0
+}
+
+/// One line per loaded asset listing its holders, for a log
+pub fn report() -> String {
+    holders().iter().map(|(path, holders)| {
+        if holders.is_empty() {
+            format!("{}: unused\n", path)
+        } else {
+            format!("{}: {}\n", path, holders.iter().map(|holder| holder.to_string()).collect::<Vec<_>>().join(", "))
+        }
+    }).collect()
+}
+
+/// Loaded assets that no entity, handle or the engine reaches, directly or through other assets
+pub fn unused() -> Vec<String> {
+    let (holders, uses) = graph();
+    unreached(&holders, &uses)
+}
+
+/// Assets of `holders` that nothing but other assets holds and no asset held otherwise reaches
+/// through `uses`, which lists the assets each asset uses
+pub fn unreached(holders: &BTreeMap<String, Vec<Holder>>, uses: &BTreeMap<String, Vec<String>>) -> Vec<String> {
+    let mut kept: HashSet<&str> = HashSet::new();
+    let mut stack: Vec<&str> = holders.iter()
+        .filter(|&(_, list)| list.iter().any(|holder| !matches!(*holder, Holder::Asset(_))))
+        .map(|(path, _)| path.as_str())
+        .collect();
+    while let Some(path) = stack.pop() {
+        if kept.insert(path) {
+            stack.extend(uses.get(path).into_iter().flat_map(|used| used.iter().map(|used| used.as_str())));
+        }
+    }
+    holders.keys().filter(|path| !kept.contains(path.as_str())).cloned().collect()
+}
+
+/// Unload the assets `unused` lists, returning their paths. Engine handles to them must not be
+/// used afterwards, `asset_hndl_ptr` calls `error` on a missing asset, which exits the program.
+pub fn unload_unused() -> Vec<String> {
+    let unused = unused();
+    unsafe {
+        for path in &unused {
+            let path = CString::new(path.as_str()).unwrap();
+            file_unload(P(path.as_ptr()));
+        }
+        // Handles cache the asset pointer until the cache is flushed
+        asset_cache_flush();
+    }
+    unused
+}
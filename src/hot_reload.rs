@@ -0,0 +1,370 @@
+//! Reloading assets when their files change on disk
+//!
+//! A `Watcher` watches the folders of loaded assets with inotify and reloads the files written
+//! there once they have settled. The engine's loaders exit on a broken file, so files are checked
+//! before reloading: one that doesn't check keeps its loaded asset and the `Reload` carries the
+//! error. Reloading a shader also reloads the loaded materials using it, relinking their programs.
+//!
+//! `engine::enable_hot_reload` polls a watcher between frames of the main loop.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::mem;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use libc::{c_char, c_int};
+use corange::*;
+use dds;
+use formats::ani::Ani;
+use formats::bmf::Bmf;
+use formats::cfg::Cfg;
+use formats::col::Col;
+use formats::effect::Effect;
+use formats::lang::Lang;
+use formats::mat::{Mat, Value};
+use formats::skl::Skl;
+use gltf;
+use image_formats;
+
+/// Time a file has to go without writes before it is reloaded, as editors often save in steps
+const SETTLE_TIME: Duration = Duration::from_millis(100);
+
+/// How often the folders of newly loaded assets are added to the watch
+const RESCAN_TIME: Duration = Duration::from_secs(1);
+
+/// Extensions of the shader loaders
+pub const SHADER_EXTENSIONS: &'static [&'static str] = &["vs", "fs", "gs", "tcs", "tes"];
+
+/// Size of the buffer receiving shader compiler logs
+const LOG_SIZE: usize = 2048;
+
+/// A changed file and what reloading it did
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reload {
+    /// Full path of the changed file
+    pub path: String,
+    /// Assets reloaded along with it, like the materials using a shader
+    pub dependents: Vec<String>,
+    /// Why the file was left loaded as it was
+    pub result: Result<(), String>
+}
+
+impl Reload {
+    /// One line describing the reload, for a toast or a log
+    pub fn message(&self) -> String {
+        let name = Path::new(&self.path).file_name().map_or(self.path.clone(), |name| name.to_string_lossy().into_owned());
+        match self.result {
+            Ok(()) if self.dependents.is_empty() => format!("Reloaded {}", name),
+            Ok(()) => format!("Reloaded {} and {} assets using it", name, self.dependents.len()),
+            Err(ref err) => {
+                // Errors usually start with the path, which the name already gives
+                let prefix = format!("{}: ", self.path);
+                let err = if err.starts_with(&prefix) { &err[prefix.len()..] } else { &err[..] };
+                format!("Kept the loaded {}, {}", name, err)
+            }
+        }
+    }
+}
+
+/// Show `reload` in a UI toast, a ready made callback for `engine::enable_hot_reload`
+pub fn toast(reload: &Reload) {
+    let message = CString::new(reload.message().replace('\0', "")).unwrap();
+    unsafe { ui_toast_popup(str("%s"), message.as_ptr()); }
+}
+
+thread_local! {
+    // Paths gathered by `collect_path`
+    static PATHS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+unsafe extern "C" fn collect_path(path: *mut c_char) {
+    let path = CStr::from_ptr(path).to_string_lossy().into_owned();
+    PATHS.with(|paths| paths.borrow_mut().push(path));
+}
+
+/// Full paths of every loaded asset, sorted
+pub fn loaded_paths() -> Vec<String> {
+    unsafe { asset_map_paths(Some(collect_path)); }
+    let mut paths = PATHS.with(|paths| mem::take(&mut *paths.borrow_mut()));
+    paths.sort();
+    paths
+}
+
+fn mapped(filename: &str) -> String {
+    unsafe {
+        let filename = CString::new(filename).unwrap();
+        let mapped = asset_map_filename(P(filename.as_ptr()));
+        CStr::from_ptr(mapped.ptr.as_ptr()).to_string_lossy().into_owned()
+    }
+}
+
+fn extension(path: &str) -> &str {
+    Path::new(path).extension().and_then(|extension| extension.to_str()).unwrap_or("")
+}
+
+fn exists(path: &str) -> bool {
+    let path = CString::new(path).unwrap();
+    unsafe { file_exists(P(path.as_ptr())) != 0 }
+}
+
+/// Assets loaded along with `path` must be there, or the loader exits
+fn check_exists(path: &str, referenced: &str) -> Result<(), String> {
+    if exists(referenced) { Ok(()) } else { Err(format!("{}: {} doesn't exist", path, referenced)) }
+}
+
+fn check_shader(path: &str) -> Result<(), String> {
+    let filename = CString::new(path).unwrap();
+    let mut log = vec![0 as c_char; LOG_SIZE];
+    unsafe {
+        if shader_file_compiles(filename.as_ptr() as *mut c_char, log.as_mut_ptr(), LOG_SIZE as c_int) != 0 {
+            Ok(())
+        } else {
+            let log = CStr::from_ptr(log.as_ptr()).to_string_lossy();
+            Err(format!("{}: {}", path, log.trim_end()))
+        }
+    }
+}
+
+fn check_dds(path: &str) -> Result<(), String> {
+    let bytes = fs::read(path).map_err(|err| format!("{}: {}", path, err))?;
+    dds::parse(&bytes).map(|_| ()).map_err(|err| format!("{}: {}", path, err))
+}
+
+/// Check `path` would load, for the formats that can be read without loading them
+pub fn check(path: &str) -> Result<(), String> {
+    match extension(path) {
+        "bmf" => {
+            let bmf = Bmf::load(path)?;
+            check_exists(path, &bmf.material)
+        }
+        "mat" => Mat::load(path)?.paths().into_iter().try_for_each(|referenced| check_exists(path, referenced)),
+        "effect" => {
+            let effect = Effect::load(path)?;
+            effect.texture.iter().chain(effect.texture_nm.iter()).try_for_each(|texture| check_exists(path, texture))
+        }
+        "skl" => Skl::load(path).map(|_| ()),
+        "ani" => Ani::load(path).map(|_| ()),
+        "col" => Col::load(path).map(|_| ()),
+        "cfg" => Cfg::load(path).map(|_| ()),
+        "lang" => Lang::load(path).map(|_| ()),
+        "gltf" | "glb" => gltf::check_file(path),
+        "dds" => check_dds(path),
+        extension if image_formats::EXTENSIONS.contains(&extension) => image_formats::read_file(path).map(|_| ()),
+        extension if SHADER_EXTENSIONS.contains(&extension) => check_shader(path),
+        _ => Ok(())
+    }
+}
+
+fn reload_file(path: &str) {
+    let path = CString::new(path).unwrap();
+    unsafe { file_reload(P(path.as_ptr())); }
+}
+
+/// Loaded materials with a shader item mapping to `shader`
+fn materials_using(shader: &str, loaded: &[String]) -> Vec<String> {
+    loaded.iter()
+        .filter(|path| extension(path) == "mat")
+        .filter(|path| Mat::load(path).map(|mat| {
+            mat.entries.iter().flat_map(|entry| entry.items.iter()).any(|item| match item.value {
+                Value::Shader(ref referenced) => mapped(referenced) == shader,
+                _ => false
+            })
+        }).unwrap_or(false))
+        .cloned()
+        .collect()
+}
+
+/// Check and reload the loaded asset at `path`, with the assets depending on it
+pub fn reload(path: &str) -> Reload {
+    let path = mapped(path);
+    let result = check(&path);
+    let mut dependents = Vec::new();
+    if result.is_ok() {
+        reload_file(&path);
+        if SHADER_EXTENSIONS.contains(&extension(&path)) {
+            // Materials link their programs on load, so reloading them picks up the new shader
+            for material in materials_using(&path, &loaded_paths()) {
+                if check(&material).is_ok() {
+                    reload_file(&material);
+                    dependents.push(material);
+                }
+            }
+        }
+    }
+    Reload {
+        path: path,
+        dependents: dependents,
+        result: result
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod inotify {
+    use std::io;
+    use std::ffi::CString;
+    use libc;
+    use libc::{c_char, c_int, c_void};
+
+    const IN_CLOSE_WRITE: u32 = 0x8;
+    const IN_MOVED_TO: u32 = 0x80;
+    const IN_NONBLOCK: c_int = 0o4000;
+    const IN_CLOEXEC: c_int = 0o2000000;
+
+    /// Size of `struct inotify_event` before the name
+    const EVENT_SIZE: usize = 16;
+
+    extern "C" {
+        fn inotify_init1(flags: c_int) -> c_int;
+        fn inotify_add_watch(fd: c_int, pathname: *const c_char, mask: u32) -> c_int;
+    }
+
+    pub fn init() -> Result<c_int, String> {
+        let fd = unsafe { inotify_init1(IN_NONBLOCK | IN_CLOEXEC) };
+        if fd < 0 { Err(format!("inotify: {}", io::Error::last_os_error())) } else { Ok(fd) }
+    }
+
+    /// Watch for files in `folder` written or moved into place
+    pub fn add_watch(fd: c_int, folder: &str) -> Result<c_int, String> {
+        let path = CString::new(folder).unwrap();
+        let wd = unsafe { inotify_add_watch(fd, path.as_ptr(), IN_CLOSE_WRITE | IN_MOVED_TO) };
+        if wd < 0 { Err(format!("{}: {}", folder, io::Error::last_os_error())) } else { Ok(wd) }
+    }
+
+    /// Watch descriptors and file names of the events waiting
+    pub fn read(fd: c_int) -> Vec<(c_int, String)> {
+        let mut events = Vec::new();
+        let mut buffer = [0u8; 4096];
+        loop {
+            let read = unsafe { libc::read(fd, buffer.as_mut_ptr() as *mut c_void, buffer.len() as libc::size_t) };
+            if read <= 0 {
+                return events;
+            }
+            let bytes = &buffer[..read as usize];
+            let mut i = 0;
+            while i + EVENT_SIZE <= bytes.len() {
+                let field = |offset: usize| {
+                    let mut value = [0u8; 4];
+                    value.copy_from_slice(&bytes[i + offset..i + offset + 4]);
+                    u32::from_ne_bytes(value)
+                };
+                let (wd, length) = (field(0) as c_int, field(12) as usize);
+                let name = &bytes[i + EVENT_SIZE..(i + EVENT_SIZE + length).min(bytes.len())];
+                let name = name.split(|&byte| byte == 0).next().unwrap_or(&[]);
+                if !name.is_empty() {
+                    events.push((wd, String::from_utf8_lossy(name).into_owned()));
+                }
+                i += EVENT_SIZE + length;
+            }
+        }
+    }
+
+    pub fn close(fd: c_int) {
+        unsafe { libc::close(fd); }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod inotify {
+    use libc::c_int;
+
+    pub fn init() -> Result<c_int, String> {
+        Err("hot reloading watches files with inotify, which is only available on Linux".to_string())
+    }
+
+    pub fn add_watch(_fd: c_int, _folder: &str) -> Result<c_int, String> {
+        unreachable!()
+    }
+
+    pub fn read(_fd: c_int) -> Vec<(c_int, String)> {
+        unreachable!()
+    }
+
+    pub fn close(_fd: c_int) {}
+}
+
+/// Watches the folders of loaded assets and reloads the files changed in them
+pub struct Watcher {
+    fd: c_int,
+    /// Watched folders by watch descriptor
+    folders: HashMap<c_int, String>,
+    /// Changed files of loaded assets and when they were last written
+    pending: HashMap<String, Instant>,
+    scanned: Option<Instant>
+}
+
+impl Watcher {
+    pub fn new() -> Result<Watcher, String> {
+        Ok(Watcher {
+            fd: inotify::init()?,
+            folders: HashMap::new(),
+            pending: HashMap::new(),
+            scanned: None
+        })
+    }
+
+    /// Watch `folder` as well as the folders of loaded assets, e.g. before loading from it
+    pub fn watch_folder(&mut self, folder: &str) -> Result<(), String> {
+        let folder = mapped(folder);
+        if !self.folders.values().any(|watched| *watched == folder) {
+            let wd = inotify::add_watch(self.fd, &folder)?;
+            self.folders.insert(wd, folder);
+        }
+        Ok(())
+    }
+
+    /// Watch the folder of every loaded asset backed by a file, done by `poll` every second
+    pub fn watch_loaded(&mut self) -> Result<(), String> {
+        self.scanned = Some(Instant::now());
+        let mut errors = Vec::new();
+        for path in loaded_paths() {
+            let path = Path::new(&path);
+            if let (true, Some(folder)) = (path.is_file(), path.parent()) {
+                if let Err(err) = self.watch_folder(&folder.to_string_lossy()) {
+                    if !errors.contains(&err) {
+                        errors.push(err);
+                    }
+                }
+            }
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors.join(", ")) }
+    }
+
+    /// Reload the loaded assets whose files have changed and settled since the last poll
+    pub fn poll(&mut self) -> Vec<Reload> {
+        if self.scanned.map_or(true, |scanned| scanned.elapsed() >= RESCAN_TIME) {
+            if let Err(err) = self.watch_loaded() {
+                let message = CString::new(format!("[WARNING] ({}) Can't watch {}", file!(), err.replace('\0', ""))).unwrap();
+                unsafe { warning_(message.as_ptr()); }
+            }
+        }
+
+        let now = Instant::now();
+        for (wd, name) in inotify::read(self.fd) {
+            if let Some(folder) = self.folders.get(&wd) {
+                let path = format!("{}/{}", folder.trim_end_matches('/'), name);
+                let filename = CString::new(path.as_str()).unwrap();
+                if unsafe { file_isloaded(P(filename.as_ptr())) } != 0 {
+                    self.pending.insert(path, now);
+                }
+            }
+        }
+
+        let mut settled: Vec<String> = self.pending.iter()
+            .filter(|&(_, written)| now.duration_since(*written) >= SETTLE_TIME)
+            .map(|(path, _)| path.clone())
+            .collect();
+        settled.sort();
+        for path in &settled {
+            self.pending.remove(path);
+        }
+        settled.iter().map(|path| reload(path)).collect()
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        inotify::close(self.fd);
+    }
+}
@@ -0,0 +1,117 @@
+//! PNG and JPEG support for textures and images
+//!
+//! `register` adds `.png`, `.jpg` and `.jpeg` as texture handlers, so `folder_load` and
+//! `asset_hndl_new_load` pick them up like `.dds`, and as image handlers for
+//! `image_read_from_file`. Textures get a full mipmap chain from `texture_generate_mipmaps`.
+//!
+//! Decoding is plain Rust and doesn't touch GL, see `decode` and `read_file`.
+
+use std::ffi::{CStr, CString};
+use std::ptr;
+use std::sync::Once;
+use libc::{c_char, c_uchar, c_int};
+use corange::*;
+use archive;
+use jpeg;
+use png;
+
+/// Extensions the decoders are registered for
+pub const EXTENSIONS: &'static [&'static str] = &["png", "jpg", "jpeg"];
+
+/// Decoded pixels, RGBA8 with rows from top to bottom like the engine's `image`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<u8>
+}
+
+impl Image {
+    /// Transparent black image
+    pub fn new(width: usize, height: usize) -> Image {
+        Image { width: width, height: height, data: vec![0; width * height * 4] }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * self.width + x) * 4;
+        [self.data[i], self.data[i + 1], self.data[i + 2], self.data[i + 3]]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, pixel: [u8; 4]) {
+        let i = (y * self.width + x) * 4;
+        self.data[i..i + 4].copy_from_slice(&pixel);
+    }
+
+    /// Copy into an engine `image`, owned by the caller and freed with `image_delete`
+    pub fn to_image(&self) -> *mut image {
+        unsafe { image_new(self.width as c_int, self.height as c_int, self.data.as_ptr() as *mut c_uchar) }
+    }
+
+    /// Upload as a mipmapped texture the way the registered handlers do, freed with `texture_delete`
+    pub fn to_texture(&self) -> *mut texture {
+        unsafe {
+            let i = self.to_image();
+            let t = texture_new();
+            texture_set_image(t, i);
+            texture_generate_mipmaps(t);
+            texture_set_filtering_anisotropic(t);
+            image_delete(i);
+            t
+        }
+    }
+}
+
+/// Decode PNG or JPEG data, telling the two apart by their signatures
+pub fn decode(bytes: &[u8]) -> Result<Image, String> {
+    if png::is_png(bytes) {
+        png::decode(bytes)
+    } else if jpeg::is_jpeg(bytes) {
+        jpeg::decode(bytes)
+    } else {
+        Err("not a PNG or JPEG image".to_string())
+    }
+}
+
+/// Read and decode the image at `path`, errors name the file
+pub fn read_file(path: &str) -> Result<Image, String> {
+    let bytes = archive::read_file(path).map_err(|err| format!("{}: {}", path, err))?;
+    decode(&bytes).map_err(|err| format!("{}: {}", path, err))
+}
+
+static REGISTER: Once = Once::new();
+
+/// Register the texture and image handlers, called by `engine::initialize`
+pub fn register() {
+    REGISTER.call_once(|| unsafe {
+        for extension in EXTENSIONS {
+            let extension = CString::new(*extension).unwrap();
+            asset_handler_cast(*TEXTURE_TYPE, extension.as_ptr(), texture_load_file, texture_delete_asset);
+            image_handler_add(extension.as_ptr(), image_load_file);
+        }
+    });
+}
+
+fn error(message: &str) {
+    let message = CString::new(format!("[ERROR] ({}) {}", file!(), message.replace('\0', ""))).unwrap();
+    unsafe { error_(message.as_ptr()); }
+}
+
+unsafe extern "C" fn image_load_file(filename: *const c_char) -> *mut image {
+    let filename = CStr::from_ptr(filename).to_string_lossy().into_owned();
+    match read_file(&filename) {
+        Ok(pixels) => pixels.to_image(),
+        Err(err) => { error(&err); ptr::null_mut() }
+    }
+}
+
+unsafe extern "C" fn texture_load_file(filename: *const c_char) -> *mut asset {
+    let filename = CStr::from_ptr(filename).to_string_lossy().into_owned();
+    match read_file(&filename) {
+        Ok(pixels) => pixels.to_texture() as *mut asset,
+        Err(err) => { error(&err); ptr::null_mut() }
+    }
+}
+
+unsafe extern "C" fn texture_delete_asset(a: *mut asset) {
+    texture_delete(a as *mut texture);
+}
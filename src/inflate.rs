@@ -0,0 +1,257 @@
+//! DEFLATE and zlib decompression, as used by PNG
+
+/// Decompress a zlib stream, checking its header and Adler-32 checksum
+pub fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() < 6 {
+        return Err("zlib stream is truncated".to_string());
+    }
+    let (cmf, flags) = (data[0], data[1]);
+    if cmf & 0x0F != 8 || cmf >> 4 > 7 {
+        return Err("zlib stream doesn't use deflate".to_string());
+    }
+    if ((cmf as u16) << 8 | flags as u16) % 31 != 0 {
+        return Err("zlib header checksum is wrong".to_string());
+    }
+    if flags & 0x20 != 0 {
+        return Err("zlib stream needs a preset dictionary".to_string());
+    }
+
+    let mut inflater = Inflater { input: &data[2..], offset: 0, bits: 0, count: 0, output: Vec::new() };
+    inflater.run()?;
+    let trailer = inflater.offset;
+    let checksum = inflater.input.get(trailer..trailer + 4).ok_or("zlib stream is missing its checksum")?;
+    let expected = (checksum[0] as u32) << 24 | (checksum[1] as u32) << 16 | (checksum[2] as u32) << 8 | checksum[3] as u32;
+    if adler32(&inflater.output) != expected {
+        return Err("zlib checksum doesn't match the data".to_string());
+    }
+    Ok(inflater.output)
+}
+
+/// Decompress raw DEFLATE data
+pub fn inflate(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut inflater = Inflater { input: data, offset: 0, bits: 0, count: 0, output: Vec::new() };
+    inflater.run()?;
+    Ok(inflater.output)
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    b << 16 | a
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+    35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+    257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+/// Order the code length code lengths are stored in
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// Canonical Huffman code, decoded a bit at a time by code length
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Huffman, String> {
+        let mut counts = [0u16; 16];
+        for &length in lengths { counts[length as usize] += 1; }
+        counts[0] = 0;
+
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - count as i32;
+            if left < 0 {
+                return Err("deflate stream has an oversubscribed Huffman code".to_string());
+            }
+        }
+
+        let mut offsets = [0u16; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts: counts, symbols: symbols })
+    }
+}
+
+struct Inflater<'a> {
+    input: &'a [u8],
+    offset: usize,
+    bits: u32,
+    count: u32,
+    output: Vec<u8>
+}
+
+impl<'a> Inflater<'a> {
+    fn bits(&mut self, needed: u32) -> Result<u32, String> {
+        while self.count < needed {
+            let byte = *self.input.get(self.offset).ok_or("deflate stream is truncated")?;
+            self.offset += 1;
+            self.bits |= (byte as u32) << self.count;
+            self.count += 8;
+        }
+        let value = self.bits & ((1u32 << needed) - 1);
+        self.bits >>= needed;
+        self.count -= needed;
+        Ok(value)
+    }
+
+    fn decode(&mut self, huffman: &Huffman) -> Result<u16, String> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..16 {
+            code |= self.bits(1)? as i32;
+            let count = huffman.counts[length] as i32;
+            if code - count < first {
+                return Ok(huffman.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("deflate stream has an invalid Huffman code".to_string())
+    }
+
+    fn run(&mut self) -> Result<(), String> {
+        loop {
+            let last = self.bits(1)? == 1;
+            match self.bits(2)? {
+                0 => self.stored()?,
+                1 => {
+                    let mut lengths = [0u8; 288];
+                    for (symbol, length) in lengths.iter_mut().enumerate() {
+                        *length = match symbol { 0..=143 => 8, 144..=255 => 9, 256..=279 => 7, _ => 8 };
+                    }
+                    let literals = Huffman::new(&lengths)?;
+                    let distances = Huffman::new(&[5u8; 30])?;
+                    self.codes(&literals, &distances)?;
+                }
+                2 => {
+                    let (literals, distances) = self.dynamic_tables()?;
+                    self.codes(&literals, &distances)?;
+                }
+                _ => return Err("deflate stream has an invalid block type".to_string())
+            }
+            if last {
+                // Whatever follows starts on a byte boundary
+                self.bits = 0;
+                self.count = 0;
+                return Ok(());
+            }
+        }
+    }
+
+    fn stored(&mut self) -> Result<(), String> {
+        self.bits = 0;
+        self.count = 0;
+        let header = self.input.get(self.offset..self.offset + 4).ok_or("deflate stream is truncated")?;
+        let length = header[0] as usize | (header[1] as usize) << 8;
+        let complement = header[2] as usize | (header[3] as usize) << 8;
+        if length != !complement & 0xFFFF {
+            return Err("deflate stored block has a corrupt length".to_string());
+        }
+        self.offset += 4;
+        let data = self.input.get(self.offset..self.offset + length).ok_or("deflate stream is truncated")?;
+        self.output.extend_from_slice(data);
+        self.offset += length;
+        Ok(())
+    }
+
+    fn dynamic_tables(&mut self) -> Result<(Huffman, Huffman), String> {
+        let literal_count = self.bits(5)? as usize + 257;
+        let distance_count = self.bits(5)? as usize + 1;
+        let code_count = self.bits(4)? as usize + 4;
+        if literal_count > 286 || distance_count > 30 {
+            return Err("deflate stream has too many codes".to_string());
+        }
+
+        let mut code_lengths = [0u8; 19];
+        for &position in &CODE_LENGTH_ORDER[..code_count] {
+            code_lengths[position] = self.bits(3)? as u8;
+        }
+        let code_lengths = Huffman::new(&code_lengths)?;
+
+        let mut lengths = vec![0u8; literal_count + distance_count];
+        let mut index = 0;
+        while index < lengths.len() {
+            let symbol = self.decode(&code_lengths)?;
+            let (value, repeat) = match symbol {
+                0..=15 => (symbol as u8, 1),
+                16 => {
+                    if index == 0 {
+                        return Err("deflate stream repeats a length before the first".to_string());
+                    }
+                    (lengths[index - 1], 3 + self.bits(2)? as usize)
+                }
+                17 => (0, 3 + self.bits(3)? as usize),
+                _ => (0, 11 + self.bits(7)? as usize)
+            };
+            if index + repeat > lengths.len() {
+                return Err("deflate stream has too many code lengths".to_string());
+            }
+            for length in &mut lengths[index..index + repeat] { *length = value; }
+            index += repeat;
+        }
+        if lengths[256] == 0 {
+            return Err("deflate stream has no end of block code".to_string());
+        }
+
+        let literals = Huffman::new(&lengths[..literal_count])?;
+        let distances = Huffman::new(&lengths[literal_count..])?;
+        Ok((literals, distances))
+    }
+
+    fn codes(&mut self, literals: &Huffman, distances: &Huffman) -> Result<(), String> {
+        loop {
+            let symbol = self.decode(literals)? as usize;
+            if symbol < 256 {
+                self.output.push(symbol as u8);
+                continue;
+            }
+            if symbol == 256 {
+                return Ok(());
+            }
+
+            let symbol = symbol - 257;
+            if symbol >= LENGTH_BASE.len() {
+                return Err("deflate stream has an invalid length code".to_string());
+            }
+            let length = LENGTH_BASE[symbol] as usize + self.bits(LENGTH_EXTRA[symbol] as u32)? as usize;
+
+            let symbol = self.decode(distances)? as usize;
+            if symbol >= DISTANCE_BASE.len() {
+                return Err("deflate stream has an invalid distance code".to_string());
+            }
+            let distance = DISTANCE_BASE[symbol] as usize + self.bits(DISTANCE_EXTRA[symbol] as u32)? as usize;
+            if distance > self.output.len() {
+                return Err("deflate stream refers back before its start".to_string());
+            }
+
+            let start = self.output.len() - distance;
+            for i in 0..length {
+                let byte = self.output[start + i];
+                self.output.push(byte);
+            }
+        }
+    }
+}
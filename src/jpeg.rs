@@ -0,0 +1,506 @@
+//! Baseline JPEG decoder
+//!
+//! Reads sequential Huffman coded JPEGs with 8 bit samples, greyscale or three component
+//! YCbCr (or RGB when an Adobe marker says so), any chroma subsampling, restart intervals and
+//! interleaved or separate scans. Progressive, arithmetic coded, lossless and 12 bit files are
+//! refused with an error naming the mode.
+
+use std::f32::consts::PI;
+use image_formats::Image;
+
+/// Position in an 8x8 block of each coefficient in zig-zag order
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5,
+    12, 19, 26, 33, 40, 48, 41, 34, 27, 20, 13, 6, 7, 14, 21, 28,
+    35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51,
+    58, 59, 52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63];
+
+/// Decoded images larger than this are refused rather than allocated
+const MAX_PIXELS: usize = 1 << 28;
+
+#[derive(Clone)]
+struct Huffman {
+    /// Largest code of each length, -1 when there are none
+    max_code: [i32; 18],
+    /// Index in `values` of the first code of each length, minus that code
+    offset: [i32; 17],
+    values: Vec<u8>
+}
+
+impl Huffman {
+    fn new(counts: &[u8], values: &[u8]) -> Result<Huffman, String> {
+        let mut max_code = [-1i32; 18];
+        let mut offset = [0i32; 17];
+        let mut code = 0i32;
+        let mut index = 0i32;
+        for length in 1..17 {
+            let count = counts[length - 1] as i32;
+            offset[length] = index - code;
+            if count > 0 {
+                code += count;
+                index += count;
+                max_code[length] = code - 1;
+            }
+            if code > 1 << length {
+                return Err("JPEG Huffman table is oversubscribed".to_string());
+            }
+            code <<= 1;
+        }
+        // Sentinel so decoding always stops
+        max_code[17] = i32::MAX;
+        Ok(Huffman { max_code: max_code, offset: offset, values: values.to_vec() })
+    }
+}
+
+struct Component {
+    id: u8,
+    h: usize,
+    v: usize,
+    quant: usize,
+    /// Samples covering whole MCUs, `stride` wide
+    samples: Vec<u8>,
+    stride: usize,
+    dc_table: usize,
+    ac_table: usize,
+    prediction: i32
+}
+
+struct Frame {
+    width: usize,
+    height: usize,
+    components: Vec<Component>,
+    h_max: usize,
+    v_max: usize,
+    mcus_x: usize,
+    mcus_y: usize
+}
+
+/// Entropy coded data reader, which stops in front of markers
+struct Bits<'a> {
+    data: &'a [u8],
+    offset: usize,
+    buffer: u32,
+    count: u32
+}
+
+impl<'a> Bits<'a> {
+    fn fill(&mut self) {
+        while self.count <= 24 {
+            let mut byte = 0;
+            if self.offset < self.data.len() && self.data[self.offset] != 0xFF {
+                byte = self.data[self.offset];
+                self.offset += 1;
+            } else if self.offset + 1 < self.data.len() && self.data[self.offset] == 0xFF && self.data[self.offset + 1] == 0 {
+                byte = 0xFF;
+                self.offset += 2;
+            }
+            // At a marker or the end of the data, zeros are fed in
+            self.buffer |= (byte as u32) << (24 - self.count);
+            self.count += 8;
+        }
+    }
+
+    fn bit(&mut self) -> u32 {
+        self.bits(1)
+    }
+
+    fn bits(&mut self, count: u32) -> u32 {
+        if count == 0 { return 0; }
+        self.fill();
+        let value = self.buffer >> (32 - count);
+        self.buffer <<= count;
+        self.count -= count;
+        value
+    }
+
+    /// Drop the partial byte and skip the restart marker that should follow
+    fn restart(&mut self) -> Result<(), String> {
+        self.buffer = 0;
+        self.count = 0;
+        while self.offset < self.data.len() && self.data[self.offset] != 0xFF {
+            self.offset += 1;
+        }
+        while self.offset < self.data.len() && self.data[self.offset] == 0xFF {
+            self.offset += 1;
+        }
+        match self.data.get(self.offset) {
+            Some(&marker) if (0xD0..=0xD7).contains(&marker) => { self.offset += 1; Ok(()) }
+            _ => Err("JPEG is missing a restart marker".to_string())
+        }
+    }
+
+    fn decode(&mut self, table: &Huffman) -> Result<u8, String> {
+        let mut code = 0i32;
+        for length in 1..17 {
+            code = code << 1 | self.bit() as i32;
+            if code <= table.max_code[length] {
+                return table.values.get((code + table.offset[length]) as usize).cloned()
+                    .ok_or_else(|| "JPEG Huffman table is missing values".to_string());
+            }
+        }
+        Err("JPEG has an invalid Huffman code".to_string())
+    }
+
+    /// Read `size` bits of a coefficient and sign extend them
+    fn receive(&mut self, size: u8) -> i32 {
+        if size == 0 { return 0; }
+        let value = self.bits(size as u32) as i32;
+        if value < 1 << (size - 1) { value - (1 << size) + 1 } else { value }
+    }
+}
+
+/// Whether `bytes` starts with a JPEG start of image marker
+pub fn is_jpeg(bytes: &[u8]) -> bool {
+    bytes.starts_with(&[0xFF, 0xD8, 0xFF])
+}
+
+/// Decode a complete baseline JPEG file into RGBA8 pixels
+pub fn decode(bytes: &[u8]) -> Result<Image, String> {
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return Err("not a JPEG file".to_string());
+    }
+
+    let mut quant = [[0u16; 64]; 4];
+    let mut dc_tables: Vec<Option<Huffman>> = vec![None; 4];
+    let mut ac_tables: Vec<Option<Huffman>> = vec![None; 4];
+    let mut frame: Option<Frame> = None;
+    let mut restart_interval = 0;
+    let mut adobe_transform: Option<u8> = None;
+    let mut offset = 2;
+
+    loop {
+        // Markers may be padded with any number of fill bytes
+        while offset < bytes.len() && bytes[offset] != 0xFF { offset += 1; }
+        while offset < bytes.len() && bytes[offset] == 0xFF { offset += 1; }
+        let marker = *bytes.get(offset).ok_or("JPEG ends before its end of image marker")?;
+        offset += 1;
+
+        match marker {
+            0xD9 => break,
+            0xD0..=0xD8 | 0x01 => continue,
+            _ => {}
+        }
+
+        let length = bytes.get(offset..offset + 2).map(|b| (b[0] as usize) << 8 | b[1] as usize)
+            .filter(|&length| length >= 2)
+            .ok_or("JPEG segment is truncated")?;
+        let segment = bytes.get(offset + 2..offset + length).ok_or("JPEG segment is truncated")?;
+        offset += length;
+
+        match marker {
+            0xDB => read_quantization(segment, &mut quant)?,
+            0xC4 => read_huffman(segment, &mut dc_tables, &mut ac_tables)?,
+            0xDD => {
+                if segment.len() < 2 {
+                    return Err("JPEG restart interval is truncated".to_string());
+                }
+                restart_interval = (segment[0] as usize) << 8 | segment[1] as usize;
+            }
+            0xEE if segment.len() >= 12 && segment.starts_with(b"Adobe") => adobe_transform = Some(segment[11]),
+            0xC0 | 0xC1 => {
+                if frame.is_some() {
+                    return Err("JPEG has more than one frame".to_string());
+                }
+                frame = Some(read_frame(segment)?);
+            }
+            0xC2 | 0xC6 | 0xCA | 0xCE => return Err("progressive JPEGs are not supported".to_string()),
+            0xC3 | 0xC7 | 0xCB | 0xCF => return Err("lossless JPEGs are not supported".to_string()),
+            0xC5 | 0xC9 | 0xCD => return Err("hierarchical or arithmetic coded JPEGs are not supported".to_string()),
+            0xDA => {
+                let frame = frame.as_mut().ok_or("JPEG scan comes before its frame header")?;
+                let scan = read_scan(segment, frame)?;
+                let tables = Tables { quant: &quant, dc: &dc_tables, ac: &ac_tables };
+                offset += decode_scan(&bytes[offset..], frame, &scan, &tables, restart_interval)?;
+            }
+            _ => {}
+        }
+    }
+
+    let frame = frame.ok_or("JPEG has no frame header")?;
+    if frame.components.iter().any(|component| component.samples.is_empty()) {
+        return Err("JPEG has no image data".to_string());
+    }
+    Ok(convert(&frame, adobe_transform))
+}
+
+fn read_quantization(mut segment: &[u8], quant: &mut [[u16; 64]; 4]) -> Result<(), String> {
+    while !segment.is_empty() {
+        let precision = segment[0] >> 4;
+        let id = (segment[0] & 0x0F) as usize;
+        let size = if precision == 0 { 64 } else { 128 };
+        if id > 3 || segment.len() < 1 + size {
+            return Err("JPEG quantization table is invalid".to_string());
+        }
+        for (i, &position) in ZIGZAG.iter().enumerate() {
+            quant[id][position] = if precision == 0 {
+                segment[1 + i] as u16
+            } else {
+                (segment[1 + 2 * i] as u16) << 8 | segment[2 + 2 * i] as u16
+            };
+        }
+        segment = &segment[1 + size..];
+    }
+    Ok(())
+}
+
+fn read_huffman(mut segment: &[u8], dc: &mut [Option<Huffman>], ac: &mut [Option<Huffman>]) -> Result<(), String> {
+    while !segment.is_empty() {
+        if segment.len() < 17 {
+            return Err("JPEG Huffman table is truncated".to_string());
+        }
+        let class = segment[0] >> 4;
+        let id = (segment[0] & 0x0F) as usize;
+        let counts = &segment[1..17];
+        let total: usize = counts.iter().map(|&count| count as usize).sum();
+        if class > 1 || id > 3 || total > 256 || segment.len() < 17 + total {
+            return Err("JPEG Huffman table is invalid".to_string());
+        }
+        let table = Huffman::new(counts, &segment[17..17 + total])?;
+        if class == 0 { dc[id] = Some(table); } else { ac[id] = Some(table); }
+        segment = &segment[17 + total..];
+    }
+    Ok(())
+}
+
+fn read_frame(segment: &[u8]) -> Result<Frame, String> {
+    if segment.len() < 6 {
+        return Err("JPEG frame header is truncated".to_string());
+    }
+    if segment[0] != 8 {
+        return Err(format!("{} bit JPEGs are not supported", segment[0]));
+    }
+    let height = (segment[1] as usize) << 8 | segment[2] as usize;
+    let width = (segment[3] as usize) << 8 | segment[4] as usize;
+    let count = segment[5] as usize;
+    if width == 0 || height == 0 {
+        return Err("JPEG is empty or sets its height later, which is not supported".to_string());
+    }
+    if width * height > MAX_PIXELS {
+        return Err(format!("JPEG is too large at {}x{}", width, height));
+    }
+    if count != 1 && count != 3 {
+        return Err(format!("JPEGs with {} components are not supported", count));
+    }
+    if segment.len() < 6 + 3 * count {
+        return Err("JPEG frame header is truncated".to_string());
+    }
+
+    let mut components = Vec::new();
+    for c in segment[6..6 + 3 * count].chunks(3) {
+        let (h, v) = ((c[1] >> 4) as usize, (c[1] & 0x0F) as usize);
+        if h == 0 || h > 4 || v == 0 || v > 4 || c[2] > 3 {
+            return Err("JPEG component has invalid sampling factors or tables".to_string());
+        }
+        components.push(Component {
+            id: c[0], h: h, v: v, quant: c[2] as usize,
+            samples: Vec::new(), stride: 0, dc_table: 0, ac_table: 0, prediction: 0
+        });
+    }
+
+    let h_max = components.iter().map(|c| c.h).max().unwrap();
+    let v_max = components.iter().map(|c| c.v).max().unwrap();
+    let mcus_x = (width + 8 * h_max - 1) / (8 * h_max);
+    let mcus_y = (height + 8 * v_max - 1) / (8 * v_max);
+    Ok(Frame {
+        width: width, height: height, components: components,
+        h_max: h_max, v_max: v_max, mcus_x: mcus_x, mcus_y: mcus_y
+    })
+}
+
+struct Tables<'a> {
+    quant: &'a [[u16; 64]; 4],
+    dc: &'a [Option<Huffman>],
+    ac: &'a [Option<Huffman>]
+}
+
+/// Indices into the frame's components of the components in a scan
+fn read_scan(segment: &[u8], frame: &mut Frame) -> Result<Vec<usize>, String> {
+    let count = *segment.first().ok_or("JPEG scan header is truncated")? as usize;
+    if count == 0 || count > frame.components.len() || segment.len() < 4 + 2 * count {
+        return Err("JPEG scan header is invalid".to_string());
+    }
+    let mut scan = Vec::new();
+    for c in segment[1..1 + 2 * count].chunks(2) {
+        let index = frame.components.iter().position(|component| component.id == c[0])
+            .ok_or("JPEG scan refers to an unknown component")?;
+        let component = &mut frame.components[index];
+        component.dc_table = (c[1] >> 4) as usize;
+        component.ac_table = (c[1] & 0x0F) as usize;
+        if component.dc_table > 3 || component.ac_table > 3 {
+            return Err("JPEG scan refers to an invalid Huffman table".to_string());
+        }
+        scan.push(index);
+    }
+    let blocks: usize = scan.iter().map(|&index| frame.components[index].h * frame.components[index].v).sum();
+    if scan.len() > 1 && blocks > 10 {
+        return Err("JPEG scan has too many blocks per MCU".to_string());
+    }
+    Ok(scan)
+}
+
+/// Decode one scan, returning how many bytes of entropy coded data it used
+fn decode_scan(data: &[u8], frame: &mut Frame, scan: &[usize], tables: &Tables, restart_interval: usize) -> Result<usize, String> {
+    let mut bits = Bits { data: data, offset: 0, buffer: 0, count: 0 };
+    let (mcus_x, mcus_y) = (frame.mcus_x, frame.mcus_y);
+    let (h_max, v_max) = (frame.h_max, frame.v_max);
+
+    for &index in scan {
+        let component = &mut frame.components[index];
+        if tables.dc[component.dc_table].is_none() || tables.ac[component.ac_table].is_none() {
+            return Err("JPEG scan uses a Huffman table that isn't defined".to_string());
+        }
+        if component.samples.is_empty() {
+            component.stride = mcus_x * component.h * 8;
+            component.samples = vec![0; component.stride * mcus_y * component.v * 8];
+        }
+        component.prediction = 0;
+    }
+
+    // A scan with a single component codes just the blocks covering it, one block per MCU
+    let (units_x, units_y) = if scan.len() == 1 {
+        let component = &frame.components[scan[0]];
+        let width = (frame.width * component.h + h_max - 1) / h_max;
+        let height = (frame.height * component.v + v_max - 1) / v_max;
+        ((width + 7) / 8, (height + 7) / 8)
+    } else {
+        (mcus_x, mcus_y)
+    };
+
+    let mut block = [0f32; 64];
+    for unit in 0..units_x * units_y {
+        if restart_interval > 0 && unit > 0 && unit % restart_interval == 0 {
+            bits.restart()?;
+            for &index in scan { frame.components[index].prediction = 0; }
+        }
+        let (unit_x, unit_y) = (unit % units_x, unit / units_x);
+
+        for &index in scan {
+            let component = &mut frame.components[index];
+            let (blocks_x, blocks_y) = if scan.len() == 1 { (1, 1) } else { (component.h, component.v) };
+            for by in 0..blocks_y {
+                for bx in 0..blocks_x {
+                    decode_block(&mut bits, component, tables, &mut block)?;
+                    let x = (unit_x * blocks_x + bx) * 8;
+                    let y = (unit_y * blocks_y + by) * 8;
+                    idct(&block, &mut component.samples[y * component.stride + x..], component.stride);
+                }
+            }
+        }
+    }
+
+    // Stop in front of the marker that ends the scan
+    let mut end = bits.offset;
+    while end < data.len() && !(data[end] == 0xFF && end + 1 < data.len() && data[end + 1] != 0 && !(0xD0..=0xD7).contains(&data[end + 1])) {
+        end += 1;
+    }
+    Ok(end)
+}
+
+fn decode_block(bits: &mut Bits, component: &mut Component, tables: &Tables, block: &mut [f32; 64]) -> Result<(), String> {
+    let quant = &tables.quant[component.quant];
+    let dc = tables.dc[component.dc_table].as_ref().unwrap();
+    let ac = tables.ac[component.ac_table].as_ref().unwrap();
+
+    *block = [0.0; 64];
+    let size = bits.decode(dc)?;
+    if size > 11 {
+        return Err("JPEG DC coefficient is out of range".to_string());
+    }
+    component.prediction += bits.receive(size);
+    block[0] = (component.prediction * quant[0] as i32) as f32;
+
+    let mut k = 1;
+    while k < 64 {
+        let symbol = bits.decode(ac)?;
+        let (run, size) = ((symbol >> 4) as usize, symbol & 0x0F);
+        if size == 0 {
+            if run == 15 { k += 16; continue; }
+            break;
+        }
+        k += run;
+        if k > 63 {
+            return Err("JPEG block has too many coefficients".to_string());
+        }
+        let position = ZIGZAG[k];
+        block[position] = (bits.receive(size) * quant[position] as i32) as f32;
+        k += 1;
+    }
+    Ok(())
+}
+
+lazy_static! {
+    // Basis functions of the inverse DCT, indexed [frequency][sample]
+    static ref COSINES: [[f32; 8]; 8] = {
+        let mut table = [[0f32; 8]; 8];
+        for (u, row) in table.iter_mut().enumerate() {
+            let scale = if u == 0 { 0.5 / 2f32.sqrt() } else { 0.5 };
+            for (x, value) in row.iter_mut().enumerate() {
+                *value = scale * ((2 * x + 1) as f32 * u as f32 * PI / 16.0).cos();
+            }
+        }
+        table
+    };
+}
+
+/// Inverse DCT of a dequantized block, written as level shifted samples
+fn idct(block: &[f32; 64], out: &mut [u8], stride: usize) {
+    let cosines = &*COSINES;
+    let mut rows = [0f32; 64];
+    for v in 0..8 {
+        for x in 0..8 {
+            rows[v * 8 + x] = (0..8).map(|u| cosines[u][x] * block[v * 8 + u]).sum();
+        }
+    }
+    for y in 0..8 {
+        for x in 0..8 {
+            let value: f32 = (0..8).map(|v| cosines[v][y] * rows[v * 8 + x]).sum();
+            out[y * stride + x] = (value + 128.0).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
+/// Sample of a subsampled component at full resolution pixel (x, y), interpolated linearly
+fn upsample(frame: &Frame, component: &Component, x: usize, y: usize) -> f32 {
+    if component.h == frame.h_max && component.v == frame.v_max {
+        return component.samples[y * component.stride + x] as f32;
+    }
+    let width = (frame.width * component.h + frame.h_max - 1) / frame.h_max;
+    let height = (frame.height * component.v + frame.v_max - 1) / frame.v_max;
+    let u = ((x as f32 + 0.5) * component.h as f32 / frame.h_max as f32 - 0.5).max(0.0).min((width - 1) as f32);
+    let v = ((y as f32 + 0.5) * component.v as f32 / frame.v_max as f32 - 0.5).max(0.0).min((height - 1) as f32);
+    let (u0, v0) = (u as usize, v as usize);
+    let (u1, v1) = ((u0 + 1).min(width - 1), (v0 + 1).min(height - 1));
+    let (fu, fv) = (u - u0 as f32, v - v0 as f32);
+    let at = |u: usize, v: usize| component.samples[v * component.stride + u] as f32;
+    let top = at(u0, v0) * (1.0 - fu) + at(u1, v0) * fu;
+    let bottom = at(u0, v1) * (1.0 - fu) + at(u1, v1) * fu;
+    top * (1.0 - fv) + bottom * fv
+}
+
+fn convert(frame: &Frame, adobe_transform: Option<u8>) -> Image {
+    let mut image = Image::new(frame.width, frame.height);
+    // Three components are YCbCr unless an Adobe marker says they aren't transformed
+    let rgb = adobe_transform == Some(0);
+    let byte = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+
+    for y in 0..frame.height {
+        for x in 0..frame.width {
+            let pixel = if frame.components.len() == 1 {
+                let grey = byte(upsample(frame, &frame.components[0], x, y));
+                [grey, grey, grey, 255]
+            } else {
+                let c0 = upsample(frame, &frame.components[0], x, y);
+                let c1 = upsample(frame, &frame.components[1], x, y);
+                let c2 = upsample(frame, &frame.components[2], x, y);
+                if rgb {
+                    [byte(c0), byte(c1), byte(c2), 255]
+                } else {
+                    let (cb, cr) = (c1 - 128.0, c2 - 128.0);
+                    [byte(c0 + 1.402 * cr), byte(c0 - 0.344_136 * cb - 0.714_136 * cr), byte(c0 + 1.772 * cb), 255]
+                }
+            };
+            image.set(x, y, pixel);
+        }
+    }
+    image
+}
@@ -0,0 +1,251 @@
+//! Minimal JSON reader, enough for the asset formats that use it
+
+use std::char;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(BTreeMap<String, Json>)
+}
+
+static NULL: Json = Json::Null;
+
+impl Json {
+    /// Member `key` of an object, `Null` for anything missing
+    pub fn get(&self, key: &str) -> &Json {
+        match *self {
+            Json::Object(ref members) => members.get(key).unwrap_or(&NULL),
+            _ => &NULL
+        }
+    }
+
+    /// Element `index` of an array, `Null` for anything missing
+    pub fn at(&self, index: usize) -> &Json {
+        match *self {
+            Json::Array(ref elements) => elements.get(index).unwrap_or(&NULL),
+            _ => &NULL
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == Json::Null
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self { Json::Bool(value) => Some(value), _ => None }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self { Json::Number(value) => Some(value), _ => None }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        self.as_f64().map(|value| value as f32)
+    }
+
+    /// Numbers that are whole and not negative
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_f64().and_then(|value| {
+            if value >= 0.0 && value.fract() == 0.0 { Some(value as usize) } else { None }
+        })
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self { Json::String(ref value) => Some(value), _ => None }
+    }
+
+    /// Elements of an array, empty for anything else
+    pub fn members(&self) -> &[Json] {
+        match *self { Json::Array(ref elements) => elements, _ => &[] }
+    }
+
+    /// Array of numbers, `None` if anything else is in it
+    pub fn as_f32_vec(&self) -> Option<Vec<f32>> {
+        match *self {
+            Json::Array(ref elements) => elements.iter().map(|element| element.as_f32()).collect(),
+            _ => None
+        }
+    }
+}
+
+/// Parse a complete JSON document. Errors name the line they happened on.
+pub fn parse(text: &str) -> Result<Json, String> {
+    let mut parser = Parser { text: text.as_bytes(), offset: 0 };
+    let value = parser.value(0)?;
+    parser.whitespace();
+    if parser.offset != parser.text.len() {
+        return Err(parser.error("trailing characters after the document"));
+    }
+    Ok(value)
+}
+
+/// Deeper nesting than this is treated as malformed rather than overflowing the stack
+const MAX_DEPTH: usize = 256;
+
+struct Parser<'a> {
+    text: &'a [u8],
+    offset: usize
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> String {
+        let line = 1 + self.text[..self.offset.min(self.text.len())].iter().filter(|&&c| c == b'\n').count();
+        format!("line {}: {}", line, message)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.offset).cloned()
+    }
+
+    fn whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                b' ' | b'\t' | b'\n' | b'\r' => self.offset += 1,
+                _ => break
+            }
+        }
+    }
+
+    fn expect(&mut self, literal: &str, value: Json) -> Result<Json, String> {
+        if self.text[self.offset..].starts_with(literal.as_bytes()) {
+            self.offset += literal.len();
+            Ok(value)
+        } else {
+            Err(self.error("unexpected character"))
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, String> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.whitespace();
+        match self.peek() {
+            Some(b'{') => self.object(depth),
+            Some(b'[') => self.array(depth),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b't') => self.expect("true", Json::Bool(true)),
+            Some(b'f') => self.expect("false", Json::Bool(false)),
+            Some(b'n') => self.expect("null", Json::Null),
+            Some(b'-') | Some(b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of document"))
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Json, String> {
+        self.offset += 1;
+        let mut members = BTreeMap::new();
+        self.whitespace();
+        if self.peek() == Some(b'}') {
+            self.offset += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected a member name"));
+            }
+            let key = self.string()?;
+            self.whitespace();
+            if self.peek() != Some(b':') {
+                return Err(self.error("expected ':' after a member name"));
+            }
+            self.offset += 1;
+            let value = self.value(depth + 1)?;
+            members.insert(key, value);
+            self.whitespace();
+            match self.peek() {
+                Some(b',') => self.offset += 1,
+                Some(b'}') => { self.offset += 1; return Ok(Json::Object(members)); }
+                _ => return Err(self.error("expected ',' or '}' in object"))
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Json, String> {
+        self.offset += 1;
+        let mut elements = Vec::new();
+        self.whitespace();
+        if self.peek() == Some(b']') {
+            self.offset += 1;
+            return Ok(Json::Array(elements));
+        }
+        loop {
+            elements.push(self.value(depth + 1)?);
+            self.whitespace();
+            match self.peek() {
+                Some(b',') => self.offset += 1,
+                Some(b']') => { self.offset += 1; return Ok(Json::Array(elements)); }
+                _ => return Err(self.error("expected ',' or ']' in array"))
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.offset;
+        while let Some(c) = self.peek() {
+            match c {
+                b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9' => self.offset += 1,
+                _ => break
+            }
+        }
+        let text = String::from_utf8_lossy(&self.text[start..self.offset]);
+        text.parse::<f64>().map(Json::Number).map_err(|_| {
+            self.offset = start;
+            self.error(&format!("malformed number '{}'", text))
+        })
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self.text.get(self.offset..self.offset + 4)
+            .and_then(|digits| ::std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok());
+        match digits {
+            Some(value) => { self.offset += 4; Ok(value) }
+            None => Err(self.error("malformed \\u escape"))
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.offset += 1;
+        let mut bytes = Vec::new();
+        loop {
+            let c = match self.peek() {
+                Some(c) => c,
+                None => return Err(self.error("unterminated string"))
+            };
+            self.offset += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let escape = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+                    self.offset += 1;
+                    let decoded = match escape {
+                        b'"' => '"', b'\\' => '\\', b'/' => '/',
+                        b'b' => '\u{8}', b'f' => '\u{c}', b'n' => '\n', b'r' => '\r', b't' => '\t',
+                        b'u' => {
+                            let mut code = self.hex4()?;
+                            if (0xD800..0xDC00).contains(&code) && self.text[self.offset..].starts_with(b"\\u") {
+                                self.offset += 2;
+                                let low = self.hex4()?;
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+                            }
+                            char::from_u32(code).unwrap_or('\u{FFFD}')
+                        }
+                        _ => return Err(self.error("unknown escape in string"))
+                    };
+                    let mut buffer = [0; 4];
+                    bytes.extend_from_slice(decoded.encode_utf8(&mut buffer).as_bytes());
+                }
+                _ => bytes.push(c)
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| self.error("string is not valid UTF-8"))
+    }
+}
@@ -8,3 +8,28 @@ pub mod engine;
 pub mod viewport;
 pub mod renderer;
 pub mod camera;
+pub mod camera_path;
+pub mod follow_camera;
+pub mod render_target;
+pub mod picking;
+pub mod overlay;
+pub mod physics;
+pub mod character;
+pub mod collision_mesh;
+pub mod mesh_conversion;
+pub mod json;
+pub mod gltf;
+pub mod inflate;
+pub mod png;
+pub mod jpeg;
+pub mod image_formats;
+pub mod dds;
+pub mod formats;
+pub mod hot_reload;
+pub mod archive;
+pub mod core_assets;
+pub mod loading;
+pub mod handles;
+pub mod dependencies;
+pub mod light;
+pub mod statistics;
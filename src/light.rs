@@ -0,0 +1,69 @@
+use corange::*;
+
+#[derive(Clone)]
+pub struct SpotLight {
+    pub position: vec3,
+    pub target: vec3,
+    pub diffuse_color: vec3,
+    pub specular_color: vec3,
+    pub ambient_color: vec3,
+    pub power: f32,
+    pub falloff: f32,
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+    pub cookie: Option<String>,
+    pub cast_shadows: bool,
+    pub enabled: bool
+}
+
+impl Default for SpotLight {
+    fn default() -> SpotLight {
+        unsafe {
+            SpotLight {
+                position: vec3_new(0.0, 10.0, 0.0),
+                target: vec3_zero(),
+                diffuse_color: vec3_one(),
+                specular_color: vec3_one(),
+                ambient_color: vec3_zero(),
+                power: 5.0,
+                falloff: 0.5,
+                inner_angle: 0.4,
+                outer_angle: 0.6,
+                cookie: None,
+                cast_shadows: true,
+                enabled: true
+            }
+        }
+    }
+}
+
+impl SpotLight {
+    /// Create a new spot light entity. Lights must be submitted every frame with `renderer_add_dyn_light`
+    pub fn create(self, name:&'static str) -> *mut light {
+        unsafe {
+            let light = entity_new_type_id(str(name), *LIGHT_TYPE) as *mut light;
+            light_set_type(light, LIGHT_TYPE_SPOT as i32);
+            self.apply(light);
+            light
+        }
+    }
+
+    pub fn apply(self, light:*mut light) {
+        unsafe {
+            (*light).position = self.position;
+            (*light).target = self.target;
+            (*light).diffuse_color = self.diffuse_color;
+            (*light).specular_color = self.specular_color;
+            (*light).ambient_color = self.ambient_color;
+            (*light).power = self.power;
+            (*light).falloff = self.falloff;
+            (*light).cast_shadows = if self.cast_shadows { 1 } else { 0 };
+            (*light).enabled = if self.enabled { 1 } else { 0 };
+            light_set_cone(light, self.inner_angle, self.outer_angle);
+            match self.cookie {
+                Some(texture) => light_set_cookie(light, asset_hndl_new_load(path(to_static_str(texture)))),
+                None => light_set_cookie(light, asset_hndl_null())
+            }
+        }
+    }
+}
@@ -0,0 +1,400 @@
+//! Loading assets in the background
+//!
+//! A `Loader` prepares files on worker threads and adds them to the engine on the main thread,
+//! which owns the GL context, a few per frame within a time budget. Workers read and check
+//! textures and decode PNG and JPEG images, and read meshes into the engine's CPU-side meshes,
+//! so the main thread only uploads them and adds the result. Other files are checked and loaded
+//! by their engine handler on the main thread. Textures are added first, then the files other
+//! handlers load and then meshes, so materials find their textures loaded and meshes their
+//! materials. Files that don't check are left out and listed in `Progress::errors` rather than
+//! exiting like `folder_load`. Unlike `file_load`, `.obj`, `.smd` and `.ply` files don't get a
+//! `.bmf` written next to them.
+//!
+//! `engine::load_folder_in_background` queues files on the engine's loader, which uploads between
+//! frames of the main loop, and `engine::show_loading_screen` shows a `LoadingScreen` meanwhile.
+
+use std::ffi::{CStr, CString};
+use std::panic;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use libc::{c_char, c_int};
+use corange::*;
+use archive;
+use dds;
+use gltf;
+use hot_reload;
+use image_formats;
+use image_formats::Image;
+use mesh_conversion;
+use mesh_conversion::Source;
+
+/// Upload time per frame unless set otherwise, about half a frame at 60 frames per second
+pub const DEFAULT_BUDGET: Duration = Duration::from_millis(8);
+
+const MESH_EXTENSIONS: &'static [&'static str] = &["bmf", "obj", "smd", "ply", "gltf", "glb"];
+
+/// Worker threads for a loader, leaving a core to the main thread
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |cores| cores.get().saturating_sub(1).max(1))
+}
+
+/// How far the files queued since loading last finished have got
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Progress {
+    /// Files added or left out
+    pub loaded: usize,
+    pub total: usize,
+    /// Why files were left out, starting with their path
+    pub errors: Vec<String>
+}
+
+impl Progress {
+    /// Between 0 and 1, 1 when nothing is queued
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 { 1.0 } else { self.loaded as f32 / self.total as f32 }
+    }
+
+    pub fn done(&self) -> bool {
+        self.loaded == self.total
+    }
+}
+
+/// Order files are added in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Stage {
+    Texture,
+    File,
+    Mesh
+}
+
+/// What a worker made of a file
+enum Decoded {
+    /// File contents and their checked header
+    Dds(Vec<u8>, dds::Header),
+    Image(Image),
+    /// Meshes of a `.bmf`, `.obj`, `.smd` or `.ply` file
+    Meshes(Source),
+    Gltf(gltf::Imported),
+    /// Loaded by the engine's handler on the main thread
+    File
+}
+
+struct Pending {
+    id: usize,
+    path: String,
+    stage: Stage,
+    decoded: Option<Result<Decoded, String>>
+}
+
+fn extension(path: &str) -> &str {
+    Path::new(path).extension().and_then(|extension| extension.to_str()).unwrap_or("")
+}
+
+fn stage(path: &str) -> Stage {
+    match extension(path) {
+        "dds" => Stage::Texture,
+        extension if image_formats::EXTENSIONS.contains(&extension) => Stage::Texture,
+        extension if MESH_EXTENSIONS.contains(&extension) => Stage::Mesh,
+        _ => Stage::File
+    }
+}
+
+fn mapped(filename: &str) -> String {
+    unsafe {
+        let filename = CString::new(filename).unwrap();
+        let mapped = asset_map_filename(P(filename.as_ptr()));
+        CStr::from_ptr(mapped.ptr.as_ptr()).to_string_lossy().into_owned()
+    }
+}
+
+fn is_loaded(path: &str) -> bool {
+    let path = CString::new(path).unwrap();
+    unsafe { file_isloaded(P(path.as_ptr())) != 0 }
+}
+
+/// Read and check `path`, on a worker thread
+fn decode(path: &str) -> Result<Decoded, String> {
+    let in_file = |err: String| format!("{}: {}", path, err);
+    match extension(path) {
+        "dds" => {
+            let bytes = archive::read_file(path).map_err(|err| in_file(err.to_string()))?;
+            let header = dds::parse(&bytes).map_err(in_file)?;
+            Ok(Decoded::Dds(bytes, header))
+        }
+        "gltf" | "glb" => gltf::import_file(path).map(Decoded::Gltf),
+        extension if MESH_EXTENSIONS.contains(&extension) => {
+            // The obj, smd and ply readers exit on a file they can't open
+            if !archive::is_file(path) {
+                return Err(in_file("no such file".to_string()));
+            }
+            mesh_conversion::load_meshes(path).map(Decoded::Meshes)
+        }
+        extension if image_formats::EXTENSIONS.contains(&extension) => image_formats::read_file(path).map(Decoded::Image),
+        _ => Ok(Decoded::File)
+    }
+}
+
+/// Add a decoded file to the engine, on the main thread
+fn add(path: &str, decoded: Decoded) -> Result<(), String> {
+    // Materials load their textures and meshes their materials, which may have added it already
+    if is_loaded(path) {
+        return Ok(());
+    }
+
+    let filename = CString::new(path).unwrap();
+    unsafe {
+        let asset = match decoded {
+            Decoded::Dds(bytes, header) => header.to_texture(&bytes) as *mut asset,
+            Decoded::Image(pixels) => pixels.to_texture() as *mut asset,
+            Decoded::Meshes(source) => {
+                // The engine exits on a material that doesn't load, so it is checked and loaded first
+                if !source.material.is_empty() {
+                    let material = mapped(&source.material);
+                    if !is_loaded(&material) {
+                        hot_reload::check(&material).map_err(|err| format!("{}: material {}", path, err))?;
+                        let material = CString::new(material).unwrap();
+                        file_load(P(material.as_ptr()));
+                    }
+                }
+                source.to_renderable() as *mut asset
+            }
+            Decoded::Gltf(imported) => gltf::load_imported(path, &imported) as *mut asset,
+            Decoded::File => {
+                hot_reload::check(path)?;
+                file_load(P(filename.as_ptr()));
+                return Ok(());
+            }
+        };
+        file_add(P(filename.as_ptr()), asset);
+    }
+    Ok(())
+}
+
+fn spawn_worker(jobs: Arc<Mutex<Receiver<(usize, String)>>>, results: Sender<(usize, Result<Decoded, String>)>) -> JoinHandle<()> {
+    thread::spawn(move || loop {
+        let (id, path) = match jobs.lock().unwrap().recv() {
+            Ok(job) => job,
+            Err(_) => return
+        };
+        // A decoder bug shouldn't leave the file pending forever
+        let decoded = panic::catch_unwind(|| decode(&path)).unwrap_or_else(|_| Err(format!("{}: decoding failed", path)));
+        if results.send((id, decoded)).is_err() {
+            return;
+        }
+    })
+}
+
+/// Worker threads reading queued files and the files waiting to be added, see the module
+/// documentation
+pub struct Loader {
+    jobs: Option<Sender<(usize, String)>>,
+    results: Receiver<(usize, Result<Decoded, String>)>,
+    workers: Vec<JoinHandle<()>>,
+    /// Sorted by stage, then by when they were queued
+    pending: Vec<Pending>,
+    next_id: usize,
+    progress: Progress,
+    budget: Duration
+}
+
+impl Loader {
+    pub fn new(threads: usize) -> Loader {
+        let (jobs, job_receiver) = channel();
+        let (result_sender, results) = channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        let workers = (0..threads.max(1)).map(|_| spawn_worker(job_receiver.clone(), result_sender.clone())).collect();
+        Loader {
+            jobs: Some(jobs),
+            results: results,
+            workers: workers,
+            pending: Vec::new(),
+            next_id: 0,
+            progress: Progress::default(),
+            budget: DEFAULT_BUDGET
+        }
+    }
+
+    /// Time `upload` may spend per call, it always adds at least one ready file
+    pub fn set_budget(&mut self, budget: Duration) {
+        self.budget = budget;
+    }
+
+    /// Queue `filename` unless it is loaded or queued already
+    pub fn load_file(&mut self, filename: &str) {
+        let path = mapped(filename);
+        if is_loaded(&path) || self.pending.iter().any(|pending| pending.path == path) {
+            return;
+        }
+        if self.pending.is_empty() {
+            self.progress = Progress::default();
+        }
+
+        let pending = Pending { id: self.next_id, path: path.clone(), stage: stage(&path), decoded: None };
+        let i = self.pending.iter().position(|other| other.stage > pending.stage).unwrap_or(self.pending.len());
+        self.pending.insert(i, pending);
+        self.jobs.as_ref().unwrap().send((self.next_id, path)).unwrap();
+        self.next_id += 1;
+        self.progress.total += 1;
+    }
+
+    /// Queue the files in `folder` that aren't loaded, like `folder_load`
+    pub fn load_folder(&mut self, folder: &str) -> Result<(), String> {
+        let folder = mapped(folder);
+        let filenames = archive::folder_files(&folder).map_err(|err| format!("{}: {}", folder, err))?;
+        for filename in &filenames {
+            self.load_file(filename);
+        }
+        Ok(())
+    }
+
+    pub fn progress(&self) -> Progress {
+        self.progress.clone()
+    }
+
+    /// Add the files the workers have finished within the budget, returning how many were added
+    pub fn upload(&mut self) -> usize {
+        let deadline = Instant::now() + self.budget;
+        self.upload_until(Some(deadline))
+    }
+
+    /// Add every queued file, waiting for the workers
+    pub fn wait(&mut self) -> Progress {
+        loop {
+            self.upload_until(None);
+            if self.pending.is_empty() {
+                break;
+            }
+            match self.results.recv() {
+                Ok(result) => self.store(result),
+                Err(_) => break
+            }
+        }
+        self.progress()
+    }
+
+    fn store(&mut self, (id, decoded): (usize, Result<Decoded, String>)) {
+        if let Some(pending) = self.pending.iter_mut().find(|pending| pending.id == id) {
+            pending.decoded = Some(decoded);
+        }
+    }
+
+    /// A decoded file of the first stage still pending
+    fn next_ready(&self) -> Option<usize> {
+        let stage = self.pending.first()?.stage;
+        self.pending.iter().take_while(|pending| pending.stage == stage).position(|pending| pending.decoded.is_some())
+    }
+
+    fn upload_until(&mut self, deadline: Option<Instant>) -> usize {
+        while let Ok(result) = self.results.try_recv() {
+            self.store(result);
+        }
+
+        let mut added = 0;
+        while let Some(i) = self.next_ready() {
+            let Pending { path, decoded, .. } = self.pending.remove(i);
+            let result = decoded.unwrap().and_then(|decoded| add(&path, decoded));
+            if let Err(err) = result {
+                self.progress.errors.push(err);
+            }
+            self.progress.loaded += 1;
+            added += 1;
+            if deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+                break;
+            }
+        }
+        added
+    }
+}
+
+impl Drop for Loader {
+    fn drop(&mut self) {
+        // Workers stop once the queue is closed
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Width of the progress bar in pixels
+const BAR_WIDTH: f32 = 300.0;
+
+/// A spinner over a progress bar and a count of the files loaded, in the middle of the viewport,
+/// made of UI elements named after it. They are deleted with it.
+pub struct LoadingScreen {
+    name: String,
+    label: String
+}
+
+impl LoadingScreen {
+    pub fn new(name: &str) -> LoadingScreen {
+        let c_name = CString::new(name).unwrap();
+        unsafe {
+            let spinner = ui_elem_new_type_id(str("%s_spinner"), *SPINNER_TYPE, c_name.as_ptr()) as *mut ui_spinner;
+            (*spinner).color = vec4_white();
+
+            let slot = ui_elem_new_type_id(str("%s_slot"), *RECTANGLE_TYPE, c_name.as_ptr()) as *mut ui_rectangle;
+            ui_rectangle_set_color(slot, vec4_new(0.0, 0.0, 0.0, 0.5));
+            ui_rectangle_set_border(slot, 1.0, vec4_white());
+
+            ui_elem_new_type_id(str("%s_bar"), *RECTANGLE_TYPE, c_name.as_ptr());
+
+            let label = ui_elem_new_type_id(str("%s_label"), *TEXT_TYPE, c_name.as_ptr()) as *mut ui_text;
+            ui_text_align(label, TEXT_ALIGN_CENTER as c_int, TEXT_ALIGN_TOP as c_int);
+        }
+        LoadingScreen { name: name.to_string(), label: String::new() }
+    }
+
+    fn element(&self, part: &str, type_id: i32) -> *mut ui_elem {
+        let name = CString::new(format!("{}_{}", self.name, part)).unwrap();
+        unsafe { ui_elem_get_as_type_id(str("%s"), type_id, name.as_ptr()) }
+    }
+
+    /// Show `progress`, and follow the middle of the viewport when it is resized
+    pub fn update(&mut self, progress: &Progress) {
+        unsafe {
+            let center = vec2_new(graphics_viewport_width() as f32 * 0.5, graphics_viewport_height() as f32 * 0.5);
+
+            let spinner = self.element("spinner", *SPINNER_TYPE) as *mut ui_spinner;
+            let size = vec2_sub((*spinner).bottom_right, (*spinner).top_left);
+            (*spinner).top_left = vec2_new(center.x - size.x * 0.5, center.y - size.y);
+            (*spinner).bottom_right = vec2_add((*spinner).top_left, size);
+
+            let top_left = vec2_new(center.x - BAR_WIDTH * 0.5, center.y + 20.0);
+            let slot = self.element("slot", *RECTANGLE_TYPE) as *mut ui_rectangle;
+            ui_rectangle_move(slot, top_left);
+            ui_rectangle_resize(slot, vec2_new(BAR_WIDTH, 8.0));
+            let bar = self.element("bar", *RECTANGLE_TYPE) as *mut ui_rectangle;
+            ui_rectangle_move(bar, top_left);
+            ui_rectangle_resize(bar, vec2_new(BAR_WIDTH * progress.fraction(), 8.0));
+
+            let label = self.element("label", *TEXT_TYPE) as *mut ui_text;
+            let text = format!("Loading {} of {}", progress.loaded, progress.total);
+            if text != self.label {
+                let string = CString::new(text.as_str()).unwrap();
+                ui_text_draw_string(label, string.as_ptr() as *mut c_char);
+                self.label = text;
+            }
+            let position = vec2_new(center.x, center.y + 36.0);
+            if (*label).position.x != position.x || (*label).position.y != position.y {
+                ui_text_move(label, position);
+            }
+        }
+    }
+}
+
+impl Drop for LoadingScreen {
+    fn drop(&mut self) {
+        let name = CString::new(self.name.as_str()).unwrap();
+        unsafe {
+            for part in &["spinner", "slot", "bar", "label"] {
+                let part = CString::new(*part).unwrap();
+                ui_elem_delete(str("%s_%s"), name.as_ptr(), part.as_ptr());
+            }
+        }
+    }
+}
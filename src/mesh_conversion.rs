@@ -0,0 +1,402 @@
+use std::env;
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::path::Path;
+use std::ptr;
+use std::slice;
+use libc;
+use libc::{c_char, c_int, c_void};
+use corange::*;
+use collision_mesh::RENDERABLE_EXTENSIONS;
+use formats::bmf::{Bmf, Surface, Vertex, Weight};
+use gltf;
+
+const RIGGED_TRANSFORM: &'static str = "rigged renderables can't be scaled or flipped without their skeleton";
+
+/// Axis flips and scaling applied to positions, normals and tangents, plus attribute regeneration
+#[derive(Clone, Debug)]
+pub struct ConvertOptions {
+    /// Uniform scale applied to positions
+    pub scale: f32,
+    /// Negate the x, y and z axes
+    pub flip: [bool; 3],
+    /// The source is z up, rotate it so y is up like the engine
+    pub z_up: bool,
+    /// Regenerate normals with `mesh_generate_normals`
+    pub normals: bool,
+    /// Regenerate tangents with `mesh_generate_tangents`
+    pub tangents: bool
+}
+
+impl Default for ConvertOptions {
+    fn default() -> ConvertOptions {
+        ConvertOptions {
+            scale: 1.0,
+            flip: [false; 3],
+            z_up: false,
+            normals: false,
+            tangents: false
+        }
+    }
+}
+
+impl ConvertOptions {
+    /// Whether vertices are moved, which rigged renderables don't allow
+    pub fn transforms(&self) -> bool {
+        self.scale != 1.0 || self.flip.iter().any(|&flip| flip) || self.z_up
+    }
+
+    /// Whether the surfaces have to be rebuilt at all
+    pub fn modifies(&self) -> bool {
+        self.transforms() || self.normals || self.tangents
+    }
+
+    /// Rotate and flip a direction, without the scale
+    pub fn axes(&self, v: vec3) -> vec3 {
+        let v = if self.z_up { vec3 { x: v.x, y: v.z, z: -v.y } } else { v };
+        vec3 {
+            x: if self.flip[0] { -v.x } else { v.x },
+            y: if self.flip[1] { -v.y } else { v.y },
+            z: if self.flip[2] { -v.z } else { v.z }
+        }
+    }
+
+    /// Whether the transform mirrors, so triangle winding has to be reversed
+    pub fn mirrors(&self) -> bool {
+        self.flip.iter().filter(|&&flip| flip).count() % 2 == 1
+    }
+
+    /// Transform the vertices of `m` and regenerate its normals and tangents as asked
+    pub fn apply_mesh(&self, m: *mut mesh) {
+        unsafe {
+            if self.transforms() {
+                for j in 0..(*m).num_verts as isize {
+                    let v = &mut *(*m).verticies.offset(j);
+                    v.position = vec3_mul(self.axes(v.position), self.scale);
+                    v.normal = self.axes(v.normal);
+                    v.tangent = self.axes(v.tangent);
+                    v.binormal = self.axes(v.binormal);
+                }
+                if self.mirrors() {
+                    for t in 0..(*m).num_triangles as isize {
+                        ptr::swap((*m).triangles.offset(t * 3 + 1), (*m).triangles.offset(t * 3 + 2));
+                    }
+                }
+            }
+            if self.normals { mesh_generate_normals(m); }
+            if self.tangents { mesh_generate_tangents(m); }
+        }
+    }
+}
+
+/// Vertex and triangle count of one surface
+#[derive(Clone, Copy, Debug)]
+pub struct SurfaceReport {
+    pub vertices: usize,
+    pub triangles: usize
+}
+
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub surfaces: Vec<SurfaceReport>,
+    pub rigged: bool,
+    /// Material the `.bmf` refers to
+    pub material: String
+}
+
+impl Report {
+    pub fn vertices(&self) -> usize {
+        self.surfaces.iter().map(|surface| surface.vertices).sum()
+    }
+
+    pub fn triangles(&self) -> usize {
+        self.surfaces.iter().map(|surface| surface.triangles).sum()
+    }
+}
+
+/// Surface counts and material of `r`
+pub fn report(r: *mut renderable) -> Report {
+    unsafe {
+        Report {
+            surfaces: (0..(*r).num_surfaces).map(|i| {
+                let s = *(*r).surfaces.offset(i as isize);
+                SurfaceReport { vertices: (*s).num_verticies as usize, triangles: (*s).num_triangles as usize }
+            }).collect(),
+            rigged: (*r).is_rigged != 0,
+            material: CStr::from_ptr((*r).material.path.ptr.as_ptr()).to_string_lossy().into_owned()
+        }
+    }
+}
+
+/// Transform the surfaces of `r` and regenerate their normals and tangents as asked.
+/// Needs a GL context since surfaces are read back from and rebuilt into vertex buffers.
+pub fn apply(r: *mut renderable, options: &ConvertOptions) -> Result<(), String> {
+    if !options.modifies() {
+        return Ok(());
+    }
+    unsafe {
+        if (*r).is_rigged != 0 && options.transforms() {
+            return Err(RIGGED_TRANSFORM.to_string());
+        }
+
+        for i in 0..(*r).num_surfaces {
+            let count = (**(*r).surfaces.offset(i as isize)).num_verticies as usize;
+            let mut weights = vec![vertex_weight::default(); count];
+            let m = renderable_surface_to_mesh(r, i, weights.as_mut_ptr());
+            options.apply_mesh(m);
+            renderable_set_surface(r, i, m, weights.as_mut_ptr());
+            mesh_delete(m);
+        }
+        Ok(())
+    }
+}
+
+/// Write `r` with `bmf_save_file`. Needs a GL context to read the vertex buffers back.
+pub fn save(r: *mut renderable, path: &str) {
+    unsafe {
+        let path = CString::new(path).unwrap();
+        bmf_save_file(r, path.as_ptr() as *mut c_char);
+    }
+}
+
+/// Whether `path` has the extension of a registered renderable loader other than `.bmf`
+pub fn is_source(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase())
+        .map_or(false, |extension| extension != "bmf" && RENDERABLE_EXTENSIONS.contains(&extension.as_str()))
+}
+
+fn mapped(filename: &str) -> String {
+    unsafe {
+        let filename = CString::new(filename).unwrap();
+        let mapped = asset_map_filename(P(filename.as_ptr()));
+        CStr::from_ptr(mapped.ptr.as_ptr()).to_string_lossy().into_owned()
+    }
+}
+
+/// Meshes of a renderable file as its loader reads them, before they go into vertex buffers.
+/// The meshes are deleted with it.
+pub struct Source {
+    pub meshes: Vec<*mut mesh>,
+    /// One per vertex of every mesh, for rigged files
+    pub weights: Option<Vec<Vec<vertex_weight>>>,
+    /// The material path the loader gives the renderable as stored, empty for none
+    pub material: String
+}
+
+// The meshes are heap memory only the source points to, so files can be read on worker threads
+unsafe impl Send for Source {}
+
+impl Source {
+    /// The meshes as a model for the C functions taking one, valid while `self` is
+    pub fn model(&mut self) -> model {
+        model { num_meshes: self.meshes.len() as c_int, meshes: self.meshes.as_mut_ptr() }
+    }
+
+    /// Upload the meshes as the surfaces of a new renderable with a handle to `material`, which
+    /// isn't loaded. Needs a GL context.
+    pub fn to_renderable(&self) -> *mut renderable {
+        unsafe {
+            let r = renderable_new();
+            (*r).is_rigged = self.weights.is_some() as u8;
+            for (i, &m) in self.meshes.iter().enumerate() {
+                let surface = match self.weights {
+                    Some(ref weights) => {
+                        let surface = renderable_surface_new_rigged(m, weights[i].as_ptr() as *mut vertex_weight);
+                        (*surface).bound = mesh_bounding_sphere(m);
+                        surface
+                    }
+                    None => renderable_surface_new(m)
+                };
+                (*r).num_surfaces += 1;
+                (*r).surfaces = libc::realloc((*r).surfaces as *mut c_void,
+                    mem::size_of::<*mut renderable_surface>() * (*r).num_surfaces as usize) as *mut *mut renderable_surface;
+                *(*r).surfaces.offset((*r).num_surfaces as isize - 1) = surface;
+            }
+            if !self.material.is_empty() {
+                let material = CString::new(self.material.as_str()).unwrap();
+                (*r).material = asset_hndl_new(P(material.as_ptr()));
+            }
+            r
+        }
+    }
+}
+
+impl Drop for Source {
+    fn drop(&mut self) {
+        for &m in &self.meshes {
+            unsafe { mesh_delete(m); }
+        }
+    }
+}
+
+unsafe fn items<'a, T>(items: *const T, count: usize) -> &'a [T] {
+    if count == 0 { &[] } else { slice::from_raw_parts(items, count) }
+}
+
+/// Take the meshes out of a model the C loaders return, freeing the model itself
+unsafe fn take_meshes(m: *mut model) -> Vec<*mut mesh> {
+    let meshes = items((*m).meshes, (*m).num_meshes as usize).to_vec();
+    libc::free((*m).meshes as *mut c_void);
+    libc::free(m as *mut c_void);
+    meshes
+}
+
+/// A mesh holding the vertices and indices of a `.bmf` surface
+unsafe fn surface_mesh(surface: &Surface) -> *mut mesh {
+    let v3 = |v: [f32; 3]| vec3 { x: v[0], y: v[1], z: v[2] };
+    let m = mesh_new();
+    (*m).num_verts = surface.vertices.len() as c_int;
+    (*m).num_triangles = (surface.indices.len() / 3) as c_int;
+    (*m).verticies = libc::malloc(mem::size_of::<vertex>() * surface.vertices.len()) as *mut vertex;
+    (*m).triangles = libc::malloc(mem::size_of::<u32>() * surface.indices.len()) as *mut u32;
+    for (i, v) in surface.vertices.iter().enumerate() {
+        *(*m).verticies.offset(i as isize) = vertex {
+            position: v3(v.position),
+            normal: v3(v.normal),
+            tangent: v3(v.tangent),
+            binormal: v3(v.binormal),
+            color: vec4 { x: v.color[0], y: v.color[1], z: v.color[2], w: v.color[3] },
+            uvs: vec2 { x: v.uvs[0], y: v.uvs[1] }
+        };
+    }
+    ptr::copy_nonoverlapping(surface.indices.as_ptr(), (*m).triangles, surface.indices.len());
+    m
+}
+
+/// Read `filename` with the mesh half of its loader, which needs no GL context
+fn load_source(filename: &str, extension: &str) -> Result<Source, String> {
+    let path = CString::new(filename).unwrap();
+    let path = path.as_ptr() as *mut c_char;
+    unsafe {
+        match extension {
+            "bmf" => {
+                let bmf = Bmf::load(filename)?;
+                let weights = if bmf.rigged {
+                    Some(bmf.surfaces.iter().map(|surface| surface.weights.iter()
+                        .map(|weight| vertex_weight { bone_ids: weight.bone_ids, bone_weights: weight.bone_weights })
+                        .collect()).collect())
+                } else { None };
+                let meshes = bmf.surfaces.iter().map(|surface| surface_mesh(surface)).collect();
+                Ok(Source { meshes: meshes, weights: weights, material: bmf.material })
+            }
+            "obj" => Ok(Source { meshes: take_meshes(obj_load_model(path)), weights: None, material: material(filename, extension) }),
+            "ply" => Ok(Source { meshes: take_meshes(ply_load_model(path)), weights: None, material: material(filename, extension) }),
+            "smd" => {
+                let mut weights = ptr::null_mut();
+                let meshes = take_meshes(smd_load_model(path, &mut weights));
+                let copied = meshes.iter().enumerate().map(|(i, &m)| {
+                    let mesh_weights = *weights.offset(i as isize);
+                    let copy = items(mesh_weights, (*m).num_verts as usize).to_vec();
+                    libc::free(mesh_weights as *mut c_void);
+                    copy
+                }).collect();
+                libc::free(weights as *mut c_void);
+                Ok(Source { meshes: meshes, weights: Some(copied), material: material(filename, extension) })
+            }
+            _ => {
+                let loaded = gltf::load_meshes(filename)?;
+                let rigged = loaded.iter().any(|&(_, ref weights)| weights.is_some());
+                let meshes = loaded.iter().map(|&(m, _)| m).collect();
+                let weights = if rigged {
+                    Some(loaded.into_iter().map(|(_, weights)| weights.unwrap_or_default()).collect())
+                } else { None };
+                Ok(Source { meshes: meshes, weights: weights, material: material(filename, extension) })
+            }
+        }
+    }
+}
+
+/// Read the renderable file at `filename`, of any of `RENDERABLE_EXTENSIONS`, into meshes
+/// without a GL context
+pub fn load_meshes(filename: &str) -> Result<Source, String> {
+    let extension = Path::new(filename).extension().and_then(|extension| extension.to_str()).unwrap_or("").to_lowercase();
+    if !RENDERABLE_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(format!("{}: not a renderable, expected one of {}", filename, RENDERABLE_EXTENSIONS.join(", ")));
+    }
+    load_source(&mapped(filename), &extension)
+}
+
+/// `path` as `bmf_save_file` stores it, starting with `.` when it is below the working directory
+fn stored_path(path: &str) -> String {
+    let current = env::current_dir().map(|dir| dir.to_string_lossy().into_owned()).unwrap_or_default();
+    let path = if !current.is_empty() && path.starts_with(&current) {
+        format!(".{}", &path[current.len()..])
+    } else {
+        path.to_string()
+    };
+    path.replace('\\', "/")
+}
+
+/// The material the loader of `filename` gives its renderable: `<name>.mat` next to obj, smd and
+/// ply files if it exists, named up to the first '.', and the derived `<file>.mat` for glTF
+fn material(filename: &str, extension: &str) -> String {
+    if extension == "gltf" || extension == "glb" {
+        return stored_path(&mapped(&format!("{}.mat", filename)));
+    }
+    let path = Path::new(filename);
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+    let stem = name.split('.').next().unwrap_or(name);
+    let candidate = path.with_file_name(format!("{}.mat", stem));
+    if candidate.is_file() {
+        stored_path(&mapped(&candidate.to_string_lossy()))
+    } else {
+        String::new()
+    }
+}
+
+unsafe fn surface(m: *mut mesh, weights: Option<&Vec<vertex_weight>>) -> Surface {
+    let xyz = |v: vec3| [v.x, v.y, v.z];
+    Surface {
+        vertices: items((*m).verticies, (*m).num_verts as usize).iter().map(|v| Vertex {
+            position: xyz(v.position),
+            normal: xyz(v.normal),
+            tangent: xyz(v.tangent),
+            binormal: xyz(v.binormal),
+            uvs: [v.uvs.x, v.uvs.y],
+            color: [v.color.x, v.color.y, v.color.z, v.color.w]
+        }).collect(),
+        weights: weights.map_or(Vec::new(), |weights| weights.iter()
+            .map(|weight| Weight { bone_ids: weight.bone_ids, bone_weights: weight.bone_weights })
+            .collect()),
+        indices: items((*m).triangles, (*m).num_triangles as usize * 3).to_vec()
+    }
+}
+
+/// Read the mesh at `input`, apply `options` and write it to `output` as `.bmf`. Only the mesh
+/// half of the loaders runs, so this needs no GL context, doesn't touch the loaded assets and
+/// doesn't write the `.bmf` the obj, smd and ply loaders put next to their source.
+pub fn convert(input: &str, output: &str, options: &ConvertOptions) -> Result<Report, String> {
+    let input_path = Path::new(input);
+    if !input_path.is_file() {
+        return Err(format!("{}: no such file", input));
+    }
+    if !is_source(input_path) {
+        let sources: Vec<&str> = RENDERABLE_EXTENSIONS.iter().cloned().filter(|&extension| extension != "bmf").collect();
+        return Err(format!("{}: not a mesh, expected one of {}", input, sources.join(", ")));
+    }
+
+    let extension = input_path.extension().and_then(|extension| extension.to_str()).unwrap_or("").to_lowercase();
+    let filename = mapped(input);
+    let source = load_source(&filename, &extension)?;
+    let rigged = source.weights.is_some();
+    if rigged && options.transforms() {
+        return Err(format!("{}: {}", input, RIGGED_TRANSFORM));
+    }
+
+    let surfaces = source.meshes.iter().enumerate().map(|(i, &m)| {
+        options.apply_mesh(m);
+        unsafe { surface(m, source.weights.as_ref().map(|weights| &weights[i])) }
+    }).collect();
+    let bmf = Bmf { rigged: rigged, material: source.material.clone(), surfaces: surfaces };
+    bmf.save(output)?;
+
+    Ok(Report {
+        surfaces: bmf.surfaces.iter()
+            .map(|surface| SurfaceReport { vertices: surface.vertices.len(), triangles: surface.indices.len() / 3 })
+            .collect(),
+        rigged: rigged,
+        material: bmf.material
+    })
+}
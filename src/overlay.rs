@@ -0,0 +1,117 @@
+use std::ffi::CString;
+use libc::c_char;
+use corange;
+use corange::*;
+use follow_camera::FollowTarget;
+
+/// World position projected into viewport pixels, origin in the top left corner
+#[derive(Clone, Copy, Debug)]
+pub struct ScreenPosition {
+    pub x: f32,
+    pub y: f32,
+    /// Normalized device depth, -1 at the near and 1 at the far clip plane
+    pub depth: f32,
+    /// False for points behind the camera, whose coordinates are meaningless
+    pub visible: bool
+}
+
+impl ScreenPosition {
+    /// Whether the point is visible and inside the viewport
+    pub fn on_screen(&self) -> bool {
+        unsafe {
+            self.visible &&
+            self.x >= 0.0 && self.x <= graphics_viewport_width() as f32 &&
+            self.y >= 0.0 && self.y <= graphics_viewport_height() as f32
+        }
+    }
+}
+
+/// Project `position` through the view-projection matrix of `camera`
+pub fn world_to_screen(camera: *mut corange::camera, position: vec3) -> ScreenPosition {
+    unsafe {
+        let clip = mat4_mul_vec4(camera_view_proj_matrix(camera), vec4_new(position.x, position.y, position.z, 1.0));
+        let visible = clip.w > 0.0 && clip.z >= -clip.w;
+        let w = if clip.w.abs() > 1e-6 { clip.w } else { 1e-6 };
+        ScreenPosition {
+            x: (clip.x / w + 1.0) * 0.5 * graphics_viewport_width() as f32,
+            y: (1.0 - clip.y / w) * 0.5 * graphics_viewport_height() as f32,
+            depth: clip.z / w,
+            visible: visible
+        }
+    }
+}
+
+/// UI element registered with the UI manager under a name
+#[derive(Clone, Debug, PartialEq)]
+pub enum UiElement {
+    /// Text is moved to the projected point, its alignment decides the anchor
+    Text(String),
+    /// Buttons are centered on the projected point
+    Button(String)
+}
+
+impl UiElement {
+    pub fn name(&self) -> &str {
+        match *self {
+            UiElement::Text(ref name) => name,
+            UiElement::Button(ref name) => name
+        }
+    }
+}
+
+/// Keeps a UI element over the screen position of an entity, for name tags,
+/// health bars and waypoint markers
+#[derive(Clone)]
+pub struct Attachment {
+    pub element: UiElement,
+    pub target: FollowTarget,
+    /// World space offset from the target, e.g. above a character's head
+    pub offset: vec3,
+    /// Hide the element while the target is behind the camera
+    pub hide_behind: bool
+}
+
+// Lives in the engine's `ATTACHMENTS` global. Its target is only dereferenced in `engine::run`,
+// on the thread that owns the GL context.
+unsafe impl Send for Attachment {}
+
+impl Attachment {
+    pub fn new(element: UiElement, target: FollowTarget) -> Attachment {
+        unsafe {
+            Attachment {
+                element: element,
+                target: target,
+                offset: vec3_zero(),
+                hide_behind: true
+            }
+        }
+    }
+
+    pub fn update(&self, camera: *mut corange::camera) {
+        unsafe {
+            let name = CString::new(self.element.name()).unwrap();
+            let name = name.as_ptr() as *mut c_char;
+            if ui_elem_exists(name) == 0 { return; }
+
+            let screen = world_to_screen(camera, vec3_add(self.target.position(), self.offset));
+            let active = if screen.visible || !self.hide_behind { 1 } else { 0 };
+            match self.element {
+                UiElement::Text(_) => {
+                    let text = ui_elem_get_as_type_id(name, *TEXT_TYPE) as *mut ui_text;
+                    (*text).active = active;
+                    if screen.visible {
+                        ui_text_move(text, vec2_new(screen.x, screen.y));
+                    }
+                }
+                UiElement::Button(_) => {
+                    let button = ui_elem_get_as_type_id(name, *BUTTON_TYPE) as *mut ui_button;
+                    (*button).active = active;
+                    if screen.visible {
+                        let size = ui_button_size(button);
+                        ui_button_move(button, vec2_new(screen.x - size.x * 0.5, screen.y - size.y * 0.5));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,822 @@
+use std::f32;
+use corange::*;
+
+/// Collision shape of a rigid body in the local space of its physics object,
+/// scaled and rotated along with it
+#[derive(Clone, Copy)]
+pub enum Shape {
+    Sphere(f32),
+    /// Half extents along each axis
+    Box(vec3),
+    /// Radius along each axis
+    Ellipsoid(vec3),
+    /// The object's `collision_body`, collided as the convex hull of its vertices
+    Mesh
+}
+
+/// Physics object simulated by a physics world. Position, rotation, scale, velocity,
+/// acceleration, elasticity and friction are read from the entity every step and the
+/// results written back to it.
+pub struct RigidBody {
+    pub object: *mut physics_object,
+    pub shape: Shape,
+    /// Zero for kinematic bodies, which are moved by hand and never pushed by contacts
+    pub mass: f32,
+    /// Axis scaled by the rotation speed in radians per second
+    pub angular_velocity: vec3,
+    /// Fraction of linear velocity lost per second
+    pub linear_damping: f32,
+    /// Fraction of angular velocity lost per second
+    pub angular_damping: f32,
+    hull: Vec<vec3>
+}
+
+impl RigidBody {
+    pub fn new(object: *mut physics_object, shape: Shape, mass: f32) -> RigidBody {
+        unsafe {
+            let mut hull = Vec::new();
+            if let Shape::Mesh = shape {
+                if asset_hndl_isnull(&mut (*object).collision_body) == 0 {
+                    cmesh_vertices(asset_hndl_ptr(&mut (*object).collision_body) as *mut cmesh, &mut hull);
+                }
+            }
+            RigidBody {
+                object: object,
+                shape: shape,
+                mass: mass,
+                angular_velocity: vec3_zero(),
+                linear_damping: 0.05,
+                angular_damping: 0.3,
+                hull: hull
+            }
+        }
+    }
+
+    fn inverse_mass(&self) -> f32 {
+        if self.mass > 0.0 { 1.0 / self.mass } else { 0.0 }
+    }
+
+    /// Inverse of a scalar approximation of the inertia tensor
+    fn inverse_inertia(&self) -> f32 {
+        unsafe {
+            if self.mass <= 0.0 { return 0.0; }
+            let scale = (*self.object).scale;
+            let inertia = match self.shape {
+                Shape::Sphere(radius) => {
+                    let radius = radius * max_component(scale);
+                    0.4 * self.mass * radius * radius
+                }
+                Shape::Box(half) => 2.0 / 9.0 * self.mass * vec3_length_sqrd(vec3_mul_vec3(half, scale)),
+                Shape::Ellipsoid(radii) => 2.0 / 15.0 * self.mass * vec3_length_sqrd(vec3_mul_vec3(radii, scale)),
+                Shape::Mesh => {
+                    let radius = self.radius();
+                    0.4 * self.mass * radius * radius
+                }
+            };
+            if inertia > 0.0 { 1.0 / inertia } else { 0.0 }
+        }
+    }
+
+    /// World space radius of a sphere around the object position enclosing the shape
+    fn radius(&self) -> f32 {
+        unsafe {
+            let local = match self.shape {
+                Shape::Sphere(radius) => radius,
+                Shape::Box(half) => vec3_length(half),
+                Shape::Ellipsoid(radii) => max_component(radii),
+                Shape::Mesh => self.hull.iter().fold(0.0f32, |radius, &v| radius.max(vec3_length(v)))
+            };
+            local * max_component((*self.object).scale)
+        }
+    }
+
+    fn convex(&self) -> Convex {
+        unsafe {
+            let object = self.object;
+            let center = (*object).position;
+            let scale = (*object).scale;
+            let rotation = mat4_to_mat3(mat4_rotation_quat((*object).rotation));
+            match self.shape {
+                Shape::Sphere(radius) => Convex::Sphere(center, radius * max_component(scale)),
+                Shape::Box(half) => Convex::Box(center, rotation, vec3_mul_vec3(half, scale)),
+                Shape::Ellipsoid(radii) => Convex::Ellipsoid(center, rotation, vec3_mul_vec3(radii, scale)),
+                Shape::Mesh => {
+                    let world = physics_object_world(object);
+                    Convex::Hull(center, self.hull.iter().map(|&v| mat4_mul_vec3(world, v)).collect())
+                }
+            }
+        }
+    }
+}
+
+fn max_component(v: vec3) -> f32 {
+    v.x.abs().max(v.y.abs()).max(v.z.abs())
+}
+
+fn cmesh_vertices(mesh: *mut cmesh, out: &mut Vec<vec3>) {
+    unsafe {
+        for i in 0..(*mesh).triangles_num {
+            let triangle = *(*mesh).triangles.offset(i as isize);
+            out.push(triangle.a);
+            out.push(triangle.b);
+            out.push(triangle.c);
+        }
+    }
+}
+
+/// Convex volume in world space described by its support function
+enum Convex {
+    Sphere(vec3, f32),
+    Box(vec3, mat3, vec3),
+    Ellipsoid(vec3, mat3, vec3),
+    Hull(vec3, Vec<vec3>),
+    Triangle([vec3; 3])
+}
+
+impl Convex {
+    fn center(&self) -> vec3 {
+        unsafe {
+            match *self {
+                Convex::Sphere(center, _) |
+                Convex::Box(center, _, _) |
+                Convex::Ellipsoid(center, _, _) |
+                Convex::Hull(center, _) => center,
+                Convex::Triangle(ref points) => vec3_div(vec3_add(vec3_add(points[0], points[1]), points[2]), 3.0)
+            }
+        }
+    }
+
+    /// Furthest point of the volume in direction `d`
+    fn support(&self, d: vec3) -> vec3 {
+        unsafe {
+            match *self {
+                Convex::Sphere(center, radius) => vec3_add(center, vec3_mul(vec3_normalize(d), radius)),
+                Convex::Box(center, rotation, half) => {
+                    let local = mat3_mul_vec3(mat3_transpose(rotation), d);
+                    let corner = vec3_new(
+                        if local.x < 0.0 { -half.x } else { half.x },
+                        if local.y < 0.0 { -half.y } else { half.y },
+                        if local.z < 0.0 { -half.z } else { half.z });
+                    vec3_add(center, mat3_mul_vec3(rotation, corner))
+                }
+                Convex::Ellipsoid(center, rotation, radii) => {
+                    let local = mat3_mul_vec3(mat3_transpose(rotation), d);
+                    let stretched = vec3_length(vec3_mul_vec3(local, radii));
+                    if stretched == 0.0 { return center; }
+                    let point = vec3_div(vec3_mul_vec3(local, vec3_mul_vec3(radii, radii)), stretched);
+                    vec3_add(center, mat3_mul_vec3(rotation, point))
+                }
+                Convex::Hull(center, ref points) => furthest(points, d).unwrap_or(center),
+                Convex::Triangle(ref points) => furthest(points, d).unwrap()
+            }
+        }
+    }
+
+    /// Corners which can rest flat on other volumes, for building contact manifolds
+    fn corners(&self) -> Vec<vec3> {
+        unsafe {
+            match *self {
+                Convex::Box(center, rotation, half) => {
+                    let mut corners = Vec::with_capacity(8);
+                    for &x in &[-half.x, half.x] {
+                        for &y in &[-half.y, half.y] {
+                            for &z in &[-half.z, half.z] {
+                                corners.push(vec3_add(center, mat3_mul_vec3(rotation, vec3_new(x, y, z))));
+                            }
+                        }
+                    }
+                    corners
+                }
+                _ => Vec::new()
+            }
+        }
+    }
+
+    /// Whether `point`, lying on the surface of the volume, is within one of its flat faces
+    fn covers(&self, point: vec3) -> bool {
+        unsafe {
+            match *self {
+                Convex::Box(center, rotation, half) => {
+                    let local = mat3_mul_vec3(mat3_transpose(rotation), vec3_sub(point, center));
+                    local.x.abs() <= half.x + CONTACT_TOLERANCE &&
+                    local.y.abs() <= half.y + CONTACT_TOLERANCE &&
+                    local.z.abs() <= half.z + CONTACT_TOLERANCE
+                }
+                Convex::Triangle(ref points) => {
+                    let (u, v, w) = barycentric(point, points[0], points[1], points[2]);
+                    u >= -CONTACT_TOLERANCE && v >= -CONTACT_TOLERANCE && w >= -CONTACT_TOLERANCE
+                }
+                Convex::Hull(_, _) => true,
+                Convex::Sphere(_, _) | Convex::Ellipsoid(_, _, _) => false
+            }
+        }
+    }
+}
+
+fn furthest(points: &[vec3], d: vec3) -> Option<vec3> {
+    unsafe {
+        let mut best: Option<(vec3, f32)> = None;
+        for &point in points {
+            let distance = vec3_dot(point, d);
+            if best.map_or(true, |(_, b)| distance > b) {
+                best = Some((point, distance));
+            }
+        }
+        best.map(|(point, _)| point)
+    }
+}
+
+/// Barycentric coordinates of `p` projected onto the plane of triangle `abc`
+fn barycentric(p: vec3, a: vec3, b: vec3, c: vec3) -> (f32, f32, f32) {
+    unsafe {
+        let (v0, v1, v2) = (vec3_sub(b, a), vec3_sub(c, a), vec3_sub(p, a));
+        let (d00, d01, d11) = (vec3_dot(v0, v0), vec3_dot(v0, v1), vec3_dot(v1, v1));
+        let (d20, d21) = (vec3_dot(v2, v0), vec3_dot(v2, v1));
+        let denominator = d00 * d11 - d01 * d01;
+        if denominator.abs() < 1e-12 { return (1.0, 0.0, 0.0); }
+        let v = (d11 * d20 - d01 * d21) / denominator;
+        let w = (d00 * d21 - d01 * d20) / denominator;
+        (1.0 - v - w, v, w)
+    }
+}
+
+/// Point of the Minkowski difference of two volumes along with the points it came from
+#[derive(Clone, Copy)]
+struct Vertex {
+    point: vec3,
+    a: vec3
+}
+
+fn minkowski(a: &Convex, b: &Convex, d: vec3) -> Vertex {
+    unsafe {
+        let pa = a.support(d);
+        let pb = b.support(vec3_neg(d));
+        Vertex { point: vec3_sub(pa, pb), a: pa }
+    }
+}
+
+const GJK_MAX_ITERATIONS: usize = 64;
+const EPA_MAX_ITERATIONS: usize = 64;
+const EPA_TOLERANCE: f32 = 0.0001;
+
+/// Tetrahedron of the Minkowski difference enclosing the origin, if the volumes intersect
+fn gjk(a: &Convex, b: &Convex) -> Option<[Vertex; 4]> {
+    unsafe {
+        let mut d = vec3_sub(a.center(), b.center());
+        if vec3_length_sqrd(d) < 1e-12 { d = vec3_new(1.0, 0.0, 0.0); }
+
+        // Simplex points, newest first
+        let mut s = [minkowski(a, b, d); 4];
+        d = vec3_neg(s[0].point);
+        s[1] = s[0];
+        s[0] = minkowski(a, b, d);
+        if vec3_dot(s[0].point, d) < 0.0 { return None; }
+
+        let mut count = 2;
+        d = line_direction(s[0].point, s[1].point);
+
+        for _ in 0..GJK_MAX_ITERATIONS {
+            if vec3_length_sqrd(d) < 1e-12 { return None; }
+            let v = minkowski(a, b, d);
+            if vec3_dot(v.point, d) < 0.0 { return None; }
+            s = [v, s[0], s[1], s[2]];
+            count += 1;
+
+            let ao = vec3_neg(s[0].point);
+            let ab = vec3_sub(s[1].point, s[0].point);
+            let ac = vec3_sub(s[2].point, s[0].point);
+
+            if count == 3 {
+                let abc = vec3_cross(ab, ac);
+                if vec3_dot(vec3_cross(ab, abc), ao) > 0.0 {
+                    count = 2;
+                    d = line_direction(s[0].point, s[1].point);
+                } else if vec3_dot(vec3_cross(abc, ac), ao) > 0.0 {
+                    s[1] = s[2];
+                    count = 2;
+                    d = line_direction(s[0].point, s[1].point);
+                } else if vec3_dot(abc, ao) > 0.0 {
+                    d = abc;
+                } else {
+                    s.swap(1, 2);
+                    d = vec3_neg(abc);
+                }
+            } else {
+                let ad = vec3_sub(s[3].point, s[0].point);
+                let abc = vec3_cross(ab, ac);
+                let acd = vec3_cross(ac, ad);
+                let adb = vec3_cross(ad, ab);
+                count = 3;
+                if vec3_dot(abc, ao) > 0.0 {
+                    d = abc;
+                } else if vec3_dot(acd, ao) > 0.0 {
+                    s = [s[0], s[2], s[3], s[3]];
+                    d = acd;
+                } else if vec3_dot(adb, ao) > 0.0 {
+                    s = [s[0], s[3], s[1], s[3]];
+                    d = adb;
+                } else {
+                    return Some(s);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Direction from the segment `ab` towards the origin
+fn line_direction(a: vec3, b: vec3) -> vec3 {
+    unsafe {
+        let ab = vec3_sub(b, a);
+        let d = vec3_cross(vec3_cross(ab, vec3_neg(a)), ab);
+        if vec3_length_sqrd(d) > 1e-12 { return d; }
+        // Origin lies on the line, any perpendicular will do
+        let d = vec3_cross(ab, vec3_new(1.0, 0.0, 0.0));
+        if vec3_length_sqrd(d) > 1e-12 { d } else { vec3_cross(ab, vec3_new(0.0, 0.0, 1.0)) }
+    }
+}
+
+/// Face of the expanding polytope with its outward normal and distance from the origin
+#[derive(Clone, Copy)]
+struct Face {
+    indices: [usize; 3],
+    normal: vec3,
+    distance: f32
+}
+
+/// Face wound to point away from `inside`, a point within the polytope
+fn face(vertices: &[Vertex], inside: vec3, i: usize, j: usize, k: usize) -> Face {
+    unsafe {
+        let (a, b, c) = (vertices[i].point, vertices[j].point, vertices[k].point);
+        let normal = vec3_normalize(vec3_cross(vec3_sub(b, a), vec3_sub(c, a)));
+        if vec3_length_sqrd(normal) == 0.0 {
+            return Face { indices: [i, j, k], normal: normal, distance: f32::MAX };
+        }
+        if vec3_dot(normal, vec3_sub(a, inside)) < 0.0 {
+            Face { indices: [i, k, j], normal: vec3_neg(normal), distance: -vec3_dot(normal, a) }
+        } else {
+            Face { indices: [i, j, k], normal: normal, distance: vec3_dot(normal, a) }
+        }
+    }
+}
+
+/// Penetration normal pointing from `a` to `b`, depth and contact point on `a`
+fn epa(a: &Convex, b: &Convex, simplex: [Vertex; 4]) -> (vec3, f32, vec3) {
+    unsafe {
+        let mut vertices = simplex.to_vec();
+        let inside = vec3_div(simplex.iter().fold(vec3_zero(), |sum, v| vec3_add(sum, v.point)), 4.0);
+        let mut faces = vec![
+            face(&vertices, inside, 0, 1, 2), face(&vertices, inside, 0, 2, 3),
+            face(&vertices, inside, 0, 3, 1), face(&vertices, inside, 1, 3, 2)];
+
+        let mut closest = faces[0];
+        for _ in 0..EPA_MAX_ITERATIONS {
+            closest = *faces.iter().fold(&faces[0], |best, f| if f.distance < best.distance { f } else { best });
+            if closest.distance == f32::MAX { break; }
+
+            let v = minkowski(a, b, closest.normal);
+            if vec3_dot(v.point, closest.normal) - closest.distance < EPA_TOLERANCE { break; }
+
+            // Remove faces seen from the new point, keeping the edges of the hole
+            let mut edges: Vec<(usize, usize)> = Vec::new();
+            faces.retain(|f| {
+                if vec3_dot(f.normal, vec3_sub(v.point, vertices[f.indices[0]].point)) <= 0.0 {
+                    return true;
+                }
+                for e in 0..3 {
+                    let edge = (f.indices[e], f.indices[(e + 1) % 3]);
+                    match edges.iter().position(|&(i, j)| i == edge.1 && j == edge.0) {
+                        Some(shared) => { edges.swap_remove(shared); }
+                        None => edges.push(edge)
+                    }
+                }
+                false
+            });
+            if edges.is_empty() { break; }
+
+            vertices.push(v);
+            let index = vertices.len() - 1;
+            for (i, j) in edges {
+                faces.push(face(&vertices, inside, i, j, index));
+            }
+        }
+
+        let [i, j, k] = closest.indices;
+        let projected = vec3_mul(closest.normal, closest.distance);
+        let (u, v, w) = barycentric(projected, vertices[i].point, vertices[j].point, vertices[k].point);
+        let point = vec3_add(vec3_add(vec3_mul(vertices[i].a, u), vec3_mul(vertices[j].a, v)), vec3_mul(vertices[k].a, w));
+        (closest.normal, closest.distance.min(1e6), point)
+    }
+}
+
+/// Largest separation at which contact points are still kept, in world units
+const CONTACT_TOLERANCE: f32 = 0.01;
+/// Penetration left uncorrected so resting contacts persist between steps
+const CONTACT_SLOP: f32 = 0.005;
+/// Fraction of the remaining penetration corrected every step
+const CONTACT_CORRECTION: f32 = 0.4;
+/// Approach speed below which contacts don't bounce
+const RESTITUTION_THRESHOLD: f32 = 0.5;
+
+struct ContactPoint {
+    point: vec3,
+    depth: f32
+}
+
+/// Contacts between a body and another body (`Some` index) or static geometry (`None`).
+/// The normal points from the first body to the other.
+struct Manifold {
+    a: usize,
+    b: Option<usize>,
+    normal: vec3,
+    depth: f32,
+    points: Vec<ContactPoint>
+}
+
+fn collide(a: &Convex, b: &Convex) -> Option<(vec3, f32, Vec<ContactPoint>)> {
+    unsafe {
+        let simplex = match gjk(a, b) { Some(simplex) => simplex, None => return None };
+        let (normal, depth, point) = epa(a, b, simplex);
+        if vec3_length_sqrd(normal) == 0.0 { return None; }
+
+        // Flat faces resting on each other touch at several corners
+        let mut points = Vec::new();
+        let deepest_b = vec3_dot(b.support(vec3_neg(normal)), normal);
+        for corner in a.corners() {
+            let depth = vec3_dot(corner, normal) - deepest_b;
+            if depth > -CONTACT_TOLERANCE && b.covers(vec3_sub(corner, vec3_mul(normal, depth))) {
+                points.push(ContactPoint { point: vec3_sub(corner, vec3_mul(normal, depth * 0.5)), depth: depth });
+            }
+        }
+        let deepest_a = vec3_dot(a.support(normal), normal);
+        for corner in b.corners() {
+            let depth = deepest_a - vec3_dot(corner, normal);
+            if depth > -CONTACT_TOLERANCE && a.covers(vec3_add(corner, vec3_mul(normal, depth))) {
+                points.push(ContactPoint { point: vec3_add(corner, vec3_mul(normal, depth * 0.5)), depth: depth });
+            }
+        }
+        if points.is_empty() {
+            points.push(ContactPoint { point: vec3_sub(point, vec3_mul(normal, depth * 0.5)), depth: depth });
+        }
+
+        Some((normal, depth, points))
+    }
+}
+
+/// Whether a mesh space box transformed by `world` comes within the bounding sphere
+fn node_near(node: &cmesh_node, world: mat4, bound: sphere) -> bool {
+    unsafe {
+        let center = mat4_mul_vec3(world, vec3_div(vec3_add(node.min, node.max), 2.0));
+        let half = vec3_div(vec3_sub(node.max, node.min), 2.0);
+        let extent = vec3_new(
+            world.xx.abs() * half.x + world.xy.abs() * half.y + world.xz.abs() * half.z,
+            world.yx.abs() * half.x + world.yy.abs() * half.y + world.yz.abs() * half.z,
+            world.zx.abs() * half.x + world.zy.abs() * half.y + world.zz.abs() * half.z);
+        let offset = vec3_sub(bound.center, center);
+        let outside = vec3_new(
+            (offset.x.abs() - extent.x).max(0.0),
+            (offset.y.abs() - extent.y).max(0.0),
+            (offset.z.abs() - extent.z).max(0.0));
+        vec3_length(outside) <= bound.radius
+    }
+}
+
+/// Triangles of a collision mesh in world space near a bounding sphere
+fn cmesh_triangles(mesh: *mut cmesh, index: isize, world: mat4, bound: sphere, out: &mut Vec<[vec3; 3]>) {
+    unsafe {
+        let node = &*(*mesh).nodes.offset(index);
+        if !node_near(node, world, bound) { return; }
+        if node.count == 0 {
+            cmesh_triangles(mesh, node.first as isize, world, bound, out);
+            cmesh_triangles(mesh, node.first as isize + 1, world, bound, out);
+            return;
+        }
+
+        for i in node.first..node.first + node.count {
+            let triangle = *(*mesh).triangles.offset(i as isize);
+            let a = mat4_mul_vec3(world, triangle.a);
+            let b = mat4_mul_vec3(world, triangle.b);
+            let c = mat4_mul_vec3(world, triangle.c);
+            let normal = vec3_normalize(vec3_cross(vec3_sub(b, a), vec3_sub(c, a)));
+            if vec3_dot(vec3_sub(bound.center, a), normal).abs() > bound.radius { continue; }
+            let center = vec3_div(vec3_add(vec3_add(a, b), c), 3.0);
+            let extent = vec3_dist(center, a).max(vec3_dist(center, b)).max(vec3_dist(center, c));
+            if vec3_dist(center, bound.center) > extent + bound.radius { continue; }
+            out.push([a, b, c]);
+        }
+    }
+}
+
+/// Per body state while solving a step
+#[derive(Clone, Copy)]
+struct Motion {
+    velocity: vec3,
+    angular_velocity: vec3,
+    inverse_mass: f32,
+    inverse_inertia: f32,
+    center: vec3
+}
+
+impl Motion {
+    fn fixed() -> Motion {
+        unsafe {
+            Motion {
+                velocity: vec3_zero(),
+                angular_velocity: vec3_zero(),
+                inverse_mass: 0.0,
+                inverse_inertia: 0.0,
+                center: vec3_zero()
+            }
+        }
+    }
+
+    fn point_velocity(&self, r: vec3) -> vec3 {
+        unsafe { vec3_add(self.velocity, vec3_cross(self.angular_velocity, r)) }
+    }
+
+    fn apply(&mut self, r: vec3, impulse: vec3) {
+        unsafe {
+            self.velocity = vec3_add(self.velocity, vec3_mul(impulse, self.inverse_mass));
+            self.angular_velocity = vec3_add(self.angular_velocity, vec3_mul(vec3_cross(r, impulse), self.inverse_inertia));
+        }
+    }
+
+    fn effective_mass(&self, r: vec3, direction: vec3) -> f32 {
+        unsafe { self.inverse_mass + self.inverse_inertia * vec3_length_sqrd(vec3_cross(r, direction)) }
+    }
+}
+
+/// Contact point prepared for the impulse solver
+struct Constraint {
+    a: usize,
+    b: Option<usize>,
+    ra: vec3,
+    rb: vec3,
+    normal: vec3,
+    tangents: [vec3; 2],
+    target: f32,
+    friction: f32,
+    normal_impulse: f32,
+    tangent_impulses: [f32; 2]
+}
+
+/// Collection of rigid bodies and static colliders stepped together at a fixed timestep.
+/// Doesn't need a window or renderer so it can run headless.
+pub struct PhysicsWorld {
+    /// Length of a simulation step in seconds
+    pub timestep: f32,
+    /// Most steps taken per update, time beyond that is dropped
+    pub max_steps: usize,
+    /// Impulse solver iterations per step
+    pub iterations: usize,
+    pub bodies: Vec<RigidBody>,
+    /// Static objects whose collision bodies dynamic bodies collide with
+    pub statics: Vec<*mut static_object>,
+    accumulator: f32
+}
+
+impl Default for PhysicsWorld {
+    fn default() -> PhysicsWorld {
+        PhysicsWorld {
+            timestep: 1.0 / 60.0,
+            max_steps: 5,
+            iterations: 10,
+            bodies: Vec::new(),
+            statics: Vec::new(),
+            accumulator: 0.0
+        }
+    }
+}
+
+impl PhysicsWorld {
+    pub fn new() -> PhysicsWorld {
+        PhysicsWorld::default()
+    }
+
+    pub fn add_body(&mut self, object: *mut physics_object, shape: Shape, mass: f32) -> &mut RigidBody {
+        self.bodies.push(RigidBody::new(object, shape, mass));
+        self.bodies.last_mut().unwrap()
+    }
+
+    pub fn body(&mut self, object: *mut physics_object) -> Option<&mut RigidBody> {
+        self.bodies.iter_mut().find(|body| body.object == object)
+    }
+
+    pub fn remove_body(&mut self, object: *mut physics_object) {
+        self.bodies.retain(|body| body.object != object);
+    }
+
+    pub fn add_static(&mut self, object: *mut static_object) {
+        self.statics.push(object);
+    }
+
+    pub fn remove_static(&mut self, object: *mut static_object) {
+        self.statics.retain(|&s| s != object);
+    }
+
+    /// Advance by `elapsed` seconds in fixed steps, returns the number of steps taken
+    pub fn update(&mut self, elapsed: f32) -> usize {
+        self.accumulator += elapsed;
+        let mut steps = 0;
+        while self.accumulator >= self.timestep && steps < self.max_steps {
+            self.step();
+            self.accumulator -= self.timestep;
+            steps += 1;
+        }
+        if steps == self.max_steps {
+            self.accumulator = self.accumulator.min(self.timestep);
+        }
+        steps
+    }
+
+    /// Advance the simulation by a single timestep
+    pub fn step(&mut self) {
+        unsafe {
+            let h = self.timestep;
+
+            // Integrate accelerations
+            let mut motions: Vec<Motion> = self.bodies.iter().map(|body| {
+                let object = body.object;
+                let mut motion = Motion {
+                    velocity: (*object).velocity,
+                    angular_velocity: body.angular_velocity,
+                    inverse_mass: body.inverse_mass(),
+                    inverse_inertia: body.inverse_inertia(),
+                    center: (*object).position
+                };
+                if body.mass > 0.0 {
+                    motion.velocity = vec3_add(motion.velocity, vec3_mul((*object).acceleration, h));
+                    motion.velocity = vec3_mul(motion.velocity, 1.0 / (1.0 + h * body.linear_damping));
+                    motion.angular_velocity = vec3_mul(motion.angular_velocity, 1.0 / (1.0 + h * body.angular_damping));
+                }
+                motion
+            }).collect();
+
+            let manifolds = self.contacts();
+            let mut constraints = self.constraints(&manifolds, &motions);
+
+            // Solve contact impulses
+            for _ in 0..self.iterations {
+                for c in constraints.iter_mut() {
+                    let mut ma = motions[c.a];
+                    let mut mb = c.b.map_or(Motion::fixed(), |b| motions[b]);
+
+                    let relative = vec3_sub(mb.point_velocity(c.rb), ma.point_velocity(c.ra));
+                    let k = ma.effective_mass(c.ra, c.normal) + mb.effective_mass(c.rb, c.normal);
+                    if k <= 0.0 { continue; }
+                    let impulse = (c.target - vec3_dot(relative, c.normal)) / k;
+                    let total = (c.normal_impulse + impulse).max(0.0);
+                    let impulse = vec3_mul(c.normal, total - c.normal_impulse);
+                    c.normal_impulse = total;
+                    ma.apply(c.ra, vec3_neg(impulse));
+                    mb.apply(c.rb, impulse);
+
+                    for t in 0..2 {
+                        let tangent = c.tangents[t];
+                        let relative = vec3_sub(mb.point_velocity(c.rb), ma.point_velocity(c.ra));
+                        let k = ma.effective_mass(c.ra, tangent) + mb.effective_mass(c.rb, tangent);
+                        if k <= 0.0 { continue; }
+                        let limit = c.friction * c.normal_impulse;
+                        let impulse = -vec3_dot(relative, tangent) / k;
+                        let total = (c.tangent_impulses[t] + impulse).max(-limit).min(limit);
+                        let impulse = vec3_mul(tangent, total - c.tangent_impulses[t]);
+                        c.tangent_impulses[t] = total;
+                        ma.apply(c.ra, vec3_neg(impulse));
+                        mb.apply(c.rb, impulse);
+                    }
+
+                    motions[c.a] = ma;
+                    if let Some(b) = c.b { motions[b] = mb; }
+                }
+            }
+
+            // Push overlapping bodies apart
+            let mut corrections = vec![vec3_zero(); self.bodies.len()];
+            for m in &manifolds {
+                let ia = motions[m.a].inverse_mass;
+                let ib = m.b.map_or(0.0, |b| motions[b].inverse_mass);
+                if ia + ib <= 0.0 { continue; }
+                let amount = vec3_mul(m.normal, (m.depth - CONTACT_SLOP).max(0.0) * CONTACT_CORRECTION / (ia + ib));
+                corrections[m.a] = vec3_sub(corrections[m.a], vec3_mul(amount, ia));
+                if let Some(b) = m.b {
+                    corrections[b] = vec3_add(corrections[b], vec3_mul(amount, ib));
+                }
+            }
+
+            // Integrate velocities and write results back to the entities
+            for (i, body) in self.bodies.iter_mut().enumerate() {
+                let object = body.object;
+                let motion = motions[i];
+                (*object).previous_position = (*object).position;
+                (*object).position = vec3_add(vec3_add((*object).position, vec3_mul(motion.velocity, h)), corrections[i]);
+                (*object).velocity = motion.velocity;
+                body.angular_velocity = motion.angular_velocity;
+
+                let speed = vec3_length(motion.angular_velocity);
+                if speed > 0.0 {
+                    let axis = vec3_div(motion.angular_velocity, speed);
+                    let spin = quat_angle_axis(speed * h, axis);
+                    (*object).rotation = quat_normalize(quat_mul_quat(spin, (*object).rotation));
+                    (*object).angular_velocity = quat_angle_axis(speed, axis);
+                } else {
+                    (*object).angular_velocity = quat_id();
+                }
+            }
+        }
+    }
+
+    /// Contacts between dynamic bodies and with static collision meshes
+    fn contacts(&self) -> Vec<Manifold> {
+        unsafe {
+            let convexes: Vec<Convex> = self.bodies.iter().map(|body| body.convex()).collect();
+            let bounds: Vec<sphere> = self.bodies.iter().map(|body| {
+                sphere_new((*body.object).position, body.radius() + CONTACT_TOLERANCE)
+            }).collect();
+
+            let mut manifolds = Vec::new();
+            for a in 0..self.bodies.len() {
+                for b in (a + 1)..self.bodies.len() {
+                    if self.bodies[a].mass <= 0.0 && self.bodies[b].mass <= 0.0 { continue; }
+                    if vec3_dist(bounds[a].center, bounds[b].center) > bounds[a].radius + bounds[b].radius { continue; }
+                    if let Some((normal, depth, points)) = collide(&convexes[a], &convexes[b]) {
+                        manifolds.push(Manifold { a: a, b: Some(b), normal: normal, depth: depth, points: points });
+                    }
+                }
+            }
+
+            let mut triangles = Vec::new();
+            for &object in &self.statics {
+                if asset_hndl_isnull(&mut (*object).collision_body) != 0 { continue; }
+                let mesh = asset_hndl_ptr(&mut (*object).collision_body) as *mut cmesh;
+                let world = static_object_world(object);
+                if (*mesh).nodes_num == 0 { continue; }
+                let mesh_bound = cmesh_bound(mesh);
+                let mesh_bound = sphere_new(mat4_mul_vec3(world, mesh_bound.center), mesh_bound.radius * max_component((*object).scale));
+
+                for a in 0..self.bodies.len() {
+                    if self.bodies[a].mass <= 0.0 { continue; }
+                    if vec3_dist(bounds[a].center, mesh_bound.center) > bounds[a].radius + mesh_bound.radius { continue; }
+                    triangles.clear();
+                    cmesh_triangles(mesh, 0, world, bounds[a], &mut triangles);
+                    for &triangle in &triangles {
+                        if let Some((normal, depth, points)) = collide(&convexes[a], &Convex::Triangle(triangle)) {
+                            manifolds.push(Manifold { a: a, b: None, normal: normal, depth: depth, points: points });
+                        }
+                    }
+                }
+            }
+            manifolds
+        }
+    }
+
+    fn constraints(&self, manifolds: &[Manifold], motions: &[Motion]) -> Vec<Constraint> {
+        unsafe {
+            let mut constraints = Vec::new();
+            for m in manifolds {
+                let a = &*self.bodies[m.a].object;
+                let (elasticity, friction) = match m.b {
+                    Some(b) => {
+                        let b = &*self.bodies[b].object;
+                        (a.elasticity.max(b.elasticity), (a.friction * b.friction).sqrt())
+                    }
+                    None => (a.elasticity, a.friction)
+                };
+
+                let helper = if m.normal.x.abs() < 0.57 { vec3_new(1.0, 0.0, 0.0) } else { vec3_new(0.0, 1.0, 0.0) };
+                let t0 = vec3_normalize(vec3_cross(m.normal, helper));
+                let t1 = vec3_cross(m.normal, t0);
+
+                let ma = motions[m.a];
+                let mb = m.b.map_or(Motion::fixed(), |b| motions[b]);
+                for p in &m.points {
+                    let ra = vec3_sub(p.point, ma.center);
+                    let rb = if m.b.is_some() { vec3_sub(p.point, mb.center) } else { vec3_zero() };
+                    let approach = vec3_dot(vec3_sub(mb.point_velocity(rb), ma.point_velocity(ra)), m.normal);
+
+                    // Separated points only stop bodies closing the gap within this step
+                    let target = if p.depth < 0.0 {
+                        p.depth / self.timestep
+                    } else if approach < -RESTITUTION_THRESHOLD {
+                        -elasticity * approach
+                    } else {
+                        0.0
+                    };
+
+                    constraints.push(Constraint {
+                        a: m.a,
+                        b: m.b,
+                        ra: ra,
+                        rb: rb,
+                        normal: m.normal,
+                        tangents: [t0, t1],
+                        target: target,
+                        friction: friction,
+                        normal_impulse: 0.0,
+                        tangent_impulses: [0.0, 0.0]
+                    });
+                }
+            }
+            constraints
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::ffi::CStr;
+use libc::c_int;
+use corange;
+use corange::*;
+
+/// Half-line in world space. `direction` is normalized.
+#[derive(Clone, Copy)]
+pub struct Ray {
+    pub origin: vec3,
+    pub direction: vec3,
+    pub length: f32
+}
+
+/// Nearest entity hit by a ray. `type_id` tells which object `entity` points to.
+#[derive(Clone)]
+pub struct Hit {
+    pub entity: *mut entity,
+    pub type_id: i32,
+    pub name: String,
+    pub point: vec3,
+    pub normal: vec3,
+    pub distance: f32
+}
+
+fn unproject(inverse: mat4, x: f32, y: f32, z: f32) -> vec3 {
+    unsafe {
+        let p = mat4_mul_vec4(inverse, vec4_new(x, y, z, 1.0));
+        vec3_div(vec3_new(p.x, p.y, p.z), p.w)
+    }
+}
+
+impl Ray {
+    /// Ray from the near to the far clip plane through viewport pixel `(x, y)`,
+    /// with the origin in the top left corner as reported by SDL mouse events
+    pub fn from_screen(camera: *mut corange::camera, x: i32, y: i32) -> Ray {
+        unsafe {
+            let ndc_x = 2.0 * (x as f32 + 0.5) / graphics_viewport_width() as f32 - 1.0;
+            let ndc_y = 1.0 - 2.0 * (y as f32 + 0.5) / graphics_viewport_height() as f32;
+            let inverse = mat4_inverse(camera_view_proj_matrix(camera));
+            let near = unproject(inverse, ndc_x, ndc_y, -1.0);
+            let far = unproject(inverse, ndc_x, ndc_y, 1.0);
+            Ray {
+                origin: near,
+                direction: vec3_normalize(vec3_sub(far, near)),
+                length: vec3_dist(near, far)
+            }
+        }
+    }
+
+    pub fn at(&self, distance: f32) -> vec3 {
+        unsafe { vec3_add(self.origin, vec3_mul(self.direction, distance)) }
+    }
+
+    /// Whether the ray passes through the sphere before reaching its end
+    pub fn intersects_sphere(&self, s: sphere) -> bool {
+        unsafe {
+            let offset = vec3_sub(s.center, self.origin);
+            let along = vec3_dot(offset, self.direction);
+            if along < -s.radius || along > self.length + s.radius { return false; }
+            vec3_dot(offset, offset) - along * along <= s.radius * s.radius
+        }
+    }
+
+    /// Point, normal and distance where the ray enters the sphere, or its origin if it starts inside
+    pub fn collide_sphere(&self, s: sphere) -> Option<(vec3, vec3, f32)> {
+        if !self.intersects_sphere(s) { return None; }
+        unsafe {
+            let offset = vec3_sub(s.center, self.origin);
+            let along = vec3_dot(offset, self.direction);
+            let half_chord = (s.radius * s.radius - (vec3_dot(offset, offset) - along * along)).max(0.0).sqrt();
+            let distance = (along - half_chord).max(0.0);
+            let point = self.at(distance);
+            let normal = if s.radius > 0.0 { vec3_div(vec3_sub(point, s.center), s.radius) } else { vec3_neg(self.direction) };
+            Some((point, normal, distance))
+        }
+    }
+
+    /// Nearest hit against a collision mesh placed in the world
+    pub fn collide_mesh(&self, mesh: *mut cmesh, world: mat4, world_normal: mat3) -> Option<(vec3, vec3, f32)> {
+        unsafe {
+            if !self.intersects_sphere(sphere_transform(cmesh_bound(mesh), world)) { return None; }
+
+            let sweep = vec3_mul(self.direction, self.length);
+            let hit = point_collide_mesh(self.origin, sweep, mesh, world, world_normal);
+            if hit.collided == 0 { return None; }
+            Some((hit.point, hit.norm, hit.time * self.length))
+        }
+    }
+
+    /// Nearest hit against the bounding sphere of a renderable placed in the world
+    fn collide_renderable(&self, mut renderable: asset_hndl, world: mat4) -> Option<(vec3, vec3, f32)> {
+        unsafe {
+            if asset_hndl_isnull(&mut renderable) != 0 { return None; }
+            let r = asset_hndl_ptr(&mut renderable) as *mut corange::renderable;
+            if (*r).num_surfaces == 0 { return None; }
+            self.collide_sphere(sphere_transform(renderable_bound(r), world))
+        }
+    }
+
+    /// Nearest hit against `object` of type `type_id`. Static, physics and instance objects are
+    /// hit against their collision body, or their renderable's bounds if they have none, and
+    /// animated objects against their renderable's bounds in the rest pose.
+    pub fn collide(&self, object: *mut entity, type_id: i32) -> Option<(vec3, vec3, f32)> {
+        unsafe {
+            if type_id == *STATIC_TYPE {
+                let so = object as *mut static_object;
+                if asset_hndl_isnull(&mut (*so).collision_body) != 0 {
+                    return self.collide_renderable((*so).renderable, static_object_world(so));
+                }
+                let mesh = asset_hndl_ptr(&mut (*so).collision_body) as *mut cmesh;
+                self.collide_mesh(mesh, static_object_world(so), static_object_world_normal(so))
+            } else if type_id == *PHYSICS_TYPE {
+                let po = object as *mut physics_object;
+                if asset_hndl_isnull(&mut (*po).collision_body) != 0 {
+                    return self.collide_renderable((*po).renderable, physics_object_world(po));
+                }
+                let mesh = asset_hndl_ptr(&mut (*po).collision_body) as *mut cmesh;
+                self.collide_mesh(mesh, physics_object_world(po), physics_object_world_normal(po))
+            } else if type_id == *ANIMATED_TYPE {
+                let ao = object as *mut animated_object;
+                self.collide_renderable((*ao).renderable, mat4_world((*ao).position, (*ao).scale, (*ao).rotation))
+            } else if type_id == *INSTANCE_TYPE {
+                let io = object as *mut instance_object;
+                if asset_hndl_isnull(&mut (*io).collision_body) != 0 {
+                    return (0..(*io).num_instances)
+                        .filter_map(|i| self.collide_renderable((*io).renderable, instance_object_world(io, i)))
+                        .fold(None, nearer);
+                }
+                let mesh = asset_hndl_ptr(&mut (*io).collision_body) as *mut cmesh;
+                (0..(*io).num_instances)
+                    .filter_map(|i| self.collide_mesh(mesh, instance_object_world(io, i), instance_object_world_normal(io, i)))
+                    .fold(None, nearer)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+fn nearer(nearest: Option<(vec3, vec3, f32)>, hit: (vec3, vec3, f32)) -> Option<(vec3, vec3, f32)> {
+    match nearest {
+        Some(nearest) if nearest.2 <= hit.2 => Some(nearest),
+        _ => Some(hit)
+    }
+}
+
+/// Nearest static, animated, physics or instance object the ray hits
+pub fn pick_ray(ray: Ray) -> Option<Hit> {
+    unsafe {
+        let mut nearest: Option<Hit> = None;
+        for &type_id in &[*STATIC_TYPE, *ANIMATED_TYPE, *PHYSICS_TYPE, *INSTANCE_TYPE] {
+            let count = entity_type_count_type_id(type_id);
+            let mut objects: Vec<*mut entity> = vec![0 as *mut entity; count as usize];
+            let mut returned: c_int = 0;
+            entities_get_type_id(objects.as_mut_ptr(), &mut returned, type_id);
+            objects.truncate(returned as usize);
+
+            for object in objects {
+                if let Some((point, normal, distance)) = ray.collide(object, type_id) {
+                    if nearest.as_ref().map_or(true, |hit| distance < hit.distance) {
+                        nearest = Some(Hit {
+                            entity: object,
+                            type_id: type_id,
+                            name: CStr::from_ptr(entity_name(object)).to_string_lossy().into_owned(),
+                            point: point,
+                            normal: normal,
+                            distance: distance
+                        });
+                    }
+                }
+            }
+        }
+        nearest
+    }
+}
+
+/// Nearest object under viewport pixel `(x, y)` as seen through `camera`
+pub fn pick(camera: *mut corange::camera, x: i32, y: i32) -> Option<Hit> {
+    pick_ray(Ray::from_screen(camera, x, y))
+}
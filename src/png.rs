@@ -0,0 +1,316 @@
+//! PNG decoder
+//!
+//! Reads every standard PNG: greyscale, truecolour, palette and their alpha variants at all bit
+//! depths, interlaced or not, with `tRNS` transparency. 16 bit channels are rounded to 8 bits
+//! since engine images are RGBA8. Ancillary chunks other than `tRNS` are ignored.
+
+use image_formats::Image;
+use inflate;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Adam7 passes as (x start, y start, x step, y step)
+const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4), (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2)];
+
+/// Decoded images larger than this are refused rather than allocated
+const MAX_PIXELS: usize = 1 << 28;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Color {
+    Grey,
+    Truecolor,
+    Palette,
+    GreyAlpha,
+    TruecolorAlpha
+}
+
+impl Color {
+    fn channels(self) -> usize {
+        match self {
+            Color::Grey | Color::Palette => 1,
+            Color::GreyAlpha => 2,
+            Color::Truecolor => 3,
+            Color::TruecolorAlpha => 4
+        }
+    }
+}
+
+struct Header {
+    width: usize,
+    height: usize,
+    depth: u8,
+    color: Color,
+    interlaced: bool
+}
+
+impl Header {
+    fn bits_per_pixel(&self) -> usize {
+        self.color.channels() * self.depth as usize
+    }
+
+    /// Bytes in one row of a `width` pixels wide (sub)image, without the filter byte
+    fn row_bytes(&self, width: usize) -> usize {
+        (width * self.bits_per_pixel() + 7) / 8
+    }
+}
+
+/// Whether `bytes` starts with the PNG signature
+pub fn is_png(bytes: &[u8]) -> bool {
+    bytes.starts_with(&SIGNATURE)
+}
+
+/// Decode a complete PNG file into RGBA8 pixels
+pub fn decode(bytes: &[u8]) -> Result<Image, String> {
+    if !is_png(bytes) {
+        return Err("not a PNG file".to_string());
+    }
+
+    let mut header: Option<Header> = None;
+    let mut palette: Vec<[u8; 4]> = Vec::new();
+    let mut transparent: Option<[u16; 3]> = None;
+    let mut compressed: Vec<u8> = Vec::new();
+    let mut ended = false;
+
+    let mut offset = SIGNATURE.len();
+    while offset < bytes.len() {
+        let length = be32(bytes.get(offset..offset + 4).ok_or("PNG chunk is truncated")?) as usize;
+        let kind = bytes.get(offset + 4..offset + 8).ok_or("PNG chunk is truncated")?;
+        let data = bytes.get(offset + 8..offset + 8 + length).ok_or("PNG chunk is truncated")?;
+        let stored = be32(bytes.get(offset + 8 + length..offset + 12 + length).ok_or("PNG chunk is truncated")?);
+        let name = String::from_utf8_lossy(kind).into_owned();
+        if crc32(&bytes[offset + 4..offset + 8 + length]) != stored {
+            return Err(format!("PNG chunk {} is corrupt", name));
+        }
+        offset += 12 + length;
+
+        if header.is_none() && kind != b"IHDR" {
+            return Err("PNG doesn't start with an IHDR chunk".to_string());
+        }
+        match kind {
+            b"IHDR" => header = Some(read_header(data)?),
+            b"PLTE" => {
+                if data.len() % 3 != 0 || data.len() > 256 * 3 {
+                    return Err("PNG palette has an invalid size".to_string());
+                }
+                palette = data.chunks(3).map(|c| [c[0], c[1], c[2], 255]).collect();
+            }
+            b"tRNS" => match header.as_ref().map(|header| header.color) {
+                Some(Color::Palette) => {
+                    for (entry, &alpha) in palette.iter_mut().zip(data) { entry[3] = alpha; }
+                }
+                Some(Color::Grey) if data.len() >= 2 => {
+                    let grey = be16(data);
+                    transparent = Some([grey, grey, grey]);
+                }
+                Some(Color::Truecolor) if data.len() >= 6 => {
+                    transparent = Some([be16(data), be16(&data[2..]), be16(&data[4..])]);
+                }
+                _ => {}
+            },
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => { ended = true; break; }
+            _ => {
+                // Unknown critical chunks change how the image has to be read
+                if kind[0] & 0x20 == 0 {
+                    return Err(format!("PNG has an unsupported critical chunk {}", name));
+                }
+            }
+        }
+    }
+
+    let header = header.ok_or("PNG has no IHDR chunk")?;
+    if !ended {
+        return Err("PNG is missing its IEND chunk".to_string());
+    }
+    if compressed.is_empty() {
+        return Err("PNG has no image data".to_string());
+    }
+    if header.color == Color::Palette && palette.is_empty() {
+        return Err("PNG uses a palette but has no PLTE chunk".to_string());
+    }
+
+    let raw = inflate::zlib_decompress(&compressed).map_err(|err| format!("PNG image data: {}", err))?;
+    let mut image = Image::new(header.width, header.height);
+
+    if header.interlaced {
+        let mut consumed = 0;
+        for &(x0, y0, dx, dy) in &ADAM7 {
+            if x0 >= header.width || y0 >= header.height { continue; }
+            let width = (header.width - x0 + dx - 1) / dx;
+            let height = (header.height - y0 + dy - 1) / dy;
+            let size = height * (header.row_bytes(width) + 1);
+            let pass = raw.get(consumed..consumed + size).ok_or("PNG image data is truncated")?;
+            consumed += size;
+            let rows = unfilter(&header, pass, width, height)?;
+            for (y, row) in rows.chunks(header.row_bytes(width)).enumerate() {
+                for x in 0..width {
+                    let pixel = sample(&header, row, x, &palette, transparent);
+                    image.set(x0 + x * dx, y0 + y * dy, pixel);
+                }
+            }
+        }
+    } else {
+        let size = header.height * (header.row_bytes(header.width) + 1);
+        let data = raw.get(..size).ok_or("PNG image data is truncated")?;
+        let rows = unfilter(&header, data, header.width, header.height)?;
+        for (y, row) in rows.chunks(header.row_bytes(header.width)).enumerate() {
+            for x in 0..header.width {
+                let pixel = sample(&header, row, x, &palette, transparent);
+                image.set(x, y, pixel);
+            }
+        }
+    }
+
+    Ok(image)
+}
+
+fn be16(bytes: &[u8]) -> u16 {
+    (bytes[0] as u16) << 8 | bytes[1] as u16
+}
+
+fn be32(bytes: &[u8]) -> u32 {
+    (bytes[0] as u32) << 24 | (bytes[1] as u32) << 16 | (bytes[2] as u32) << 8 | bytes[3] as u32
+}
+
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { 0xEDB8_8320 ^ (crc >> 1) } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn read_header(data: &[u8]) -> Result<Header, String> {
+    if data.len() != 13 {
+        return Err("PNG IHDR chunk has the wrong size".to_string());
+    }
+    let width = be32(data) as usize;
+    let height = be32(&data[4..]) as usize;
+    if width == 0 || height == 0 {
+        return Err("PNG is empty".to_string());
+    }
+    if width.saturating_mul(height) > MAX_PIXELS {
+        return Err(format!("PNG is too large at {}x{}", width, height));
+    }
+    let depth = data[8];
+    let color = match data[9] {
+        0 => Color::Grey,
+        2 => Color::Truecolor,
+        3 => Color::Palette,
+        4 => Color::GreyAlpha,
+        6 => Color::TruecolorAlpha,
+        other => return Err(format!("PNG has an unknown colour type {}", other))
+    };
+    let valid_depth = match color {
+        Color::Grey => [1, 2, 4, 8, 16].contains(&depth),
+        Color::Palette => [1, 2, 4, 8].contains(&depth),
+        _ => depth == 8 || depth == 16
+    };
+    if !valid_depth {
+        return Err(format!("PNG has an invalid bit depth {} for its colour type", depth));
+    }
+    if data[10] != 0 || data[11] != 0 {
+        return Err("PNG uses an unknown compression or filter method".to_string());
+    }
+    if data[12] > 1 {
+        return Err("PNG uses an unknown interlace method".to_string());
+    }
+    Ok(Header { width: width, height: height, depth: depth, color: color, interlaced: data[12] == 1 })
+}
+
+/// Undo the per row filters, returning the rows without their filter bytes
+fn unfilter(header: &Header, data: &[u8], width: usize, height: usize) -> Result<Vec<u8>, String> {
+    let stride = header.row_bytes(width);
+    let step = ((header.bits_per_pixel() + 7) / 8).max(1);
+    let mut rows = vec![0u8; stride * height];
+
+    for y in 0..height {
+        let filter = data[y * (stride + 1)];
+        let line = &data[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        let (above, current) = rows.split_at_mut(y * stride);
+        let above = if y > 0 { &above[(y - 1) * stride..] } else { &[][..] };
+        let current = &mut current[..stride];
+
+        for x in 0..stride {
+            let a = if x >= step { current[x - step] } else { 0 };
+            let b = if y > 0 { above[x] } else { 0 };
+            let c = if x >= step && y > 0 { above[x - step] } else { 0 };
+            let predicted = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                other => return Err(format!("PNG row {} has an unknown filter {}", y, other))
+            };
+            current[x] = line[x].wrapping_add(predicted);
+        }
+    }
+    Ok(rows)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c }
+}
+
+/// Channel `index` of pixel `x` in an unfiltered row, at the image's bit depth
+fn channel(header: &Header, row: &[u8], x: usize, index: usize) -> u16 {
+    let channels = header.color.channels();
+    match header.depth {
+        16 => be16(&row[(x * channels + index) * 2..]),
+        8 => row[x * channels + index] as u16,
+        depth => {
+            // Sub byte depths only occur with a single channel
+            let depth = depth as usize;
+            let bit = x * depth;
+            let shift = 8 - depth - bit % 8;
+            ((row[bit / 8] >> shift) as u16) & ((1 << depth) - 1)
+        }
+    }
+}
+
+/// Scale a sample at the image's bit depth to 8 bits
+fn to_8bit(value: u16, depth: u8) -> u8 {
+    match depth {
+        16 => ((value as u32 * 255 + 32767) / 65535) as u8,
+        8 => value as u8,
+        depth => (value as u32 * 255 / ((1 << depth) - 1)) as u8
+    }
+}
+
+fn sample(header: &Header, row: &[u8], x: usize, palette: &[[u8; 4]], transparent: Option<[u16; 3]>) -> [u8; 4] {
+    let depth = header.depth;
+    match header.color {
+        Color::Palette => {
+            let index = channel(header, row, x, 0) as usize;
+            // Out of range indices are an error in the spec, black is what most decoders show
+            palette.get(index).cloned().unwrap_or([0, 0, 0, 255])
+        }
+        Color::Grey => {
+            let grey = channel(header, row, x, 0);
+            let alpha = if transparent.map_or(false, |t| t[0] == grey) { 0 } else { 255 };
+            let grey = to_8bit(grey, depth);
+            [grey, grey, grey, alpha]
+        }
+        Color::GreyAlpha => {
+            let grey = to_8bit(channel(header, row, x, 0), depth);
+            [grey, grey, grey, to_8bit(channel(header, row, x, 1), depth)]
+        }
+        Color::Truecolor => {
+            let rgb = [channel(header, row, x, 0), channel(header, row, x, 1), channel(header, row, x, 2)];
+            let alpha = if transparent == Some(rgb) { 0 } else { 255 };
+            [to_8bit(rgb[0], depth), to_8bit(rgb[1], depth), to_8bit(rgb[2], depth), alpha]
+        }
+        Color::TruecolorAlpha => [
+            to_8bit(channel(header, row, x, 0), depth),
+            to_8bit(channel(header, row, x, 1), depth),
+            to_8bit(channel(header, row, x, 2), depth),
+            to_8bit(channel(header, row, x, 3), depth)]
+    }
+}
@@ -0,0 +1,58 @@
+use corange;
+use corange::*;
+use camera::Camera;
+
+/// Secondary camera rendered into a texture asset before the main view every frame, at the
+/// texture's size and aspect ratio. The texture is registered under `path` so materials and UI
+/// elements can use it.
+#[derive(Clone)]
+pub struct RenderTarget {
+    pub path: String,
+    pub width: usize,
+    pub height: usize,
+    pub camera: Camera,
+    pub enabled: bool,
+    pub frame: u64
+}
+
+impl Default for RenderTarget {
+    fn default() -> RenderTarget {
+        RenderTarget {
+            path: String::from("./render_target.dds"),
+            width: 512,
+            height: 512,
+            camera: Camera::default(),
+            enabled: true,
+            frame: 0
+        }
+    }
+}
+
+impl RenderTarget {
+    pub fn initialize(self) -> (*mut corange::camera, *mut texture) {
+        unsafe {
+            let texture = texture_new_empty(self.width as i32, self.height as i32);
+            file_add(path(to_static_str(self.path.clone())), texture as *mut asset);
+            let camera = camera_new();
+            self.apply(camera);
+            (camera, texture)
+        }
+    }
+
+    pub fn apply(self, camera:*mut corange::camera) {
+        self.camera.apply(camera);
+    }
+
+    pub fn render(&self, renderer:*mut corange::renderer, camera:*mut corange::camera, texture:*mut texture) {
+        if self.enabled {
+            unsafe { renderer_render_to_texture(renderer, camera, texture) }
+        }
+    }
+
+    pub fn delete(self, camera:*mut corange::camera) {
+        unsafe {
+            file_unload(path(to_static_str(self.path)));
+            camera_delete(camera);
+        }
+    }
+}
@@ -1,6 +1,67 @@
 use corange;
 use corange::*;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShadowSplit {
+    Manual,
+    Linear,
+    Logarithmic,
+    Blended(f32)
+}
+
+#[derive(Clone, Debug)]
+pub struct Shadows {
+    pub cascades: usize,
+    pub split: ShadowSplit,
+    pub distance: f32,
+    /// Cascade sizes are rounded up to multiples of this many world units, which keeps shadow
+    /// edges from shimmering as the camera turns. 0 fits the cascades exactly.
+    pub snap: f32,
+    pub bias: f32,
+    pub normal_offset: f32,
+    pub resolution: Vec<usize>,
+    pub debug: bool
+}
+
+impl Default for Shadows {
+    fn default() -> Shadows {
+        Shadows {
+            cascades: 3,
+            split: ShadowSplit::Manual,
+            distance: 0.2,
+            snap: 10.0,
+            bias: 0.001,
+            normal_offset: 0.0,
+            resolution: Vec::new(),
+            debug: false
+        }
+    }
+}
+
+impl Shadows {
+    pub fn apply(self, renderer:*mut corange::renderer) {
+        let (split, blend) = match self.split {
+            ShadowSplit::Manual => (SHADOW_SPLIT_MANUAL, 0.0),
+            ShadowSplit::Linear => (SHADOW_SPLIT_LINEAR, 0.0),
+            ShadowSplit::Logarithmic => (SHADOW_SPLIT_LOGARITHMIC, 1.0),
+            ShadowSplit::Blended(blend) => (SHADOW_SPLIT_BLENDED, blend)
+        };
+        unsafe {
+            renderer_set_shadow_cascades(renderer, self.cascades as i32, split as i32, blend, self.distance);
+            renderer_set_shadow_snap(renderer, self.snap);
+            renderer_set_shadow_bias(renderer, self.bias, self.normal_offset);
+            // Setting the resolution reallocates the shadow map
+            for (cascade, &size) in self.resolution.iter().enumerate() {
+                let current = (*renderer).shadows_widths.get(cascade).cloned().zip((*renderer).shadows_heights.get(cascade).cloned());
+                if current != Some((size as i32, size as i32)) {
+                    renderer_set_shadow_resolution(renderer, cascade as i32, size as i32, size as i32);
+                }
+            }
+            renderer_set_shadow_debug(renderer, if self.debug { 1 } else { 0 });
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Renderer {
     pub configuration: String,
@@ -10,6 +71,10 @@ pub struct Renderer {
     pub sea_enabled: bool,
     pub sky_enabled: bool,
     pub sky_time: f32,
+    /// Frustum cull objects, surfaces and landscape chunks
+    pub culling_enabled: bool,
+    pub point_shadows: Option<usize>,
+    pub shadows: Shadows,
     pub frame: u64
 }
 
@@ -23,6 +88,9 @@ impl Default for Renderer {
             sea_enabled: false,
             sky_enabled: false,
             sky_time: 0.15,
+            culling_enabled: true,
+            point_shadows: None,
+            shadows: Shadows::default(),
             frame: 0
         }
     }
@@ -43,6 +111,11 @@ impl Renderer {
             renderer_set_skydome_enabled(renderer, if self.sky_enabled { 1 } else { 0 });
             renderer_set_sea_enabled(renderer, if self.sea_enabled { 1 } else { 0 });
             renderer_set_tod(renderer, self.sky_time, 0);
+            renderer_set_culling_enabled(renderer, if self.culling_enabled { 1 } else { 0 });
+            if let Some(num) = self.point_shadows {
+                renderer_set_point_shadows(renderer, num as i32);
+            }
+            self.shadows.apply(renderer);
             if let Some(texture) = self.color_correction_texture {
                 renderer_set_color_correction(renderer, asset_hndl_new_load(path(to_static_str(texture))));
             }
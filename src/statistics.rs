@@ -0,0 +1,22 @@
+use corange;
+
+#[derive(Clone, Debug, Default)]
+pub struct Statistics {
+    /// Objects, surfaces and landscape chunks frustum culled last frame
+    pub objects_culled: usize,
+    /// The same for the shadow maps
+    pub shadow_objects_culled: usize,
+    pub frame: u64
+}
+
+impl Statistics {
+    pub fn collect(renderer:*mut corange::renderer, frame:u64) -> Statistics {
+        unsafe {
+            Statistics {
+                objects_culled: (*renderer).culled_num as usize,
+                shadow_objects_culled: (*renderer).shadows_culled_num as usize,
+                frame: frame
+            }
+        }
+    }
+}
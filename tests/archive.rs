@@ -0,0 +1,107 @@
+extern crate corange_rs;
+
+use std::env;
+use std::ffi::CString;
+use std::fs;
+use std::path::Path;
+use corange_rs::archive;
+use corange_rs::archive::Archive;
+use corange_rs::corange::*;
+
+#[test]
+fn packed_folders_read_back_unchanged() {
+    let files = archive::read_folder(Path::new("./tests/formats")).unwrap();
+    assert_eq!(files.iter().map(|file| file.0.as_str()).collect::<Vec<_>>(), vec!["english.lang", "sparks.effect"]);
+
+    let bytes = archive::write(&files).unwrap();
+    assert_eq!(bytes, archive::write(&files).unwrap(), "packing the same files gives the same archive");
+    let packed = Archive::parse(bytes).unwrap();
+    assert_eq!(packed.files(), vec!["english.lang", "sparks.effect"]);
+    for (name, contents) in &files {
+        assert_eq!(&packed.read(name).unwrap(), contents);
+        assert_eq!(packed.entry(name).unwrap().compressed_size, contents.len());
+    }
+
+    let folder = env::temp_dir().join(format!("corange-archive-test-{}", std::process::id()));
+    packed.unpack(&folder).unwrap();
+    assert_eq!(archive::read_folder(&folder).unwrap(), files);
+    fs::remove_dir_all(&folder).unwrap();
+}
+
+#[test]
+fn deflated_entries_are_read() {
+    let zip = Archive::open("./tests/archive/deflated.zip").unwrap();
+    assert_eq!(zip.files(), vec!["graphics.cfg", "text/english.lang"]);
+    assert_eq!(zip.read("graphics.cfg").unwrap(), fs::read("./examples/assets/graphics.cfg").unwrap());
+    assert_eq!(zip.read("text/english.lang").unwrap(), fs::read("./tests/formats/english.lang").unwrap());
+    assert!(zip.entry("graphics.cfg").unwrap().compressed_size < zip.entry("graphics.cfg").unwrap().size);
+}
+
+#[test]
+fn broken_archives_are_reported() {
+    assert_eq!(Archive::parse(b"PK".to_vec()).unwrap_err(), "too short for a zip archive");
+    assert_eq!(Archive::parse(vec![0; 64]).unwrap_err(), "not a zip archive, missing the end of central directory");
+
+    let mut bytes = archive::write(&[("a.cfg".to_string(), b"a = 1\n".to_vec())]).unwrap();
+    bytes[30 + 5] = b'b';
+    let changed = Archive::parse(bytes).unwrap();
+    assert_eq!(changed.read("a.cfg").unwrap_err(), "a.cfg: contents don't match the checksum");
+    assert_eq!(changed.read("b.cfg").unwrap_err(), "b.cfg isn't in the archive");
+
+    let escape = Archive::open("./tests/archive/escape.zip").unwrap();
+    let folder = env::temp_dir().join(format!("corange-archive-escape-{}", std::process::id()));
+    assert_eq!(escape.unpack(&folder).unwrap_err(), "../outside.txt is outside the archive's folder");
+    assert!(!folder.join("../outside.txt").exists());
+}
+
+fn exists(path: &str) -> bool {
+    let path = CString::new(path).unwrap();
+    unsafe { file_exists(P(path.as_ptr())) != 0 }
+}
+
+fn read(path: &str) -> String {
+    String::from_utf8(archive::read_file(path).unwrap()).unwrap()
+}
+
+#[test]
+fn mounts_resolve_newest_first_over_the_disk() {
+    let folder = env::temp_dir().join(format!("corange-archive-mount-{}", std::process::id()));
+    fs::create_dir_all(&folder).unwrap();
+    fs::write(folder.join("a.cfg"), "disk").unwrap();
+    fs::write(folder.join("loose.cfg"), "disk").unwrap();
+    let write = |name: &str, files: &[(&str, &str)]| {
+        let files: Vec<(String, Vec<u8>)> = files.iter().map(|&(name, contents)| (name.to_string(), contents.as_bytes().to_vec())).collect();
+        let path = folder.join(name).to_string_lossy().into_owned();
+        fs::write(&path, archive::write(&files).unwrap()).unwrap();
+        path
+    };
+    let base = write("base.zip", &[("a.cfg", "base"), ("b.cfg", "base"), ("sub/c.cfg", "base")]);
+    let patch = write("patch.zip", &[("b.cfg", "patch")]);
+    let mounted = folder.to_string_lossy().into_owned();
+    let path = |name: &str| format!("{}/{}", mounted, name);
+
+    unsafe { asset_init(); }
+    archive::mount(&base, &mounted).unwrap();
+    archive::mount(&patch, &mounted).unwrap();
+    assert_eq!(archive::mount(&patch, &mounted).unwrap_err(), format!("{} is already mounted on {}", patch, mounted));
+    assert_eq!(archive::mounted(), vec![(mounted.clone(), vec![base.clone(), patch.clone()])]);
+
+    assert_eq!((read(&path("a.cfg")), read(&path("b.cfg")), read(&path("loose.cfg"))), ("base".into(), "patch".into(), "disk".into()));
+    assert_eq!(read(&path("sub/../sub/./c.cfg")), "base");
+    assert!(exists(&path("b.cfg")) && exists(&path("sub/c.cfg")) && exists(&path("loose.cfg")));
+    let listed: Vec<String> = ["a.cfg", "b.cfg", "base.zip", "loose.cfg", "patch.zip"].iter().map(|name| path(name)).collect();
+    assert_eq!(archive::folder_files(&mounted).unwrap(), listed);
+    assert_eq!(archive::folder_files(&path("sub")).unwrap(), vec![path("sub/c.cfg")]);
+    let tree: Vec<String> = ["a.cfg", "b.cfg", "base.zip", "loose.cfg", "patch.zip", "sub/c.cfg"].iter().map(|name| path(name)).collect();
+    assert_eq!(archive::folder_tree(&mounted).unwrap(), tree);
+
+    archive::unmount(&patch, &mounted).unwrap();
+    assert_eq!(read(&path("b.cfg")), "base");
+    assert!(archive::unmount(&patch, &mounted).is_err());
+
+    archive::unmount(&base, &mounted).unwrap();
+    assert_eq!(read(&path("a.cfg")), "disk");
+    assert!(!exists(&path("b.cfg")) && !exists(&path("sub/c.cfg")));
+    assert!(archive::mounted().is_empty());
+    fs::remove_dir_all(&folder).unwrap();
+}
@@ -0,0 +1,97 @@
+extern crate corange_rs;
+
+use std::env;
+use std::fs;
+use corange_rs::camera_path::{CameraKey, CameraPath, Easing, Interpolation};
+use corange_rs::corange::vec3;
+
+fn v(x: f32, y: f32, z: f32) -> vec3 {
+    vec3 { x: x, y: y, z: z }
+}
+
+fn key(time: f32, x: f32, roll: f32, easing: Easing) -> CameraKey {
+    CameraKey { time: time, position: v(x, 0.0, 0.0), target: v(x, 0.0, 1.0), roll: roll, easing: easing }
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-4
+}
+
+#[test]
+fn save_and_parse_round_trip() {
+    let mut path = CameraPath::new(Interpolation::Bezier);
+    path.looping = true;
+    path.add_key(key(3.0, 3.0, 0.5, Easing::Linear));
+    path.add_key(key(0.0, 0.0, 0.0, Easing::InOut));
+    path.add_key(key(1.0, 1.0, 0.1, Easing::In));
+    path.add_key(key(2.0, 2.0, 0.2, Easing::Out));
+
+    let filename = env::temp_dir().join(format!("corange-camera-path-test-{}.path", std::process::id()));
+    let filename = filename.to_string_lossy().into_owned();
+    path.save(&filename).unwrap();
+    let loaded = CameraPath::load(&filename).unwrap();
+    fs::remove_file(&filename).unwrap();
+
+    assert_eq!(loaded.interpolation, Interpolation::Bezier);
+    assert!(loaded.looping);
+    assert_eq!(loaded.keys.len(), 4);
+    for (a, b) in path.keys.iter().zip(&loaded.keys) {
+        assert_eq!(a.time, b.time);
+        assert_eq!((a.position.x, a.target.z, a.roll), (b.position.x, b.target.z, b.roll));
+        assert_eq!(a.easing, b.easing);
+    }
+    assert_eq!(loaded.to_text(), path.to_text());
+}
+
+#[test]
+fn parse_rejects_unsorted_keys_and_bad_bezier_counts() {
+    let unsorted = "key 1 0 0 0 0 0 1 0 linear\nkey 0.5 1 0 0 0 0 1 0 linear\n";
+    let err = CameraPath::parse(unsorted).err().expect("unsorted keys are rejected");
+    assert!(err.to_string().contains("line 2"), "{}", err);
+
+    let keys = "key 0 0 0 0 0 0 1 0 linear\nkey 1 1 0 0 0 0 1 0 linear\nkey 2 2 0 0 0 0 1 0 linear\n";
+    assert!(CameraPath::parse(keys).is_ok());
+    assert!(CameraPath::parse(&format!("interpolation bezier\n{}", keys)).is_err());
+    assert!(CameraPath::parse(&format!("interpolation bezier\n{}key 3 3 0 0 0 0 1 0 linear\n", keys)).is_ok());
+    assert!(CameraPath::parse("interpolation bezier\n").is_ok());
+}
+
+#[test]
+fn evaluate_at_and_between_keys() {
+    let eased = [(Easing::Linear, 0.25), (Easing::In, 0.0625), (Easing::Out, 0.4375), (Easing::InOut, 0.15625)];
+    for &(easing, quarter) in &eased {
+        for &interpolation in &[Interpolation::CatmullRom, Interpolation::Bezier] {
+            let mut path = CameraPath::new(interpolation);
+            path.add_key(key(0.0, 0.0, 0.0, easing));
+            if interpolation == Interpolation::Bezier {
+                path.add_key(key(1.0, 1.0, 0.0, easing));
+                path.add_key(key(2.0, 2.0, 0.0, easing));
+            }
+            path.add_key(key(4.0, 3.0, 1.0, easing));
+
+            let (position, target, roll) = path.evaluate(0.0).unwrap();
+            assert_eq!((position.x, target.z, roll), (0.0, 1.0, 0.0));
+            let (position, _, roll) = path.evaluate(4.0).unwrap();
+            assert!(close(position.x, 3.0) && close(roll, 1.0), "{:?} {:?} at the last key", easing, interpolation);
+            let (position, _, _) = path.evaluate(10.0).unwrap();
+            assert!(close(position.x, 3.0), "{:?} {:?} past the end", easing, interpolation);
+
+            let (position, _, roll) = path.evaluate(1.0).unwrap();
+            assert!(close(roll, quarter), "{:?} {:?} roll {} at a quarter", easing, interpolation, roll);
+            if interpolation == Interpolation::Bezier {
+                // Evenly spaced control points make the curve a straight line at constant speed
+                assert!(close(position.x, 3.0 * quarter), "{:?} position {}", easing, position.x);
+            }
+        }
+    }
+
+    let mut path = CameraPath::new(Interpolation::CatmullRom);
+    for i in 0..4 {
+        path.add_key(key(i as f32, i as f32 * 2.0, 0.0, Easing::Linear));
+    }
+    for i in 0..4 {
+        assert!(close(path.evaluate(i as f32).unwrap().0.x, i as f32 * 2.0), "passes through key {}", i);
+    }
+    // Evenly spaced keys on a line are interpolated linearly between the inner keys
+    assert!(close(path.evaluate(1.5).unwrap().0.x, 3.0));
+}
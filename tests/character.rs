@@ -0,0 +1,146 @@
+extern crate corange_rs;
+extern crate libc;
+
+mod common;
+
+use std::mem;
+use corange_rs::character::CharacterController;
+use corange_rs::corange::*;
+use common::handle;
+
+const TIMESTEP: f32 = 1.0 / 60.0;
+
+fn v(x: f32, y: f32, z: f32) -> vec3 {
+    vec3 { x: x, y: y, z: z }
+}
+
+/// Two triangles covering the corners in order, facing `normal`
+fn quad(corners: [vec3; 4], normal: vec3, out: &mut Vec<ctri>) {
+    unsafe {
+        let normal = vec3_normalize(normal);
+        out.push(ctri_new(corners[0], corners[1], corners[2], normal));
+        out.push(ctri_new(corners[0], corners[2], corners[3], normal));
+    }
+}
+
+/// Static object colliding with `triangles`
+fn collider(triangles: &[ctri]) -> *mut static_object {
+    unsafe {
+        let mesh = cmesh_new();
+        let copy = libc::malloc(mem::size_of::<ctri>() * triangles.len()) as *mut ctri;
+        for (i, &t) in triangles.iter().enumerate() {
+            *copy.offset(i as isize) = t;
+        }
+        (*mesh).triangles = copy;
+        (*mesh).triangles_num = triangles.len() as i32;
+        cmesh_build_bvh(mesh);
+
+        let object = static_object_new();
+        (*object).collision_body = handle(mesh as *mut asset);
+        object
+    }
+}
+
+/// Flat ground at height `y` between `x0` and `x1`
+fn ground(y: f32, x0: f32, x1: f32, out: &mut Vec<ctri>) {
+    quad([v(x0, y, -20.0), v(x0, y, 20.0), v(x1, y, 20.0), v(x1, y, -20.0)], v(0.0, 1.0, 0.0), out);
+}
+
+/// Wall at `x` from `y0` to `y1`, facing towards negative x
+fn wall(x: f32, y0: f32, y1: f32, out: &mut Vec<ctri>) {
+    quad([v(x, y0, -20.0), v(x, y1, -20.0), v(x, y1, 20.0), v(x, y0, 20.0)], v(-1.0, 0.0, 0.0), out);
+}
+
+/// Ramp through the origin rising `angle` radians towards positive x
+fn ramp(angle: f32, out: &mut Vec<ctri>) {
+    let (x, y) = (20.0 * angle.cos(), 20.0 * angle.sin());
+    quad([v(-x, -y, -20.0), v(-x, -y, 20.0), v(x, y, 20.0), v(x, y, -20.0)], v(-angle.sin(), angle.cos(), 0.0), out);
+}
+
+fn character(position: vec3, triangles: &[ctri]) -> CharacterController {
+    let mut controller = CharacterController::new(position, v(0.4, 0.8, 0.4));
+    controller.add_collider(collider(triangles));
+    controller
+}
+
+fn run(controller: &mut CharacterController, movement: vec3, seconds: f32) {
+    for _ in 0..(seconds / TIMESTEP) as usize {
+        controller.update(movement, TIMESTEP);
+    }
+}
+
+#[test]
+fn slides_along_a_wall() {
+    let mut triangles = Vec::new();
+    ground(0.0, -20.0, 20.0, &mut triangles);
+    wall(2.0, 0.0, 3.0, &mut triangles);
+    let mut controller = character(v(0.0, 0.81, 0.0), &triangles);
+    run(&mut controller, v(0.0, 0.0, 0.0), 0.5);
+    assert!(controller.grounded());
+
+    run(&mut controller, v(0.7, 0.0, 0.7), 2.0);
+    let p = controller.position;
+    assert!(p.x <= 2.0 - 0.4 + 0.01 && p.x > 1.5, "stopped at x {} instead of against the wall", p.x);
+    assert!(p.z > 4.0, "only slid to z {} along the wall", p.z);
+    assert!((p.y - 0.8).abs() < 0.05, "left the ground at y {}", p.y);
+    assert!(controller.grounded());
+}
+
+#[test]
+fn steps_up_low_ledges_only() {
+    for &(height, climbs) in &[(0.2, true), (0.6, false)] {
+        let mut triangles = Vec::new();
+        ground(0.0, -20.0, 1.0, &mut triangles);
+        wall(1.0, 0.0, height, &mut triangles);
+        ground(height, 1.0, 20.0, &mut triangles);
+        let mut controller = character(v(0.0, 0.81, 0.0), &triangles);
+        run(&mut controller, v(0.0, 0.0, 0.0), 0.5);
+
+        run(&mut controller, v(1.0, 0.0, 0.0), 1.0);
+        let p = controller.position;
+        if climbs {
+            assert!(p.x > 2.0, "stuck at x {} below a {} ledge", p.x, height);
+            assert!((p.y - (height + 0.8)).abs() < 0.05, "stands at y {} on a {} ledge", p.y, height);
+        } else {
+            assert!(p.x < 1.0 - 0.4 + 0.01, "climbed a {} ledge to x {}", height, p.x);
+            assert!((p.y - 0.8).abs() < 0.05);
+        }
+        assert!(controller.grounded());
+    }
+}
+
+#[test]
+fn stands_on_gentle_slopes_and_slides_down_steep_ones() {
+    // Well below the default limit of 0.8 radians the character stands still
+    let mut triangles = Vec::new();
+    ramp(0.35, &mut triangles);
+    let mut gentle = character(v(0.0, 1.0, 0.0), &triangles);
+    run(&mut gentle, v(0.0, 0.0, 0.0), 0.5);
+    assert!(gentle.grounded());
+    let rested = gentle.position;
+    run(&mut gentle, v(0.0, 0.0, 0.0), 2.0);
+    assert!(gentle.grounded());
+    unsafe {
+        assert!(vec3_dist(gentle.position, rested) < 0.01, "crept {} down a gentle slope", vec3_dist(gentle.position, rested));
+    }
+
+    // Walking up it works too
+    run(&mut gentle, v(1.0, 0.0, 0.0), 1.0);
+    assert!(gentle.position.y > rested.y + 1.0, "only climbed to y {}", gentle.position.y);
+    assert!(gentle.grounded());
+
+    // Above the limit the character can't stand and slides down
+    let mut triangles = Vec::new();
+    ramp(1.1, &mut triangles);
+    let mut steep = character(v(0.0, 1.2, 0.0), &triangles);
+    let start = steep.position;
+    run(&mut steep, v(0.0, 0.0, 0.0), 1.5);
+    assert!(!steep.grounded());
+    assert!(steep.position.y < start.y - 2.0, "only slid to y {}", steep.position.y);
+    assert!(steep.position.x < start.x - 0.5, "slid to x {} instead of down the slope", steep.position.x);
+
+    // And can't walk up it
+    let before = steep.position;
+    run(&mut steep, v(1.0, 0.0, 0.0), 0.5);
+    assert!(steep.position.y < before.y, "climbed a steep slope to y {}", steep.position.y);
+}
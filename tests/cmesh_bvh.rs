@@ -0,0 +1,139 @@
+extern crate corange_rs;
+extern crate libc;
+
+mod common;
+
+use corange_rs::corange::*;
+use common::load_sphere;
+
+/// Small deterministic generator so failures are reproducible
+struct Lcg(u32);
+
+impl Lcg {
+    fn next(&mut self) -> f32 {
+        self.0 = self.0.wrapping_mul(1664525).wrapping_add(1013904223);
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+
+    fn range(&mut self, lower: f32, upper: f32) -> f32 {
+        lower + (upper - lower) * self.next()
+    }
+
+    fn vec3(&mut self, extent: f32) -> vec3 {
+        unsafe { vec3_new(self.range(-extent, extent), self.range(-extent, extent), self.range(-extent, extent)) }
+    }
+}
+
+/// Identity, moved, rotated and non-uniformly scaled placements of the mesh
+fn worlds() -> Vec<(mat4, mat3)> {
+    unsafe {
+        let placements = vec![
+            mat4_id(),
+            mat4_world(vec3_new(2.0, -1.0, 0.5), vec3_one(), quat_id()),
+            mat4_world(vec3_new(0.0, 1.0, 0.0), vec3_new(2.0, 2.0, 2.0), quat_rotation_y(0.7)),
+            mat4_world(vec3_new(-1.0, 0.0, 3.0), vec3_new(3.0, 0.5, 1.5), quat_mul_quat(quat_rotation_x(0.4), quat_rotation_y(-1.2)))
+        ];
+        placements.into_iter().map(|world| {
+            (world, mat3_transpose(mat3_inverse(mat4_to_mat3(world))))
+        }).collect()
+    }
+}
+
+/// Sweeps from around the mesh towards points near it, so both hits and misses occur
+fn sweeps(rng: &mut Lcg, world: mat4) -> Vec<(vec3, vec3)> {
+    unsafe {
+        let center = mat4_mul_vec3(world, vec3_zero());
+        (0..400).map(|_| {
+            let start = vec3_add(center, vec3_mul(vec3_normalize(rng.vec3(1.0)), rng.range(0.5, 8.0)));
+            let end = vec3_add(center, rng.vec3(4.0));
+            (start, vec3_sub(end, start))
+        }).collect()
+    }
+}
+
+fn assert_same(fast: collision, brute: collision, query: &str) {
+    assert_eq!(fast.collided, brute.collided, "{} disagrees on whether it collided", query);
+    if brute.collided == 0 { return; }
+    unsafe {
+        assert!((fast.time - brute.time).abs() < 1e-5, "{} hit at {} instead of {}", query, fast.time, brute.time);
+        assert!(vec3_dist(fast.point, brute.point) < 1e-3, "{} hit a different point", query);
+    }
+}
+
+#[test]
+fn bvh_covers_every_triangle_once() {
+    unsafe {
+        let mesh = load_sphere();
+        let mut seen = vec![0; (*mesh).triangles_num as usize];
+        for i in 0..(*mesh).nodes_num {
+            let node = *(*mesh).nodes.offset(i as isize);
+            if node.count == 0 {
+                assert!(node.first > i && node.first + 1 < (*mesh).nodes_num);
+                continue;
+            }
+            for t in node.first..node.first + node.count {
+                let triangle = *(*mesh).triangles.offset(t as isize);
+                for &v in &[triangle.a, triangle.b, triangle.c] {
+                    assert!(v.x >= node.min.x && v.y >= node.min.y && v.z >= node.min.z);
+                    assert!(v.x <= node.max.x && v.y <= node.max.y && v.z <= node.max.z);
+                }
+                seen[t as usize] += 1;
+            }
+        }
+        assert!(seen.iter().all(|&count| count == 1));
+        cmesh_delete(mesh);
+    }
+}
+
+#[test]
+fn point_queries_match_brute_force() {
+    unsafe {
+        let mesh = load_sphere();
+        let mut rng = Lcg(1);
+        for (world, world_normal) in worlds() {
+            for (p, v) in sweeps(&mut rng, world) {
+                assert_same(
+                    point_collide_mesh(p, v, mesh, world, world_normal),
+                    point_collide_mesh_brute(p, v, mesh, world, world_normal),
+                    "point");
+            }
+        }
+        cmesh_delete(mesh);
+    }
+}
+
+#[test]
+fn sphere_queries_match_brute_force() {
+    unsafe {
+        let mesh = load_sphere();
+        let mut rng = Lcg(2);
+        for (world, world_normal) in worlds() {
+            for (p, v) in sweeps(&mut rng, world) {
+                let s = sphere_new(p, rng.range(0.05, 0.5));
+                assert_same(
+                    sphere_collide_mesh(s, v, mesh, world, world_normal),
+                    sphere_collide_mesh_brute(s, v, mesh, world, world_normal),
+                    "sphere");
+            }
+        }
+        cmesh_delete(mesh);
+    }
+}
+
+#[test]
+fn ellipsoid_queries_match_brute_force() {
+    unsafe {
+        let mesh = load_sphere();
+        let mut rng = Lcg(3);
+        for (world, world_normal) in worlds() {
+            for (p, v) in sweeps(&mut rng, world) {
+                let e = ellipsoid_new(p, vec3_new(rng.range(0.1, 0.6), rng.range(0.1, 0.6), rng.range(0.1, 0.6)));
+                assert_same(
+                    ellipsoid_collide_mesh(e, v, mesh, world, world_normal),
+                    ellipsoid_collide_mesh_brute(e, v, mesh, world, world_normal),
+                    "ellipsoid");
+            }
+        }
+        cmesh_delete(mesh);
+    }
+}
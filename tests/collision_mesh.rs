@@ -0,0 +1,122 @@
+extern crate corange_rs;
+extern crate libc;
+
+mod common;
+
+use std::env;
+use std::ffi::CString;
+use std::fs;
+use corange_rs::collision_mesh;
+use corange_rs::collision_mesh::CollisionOptions;
+use corange_rs::corange::*;
+use corange_rs::formats::bmf::Bmf;
+use common::load_sphere;
+
+fn triangles(mesh: *mut cmesh) -> Vec<ctri> {
+    unsafe { (0..(*mesh).triangles_num).map(|i| *(*mesh).triangles.offset(i as isize)).collect() }
+}
+
+fn same(a: vec3, b: vec3) -> bool {
+    a.x == b.x && a.y == b.y && a.z == b.z
+}
+
+fn sorted(mut triangles: Vec<ctri>) -> Vec<ctri> {
+    let key = |t: &ctri| [t.a.x, t.a.y, t.a.z, t.b.x, t.b.y, t.b.z, t.c.x, t.c.y, t.c.z];
+    triangles.sort_by(|t0, t1| key(t0).partial_cmp(&key(t1)).unwrap());
+    triangles
+}
+
+fn corners(mesh: *mut cmesh) -> Vec<vec3> {
+    triangles(mesh).iter().flat_map(|t| vec![t.a, t.b, t.c]).collect()
+}
+
+#[test]
+fn convex_hull_is_convex_and_contains_the_mesh() {
+    unsafe {
+        let mesh = load_sphere();
+        let hull = cmesh_convex_hull(mesh);
+        let faces = triangles(hull);
+        assert!(faces.len() >= 4);
+
+        let mut points = corners(mesh);
+        points.extend(corners(hull));
+        for face in &faces {
+            assert!(vec3_length(face.norm) > 0.99);
+            for &p in &points {
+                let outside = vec3_dot(vec3_sub(p, face.a), face.norm);
+                assert!(outside < 1e-4, "point {} in front of a hull face", outside);
+            }
+        }
+
+        cmesh_delete(hull);
+        cmesh_delete(mesh);
+    }
+}
+
+#[test]
+fn simplify_drops_collapsed_triangles() {
+    unsafe {
+        let mesh = load_sphere();
+        let before = (*mesh).triangles_num;
+        cmesh_simplify(mesh, 0.4);
+        let after = triangles(mesh);
+        assert!(!after.is_empty());
+        assert!((after.len() as i32) < before, "{} triangles before and {} after", before, after.len());
+
+        for t in &after {
+            assert!(!same(t.a, t.b) && !same(t.b, t.c) && !same(t.c, t.a), "collapsed triangle kept");
+            let cross = vec3_cross(vec3_sub(t.b, t.a), vec3_sub(t.c, t.a));
+            assert!(vec3_length(cross) > 0.0);
+        }
+
+        // The BVH is rebuilt over the remaining triangles
+        let mut seen = 0;
+        for i in 0..(*mesh).nodes_num {
+            seen += (*(*mesh).nodes.offset(i as isize)).count;
+        }
+        assert_eq!(seen as usize, after.len());
+        cmesh_delete(mesh);
+    }
+}
+
+#[test]
+fn saved_col_files_load_back() {
+    unsafe {
+        let mesh = load_sphere();
+        let filename = env::temp_dir().join(format!("corange-collision-mesh-test-{}.col", std::process::id()));
+        let cfilename = CString::new(filename.to_string_lossy().into_owned()).unwrap();
+        col_save_file(mesh, cfilename.as_ptr() as *mut _);
+        let loaded = col_load_file(cfilename.as_ptr() as *mut _);
+        fs::remove_file(&filename).unwrap();
+
+        // Building the BVH reorders triangles, so compare them in a fixed order
+        let (expected, actual) = (sorted(triangles(mesh)), sorted(triangles(loaded)));
+        assert_eq!(expected.len(), actual.len());
+        for (e, a) in expected.iter().zip(&actual) {
+            assert!(same(e.a, a.a) && same(e.b, a.b) && same(e.c, a.c), "triangle moved");
+            assert!(vec3_dist(e.norm, a.norm) < 1e-5);
+        }
+        assert!((cmesh_bound(loaded).radius - cmesh_bound(mesh).radius).abs() < 1e-5);
+
+        cmesh_delete(loaded);
+        cmesh_delete(mesh);
+    }
+}
+
+#[test]
+fn converts_without_a_gl_context() {
+    let input = "./src/corange/assets_core/objects/sphere.bmf";
+    let output = env::temp_dir().join(format!("corange-collision-mesh-convert-{}.col", std::process::id()));
+    let report = collision_mesh::convert(input, &output.to_string_lossy(), &CollisionOptions::default()).unwrap();
+    let bmf = Bmf::load(input).unwrap();
+    unsafe {
+        let cfilename = CString::new(output.to_string_lossy().into_owned()).unwrap();
+        let loaded = col_load_file(cfilename.as_ptr() as *mut _);
+        fs::remove_file(&output).unwrap();
+
+        assert_eq!(report.source_triangles, bmf.triangles());
+        assert_eq!((report.triangles, (*loaded).triangles_num as usize), (bmf.triangles(), bmf.triangles()));
+        assert!((cmesh_bound(loaded).radius - 1.0).abs() < 0.1, "radius {}", cmesh_bound(loaded).radius);
+        cmesh_delete(loaded);
+    }
+}
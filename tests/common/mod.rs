@@ -0,0 +1,24 @@
+//! Fixtures shared by the integration tests
+#![allow(dead_code)]
+
+use libc;
+use corange_rs::corange::*;
+
+/// Handle resolving straight to `a` without registering it with the asset manager
+pub fn handle(a: *mut asset) -> asset_hndl {
+    let mut h = asset_hndl::default();
+    h.path.ptr[0] = b'x' as libc::c_char;
+    h.ptr = a;
+    h.timestamp = u32::max_value();
+    h
+}
+
+/// The core sphere collision mesh, with its BVH
+pub fn load_sphere() -> *mut cmesh {
+    unsafe {
+        let mesh = col_load_file(str("./src/corange/assets_core/objects/sphere.col"));
+        assert!((*mesh).triangles_num > 0);
+        assert!((*mesh).nodes_num > 1);
+        mesh
+    }
+}
@@ -0,0 +1,49 @@
+extern crate corange_rs;
+
+use std::ffi::{CStr, CString};
+use std::path::Path;
+use corange_rs::archive;
+use corange_rs::core_assets;
+use corange_rs::corange::*;
+use corange_rs::dependencies;
+use corange_rs::dependencies::Graph;
+
+fn mapped(path: &str) -> String {
+    let path = CString::new(path).unwrap();
+    unsafe { CStr::from_ptr(asset_map_filename(P(path.as_ptr())).ptr.as_ptr()).to_string_lossy().into_owned() }
+}
+
+#[test]
+fn embedded_assets_are_read_from_memory() {
+    let folder = core_assets::mount().unwrap();
+    assert_eq!(core_assets::mount().unwrap(), folder, "mounting twice keeps the first mount");
+    unsafe {
+        let (variable, core) = (CString::new("$CORANGE").unwrap(), CString::new(folder).unwrap());
+        asset_init();
+        asset_add_path_variable(P(variable.as_ptr()), P(core.as_ptr()));
+    }
+    assert!(!Path::new(folder).exists(), "nothing is written to disk");
+
+    let path = mapped("$CORANGE/shaders/basic.fs");
+    assert_eq!(path, "/corange/assets_core/shaders/basic.fs");
+    let short = dependencies::short_path(&path);
+    assert!(short.starts_with("$CORANGE/") && short.ends_with("/shaders/basic.fs"), "{}", short);
+
+    let contents = core_assets::file("shaders/basic.fs").unwrap();
+    assert_eq!(archive::read_file(&path).unwrap(), contents);
+    let mut size = 0;
+    unsafe {
+        let filename = CString::new(path.as_str()).unwrap();
+        let data = asset_mounted_data(P(filename.as_ptr()), &mut size);
+        assert_eq!(std::slice::from_raw_parts(data as *const u8, size as usize), contents);
+    }
+    assert!(archive::folder_files(&mapped("$CORANGE/shaders")).unwrap().contains(&path));
+    assert!(archive::folder_tree(&mapped("$CORANGE")).unwrap().contains(&path));
+
+    let graph = Graph::scan(&["$CORANGE/shaders"]).unwrap();
+    assert!(graph.scanned.contains(&path));
+    assert!(graph.scanned.contains(&mapped("$CORANGE/shaders/deferred/static.vs")), "subfolders are scanned");
+    let references = &graph.assets[&mapped("$CORANGE/shaders/deferred/static.mat")];
+    assert!(!references.is_empty() && references.iter().all(|r| r.exists), "{:?}", references);
+    assert_eq!(archive::mounted(), vec![("/corange/assets_core".to_string(), vec!["assets_core".to_string()])]);
+}
@@ -0,0 +1,56 @@
+extern crate corange_rs;
+
+use std::env;
+use std::ffi::CString;
+use std::fs;
+use corange_rs::corange::*;
+use corange_rs::dependencies::Graph;
+use corange_rs::formats::bmf::Bmf;
+
+#[test]
+fn references_are_resolved_and_checked() {
+    unsafe {
+        let (variable, core) = (CString::new("$CORANGE").unwrap(), CString::new("./src/corange/assets_core/").unwrap());
+        asset_add_path_variable(P(variable.as_ptr()), P(core.as_ptr()));
+    }
+    let folder = env::temp_dir().join(format!("corange-dependencies-test-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+    let folder = fs::canonicalize(&folder).unwrap().to_string_lossy().into_owned();
+    let write = |name: &str, text: &str| fs::write(format!("{}/{}", folder, name), text).unwrap();
+
+    let mut bmf = Bmf::load("./examples/assets/piano/piano.bmf").unwrap();
+    bmf.material = format!("{}/piano.mat", folder);
+    bmf.save(&format!("{}/piano.bmf", folder)).unwrap();
+    write("piano.mat", &format!("shader shader = $CORANGE/shaders/deferred/static.vs\n\
+                                 texture diffuse_map = {0}/piano.dds\n\
+                                 texture bump_map = {0}/piano_nm.dds\n", folder));
+    write("piano.dds", "");
+    write("old.dds", "");
+    write("a.mat", &format!("texture next = {}/b.mat\n", folder));
+    write("b.mat", &format!("texture next = {}/a.mat\n", folder));
+    write("broken.bmf", "BMF");
+
+    let graph = Graph::scan(&[&folder]).unwrap();
+    let path = |name: &str| format!("{}/{}", folder, name);
+
+    assert_eq!(graph.scanned.len(), 7);
+    assert_eq!(graph.errors.len(), 1);
+    assert!(graph.errors[0].starts_with(&path("broken.bmf")), "{}", graph.errors[0]);
+    assert!(graph.assets.keys().any(|asset| asset.ends_with("/assets_core/shaders/deferred/static.vs")), "$CORANGE is followed");
+
+    let missing = graph.missing();
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0].0, path("piano.mat"));
+    assert_eq!(missing[0].1.path, path("piano_nm.dds"));
+
+    assert_eq!(graph.cycles(), vec![vec![path("a.mat"), path("b.mat")]]);
+    assert_eq!(graph.unused(&[]), vec![path("a.mat"), path("b.mat"), path("old.dds")]);
+    assert_eq!(graph.unused(&[&path("a.mat")]), vec![path("old.dds")]);
+
+    let dot = graph.to_dot();
+    assert!(dot.starts_with("digraph assets {"));
+    assert!(dot.contains("piano_nm.dds\" [color=red"));
+
+    fs::remove_dir_all(&folder).unwrap();
+}
@@ -0,0 +1,218 @@
+extern crate corange_rs;
+
+use std::env;
+use std::ffi::CString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use corange_rs::corange::{mat_read_file, material_delete, material_entry_item, material_get_entry};
+use corange_rs::formats::ani::Ani;
+use corange_rs::formats::bmf::Bmf;
+use corange_rs::formats::cfg::Cfg;
+use corange_rs::formats::col::Col;
+use corange_rs::formats::effect::{Blend, Effect};
+use corange_rs::formats::lang::Lang;
+use corange_rs::formats::mat::{Mat, Value};
+use corange_rs::formats::skl::Skl;
+
+const FOLDERS: &'static [&'static str] = &["./src/corange/assets_core", "./examples/assets", "./tests/formats"];
+
+/// Every file below `FOLDERS` with the given extension
+fn assets(extension: &str) -> Vec<String> {
+    fn walk(folder: &Path, extension: &str, out: &mut Vec<PathBuf>) {
+        for entry in fs::read_dir(folder).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                walk(&path, extension, out);
+            } else if path.extension().map_or(false, |e| e == extension) {
+                out.push(path);
+            }
+        }
+    }
+    let mut paths = Vec::new();
+    for folder in FOLDERS {
+        walk(Path::new(folder), extension, &mut paths);
+    }
+    paths.sort();
+    assert!(!paths.is_empty(), "no .{} files to test", extension);
+    paths.into_iter().map(|path| path.to_string_lossy().into_owned()).collect()
+}
+
+fn load<T, F: Fn(&str) -> Result<T, String>>(path: &str, load: F) -> T {
+    load(path).unwrap_or_else(|err| panic!("{}", err))
+}
+
+macro_rules! round_trip {
+    ($format:ident, $extension:expr) => {
+        for path in assets($extension) {
+            let parsed = load(&path, $format::load);
+            let text = parsed.to_text();
+            let reparsed = $format::parse(&text).unwrap_or_else(|err| panic!("{} written back: {}", path, err));
+            assert_eq!(parsed, reparsed, "{} changed on the way back", path);
+        }
+    }
+}
+
+#[test]
+fn bmf_files_are_written_back_byte_for_byte() {
+    for path in assets("bmf") {
+        let bmf = load(&path, Bmf::load);
+        assert!(!bmf.surfaces.is_empty() && bmf.triangles() > 0, "{} is empty", path);
+        assert_eq!(bmf.to_bytes(), fs::read(&path).unwrap(), "{} differs when written back", path);
+    }
+
+    let imrod = Bmf::load("./examples/assets/imrod/imrod.bmf").unwrap();
+    assert!(imrod.rigged);
+    assert_eq!(imrod.material, "./examples/assets/imrod/imrod.mat");
+    let surface = &imrod.surfaces[0];
+    assert_eq!(surface.weights.len(), surface.vertices.len());
+    let joints = Skl::load("./examples/assets/imrod/imrod.skl").unwrap().joints.len() as i32;
+    assert!(surface.weights.iter().all(|w| w.bone_ids.iter().all(|&id| id < joints)));
+}
+
+#[test]
+fn skl_and_ani_files_are_written_back_unchanged() {
+    for path in assets("skl").into_iter().chain(assets("ani")) {
+        let text = fs::read_to_string(&path).unwrap();
+        let written = if path.ends_with(".skl") { load(&path, Skl::load).to_text() } else { load(&path, Ani::load).to_text() };
+        assert_eq!(written.trim_end(), text.trim_end(), "{} differs when written back", path);
+    }
+
+    let skl = Skl::load("./examples/assets/imrod/imrod.skl").unwrap();
+    let ani = Ani::load("./examples/assets/imrod/imrod.ani").unwrap();
+    assert_eq!(skl.joints, ani.joints);
+    assert_eq!(skl.joint_id("thigh.L"), Some(2));
+    assert_eq!(skl.joints[2].parent, 1);
+    assert_eq!(skl.rest_pose.len(), skl.joints.len());
+    assert_eq!(ani.frames.len(), 61);
+    assert_eq!(ani.frames[60].time, 60.0);
+}
+
+#[test]
+fn text_formats_round_trip() {
+    round_trip!(Col, "col");
+    round_trip!(Mat, "mat");
+    round_trip!(Cfg, "cfg");
+    round_trip!(Effect, "effect");
+    round_trip!(Lang, "lang");
+}
+
+#[test]
+fn parsed_values() {
+    let sphere = Col::load("./src/corange/assets_core/objects/sphere.col").unwrap();
+    assert!(sphere.triangles.iter().all(|t| t.iter().all(|&i| (i as usize) < sphere.positions.len())));
+    assert!(Col::load("./src/corange/assets_core/objects/empty.col").unwrap().triangles.is_empty());
+
+    let dino = Mat::load("./examples/assets/dino/dino.mat").unwrap();
+    assert_eq!(dino.entries[0].name.as_deref(), Some("shell"));
+    assert_eq!(dino.entries[0].items[0].value, Value::Texture("./examples/assets/dino/shell.dds".to_string()));
+    assert_eq!(dino.entries[0].items[4].value, Value::Float(1.5));
+    assert!(dino.paths().contains(&"./examples/assets/dino/gun_nm.dds"));
+
+    let graphics = Cfg::load("./examples/assets/graphics.cfg").unwrap();
+    assert_eq!(graphics.get("fullscreen"), Some("false"));
+    assert_eq!(graphics.get("ssao"), Some("2"));
+
+    let sparks = Effect::load("./tests/formats/sparks.effect").unwrap();
+    assert_eq!((sparks.blend_src, sparks.blend_dst), (Blend::SrcAlpha, Blend::One));
+    assert_eq!((sparks.count, sparks.output, sparks.output_r), (200, 120.0, 40.0));
+    assert_eq!(sparks.thickness, 1.0, "settings left out keep the engine's defaults");
+    assert_eq!(sparks.keys.len(), 3);
+    assert_eq!(sparks.keys[1].color, [1.0, 0.4, 0.1, 0.5]);
+    assert_eq!(sparks.keys[1].force, [0.0, -9.8, 0.0]);
+
+    let english = Lang::load("./tests/formats/english.lang").unwrap();
+    assert_eq!(english.get("menu_start"), Some("Start Game"));
+    assert_eq!(english.get("loading"), Some("Loading assets...\nPlease wait"));
+}
+
+fn assert_error<T: std::fmt::Debug>(result: Result<T, String>, expected: &str) {
+    let err = result.unwrap_err();
+    assert!(err.starts_with(expected), "expected '{}...', got '{}'", expected, err);
+}
+
+#[test]
+fn errors_name_the_line() {
+    assert_error(Col::parse("v 0 0 0\nv 1 0 0\nf 1 2 3\n"), "line 3: position 3");
+    assert_error(Col::parse("v 0 0\n"), "line 1: expected 3 numbers");
+    assert_error(Col::parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nf 1 2 3 4\n"), "line 5: the engine only reads quads");
+    assert_eq!(Col::parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nf 1// 2// 3// 4//\n").unwrap().triangles, vec![[0, 1, 2], [0, 2, 3]]);
+
+    assert_error(Mat::parse("# comment\nfloat glossiness = 10\ncolour tint = 1\n"), "line 3: unknown material item type 'colour'");
+    assert_error(Mat::parse("float glossiness = high\n"), "line 1: expected a number, found 'high'");
+    assert_error(Mat::parse("vec3 tint = 1 0\n"), "line 1: expected 3 numbers");
+    assert_error(Mat::parse("texture diffuse_map\n"), "line 1: expected 'type name = value'");
+
+    assert_error(Cfg::parse("a = 1\n\nb = two words\n"), "line 3: the value of 'b'");
+    assert_error(Cfg::parse("a = 1\na = 2\n"), "line 2: 'a' is set twice");
+
+    assert_error(Effect::parse("lifetime 2\nblend_src add\n"), "line 2: unknown blend 'add'");
+    assert_error(Effect::parse("key time=0 rotation=0:0\n"), "line 1: expected 'key time=");
+    assert_error(Lang::parse("hello Hello\nhello Hi\n"), "line 2: 'hello' is defined twice");
+
+    let nodes = "version 1\nnodes\n  0 \"root\" -1\n  1 \"arm\"  0\nend\n";
+    assert_error(Skl::parse("version 2\n"), "line 1: version 2 isn't supported");
+    assert_error(Skl::parse("nodes\n  0 \"root\" -1\n  2 \"arm\" 0\nend\n"), "line 3: joint 2 is out of order");
+    assert_error(Skl::parse("nodes\n  0 \"root\" 1\nend\n"), "line 2: parent 1 of joint 0");
+    assert_error(Skl::parse("nodes\n  0 \"legend\" -1\nend\n"), "line 2: the engine can't read a joint named 'legend'");
+    assert_error(Skl::parse(&format!("{}skeleton\ntime 0\n  2 0 0 0 0 0 0\nend\n", nodes)), "line 8: joint 2 isn't one of the 2 nodes");
+    assert_error(Skl::parse(&format!("{}skeleton\ntime 0\n  1 0 0 0 0 0\nend\n", nodes)), "line 8: expected 6 numbers");
+    assert_error(Skl::parse(&format!("{}skeleton\ntime 0\n  0 0 0 0 0 0 0\n", nodes)), "line 8: missing 'end'");
+    assert_error(Skl::parse(&format!("{}skeleton\ntime 0\ntime 1\nend\n", nodes)), "line 8: a skeleton has a single rest pose");
+    assert_error(Ani::parse(&format!("{}skeleton\n  0 0 0 0 0 0 0\nend\n", nodes)), "line 7: joint transform before the first 'time'");
+}
+
+#[test]
+fn bmf_errors_name_the_byte() {
+    let bytes = fs::read("./src/corange/assets_core/sky/sun_sprite.bmf").unwrap();
+    assert_error(Bmf::parse(b"OBJ"), "byte 0: missing the BMF magic number");
+    // The sprite ends with its 6 indices, which no longer fit
+    assert_error(Bmf::parse(&bytes[..bytes.len() - 2]), &format!("byte {}: 6 indices don't fit", bytes.len() - 28));
+    assert_error(Bmf::parse(&bytes[..20]), "byte 8: 26 material path bytes don't fit");
+
+    let mut version = bytes.clone();
+    version[3] = 2;
+    assert_error(Bmf::parse(&version), "byte 3: version 2 isn't supported");
+
+    let mut index = bytes.clone();
+    let last = index.len() - 4;
+    index[last] = 200;
+    assert_error(Bmf::parse(&index), &format!("byte {}: surface 0 index 200 is out of range", last));
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_error(Bmf::parse(&trailing), &format!("byte {}: 1 bytes left", bytes.len()));
+
+    let err = Bmf::load("./tests/formats/missing.bmf").unwrap_err();
+    assert!(err.starts_with("./tests/formats/missing.bmf: "), "unexpected error '{}'", err);
+    assert_error(Mat::load("./examples/assets/graphics.cfg"), "./examples/assets/graphics.cfg: line 2: expected 'type name = value'");
+}
+
+#[test]
+fn engine_reads_every_vector_component() {
+    let text = "vec2 offset = 0.5 -2\nvec3 tint = 1 0.25 0.5\nfloat glossiness = 10\nvec4 color = 0.1 0.2 0.3 0.4\nsubmaterial\nvec3 tint = 3 2 1\n";
+    let filename = env::temp_dir().join(format!("corange-formats-test-{}.mat", std::process::id()));
+    // The engine skips lines without an '=', the reader rejects them
+    fs::write(&filename, format!("{}missing equals\n", text)).unwrap();
+    let cfilename = CString::new(filename.to_string_lossy().into_owned()).unwrap();
+    let parsed = Mat::parse(text).unwrap();
+
+    unsafe {
+        let m = mat_read_file(cfilename.as_ptr() as *mut _);
+        fs::remove_file(&filename).unwrap();
+        for (i, entry) in parsed.entries.iter().enumerate() {
+            let me = material_get_entry(m, i as i32);
+            for item in &entry.items {
+                let name = CString::new(item.name.clone()).unwrap();
+                let mut read = material_entry_item(me, name.as_ptr() as *mut _);
+                let value = match item.value {
+                    Value::Vec2(_) => { let v = *read.as_vec2(); Value::Vec2([v.x, v.y]) }
+                    Value::Vec3(_) => { let v = *read.as_vec3(); Value::Vec3([v.x, v.y, v.z]) }
+                    Value::Vec4(_) => { let v = *read.as_vec4(); Value::Vec4([v.x, v.y, v.z, v.w]) }
+                    _ => Value::Float(*read.as_float())
+                };
+                assert_eq!(value, item.value, "entry {} {}", i, item.name);
+            }
+        }
+        material_delete(m);
+    }
+}
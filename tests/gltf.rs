@@ -0,0 +1,172 @@
+extern crate corange_rs;
+
+use std::env;
+use std::fs;
+use std::thread;
+use corange_rs::corange::*;
+use corange_rs::gltf;
+use corange_rs::json;
+
+const STRIDED: &'static str = "./tests/gltf/strided.gltf";
+const SKINNED: &'static str = "./tests/gltf/skinned.glb";
+
+/// Write `contents` to a file of the test's own scratch folder and return its path
+fn scratch(name: &str, contents: &[u8]) -> String {
+    let folder = env::temp_dir().join(format!("corange-gltf-test-{}", std::process::id()));
+    fs::create_dir_all(&folder).unwrap();
+    let path = folder.join(name);
+    fs::write(&path, contents).unwrap();
+    fs::canonicalize(&path).unwrap().to_string_lossy().into_owned()
+}
+
+/// The JSON chunk of a `.glb` file and the chunks after it
+fn split_glb(bytes: &[u8]) -> (String, Vec<u8>) {
+    let length = bytes[12] as usize | (bytes[13] as usize) << 8 | (bytes[14] as usize) << 16 | (bytes[15] as usize) << 24;
+    (String::from_utf8(bytes[20..20 + length].to_vec()).unwrap(), bytes[20 + length..].to_vec())
+}
+
+fn glb(json: &str, rest: &[u8]) -> Vec<u8> {
+    let mut text = json.as_bytes().to_vec();
+    let padded = (text.len() + 3) & !3;
+    text.resize(padded, b' ');
+    let push = |bytes: &mut Vec<u8>, value: u32| bytes.extend_from_slice(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+    let mut bytes = Vec::new();
+    push(&mut bytes, 0x46546C67);
+    push(&mut bytes, 2);
+    push(&mut bytes, (20 + text.len() + rest.len()) as u32);
+    push(&mut bytes, text.len() as u32);
+    push(&mut bytes, 0x4E4F534A);
+    bytes.extend_from_slice(&text);
+    bytes.extend_from_slice(rest);
+    bytes
+}
+
+/// `SKINNED` with `from` replaced by `to` in its JSON
+fn skinned_with(name: &str, from: &str, to: &str) -> String {
+    let (json, rest) = split_glb(&fs::read(SKINNED).unwrap());
+    assert!(json.contains(from), "{} isn't in the fixture", from);
+    scratch(name, &glb(&json.replace(from, to), &rest))
+}
+
+/// `STRIDED` with `from` replaced by `to`
+fn strided_with(name: &str, from: &str, to: &str) -> String {
+    let text = fs::read_to_string(STRIDED).unwrap();
+    assert!(text.contains(from), "{} isn't in the fixture", from);
+    scratch(name, text.replace(from, to).as_bytes())
+}
+
+#[test]
+fn strided_and_sparse_accessors_are_read() {
+    let meshes = gltf::load_meshes(STRIDED).unwrap();
+    assert_eq!(meshes.len(), 1);
+    let (m, ref weights) = meshes[0];
+    assert!(weights.is_none());
+    unsafe {
+        assert_eq!(((*m).num_verts, (*m).num_triangles), (4, 2));
+        let vertex = |i: isize| *(*m).verticies.offset(i);
+        let triangles: Vec<u32> = (0..6).map(|i| *(*m).triangles.offset(i)).collect();
+        assert_eq!(triangles, vec![0, 1, 2, 0, 2, 3]);
+
+        // Positions and normals share a view with a 24 byte stride, and the node moves the quad up z
+        let corner = vertex(2).position;
+        assert_eq!((corner.x, corner.y, corner.z), (1.0, 1.0, 2.0));
+        assert!((0..4).all(|i| { let n = vertex(i).normal; (n.x, n.y, n.z) == (0.0, 0.0, 1.0) }));
+
+        // Only vertex 2 has a uv, set by the sparse accessor; glTF's v runs downwards
+        let uvs: Vec<(f32, f32)> = (0..4).map(|i| (vertex(i).uvs.x, vertex(i).uvs.y)).collect();
+        assert_eq!(uvs, vec![(0.0, 1.0), (0.0, 1.0), (0.5, 0.75), (0.0, 1.0)]);
+        mesh_delete(m);
+    }
+    assert!(gltf::check_file(STRIDED).is_ok());
+    assert!(gltf::references(STRIDED).unwrap().is_empty(), "data URIs aren't files");
+}
+
+#[test]
+fn skinned_weights_are_renormalized() {
+    let meshes = gltf::load_meshes(SKINNED).unwrap();
+    assert_eq!(meshes.len(), 1);
+    let (m, ref weights) = meshes[0];
+    unsafe {
+        assert_eq!(((*m).num_verts, (*m).num_triangles), (3, 1));
+        mesh_delete(m);
+    }
+    let weights = weights.as_ref().expect("a skinned primitive has weights");
+    let rounded = |weight: &vertex_weight| weight.bone_weights.iter().map(|w| (w * 1000.0).round() as i32).collect::<Vec<_>>();
+
+    // The three strongest of four influences, scaled to add up to one
+    assert_eq!(weights[0].bone_ids, [1, 0, 1]);
+    assert_eq!(rounded(&weights[0]), vec![444, 333, 222]);
+    assert_eq!((weights[1].bone_ids, rounded(&weights[1])), ([1, 0, 0], vec![1000, 0, 0]));
+    // Vertices without influences follow the first joint
+    assert_eq!((weights[2].bone_ids, rounded(&weights[2])), ([0, 0, 0], vec![1000, 0, 0]));
+}
+
+#[test]
+fn files_are_imported_off_the_main_thread() {
+    let imported = thread::spawn(|| gltf::import_file(SKINNED).map(|_| ())).join().unwrap();
+    assert!(imported.is_ok());
+    let path = strided_with("worker.gltf", "\"version\": \"2.0\"", "\"version\": \"3.0\"");
+    let err = thread::spawn(move || gltf::import_file(&path).map(|_| ()).unwrap_err()).join().unwrap();
+    assert!(err.ends_with("unsupported glTF version '3.0', expected 2.x"), "{}", err);
+}
+
+#[test]
+fn animation_channels_are_checked() {
+    assert!(gltf::check_file(SKINNED).is_ok());
+
+    let path = skinned_with("mismatched.glb", "\"output\":4", "\"output\":2");
+    let err = gltf::check_file(&path).unwrap_err();
+    assert!(err.starts_with(&path) && err.ends_with("animation 0: channel 0 has mismatched keyframes"), "{}", err);
+
+    let path = skinned_with("interpolation.glb", "\"LINEAR\"", "\"SMOOTH\"");
+    let err = gltf::check_file(&path).unwrap_err();
+    assert!(err.ends_with("animation 0: channel 0 has unknown interpolation SMOOTH"), "{}", err);
+}
+
+#[test]
+fn malformed_files_are_rejected() {
+    let path = strided_with("version.gltf", "\"version\": \"2.0\"", "\"version\": \"1.0\"");
+    assert_eq!(gltf::check_file(&path).unwrap_err(), format!("{}: unsupported glTF version '1.0', expected 2.x", path));
+    assert!(gltf::references(&path).is_err());
+
+    let bytes = fs::read(SKINNED).unwrap();
+    let path = scratch("truncated.glb", &bytes[..bytes.len() - 10]);
+    assert_eq!(gltf::check_file(&path).unwrap_err(), format!("{}: truncated chunk", path));
+
+    let path = strided_with("missing.gltf", "\"POSITION\": 0", "\"POSITION\": 9");
+    assert_eq!(gltf::load_meshes(&path).err(), Some(format!("{}: quad primitive 0: accessor 9 does not exist", path)));
+
+    let path = strided_with("past.gltf", "\"byteLength\": 96", "\"byteLength\": 72");
+    let err = gltf::check_file(&path).unwrap_err();
+    assert!(err.ends_with("accessor 0 reads past the end of buffer view 0"), "{}", err);
+
+    let path = strided_with("syntax.gltf", "\"scene\": 0,", "\"scene\": 0,,");
+    let err = gltf::check_file(&path).unwrap_err();
+    assert!(err.starts_with(&format!("{}: line 5: ", path)), "{}", err);
+}
+
+#[test]
+fn external_buffers_are_referenced_without_reading_them() {
+    let text = fs::read_to_string(STRIDED).unwrap();
+    let start = text.find("\"uri\": \"").unwrap() + 8;
+    let end = start + text[start..].find('"').unwrap();
+    let path = scratch("external.gltf", format!("{}quad%20data.bin{}", &text[..start], &text[end..]).as_bytes());
+    let folder = &path[..path.rfind('/').unwrap()];
+    assert_eq!(gltf::references(&path).unwrap(), vec![format!("{}/quad data.bin", folder)]);
+    assert!(gltf::check_file(&path).unwrap_err().contains("quad data.bin"));
+}
+
+#[test]
+fn json_errors_name_their_line() {
+    assert_eq!(json::parse("{\n  \"a\": [1, 2,]\n}").unwrap_err(), "line 2: unexpected character");
+    assert_eq!(json::parse("{\"a\": 1} 2").unwrap_err(), "line 1: trailing characters after the document");
+    assert_eq!(json::parse("[\"open").unwrap_err(), "line 1: unterminated string");
+    assert_eq!(json::parse("{\"a\" 1}").unwrap_err(), "line 1: expected ':' after a member name");
+}
+
+#[test]
+fn json_nesting_is_limited() {
+    let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    assert!(json::parse(&nested(200)).is_ok());
+    assert!(json::parse(&nested(100000)).unwrap_err().ends_with("nested too deeply"));
+}
@@ -0,0 +1,60 @@
+extern crate corange_rs;
+
+use std::collections::BTreeMap;
+use corange_rs::corange::*;
+use corange_rs::handles;
+use corange_rs::handles::{Handle, Holder};
+
+#[test]
+fn clones_are_counted_until_dropped() {
+    unsafe { asset_init(); }
+    let path = "./examples/assets/piano/piano.bmf";
+    assert_eq!(handles::handle_count(path), 0);
+
+    let first = Handle::new(path);
+    let second = first.clone();
+    let third = Handle::new("./examples/assets/piano/../piano/piano.bmf");
+    assert_eq!(handles::handle_count(path), 3, "handles on the same file count together");
+    assert_eq!(first.path(), third.path());
+
+    drop(second);
+    assert_eq!(handles::handle_count(path), 2);
+    drop(first);
+    drop(third);
+    assert_eq!(handles::handle_count(path), 0);
+    assert_eq!(handles::handle_count("./examples/assets/piano/piano.mat"), 0);
+}
+
+#[test]
+fn unused_assets_are_those_nothing_reaches() {
+    let holders: BTreeMap<String, Vec<Holder>> = vec![
+        ("level.bmf", vec![Holder::Entity("floor".to_string())]),
+        ("level.mat", vec![Holder::Asset("level.bmf".to_string())]),
+        ("level.dds", vec![Holder::Asset("level.mat".to_string())]),
+        ("held.dds", vec![Holder::Handles(1)]),
+        ("sky.mat", vec![Holder::Engine]),
+        ("sky.dds", vec![Holder::Asset("sky.mat".to_string())]),
+        ("old.bmf", vec![]),
+        ("old.mat", vec![Holder::Asset("old.bmf".to_string())]),
+        ("old.dds", vec![Holder::Asset("old.mat".to_string()), Holder::Asset("loop.mat".to_string())]),
+        ("loop.mat", vec![Holder::Asset("loop.dds".to_string())]),
+        ("loop.dds", vec![Holder::Asset("loop.mat".to_string())])
+    ].into_iter().map(|(path, list)| (path.to_string(), list)).collect();
+    let uses: BTreeMap<String, Vec<String>> = vec![
+        ("level.bmf", vec!["level.mat"]),
+        ("level.mat", vec!["level.dds"]),
+        ("sky.mat", vec!["sky.dds"]),
+        ("old.bmf", vec!["old.mat"]),
+        ("old.mat", vec!["old.dds"]),
+        ("loop.mat", vec!["loop.dds", "old.dds"]),
+        ("loop.dds", vec!["loop.mat"])
+    ].into_iter().map(|(path, used)| (path.to_string(), used.into_iter().map(String::from).collect())).collect();
+
+    // Assets only other unused assets hold are unused too, cycles included
+    assert_eq!(handles::unreached(&holders, &uses), vec!["loop.dds", "loop.mat", "old.bmf", "old.dds", "old.mat"]);
+
+    // Holding the start of a chain keeps all of it
+    let mut held = holders.clone();
+    held.get_mut("loop.mat").unwrap().push(Holder::Handles(2));
+    assert_eq!(handles::unreached(&held, &uses), vec!["old.bmf", "old.mat"]);
+}
@@ -0,0 +1,203 @@
+extern crate corange_rs;
+
+use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use corange_rs::dds;
+use corange_rs::image_formats;
+use corange_rs::image_formats::Image;
+use corange_rs::{inflate, jpeg, png};
+
+// The fixtures were written by libpng and libjpeg from the patterns below
+
+fn fixture(name: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    File::open(format!("./tests/images/{}", name)).unwrap().read_to_end(&mut bytes).unwrap();
+    bytes
+}
+
+fn decode_png(name: &str) -> Image {
+    let image = png::decode(&fixture(name)).unwrap_or_else(|err| panic!("{}: {}", name, err));
+    assert_eq!((image.width, image.height), (13, 11), "{} has the wrong size", name);
+    image
+}
+
+fn assert_pixels<F: Fn(usize, usize) -> [u8; 4]>(name: &str, image: &Image, expected: F) {
+    for y in 0..image.height {
+        for x in 0..image.width {
+            assert_eq!(image.get(x, y), expected(x, y), "{} differs at {}, {}", name, x, y);
+        }
+    }
+}
+
+/// 16 bit sample of channel `c` at (x, y)
+fn sample16(x: usize, y: usize, c: usize) -> u32 {
+    ((x * 4099 + y * 131 + c * 9973) & 0xFFFF) as u32
+}
+
+fn round16(value: u32) -> u8 {
+    ((value * 255 + 32767) / 65535) as u8
+}
+
+#[test]
+fn png_rgba8_with_every_block_type_and_interlacing() {
+    let expected = |x: usize, y: usize| [
+        (x * 255 / 12) as u8, (y * 255 / 10) as u8, ((x + y) * 37 & 255) as u8, (x * y * 13 & 255) as u8];
+    for name in &["rgba8.png", "rgba8_stored.png", "rgba8_fixed_interlaced.png"] {
+        assert_pixels(name, &decode_png(name), &expected);
+    }
+}
+
+#[test]
+fn png_16_bit_is_rounded_to_8_bit() {
+    assert_pixels("rgba16.png", &decode_png("rgba16.png"), |x, y| [
+        round16(sample16(x, y, 0)), round16(sample16(x, y, 1)), round16(sample16(x, y, 2)), round16(sample16(x, y, 3))]);
+    assert_pixels("grey_alpha16_interlaced.png", &decode_png("grey_alpha16_interlaced.png"), |x, y| {
+        let grey = round16(sample16(x, y, 0));
+        [grey, grey, grey, round16(sample16(x, y, 1))]
+    });
+}
+
+#[test]
+fn png_low_bit_depths_are_scaled() {
+    for &depth in &[1, 2, 4] {
+        let name = format!("grey{}.png", depth);
+        let maximum = (1 << depth) - 1;
+        assert_pixels(&name, &decode_png(&name), |x, y| {
+            let grey = ((x + y * 3) % (maximum + 1) * 255 / maximum) as u8;
+            [grey, grey, grey, 255]
+        });
+    }
+}
+
+#[test]
+fn png_transparency_chunks_become_alpha() {
+    assert_pixels("rgb8_trns.png", &decode_png("rgb8_trns.png"), |x, y| {
+        let alpha = if (x, y) == (2, 3) { 0 } else { 255 };
+        [(x * 20) as u8, (y * 20) as u8, 7, alpha]
+    });
+    assert_pixels("palette4_trns.png", &decode_png("palette4_trns.png"), |x, y| {
+        let i = (x * 2 + y) % 11;
+        [(i * 23) as u8, (255 - i * 20) as u8, (i * i) as u8, (255 - i * 25) as u8]
+    });
+}
+
+#[test]
+fn png_corruption_is_reported() {
+    let bytes = fixture("rgba8.png");
+    assert!(png::decode(&bytes[..bytes.len() - 20]).is_err());
+
+    let mut corrupt = bytes.clone();
+    let middle = corrupt.len() / 2;
+    corrupt[middle] ^= 0x55;
+    let err = png::decode(&corrupt).unwrap_err();
+    assert!(err.contains("corrupt"), "unexpected error '{}'", err);
+
+    assert!(png::decode(b"GIF89a").is_err());
+}
+
+#[test]
+fn inflate_rejects_bad_streams() {
+    // Stored block "abc" with a matching and a broken length complement
+    assert_eq!(inflate::inflate(&[0x01, 0x03, 0x00, 0xFC, 0xFF, b'a', b'b', b'c']).unwrap(), b"abc");
+    assert!(inflate::inflate(&[0x01, 0x03, 0x00, 0xFC, 0xFE, b'a', b'b', b'c']).is_err());
+    // Reserved block type
+    assert!(inflate::inflate(&[0x07]).is_err());
+    // Wrong Adler-32 checksum
+    assert!(inflate::zlib_decompress(&[0x78, 0x01, 0x01, 0x03, 0x00, 0xFC, 0xFF, b'a', b'b', b'c', 0, 0, 0, 0]).is_err());
+    assert_eq!(inflate::adler32(b"Wikipedia"), 0x11E6_0398);
+}
+
+/// Colours the JPEG fixtures were encoded from
+fn jpeg_source(x: usize, y: usize) -> [i32; 3] {
+    let r = (128.0 + 100.0 * (x as f64 / 7.0).sin()).round() as i32;
+    [r, (y * 255 / 28) as i32, ((x + y) * 255 / 64) as i32]
+}
+
+fn assert_close(name: &str, image: &Image, tolerance: i32, grey: bool) {
+    assert_eq!((image.width, image.height), (37, 29), "{} has the wrong size", name);
+    let mut total = 0;
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let [r, g, b] = jpeg_source(x, y);
+            let expected = if grey { [(r + g) / 2; 3] } else { [r, g, b] };
+            let pixel = image.get(x, y);
+            assert_eq!(pixel[3], 255);
+            for c in 0..3 {
+                let error = (pixel[c] as i32 - expected[c]).abs();
+                assert!(error <= tolerance, "{} is off by {} at {}, {}", name, error, x, y);
+                total += error;
+            }
+        }
+    }
+    let mean = total as f32 / (image.width * image.height * 3) as f32;
+    assert!(mean < 2.0, "{} is off by {} on average", name, mean);
+}
+
+#[test]
+fn jpeg_baseline_subsampling_and_restarts() {
+    assert_close("ycbcr444.jpg", &jpeg::decode(&fixture("ycbcr444.jpg")).unwrap(), 4, false);
+    assert_close("ycbcr422_restart.jpg", &jpeg::decode(&fixture("ycbcr422_restart.jpg")).unwrap(), 10, false);
+    assert_close("ycbcr420.jpg", &jpeg::decode(&fixture("ycbcr420.jpg")).unwrap(), 10, false);
+    assert_close("grey.jpg", &jpeg::decode(&fixture("grey.jpg")).unwrap(), 3, true);
+}
+
+#[test]
+fn jpeg_unsupported_and_truncated_files_are_reported() {
+    let err = jpeg::decode(&fixture("progressive.jpg")).unwrap_err();
+    assert!(err.contains("progressive"), "unexpected error '{}'", err);
+
+    let bytes = fixture("ycbcr420.jpg");
+    assert!(jpeg::decode(&bytes[..bytes.len() / 2]).is_err());
+    assert!(jpeg::decode(b"\x89PNG").is_err());
+}
+
+#[test]
+fn formats_are_told_apart_by_their_signatures() {
+    assert_eq!(image_formats::decode(&fixture("rgba8.png")).unwrap(), decode_png("rgba8.png"));
+    assert_eq!(image_formats::decode(&fixture("grey.jpg")).unwrap().width, 37);
+    assert!(image_formats::decode(b"BM").is_err());
+
+    let err = image_formats::read_file("./tests/images/missing.png").unwrap_err();
+    assert!(err.starts_with("./tests/images/missing.png: "), "unexpected error '{}'", err);
+}
+
+fn dds_files(folder: &Path, out: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(folder).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            dds_files(&path, out);
+        } else if path.extension().map_or(false, |extension| extension == "dds") {
+            out.push(path);
+        }
+    }
+}
+
+#[test]
+fn dds_headers_are_checked_like_the_engine_reads_them() {
+    let mut files = Vec::new();
+    dds_files(Path::new("./src/corange/assets_core"), &mut files);
+    dds_files(Path::new("./examples/assets"), &mut files);
+    assert!(!files.is_empty());
+    for file in &files {
+        let result = dds::parse(&fs::read(file).unwrap());
+        if file.ends_with("ui/logo.dds") {
+            // Not a power of two, so dds_load_file refuses it as well
+            assert_eq!(result.unwrap_err(), "DDS is 48x48, which isn't a power of two");
+        } else {
+            result.unwrap_or_else(|err| panic!("{}: {}", file.display(), err));
+        }
+    }
+
+    let bytes = fs::read("./src/corange/assets_core/ui/white.dds").unwrap();
+    let header = dds::parse(&bytes).unwrap();
+    assert_eq!(header.format, dds::Format::Dxt1);
+    assert_eq!(128 + header.data_size(), bytes.len());
+
+    assert_eq!(dds::parse(&bytes[..bytes.len() - 1]).unwrap_err(), "DDS ends early, it has 695 of 696 bytes of image data");
+    let mut resized = bytes.clone();
+    resized[16] = 12;
+    assert_eq!(dds::parse(&resized).unwrap_err(), "DDS is 12x32, which isn't a power of two");
+    assert_eq!(dds::parse(&fixture("rgba8.png")).unwrap_err(), "not a DDS file");
+}
@@ -0,0 +1,147 @@
+extern crate corange_rs;
+extern crate libc;
+
+use std::env;
+use std::fs;
+use std::mem;
+use std::thread;
+use corange_rs::corange::*;
+use corange_rs::formats::bmf::Bmf;
+use corange_rs::mesh_conversion;
+use corange_rs::mesh_conversion::{convert, ConvertOptions};
+
+fn v(x: f32, y: f32, z: f32) -> vec3 {
+    vec3 { x: x, y: y, z: z }
+}
+
+fn same(a: vec3, b: vec3) -> bool {
+    a.x == b.x && a.y == b.y && a.z == b.z
+}
+
+fn options(flip: [bool; 3], z_up: bool) -> ConvertOptions {
+    ConvertOptions { flip: flip, z_up: z_up, ..ConvertOptions::default() }
+}
+
+/// One triangle in the xy plane, wound counter clockwise around its +z normal
+fn triangle() -> *mut mesh {
+    unsafe {
+        let m = mesh_new();
+        let corners = [v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0)];
+        (*m).num_verts = 3;
+        (*m).num_triangles = 1;
+        (*m).verticies = libc::malloc(mem::size_of::<vertex>() * 3) as *mut vertex;
+        (*m).triangles = libc::malloc(mem::size_of::<u32>() * 3) as *mut u32;
+        for (i, &corner) in corners.iter().enumerate() {
+            *(*m).verticies.offset(i as isize) = vertex {
+                position: corner,
+                normal: v(0.0, 0.0, 1.0),
+                tangent: v(1.0, 0.0, 0.0),
+                binormal: v(0.0, 1.0, 0.0),
+                color: vec4_one(),
+                uvs: vec2 { x: 0.0, y: 0.0 }
+            };
+            *(*m).triangles.offset(i as isize) = i as u32;
+        }
+        m
+    }
+}
+
+/// Whether the winding of the first triangle agrees with the normal of its first vertex
+fn faces_its_normal(m: *mut mesh) -> bool {
+    unsafe {
+        let corner = |i: isize| (*(*m).verticies.offset(*(*m).triangles.offset(i) as isize)).position;
+        let face = vec3_cross(vec3_sub(corner(1), corner(0)), vec3_sub(corner(2), corner(0)));
+        vec3_dot(face, (*(*m).verticies).normal) > 0.0
+    }
+}
+
+#[test]
+fn axes_rotate_z_up_and_flip() {
+    let z_up = options([false; 3], true);
+    assert!(same(z_up.axes(v(0.0, 0.0, 1.0)), v(0.0, 1.0, 0.0)));
+    assert!(same(z_up.axes(v(0.0, 1.0, 0.0)), v(0.0, 0.0, -1.0)));
+    assert!(same(z_up.axes(v(1.0, 0.0, 0.0)), v(1.0, 0.0, 0.0)));
+
+    let flipped = options([true, false, true], false);
+    assert!(same(flipped.axes(v(1.0, 2.0, 3.0)), v(-1.0, 2.0, -3.0)));
+
+    // Flips apply after the rotation, to the engine's axes
+    let both = options([false, false, true], true);
+    assert!(same(both.axes(v(0.0, 1.0, 0.0)), v(0.0, 0.0, 1.0)));
+
+    // The scale only applies to positions
+    let scaled = ConvertOptions { scale: 2.0, ..options([true, false, false], false) };
+    assert!(same(scaled.axes(v(1.0, 1.0, 1.0)), v(-1.0, 1.0, 1.0)));
+}
+
+#[test]
+fn only_odd_flip_counts_mirror() {
+    assert!(!options([false; 3], false).mirrors());
+    assert!(!options([false; 3], true).mirrors());
+    assert!(options([true, false, false], false).mirrors());
+    assert!(options([false, true, false], true).mirrors());
+    assert!(!options([true, true, false], false).mirrors());
+    assert!(!options([false, true, true], false).mirrors());
+    assert!(options([true, true, true], false).mirrors());
+}
+
+#[test]
+fn mirroring_swaps_the_winding() {
+    let cases = [
+        options([true, false, false], false),
+        options([false, false, true], false),
+        options([true, true, false], false),
+        options([true, true, true], true),
+        options([false; 3], true)
+    ];
+    for case in &cases {
+        unsafe {
+            let m = triangle();
+            let scaled = ConvertOptions { scale: 3.0, ..case.clone() };
+            scaled.apply_mesh(m);
+
+            let indices: Vec<u32> = (0..3).map(|i| *(*m).triangles.offset(i)).collect();
+            let expected = if case.mirrors() { vec![0, 2, 1] } else { vec![0, 1, 2] };
+            assert_eq!(indices, expected, "{:?}", case);
+            assert!(faces_its_normal(m), "{:?} turned the triangle away from its normal", case);
+
+            let corner = (*(*m).verticies.offset(1)).position;
+            assert!(same(corner, vec3_mul(case.axes(v(1.0, 0.0, 0.0)), 3.0)), "{:?}", case);
+            assert!(same((*(*m).verticies).tangent, case.axes(v(1.0, 0.0, 0.0))));
+            mesh_delete(m);
+        }
+    }
+}
+
+#[test]
+fn converts_without_a_gl_context() {
+    let folder = env::temp_dir().join(format!("corange-mesh-conversion-test-{}", std::process::id()));
+    fs::create_dir_all(&folder).unwrap();
+    let (input, output) = (folder.join("quad.obj"), folder.join("converted.bmf"));
+    fs::write(&input, "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvn 0 0 1\nvt 0 0\nf 1/1/1 2/1/1 3/1/1\nf 1/1/1 3/1/1 4/1/1\n").unwrap();
+    fs::write(folder.join("quad.mat"), "").unwrap();
+
+    unsafe { asset_init(); }
+    let report = convert(&input.to_string_lossy(), &output.to_string_lossy(), &options([true, false, false], false)).unwrap();
+    let bmf = Bmf::load(&output.to_string_lossy()).unwrap();
+    let side_effect = folder.join("quad.bmf").exists();
+    fs::remove_dir_all(&folder).unwrap();
+
+    assert!(!side_effect, "the obj loader's own .bmf was written too");
+    assert_eq!((report.surfaces.len(), report.vertices(), report.triangles()), (1, 4, 2));
+    assert!(!report.rigged && report.material.ends_with("quad.mat"), "material {}", report.material);
+    assert_eq!(bmf.material, report.material);
+    assert_eq!(bmf.triangles(), 2);
+    assert!(bmf.surfaces[0].vertices.iter().all(|vertex| vertex.position[0] <= 0.0));
+}
+
+#[test]
+fn sources_are_read_off_the_main_thread() {
+    let path = "./examples/assets/piano/piano.bmf";
+    let bmf = Bmf::load(path).unwrap();
+    let source = thread::spawn(move || mesh_conversion::load_meshes(path).unwrap()).join().unwrap();
+    assert_eq!((source.meshes.len(), source.weights.is_some()), (bmf.surfaces.len(), bmf.rigged));
+    assert_eq!(source.material, bmf.material);
+    let triangles: usize = source.meshes.iter().map(|&m| unsafe { (*m).num_triangles as usize }).sum();
+    assert_eq!(triangles, bmf.triangles());
+}
@@ -0,0 +1,114 @@
+extern crate corange_rs;
+extern crate libc;
+
+mod common;
+
+use std::mem;
+use corange_rs::corange::*;
+use corange_rs::physics::{PhysicsWorld, Shape};
+use common::handle;
+
+/// Flat ground at `y = 0` from -`extent` to `extent`, split into `cells` by `cells` squares so
+/// bodies rest across triangle edges
+fn ground(extent: f32, cells: usize) -> *mut static_object {
+    unsafe {
+        let mesh = cmesh_new();
+        let count = cells * cells * 2;
+        let triangles = libc::malloc(mem::size_of::<ctri>() * count) as *mut ctri;
+        let size = 2.0 * extent / cells as f32;
+        let mut t = 0;
+        for i in 0..cells {
+            for j in 0..cells {
+                let (x0, z0) = (-extent + i as f32 * size, -extent + j as f32 * size);
+                let (x1, z1) = (x0 + size, z0 + size);
+                let up = vec3_new(0.0, 1.0, 0.0);
+                *triangles.offset(t) = ctri_new(vec3_new(x0, 0.0, z0), vec3_new(x0, 0.0, z1), vec3_new(x1, 0.0, z1), up);
+                *triangles.offset(t + 1) = ctri_new(vec3_new(x0, 0.0, z0), vec3_new(x1, 0.0, z1), vec3_new(x1, 0.0, z0), up);
+                t += 2;
+            }
+        }
+        (*mesh).triangles = triangles;
+        (*mesh).triangles_num = count as i32;
+        cmesh_build_bvh(mesh);
+
+        let object = static_object_new();
+        (*object).collision_body = handle(mesh as *mut asset);
+        object
+    }
+}
+
+fn body_at(position: vec3) -> *mut physics_object {
+    unsafe {
+        let object = physics_object_new();
+        (*object).position = position;
+        object
+    }
+}
+
+fn world_with_ground() -> PhysicsWorld {
+    let mut world = PhysicsWorld::new();
+    world.add_static(ground(10.0, 8));
+    world
+}
+
+#[test]
+fn dropped_box_settles_on_the_ground() {
+    unsafe {
+        let mut world = world_with_ground();
+        let object = body_at(vec3_new(0.0, 5.0, 0.0));
+        world.add_body(object, Shape::Box(vec3_new(0.5, 0.5, 0.5)), 1.0);
+
+        let mut lowest = std::f32::MAX;
+        for _ in 0..600 {
+            world.step();
+            lowest = lowest.min((*object).position.y);
+        }
+
+        assert!(lowest > 0.4, "box sank to {} while landing", lowest);
+        assert!(((*object).position.y - 0.5).abs() < 0.02, "box rests at {}", (*object).position.y);
+        assert!(vec3_length((*object).velocity) < 0.01, "box still moves at {}", vec3_length((*object).velocity));
+    }
+}
+
+#[test]
+fn dropped_bodies_do_not_tunnel() {
+    unsafe {
+        let shapes = [
+            (Shape::Sphere(0.5), 0.5),
+            (Shape::Box(vec3_new(0.5, 0.25, 0.5)), 0.25),
+            (Shape::Ellipsoid(vec3_new(0.6, 0.4, 0.6)), 0.4)
+        ];
+        for (i, &(shape, height)) in shapes.iter().enumerate() {
+            let mut world = world_with_ground();
+            let object = body_at(vec3_new(i as f32 * 0.3, 8.0, 0.1));
+            world.add_body(object, shape, 2.0);
+            for _ in 0..600 {
+                world.step();
+                assert!((*object).position.y > 0.0, "shape {} passed through the ground", i);
+            }
+            assert!(((*object).position.y - height).abs() < 0.03, "shape {} rests at {}", i, (*object).position.y);
+        }
+    }
+}
+
+#[test]
+fn resting_contact_stays_still() {
+    unsafe {
+        let mut world = world_with_ground();
+        let object = body_at(vec3_new(0.0, 0.5, 0.0));
+        world.add_body(object, Shape::Box(vec3_new(0.5, 0.5, 0.5)), 1.0);
+
+        // Let contacts settle, then nothing should move for the next ten seconds
+        for _ in 0..60 {
+            world.step();
+        }
+        let (position, rotation) = ((*object).position, (*object).rotation);
+        for _ in 0..600 {
+            world.step();
+        }
+        assert!(vec3_dist((*object).position, position) < 0.005, "box drifted by {}", vec3_dist((*object).position, position));
+        let turned = quat_dot((*object).rotation, rotation).abs();
+        assert!(turned > 0.9999, "box turned");
+        assert!(((*object).position.y - 0.5).abs() < 0.02);
+    }
+}